@@ -0,0 +1,1096 @@
+use std::fmt;
+use std::str::FromStr;
+
+use hashbrown::HashMap;
+use redscript_io::{
+    Breakpoint, CNameIndex, ClassIndex, Conditional, EnumIndex, EnumValueIndex, FieldIndex,
+    Function, FunctionFlags, FunctionIndex, IndexedDefinition, Instr, InvokeFlags, Jump, Local,
+    LocalFlags, LocalIndex, Offset, Parameter, ParameterFlags, ParameterIndex, PoolItemIndex,
+    Profile, ScriptBundle, SourceFileIndex, SourceReference, Switch, SwitchLabel, TypeIndex,
+    Visibility,
+};
+
+use crate::names::{Lookup, insert, is_ident_char};
+use crate::{Error, Result};
+
+#[derive(Debug)]
+pub struct Assembler<'b, 'i> {
+    bundle: &'b mut ScriptBundle<'i>,
+    symbols: Symbols,
+}
+
+impl<'b, 'i> Assembler<'b, 'i> {
+    pub fn new(bundle: &'b mut ScriptBundle<'i>) -> Self {
+        let symbols = Symbols::new(bundle);
+        Self { bundle, symbols }
+    }
+
+    pub fn assemble(&mut self, source: &str) -> Result<Vec<FunctionIndex>> {
+        let lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| Line::new(i + 1, line))
+            .filter(|line| !matches!(line, Ok(line) if line.tokens.is_empty()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut lines = &lines[..];
+        let mut functions = vec![];
+        while let Some((header, rest)) = lines.split_first() {
+            if header.tokens[0] != ".func" {
+                return Err(header.invalid("expected a .func directive"));
+            }
+            let end = rest
+                .iter()
+                .position(|line| line.tokens[0] == ".func")
+                .unwrap_or(rest.len());
+            let (listing, rest) = rest.split_at(end);
+            let body = listing
+                .iter()
+                .position(|line| !line.tokens[0].starts_with('.'))
+                .unwrap_or(listing.len());
+            let (directives, body) = listing.split_at(body);
+
+            functions.push(self.function(header, directives, body)?);
+            lines = rest;
+        }
+        Ok(functions)
+    }
+
+    fn function(
+        &mut self,
+        header: &Line<'_>,
+        directives: &[Line<'_>],
+        body: &[Line<'_>],
+    ) -> Result<FunctionIndex> {
+        let mut args = header.args();
+        let symbol = args.symbol()?;
+        args.end()?;
+
+        let existing = match &symbol {
+            SymbolRef::Index(index) => Some(
+                self.index(*index, FunctionIndex::new)
+                    .ok_or_else(|| header.unresolved("function", &symbol))?,
+            ),
+            SymbolRef::Name(name) => self.symbols.function(None, name, header)?,
+            SymbolRef::Member(class, name) => self.symbols.function(Some(class), name, header)?,
+        };
+
+        let mut decl = Declaration::default();
+        for line in directives {
+            self.directive(line, &mut decl)?;
+        }
+
+        let index = match existing {
+            Some(index) => {
+                self.redeclare(header, index, decl)?;
+                index
+            }
+            None => self.declare(header, symbol, decl)?,
+        };
+        self.body(index, body)?;
+        Ok(index)
+    }
+
+    fn directive(&self, line: &Line<'_>, decl: &mut Declaration) -> Result<()> {
+        let mut args = line.args();
+        match line.tokens[0] {
+            ".source" => {
+                let symbol = args.symbol()?;
+                let file = match &symbol {
+                    SymbolRef::Index(index) => self.index(*index, SourceFileIndex::new),
+                    SymbolRef::Name(path) => self.symbols.source_files.get(path).copied(),
+                    SymbolRef::Member(_, _) => None,
+                }
+                .ok_or_else(|| line.unresolved("source file", &symbol))?;
+                decl.source = Some(SourceReference::new(file, args.parse()?));
+            }
+            ".flags" => {
+                while let Some(flag) = args.try_next() {
+                    match flag {
+                        "protected" => decl.visibility = Visibility::Protected,
+                        "private" => decl.visibility = Visibility::Private,
+                        "static" => decl.flags.set_is_static(true),
+                        "final" => decl.flags.set_is_final(true),
+                        "native" => decl.flags.set_is_native(true),
+                        "exec" => decl.flags.set_is_exec(true),
+                        "timer" => decl.flags.set_is_timer(true),
+                        "callback" => decl.flags.set_is_callback(true),
+                        "implicit_cast" => decl.flags.set_is_implicit_cast(true),
+                        "const" => decl.flags.set_is_const(true),
+                        "thread_safe" => decl.flags.set_is_thread_safe(true),
+                        "quest" => decl.flags.set_is_quest(true),
+                        _ => return Err(line.invalid("unknown function flag")),
+                    }
+                }
+            }
+            ".param" => match args.symbol()? {
+                SymbolRef::Index(index) => {
+                    let param = self
+                        .index(index, ParameterIndex::new)
+                        .ok_or_else(|| line.unresolved("parameter", &SymbolRef::Index(index)))?;
+                    decl.params.push(Var::Existing(param));
+                }
+                SymbolRef::Name(name) => {
+                    let typ = self.type_(line, args.next()?)?;
+                    let mut flags = ParameterFlags::new();
+                    while let Some(flag) = args.try_next() {
+                        match flag {
+                            "optional" => flags.set_is_optional(true),
+                            "out" => flags.set_is_out(true),
+                            "short_circuit" => flags.set_is_short_circuit(true),
+                            "const" => flags.set_is_const(true),
+                            _ => return Err(line.invalid("unknown parameter flag")),
+                        }
+                    }
+                    decl.params.push(Var::New(name, typ, flags));
+                }
+                SymbolRef::Member(_, _) => return Err(line.invalid("expected a parameter name")),
+            },
+            ".local" => match args.symbol()? {
+                SymbolRef::Index(index) => {
+                    let local = self
+                        .index(index, LocalIndex::new)
+                        .ok_or_else(|| line.unresolved("local", &SymbolRef::Index(index)))?;
+                    decl.locals.push(Var::Existing(local));
+                }
+                SymbolRef::Name(name) => {
+                    let typ = self.type_(line, args.next()?)?;
+                    let mut flags = LocalFlags::new();
+                    while let Some(flag) = args.try_next() {
+                        match flag {
+                            "const" => flags.set_is_const(true),
+                            _ => return Err(line.invalid("unknown local flag")),
+                        }
+                    }
+                    decl.locals.push(Var::New(name, typ, flags));
+                }
+                SymbolRef::Member(_, _) => return Err(line.invalid("expected a local name")),
+            },
+            ".return" => {
+                let typ = self.type_(line, args.next()?)?;
+                let is_const = match args.try_next() {
+                    Some("const") => true,
+                    Some(_) => return Err(line.invalid("unknown return flag")),
+                    None => false,
+                };
+                decl.return_type = Some((typ, is_const));
+            }
+            _ => return Err(line.invalid("unknown directive")),
+        }
+        args.end()
+    }
+
+    fn declare(
+        &mut self,
+        header: &Line<'_>,
+        symbol: SymbolRef,
+        decl: Declaration,
+    ) -> Result<FunctionIndex> {
+        let (class, name) = match symbol {
+            SymbolRef::Member(class, name) => (Some(class), name),
+            SymbolRef::Name(name) => (None, name),
+            SymbolRef::Index(index) => {
+                return Err(header.unresolved("function", &SymbolRef::Index(index)));
+            }
+        };
+        let class_index = match &class {
+            Some(class) => Some(
+                unique(self.symbols.classes.get(class))
+                    .ok_or_else(|| header.unresolved("class", &SymbolRef::Name(class.clone())))?,
+            ),
+            None => None,
+        };
+        if decl.flags.is_native() && decl.source.is_some() {
+            return Err(header.invalid("native functions cannot have a source"));
+        }
+
+        let cname = self.bundle.cnames_mut().add(name.clone());
+        let function = Function::new(cname, decl.visibility, decl.flags)
+            .with_class(class_index)
+            .with_source(decl.source);
+        let index = self
+            .bundle
+            .define_and_init(function, |bundle, index, function| {
+                let params = decl
+                    .params
+                    .into_iter()
+                    .map(|var| define_param(bundle, index, None, var))
+                    .collect::<Vec<_>>();
+                let locals = decl
+                    .locals
+                    .into_iter()
+                    .map(|var| define_local(bundle, index, None, var))
+                    .collect::<Vec<_>>();
+                let function = function.with_parameters(params).with_locals(locals);
+                match decl.return_type {
+                    Some((typ, true)) => function.with_const_return_type(typ),
+                    Some((typ, false)) => function.with_return_type(Some(typ)),
+                    None => function,
+                }
+            });
+
+        if let Some(class) = class_index.and_then(|class| self.bundle.get_item_mut(class)) {
+            class.add_method(index);
+        }
+        insert(&mut self.symbols.functions, (class, name), index);
+        Ok(index)
+    }
+
+    fn redeclare(
+        &mut self,
+        header: &Line<'_>,
+        index: FunctionIndex,
+        decl: Declaration,
+    ) -> Result<()> {
+        if decl.flags.is_native() && decl.source.is_some() {
+            return Err(header.invalid("native functions cannot have a source"));
+        }
+
+        let function = &self.bundle[index];
+        let old_params = function.parameters().to_vec();
+        let old_locals = function.locals().to_vec();
+
+        let params = decl
+            .params
+            .into_iter()
+            .enumerate()
+            .map(|(i, var)| define_param(self.bundle, index, old_params.get(i).copied(), var))
+            .collect::<Vec<_>>();
+        let locals = decl
+            .locals
+            .into_iter()
+            .enumerate()
+            .map(|(i, var)| define_local(self.bundle, index, old_locals.get(i).copied(), var))
+            .collect::<Vec<_>>();
+
+        let function = &mut self.bundle[index];
+        // the flags that are not set by directives are derived from the rest of the definition
+        let flags = function
+            .flags()
+            .with_is_static(decl.flags.is_static())
+            .with_is_final(decl.flags.is_final())
+            .with_is_native(decl.flags.is_native())
+            .with_is_exec(decl.flags.is_exec())
+            .with_is_timer(decl.flags.is_timer())
+            .with_is_callback(decl.flags.is_callback())
+            .with_is_implicit_cast(decl.flags.is_implicit_cast())
+            .with_is_const(decl.flags.is_const())
+            .with_is_thread_safe(decl.flags.is_thread_safe())
+            .with_is_quest(decl.flags.is_quest());
+        let updated = function
+            .clone()
+            .with_flags(flags)
+            .with_visibility(decl.visibility)
+            .with_source(decl.source)
+            .with_parameters(params)
+            .with_locals(locals);
+        *function = match decl.return_type {
+            Some((typ, true)) => updated.with_const_return_type(typ),
+            Some((typ, false)) => updated.with_return_type(Some(typ)),
+            None => updated.with_return_type(None),
+        };
+        Ok(())
+    }
+
+    fn body(&mut self, index: FunctionIndex, body: &[Line<'_>]) -> Result<()> {
+        let mut labels = HashMap::new();
+        let mut count = 0;
+        for line in body {
+            match line.label() {
+                Some(label) if labels.insert(label, count).is_some() => {
+                    return Err(line.invalid("duplicate label"));
+                }
+                Some(_) => {}
+                None => count += 1,
+            }
+        }
+
+        let scope = Scope::new(self.bundle, &self.bundle[index]);
+        let lines = body
+            .iter()
+            .filter(|line| line.label().is_none())
+            .collect::<Vec<_>>();
+        let code = lines
+            .iter()
+            .map(|line| self.instr(line, &scope, &labels))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut positions = Vec::with_capacity(code.len() + 1);
+        let mut position = 0i64;
+        for instr in &code {
+            positions.push(position);
+            position += i64::from(instr.virtual_size());
+        }
+        positions.push(position);
+
+        let mut offset = 0i64;
+        let code = code
+            .into_iter()
+            .zip(&lines)
+            .map(|(instr, line)| {
+                let instr = instr
+                    .map_labels(|label| match label {
+                        Label::Relative(rel) => Some(Offset::from(rel)),
+                        Label::Instr(i) => {
+                            Some(Offset::from(i16::try_from(positions[i] - offset).ok()?))
+                        }
+                    })
+                    .ok_or(Error::LabelTooDistant(line.number))?;
+                offset += i64::from(instr.virtual_size());
+                Ok(instr)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let function = &mut self.bundle[index];
+        if function.body().code_owned().ok().as_ref() != Some(&code) {
+            function.set_code(code);
+        }
+        Ok(())
+    }
+
+    fn instr(
+        &mut self,
+        line: &Line<'_>,
+        scope: &Scope,
+        labels: &HashMap<&str, usize>,
+    ) -> Result<Instr<Label>> {
+        let mut args = line.args();
+        let label = |args: &mut Args<'_, '_>| -> Result<Label> {
+            let token = args.next()?;
+            if token.starts_with(['+', '-']) {
+                let rel = token.parse().map_err(|_| line.invalid("invalid offset"))?;
+                Ok(Label::Relative(rel))
+            } else {
+                labels
+                    .get(token)
+                    .map(|&i| Label::Instr(i))
+                    .ok_or_else(|| Error::UndefinedLabel(line.number, token.into()))
+            }
+        };
+
+        let instr = match line.tokens[0] {
+            "nop" => Instr::Nop,
+            "ref.null" => Instr::Null,
+            "i32.one" => Instr::I32One,
+            "i32.zero" => Instr::I32Zero,
+            "i8.const" => Instr::I8Const(args.parse()?),
+            "i16.const" => Instr::I16Const(args.parse()?),
+            "i32.const" => Instr::I32Const(args.parse()?),
+            "i64.const" => Instr::I64Const(args.parse()?),
+            "u8.const" => Instr::U8Const(args.parse()?),
+            "u16.const" => Instr::U16Const(args.parse()?),
+            "u32.const" => Instr::U32Const(args.parse()?),
+            "u64.const" => Instr::U64Const(args.parse()?),
+            "f32.const" => {
+                let token = args.next()?;
+                let val = match token.strip_prefix("nan:0x") {
+                    Some(bits) => u32::from_str_radix(bits, 16).ok().map(f32::from_bits),
+                    None => token.parse().ok(),
+                };
+                Instr::F32Const(val.ok_or_else(|| line.invalid("invalid float"))?)
+            }
+            "f64.const" => {
+                let token = args.next()?;
+                let val = match token.strip_prefix("nan:0x") {
+                    Some(bits) => u64::from_str_radix(bits, 16).ok().map(f64::from_bits),
+                    None => token.parse().ok(),
+                };
+                Instr::F64Const(val.ok_or_else(|| line.invalid("invalid float"))?)
+            }
+            "cname.const" => {
+                let str = args.string()?;
+                Instr::CNameConst(self.bundle.cnames_mut().add(str))
+            }
+            "enum.const" => {
+                let (enum_, value) = self.enum_value(line, &mut args)?;
+                Instr::EnumConst { enum_, value }
+            }
+            "string.const" => {
+                let str = args.string()?;
+                Instr::StringConst(self.bundle.strings_mut().add(str))
+            }
+            "tweakdb.const" => {
+                let str = args.string()?;
+                Instr::TweakDbIdConst(self.bundle.tdb_ids_mut().add(str))
+            }
+            "resource.const" => {
+                let str = args.string()?;
+                Instr::ResourceConst(self.bundle.resources_mut().add(str))
+            }
+            "true.const" => Instr::TrueConst,
+            "false.const" => Instr::FalseConst,
+            "breakpoint" => {
+                let breakpoint = Breakpoint::new(
+                    args.line_number()?,
+                    args.parse()?,
+                    args.parse()?,
+                    args.parse()?,
+                    args.parse()?,
+                );
+                let padding = match args.try_next() {
+                    Some(token) => u64::from_str_radix(token, 16)
+                        .map_err(|_| line.invalid("invalid breakpoint padding"))?,
+                    None => 0,
+                };
+                Instr::Breakpoint(Box::new(breakpoint.with_padding(padding.to_be_bytes())))
+            }
+            "assign" => Instr::Assign,
+            "target" => Instr::Target(label(&mut args)?),
+            "local" => Instr::Local(self.local(line, scope, args.next()?)?),
+            "param" => Instr::Param(self.param(line, scope, args.next()?)?),
+            "object.field" => Instr::ObjectField(self.field(line, args.next()?)?),
+            "external" => Instr::ExternalVar,
+            "switch" => {
+                let typ = self.type_(line, args.next()?)?;
+                Instr::Switch(Switch::new(typ, label(&mut args)?))
+            }
+            "switch.label" => {
+                let next_case = label(&mut args)?;
+                Instr::SwitchLabel(SwitchLabel::new(next_case, label(&mut args)?))
+            }
+            "switch.default" => Instr::SwitchDefault,
+            "jump" => Instr::Jump(Jump::new(label(&mut args)?)),
+            "jump.if_not" => Instr::JumpIfFalse(Jump::new(label(&mut args)?)),
+            "skip" => Instr::Skip(Jump::new(label(&mut args)?)),
+            "cond" => {
+                let false_label = label(&mut args)?;
+                Instr::Conditional(Conditional::new(false_label, label(&mut args)?))
+            }
+            "struct.new" => Instr::Construct {
+                arg_count: args.parse()?,
+                class: self.class(line, args.next()?)?,
+            },
+            "invoke.static" => Instr::InvokeStatic {
+                exit: Jump::new(label(&mut args)?),
+                line: args.line_number()?,
+                function: self.function_ref(line, args.next()?)?,
+                flags: args.invoke_flags()?,
+            },
+            "invoke.virtual" => Instr::InvokeVirtual {
+                exit: Jump::new(label(&mut args)?),
+                line: args.line_number()?,
+                function: self.bundle.cnames_mut().add(args.string()?),
+                flags: args.invoke_flags()?,
+            },
+            "param.end" => Instr::ParamEnd,
+            "return" => Instr::Return,
+            "struct.field" => Instr::StructField(self.field(line, args.next()?)?),
+            "ctx" => Instr::Context(Jump::new(label(&mut args)?)),
+            "eq" => Instr::Equals(self.type_(line, args.next()?)?),
+            "refstr.eq" => Instr::RefStringEqualsString(self.type_(line, args.next()?)?),
+            "str.eq" => Instr::StringEqualsRefString(self.type_(line, args.next()?)?),
+            "neq" => Instr::NotEquals(self.type_(line, args.next()?)?),
+            "refstr.neq" => Instr::RefStringNotEqualsString(self.type_(line, args.next()?)?),
+            "str.neq" => Instr::StringNotEqualsRefString(self.type_(line, args.next()?)?),
+            "object.new" => Instr::New(self.class(line, args.next()?)?),
+            "object.delete" => Instr::Delete,
+            "this" => Instr::This,
+            "profile" => {
+                let function = args.bytes()?;
+                Instr::Profile(Box::new(Profile::new(function, args.parse()?)))
+            }
+            "array.clear" => Instr::ArrayClear(self.type_(line, args.next()?)?),
+            "array.size" => Instr::ArraySize(self.type_(line, args.next()?)?),
+            "array.resize" => Instr::ArrayResize(self.type_(line, args.next()?)?),
+            "array.find_first" => Instr::ArrayFindFirst(self.type_(line, args.next()?)?),
+            "array.find_first_fast" => Instr::ArrayFindFirstFast(self.type_(line, args.next()?)?),
+            "array.find_last" => Instr::ArrayFindLast(self.type_(line, args.next()?)?),
+            "array.find_last_fast" => Instr::ArrayFindLastFast(self.type_(line, args.next()?)?),
+            "array.contains" => Instr::ArrayContains(self.type_(line, args.next()?)?),
+            "array.contains_fast" => Instr::ArrayContainsFast(self.type_(line, args.next()?)?),
+            "array.count" => Instr::ArrayCount(self.type_(line, args.next()?)?),
+            "array.count_fast" => Instr::ArrayCountFast(self.type_(line, args.next()?)?),
+            "array.push" => Instr::ArrayPush(self.type_(line, args.next()?)?),
+            "array.pop" => Instr::ArrayPop(self.type_(line, args.next()?)?),
+            "array.insert" => Instr::ArrayInsert(self.type_(line, args.next()?)?),
+            "array.remove" => Instr::ArrayRemove(self.type_(line, args.next()?)?),
+            "array.remove_fast" => Instr::ArrayRemoveFast(self.type_(line, args.next()?)?),
+            "array.grow" => Instr::ArrayGrow(self.type_(line, args.next()?)?),
+            "array.erase" => Instr::ArrayErase(self.type_(line, args.next()?)?),
+            "array.erase_fast" => Instr::ArrayEraseFast(self.type_(line, args.next()?)?),
+            "array.last" => Instr::ArrayLast(self.type_(line, args.next()?)?),
+            "array.element" => Instr::ArrayElement(self.type_(line, args.next()?)?),
+            "array.sort" => Instr::ArraySort(self.type_(line, args.next()?)?),
+            "array.sort_by" => Instr::ArraySortByPredicate(self.type_(line, args.next()?)?),
+            "static_array.size" => Instr::StaticArraySize(self.type_(line, args.next()?)?),
+            "static_array.find_first" => {
+                Instr::StaticArrayFindFirst(self.type_(line, args.next()?)?)
+            }
+            "static_array.find_first_fast" => {
+                Instr::StaticArrayFindFirstFast(self.type_(line, args.next()?)?)
+            }
+            "static_array.find_last" => Instr::StaticArrayFindLast(self.type_(line, args.next()?)?),
+            "static_array.find_last_fast" => {
+                Instr::StaticArrayFindLastFast(self.type_(line, args.next()?)?)
+            }
+            "static_array.contains" => Instr::StaticArrayContains(self.type_(line, args.next()?)?),
+            "static_array.contains_fast" => {
+                Instr::StaticArrayContainsFast(self.type_(line, args.next()?)?)
+            }
+            "static_array.count" => Instr::StaticArrayCount(self.type_(line, args.next()?)?),
+            "static_array.count_fast" => {
+                Instr::StaticArrayCountFast(self.type_(line, args.next()?)?)
+            }
+            "static_array.last" => Instr::StaticArrayLast(self.type_(line, args.next()?)?),
+            "static_array.element" => Instr::StaticArrayElement(self.type_(line, args.next()?)?),
+            "ref.to_bool" => Instr::RefToBool,
+            "wref.to_bool" => Instr::WeakRefToBool,
+            "enum.to_int" => Instr::EnumToI32 {
+                enum_type: self.type_(line, args.next()?)?,
+                size: args.parse()?,
+            },
+            "enum.from_int" => Instr::I32ToEnum {
+                enum_type: self.type_(line, args.next()?)?,
+                size: args.parse()?,
+            },
+            "ref.dyncast" => Instr::DynamicCast {
+                class: self.class(line, args.next()?)?,
+                is_weak: false,
+            },
+            "wref.dyncast" => Instr::DynamicCast {
+                class: self.class(line, args.next()?)?,
+                is_weak: true,
+            },
+            "to_string" => Instr::ToString(self.type_(line, args.next()?)?),
+            "variant.new" => Instr::ToVariant(self.type_(line, args.next()?)?),
+            "variant.extract" => Instr::FromVariant(self.type_(line, args.next()?)?),
+            "variant.is_defined" => Instr::VariantIsDefined,
+            "variant.is_ref" => Instr::VariantIsRef,
+            "variant.is_array" => Instr::VariantIsArray,
+            "variant.type_name" => Instr::VariantTypeName,
+            "variant.to_string" => Instr::VariantToString,
+            "wref.to_ref" => Instr::WeakRefToRef,
+            "ref.to_wref" => Instr::RefToWeakRef,
+            "wref.null" => Instr::WeakRefNull,
+            "as_ref" => Instr::AsRef(self.type_(line, args.next()?)?),
+            "deref" => Instr::Deref(self.type_(line, args.next()?)?),
+            mnemonic => return Err(Error::UnknownMnemonic(line.number, mnemonic.into())),
+        };
+        args.end()?;
+        Ok(instr)
+    }
+
+    fn type_(&self, line: &Line<'_>, token: &str) -> Result<TypeIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, TypeIndex::new),
+            SymbolRef::Name(name) => unique(self.symbols.types.get(name)),
+            SymbolRef::Member(_, _) => None,
+        }
+        .ok_or_else(|| line.unresolved("type", &symbol))
+    }
+
+    fn class(&self, line: &Line<'_>, token: &str) -> Result<ClassIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, ClassIndex::new),
+            SymbolRef::Name(name) => unique(self.symbols.classes.get(name)),
+            SymbolRef::Member(_, _) => None,
+        }
+        .ok_or_else(|| line.unresolved("class", &symbol))
+    }
+
+    fn field(&self, line: &Line<'_>, token: &str) -> Result<FieldIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, FieldIndex::new),
+            SymbolRef::Member(class, name) => {
+                unique(self.symbols.fields.get(&(class.clone(), name.clone())))
+            }
+            SymbolRef::Name(_) => None,
+        }
+        .ok_or_else(|| line.unresolved("field", &symbol))
+    }
+
+    fn function_ref(&self, line: &Line<'_>, token: &str) -> Result<FunctionIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, FunctionIndex::new),
+            SymbolRef::Name(name) => self.symbols.function(None, name, line)?,
+            SymbolRef::Member(class, name) => self.symbols.function(Some(class), name, line)?,
+        }
+        .ok_or_else(|| line.unresolved("function", &symbol))
+    }
+
+    fn local(&self, line: &Line<'_>, scope: &Scope, token: &str) -> Result<LocalIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, LocalIndex::new),
+            SymbolRef::Name(name) => unique(scope.locals.get(name)),
+            SymbolRef::Member(_, _) => None,
+        }
+        .ok_or_else(|| line.unresolved("local", &symbol))
+    }
+
+    fn param(&self, line: &Line<'_>, scope: &Scope, token: &str) -> Result<ParameterIndex> {
+        let symbol = parse_symbol(line.number, token)?;
+        match &symbol {
+            SymbolRef::Index(index) => self.index(*index, ParameterIndex::new),
+            SymbolRef::Name(name) => unique(scope.params.get(name)),
+            SymbolRef::Member(_, _) => None,
+        }
+        .ok_or_else(|| line.unresolved("parameter", &symbol))
+    }
+
+    fn enum_value(
+        &self,
+        line: &Line<'_>,
+        args: &mut Args<'_, '_>,
+    ) -> Result<(EnumIndex, EnumValueIndex)> {
+        let symbol = args.symbol()?;
+        match &symbol {
+            SymbolRef::Member(enum_, name) => {
+                let value = unique(self.symbols.enum_values.get(&(enum_.clone(), name.clone())))
+                    .ok_or_else(|| line.unresolved("enum value", &symbol))?;
+                Ok((self.bundle[value].enum_(), value))
+            }
+            SymbolRef::Index(enum_) => {
+                let enum_ = self
+                    .index(*enum_, EnumIndex::new)
+                    .ok_or_else(|| line.unresolved("enum", &symbol))?;
+                let symbol = args.symbol()?;
+                let value = match &symbol {
+                    SymbolRef::Index(value) => self.index(*value, EnumValueIndex::new),
+                    _ => None,
+                }
+                .ok_or_else(|| line.unresolved("enum value", &symbol))?;
+                Ok((enum_, value))
+            }
+            SymbolRef::Name(_) => Err(line.unresolved("enum value", &symbol)),
+        }
+    }
+
+    fn index<I>(&self, index: u32, new: impl FnOnce(u32) -> Option<I>) -> Option<I>
+    where
+        I: PoolItemIndex<'i> + Copy,
+    {
+        new(index).filter(|&index| self.bundle.get_item(index).is_some())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Symbols {
+    types: HashMap<String, Lookup<TypeIndex>>,
+    classes: HashMap<String, Lookup<ClassIndex>>,
+    enum_values: HashMap<(String, String), Lookup<EnumValueIndex>>,
+    functions: HashMap<(Option<String>, String), Lookup<FunctionIndex>>,
+    fields: HashMap<(String, String), Lookup<FieldIndex>>,
+    source_files: HashMap<String, SourceFileIndex>,
+}
+
+impl Symbols {
+    fn new(bundle: &ScriptBundle<'_>) -> Self {
+        let name = |index| cname(bundle, index).to_owned();
+        let class_name = |index: ClassIndex| bundle.get_item(index).map(|class| name(class.name()));
+        let enum_name = |index: EnumIndex| bundle.get_item(index).map(|enum_| name(enum_.name()));
+
+        let mut this = Self::default();
+        for def in bundle.indexed_definitions() {
+            match def {
+                IndexedDefinition::Type(i, typ) => {
+                    insert(&mut this.types, name(typ.name()), i);
+                }
+                IndexedDefinition::Class(i, class) => {
+                    insert(&mut this.classes, name(class.name()), i);
+                }
+                IndexedDefinition::EnumMember(i, member) => {
+                    let Some(enum_) = enum_name(member.enum_()) else {
+                        continue;
+                    };
+                    insert(&mut this.enum_values, (enum_, name(member.name())), i);
+                }
+                IndexedDefinition::Function(i, func) => {
+                    let class = match func.class() {
+                        Some(class) => match class_name(class) {
+                            Some(name) => Some(name),
+                            None => continue,
+                        },
+                        None => None,
+                    };
+                    insert(&mut this.functions, (class, name(func.name())), i);
+                }
+                IndexedDefinition::Field(i, field) => {
+                    let Some(class) = class_name(field.class()) else {
+                        continue;
+                    };
+                    insert(&mut this.fields, (class, name(field.name())), i);
+                }
+                IndexedDefinition::SourceFile(i, file) => {
+                    this.source_files.entry(file.path().to_owned()).or_insert(i);
+                }
+                _ => {}
+            }
+        }
+        this
+    }
+
+    fn function(
+        &self,
+        class: Option<&String>,
+        name: &str,
+        line: &Line<'_>,
+    ) -> Result<Option<FunctionIndex>> {
+        match self.functions.get(&(class.cloned(), name.to_owned())) {
+            Some(Lookup::Unique(index)) => Ok(Some(*index)),
+            Some(Lookup::Ambiguous) => {
+                let symbol = match class {
+                    Some(class) => SymbolRef::Member(class.clone(), name.into()),
+                    None => SymbolRef::Name(name.into()),
+                };
+                Err(Error::AmbiguousSymbol(
+                    line.number,
+                    "function",
+                    symbol.to_string(),
+                ))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    params: HashMap<String, Lookup<ParameterIndex>>,
+    locals: HashMap<String, Lookup<LocalIndex>>,
+}
+
+impl Scope {
+    fn new(bundle: &ScriptBundle<'_>, function: &Function<'_>) -> Self {
+        let mut this = Self::default();
+        for &param in function.parameters() {
+            if let Some(def) = bundle.get_item(param) {
+                insert(
+                    &mut this.params,
+                    cname(bundle, def.name()).to_owned(),
+                    param,
+                );
+            }
+        }
+        for &local in function.locals() {
+            if let Some(def) = bundle.get_item(local) {
+                insert(
+                    &mut this.locals,
+                    cname(bundle, def.name()).to_owned(),
+                    local,
+                );
+            }
+        }
+        this
+    }
+}
+
+#[derive(Debug, Default)]
+struct Declaration {
+    visibility: Visibility,
+    flags: FunctionFlags,
+    source: Option<SourceReference>,
+    params: Vec<Var<ParameterIndex, ParameterFlags>>,
+    locals: Vec<Var<LocalIndex, LocalFlags>>,
+    return_type: Option<(TypeIndex, bool)>,
+}
+
+#[derive(Debug)]
+enum Var<I, F> {
+    Existing(I),
+    New(String, TypeIndex, F),
+}
+
+fn define_param(
+    bundle: &mut ScriptBundle<'_>,
+    function: FunctionIndex,
+    previous: Option<ParameterIndex>,
+    var: Var<ParameterIndex, ParameterFlags>,
+) -> ParameterIndex {
+    match var {
+        Var::Existing(index) => index,
+        Var::New(name, typ, flags) => {
+            if let Some(index) = previous {
+                let param = &bundle[index];
+                if cname(bundle, param.name()) == name
+                    && param.type_() == typ
+                    && param.flags() == flags
+                {
+                    return index;
+                }
+            }
+            let name = bundle.cnames_mut().add(name);
+            bundle.define(Parameter::new(name, function, typ, flags))
+        }
+    }
+}
+
+fn define_local(
+    bundle: &mut ScriptBundle<'_>,
+    function: FunctionIndex,
+    previous: Option<LocalIndex>,
+    var: Var<LocalIndex, LocalFlags>,
+) -> LocalIndex {
+    match var {
+        Var::Existing(index) => index,
+        Var::New(name, typ, flags) => {
+            if let Some(index) = previous {
+                let local = &bundle[index];
+                if cname(bundle, local.name()) == name
+                    && local.type_() == typ
+                    && local.flags() == flags
+                {
+                    return index;
+                }
+            }
+            let name = bundle.cnames_mut().add(name);
+            bundle.define(Local::new(name, function, typ, flags))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Label {
+    Instr(usize),
+    Relative(i16),
+}
+
+#[derive(Debug)]
+struct Line<'s> {
+    number: usize,
+    tokens: Vec<&'s str>,
+}
+
+impl<'s> Line<'s> {
+    fn new(number: usize, text: &'s str) -> Result<Self> {
+        let mut tokens = vec![];
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let mut chars = rest.char_indices();
+            let mut end = rest.len();
+            let mut quoted = false;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' if quoted => {
+                        chars.next();
+                    }
+                    '"' => quoted = !quoted,
+                    c if c.is_whitespace() && !quoted => {
+                        end = i;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            if quoted {
+                return Err(Error::InvalidSyntax(number, "unterminated string"));
+            }
+            tokens.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        Ok(Self { number, tokens })
+    }
+
+    fn label(&self) -> Option<&'s str> {
+        match self.tokens[..] {
+            [token] => token.strip_suffix(':'),
+            _ => None,
+        }
+    }
+
+    fn args(&self) -> Args<'_, 's> {
+        Args {
+            number: self.number,
+            tokens: self.tokens[1..].iter(),
+        }
+    }
+
+    fn invalid(&self, message: &'static str) -> Error {
+        Error::InvalidSyntax(self.number, message)
+    }
+
+    fn unresolved(&self, kind: &'static str, symbol: &SymbolRef) -> Error {
+        Error::UnresolvedSymbol(self.number, kind, symbol.to_string())
+    }
+}
+
+#[derive(Debug)]
+struct Args<'l, 's> {
+    number: usize,
+    tokens: std::slice::Iter<'l, &'s str>,
+}
+
+impl<'s> Args<'_, 's> {
+    fn next(&mut self) -> Result<&'s str> {
+        self.try_next()
+            .ok_or(Error::InvalidSyntax(self.number, "missing operand"))
+    }
+
+    fn try_next(&mut self) -> Option<&'s str> {
+        self.tokens.next().copied()
+    }
+
+    fn parse<A: FromStr>(&mut self) -> Result<A> {
+        self.next()?
+            .parse()
+            .map_err(|_| Error::InvalidSyntax(self.number, "invalid operand"))
+    }
+
+    fn symbol(&mut self) -> Result<SymbolRef> {
+        parse_symbol(self.number, self.next()?)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let token = self.next()?;
+        match parse_quoted(token) {
+            Some((str, "")) => Ok(str),
+            _ if token == "<invalid" => {
+                Err(Error::InvalidSyntax(self.number, "missing pool entry"))
+            }
+            _ => Err(Error::InvalidSyntax(self.number, "expected a string")),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let token = self.next()?;
+        parse_quoted_bytes(token).ok_or(Error::InvalidSyntax(self.number, "expected a string"))
+    }
+
+    fn line_number(&mut self) -> Result<u16> {
+        self.next()?
+            .strip_prefix('l')
+            .and_then(|line| line.parse().ok())
+            .ok_or(Error::InvalidSyntax(self.number, "invalid line number"))
+    }
+
+    fn invoke_flags(&mut self) -> Result<InvokeFlags> {
+        let token = self.next()?;
+        if token.len() != 16 || !token.bytes().all(|b| matches!(b, b'0' | b'1')) {
+            return Err(Error::InvalidSyntax(self.number, "invalid invoke flags"));
+        }
+        let mut flags = InvokeFlags::default();
+        for (nth, bit) in token.bytes().rev().enumerate() {
+            if bit == b'1' {
+                flags.set_is_rvalue_ref(nth as u8);
+            }
+        }
+        Ok(flags)
+    }
+
+    fn end(mut self) -> Result<()> {
+        match self.tokens.next() {
+            Some(_) => Err(Error::InvalidSyntax(self.number, "unexpected operand")),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SymbolRef {
+    Index(u32),
+    Name(String),
+    Member(String, String),
+}
+
+impl fmt::Display for SymbolRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolRef::Index(index) => write!(f, "#{index}"),
+            SymbolRef::Name(name) => write!(f, "{name}"),
+            SymbolRef::Member(parent, name) => write!(f, "{parent}.{name}"),
+        }
+    }
+}
+
+fn parse_symbol(number: usize, token: &str) -> Result<SymbolRef> {
+    let invalid = || Error::InvalidSyntax(number, "invalid symbol");
+    if let Some(index) = token.strip_prefix('#') {
+        return index.parse().map(SymbolRef::Index).map_err(|_| invalid());
+    }
+
+    let mut segments = vec![];
+    let mut rest = token;
+    loop {
+        let (segment, tail) = if rest.starts_with('"') {
+            parse_quoted(rest).ok_or_else(invalid)?
+        } else {
+            let end = rest.find(|c: char| !is_ident_char(c)).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            (rest[..end].to_owned(), &rest[end..])
+        };
+        segments.push(segment);
+        match tail.strip_prefix('.') {
+            Some(tail) => rest = tail,
+            None if tail.is_empty() => break,
+            None => return Err(invalid()),
+        }
+    }
+
+    let mut segments = segments.into_iter();
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some(name), None, None) => Ok(SymbolRef::Name(name)),
+        (Some(parent), Some(name), None) => Ok(SymbolRef::Member(parent, name)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_quoted(str: &str) -> Option<(String, &str)> {
+    let body = str.strip_prefix('"')?;
+    let mut chars = body.char_indices();
+    let mut res = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((res, &body[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => res.push('\n'),
+                'r' => res.push('\r'),
+                't' => res.push('\t'),
+                '0' => res.push('\0'),
+                c @ ('\\' | '"' | '\'') => res.push(c),
+                'u' => {
+                    let hex = body[i + 2..].strip_prefix('{')?;
+                    let end = hex.find('}')?;
+                    res.push(char::from_u32(u32::from_str_radix(&hex[..end], 16).ok()?)?);
+                    chars.nth(end + 1)?;
+                }
+                _ => return None,
+            },
+            c => res.push(c),
+        }
+    }
+    None
+}
+
+// parses a string with the escapes produced by `<[u8]>::escape_ascii`
+fn parse_quoted_bytes(str: &str) -> Option<Vec<u8>> {
+    let body = str.strip_prefix('"')?.strip_suffix('"')?;
+    let mut bytes = body.bytes();
+    let mut res = vec![];
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\\' => match bytes.next()? {
+                b'n' => res.push(b'\n'),
+                b'r' => res.push(b'\r'),
+                b't' => res.push(b'\t'),
+                b @ (b'\\' | b'"' | b'\'') => res.push(b),
+                b'x' => {
+                    let hex = [bytes.next()?, bytes.next()?];
+                    res.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                }
+                _ => return None,
+            },
+            b'"' => return None,
+            b => res.push(b),
+        }
+    }
+    Some(res)
+}
+
+#[inline]
+fn unique<I: Copy>(lookup: Option<&Lookup<I>>) -> Option<I> {
+    match lookup? {
+        Lookup::Unique(index) => Some(*index),
+        Lookup::Ambiguous => None,
+    }
+}
+
+#[inline]
+fn cname<'a>(bundle: &'a ScriptBundle<'_>, index: CNameIndex) -> &'a str {
+    bundle.cnames().get(index).map_or("", |str| &**str)
+}
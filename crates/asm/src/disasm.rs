@@ -0,0 +1,631 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use hashbrown::HashMap;
+use redscript_io::{
+    Function, FunctionIndex, IndexedDefinition, Instr, LocalIndex, Offset, ParameterIndex,
+    ScriptBundle, Visibility,
+};
+
+use crate::names::{Ident, Quoted, Symbol, SymbolNames};
+use crate::{Error, Result};
+
+#[derive(Debug)]
+pub struct Disassembler<'a, 'i> {
+    names: SymbolNames<'a, 'i>,
+}
+
+impl<'a, 'i> Disassembler<'a, 'i> {
+    pub fn new(bundle: &'a ScriptBundle<'i>) -> Self {
+        Self {
+            names: SymbolNames::new(bundle),
+        }
+    }
+
+    /// Prints symbols that share their name with another symbol by name instead of by
+    /// pool index. Such listings can't be assembled back, but they no longer depend on the
+    /// layout of the pools, which makes them suitable for comparing different bundles.
+    pub fn with_ambiguous_names(mut self) -> Self {
+        self.names.set_ambiguous_names(true);
+        self
+    }
+
+    pub fn listings<'d>(
+        &'d self,
+        filter: &'d Filter,
+    ) -> impl Iterator<Item = Result<FunctionListing<'d, 'a, 'i>>> + use<'d, 'a, 'i> {
+        self.names
+            .bundle()
+            .indexed_definitions()
+            .filter_map(move |def| {
+                let IndexedDefinition::Function(index, func) = def else {
+                    return None;
+                };
+                let class = func.class().and_then(|class| self.names.class_name(class));
+                filter
+                    .matches(class, self.names.cname(func.name()))
+                    .then_some(index)
+            })
+            .map(|index| self.function(index))
+    }
+
+    pub fn function(&self, index: FunctionIndex) -> Result<FunctionListing<'_, 'a, 'i>> {
+        let function = self
+            .names
+            .bundle()
+            .get_item(index)
+            .ok_or(Error::UndefinedFunction(index.into()))?;
+        let code = function
+            .body()
+            .code_iter()
+            .with_offsets()
+            .map(|(offset, instr)| Ok((offset, instr?)))
+            .collect::<Result<Vec<_>>>()?;
+        let end = code.last().map_or(0, |(offset, instr)| {
+            offset + u32::from(instr.virtual_size())
+        });
+
+        let targets = code
+            .iter()
+            .flat_map(|(offset, instr)| {
+                jump_offsets(instr)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|rel| absolute(*offset, rel))
+            })
+            .filter(|&loc| {
+                loc == end
+                    || code
+                        .binary_search_by_key(&loc, |&(offset, _)| offset)
+                        .is_ok()
+            })
+            .collect::<BTreeSet<_>>();
+        let labels = targets
+            .into_iter()
+            .enumerate()
+            .map(|(i, loc)| (loc, i))
+            .collect();
+
+        Ok(FunctionListing {
+            names: &self.names,
+            index,
+            function,
+            code,
+            labels,
+            end,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Filter {
+    class: Option<String>,
+    function: Option<String>,
+}
+
+impl Filter {
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    pub fn function(mut self, function: impl Into<String>) -> Self {
+        self.function = Some(function.into());
+        self
+    }
+
+    fn matches(&self, class: Option<&str>, function: &str) -> bool {
+        let class_matches = match &self.class {
+            Some(expected) => class == Some(expected.as_str()),
+            None => true,
+        };
+        let function_matches = match &self.function {
+            Some(expected) => {
+                let (short, _) = function.split_once(';').unwrap_or((function, ""));
+                function == expected || short == expected
+            }
+            None => true,
+        };
+        class_matches && function_matches
+    }
+}
+
+#[derive(Debug)]
+pub struct FunctionListing<'d, 'a, 'i> {
+    names: &'d SymbolNames<'a, 'i>,
+    index: FunctionIndex,
+    function: &'a Function<'i>,
+    code: Vec<(u32, Instr)>,
+    labels: HashMap<u32, usize>,
+    end: u32,
+}
+
+impl<'d, 'a, 'i> FunctionListing<'d, 'a, 'i> {
+    #[inline]
+    pub fn code(&self) -> CodeListing<'_, 'd, 'a, 'i> {
+        CodeListing(self)
+    }
+
+    fn fmt_header(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bundle = self.names.bundle();
+        writeln!(f, ".func {}", self.names.function(self.index))?;
+
+        if let Some(source) = self.function.source() {
+            match bundle.get_item(source.file()) {
+                Some(file) => writeln!(f, ".source {} {}", Quoted(file.path()), source.line())?,
+                None => writeln!(f, ".source #{} {}", source.file(), source.line())?,
+            }
+        }
+
+        let flags = self.function.flags();
+        let visibility = match self.function.visibility() {
+            Visibility::Public => None,
+            Visibility::Protected => Some("protected"),
+            Visibility::Private => Some("private"),
+        };
+        let names = [
+            (flags.is_static(), "static"),
+            (flags.is_final(), "final"),
+            (flags.is_native(), "native"),
+            (flags.is_exec(), "exec"),
+            (flags.is_timer(), "timer"),
+            (flags.is_callback(), "callback"),
+            (flags.is_implicit_cast(), "implicit_cast"),
+            (flags.is_const(), "const"),
+            (flags.is_thread_safe(), "thread_safe"),
+            (flags.is_quest(), "quest"),
+        ];
+        let mut qualifiers = visibility
+            .into_iter()
+            .chain(names.into_iter().filter(|&(set, _)| set).map(|(_, n)| n))
+            .peekable();
+        if qualifiers.peek().is_some() {
+            write!(f, ".flags")?;
+            qualifiers.try_for_each(|name| write!(f, " {name}"))?;
+            writeln!(f)?;
+        }
+
+        for &index in self.function.parameters() {
+            let Some(param) = bundle.get_item(index) else {
+                writeln!(f, ".param #{index}")?;
+                continue;
+            };
+            let name = self.names.cname(param.name());
+            write!(
+                f,
+                ".param {} {}",
+                Ident(name),
+                self.names.type_(param.type_())
+            )?;
+            let flags = param.flags();
+            let names = [
+                (flags.is_optional(), "optional"),
+                (flags.is_out(), "out"),
+                (flags.is_short_circuit(), "short_circuit"),
+                (flags.is_const(), "const"),
+            ];
+            for (_, name) in names.into_iter().filter(|&(set, _)| set) {
+                write!(f, " {name}")?;
+            }
+            writeln!(f)?;
+        }
+
+        for &index in self.function.locals() {
+            let Some(local) = bundle.get_item(index) else {
+                writeln!(f, ".local #{index}")?;
+                continue;
+            };
+            let name = self.names.cname(local.name());
+            write!(
+                f,
+                ".local {} {}",
+                Ident(name),
+                self.names.type_(local.type_())
+            )?;
+            if local.flags().is_const() {
+                write!(f, " const")?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(typ) = self.function.return_type() {
+            write!(f, ".return {}", self.names.type_(typ))?;
+            if self.function.is_const_return() {
+                write!(f, " const")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_code(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (offset, instr) in &self.code {
+            if let Some(label) = self.labels.get(offset) {
+                writeln!(f, "L{label}:")?;
+            }
+            write!(f, "  ")?;
+            self.fmt_instr(f, *offset, instr)?;
+            writeln!(f)?;
+        }
+        if let Some(label) = self.labels.get(&self.end) {
+            writeln!(f, "L{label}:")?;
+        }
+        Ok(())
+    }
+
+    fn fmt_instr(&self, f: &mut fmt::Formatter<'_>, offset: u32, instr: &Instr) -> fmt::Result {
+        let names = self.names;
+        write!(f, "{}", mnemonic(instr))?;
+
+        match instr {
+            Instr::I8Const(val) => write!(f, " {val}"),
+            Instr::I16Const(val) => write!(f, " {val}"),
+            Instr::I32Const(val) => write!(f, " {val}"),
+            Instr::I64Const(val) => write!(f, " {val}"),
+            Instr::U8Const(val) => write!(f, " {val}"),
+            Instr::U16Const(val) => write!(f, " {val}"),
+            Instr::U32Const(val) => write!(f, " {val}"),
+            Instr::U64Const(val) => write!(f, " {val}"),
+            Instr::F32Const(val) if val.is_nan() => write!(f, " nan:0x{:08x}", val.to_bits()),
+            Instr::F32Const(val) => write!(f, " {val:?}"),
+            Instr::F64Const(val) if val.is_nan() => write!(f, " nan:0x{:016x}", val.to_bits()),
+            Instr::F64Const(val) => write!(f, " {val:?}"),
+            // missing pool entries are printed in a form that the assembler rejects
+            Instr::CNameConst(idx) => match names.bundle().cnames().get(*idx) {
+                Some(str) => write!(f, " {}", Quoted(str)),
+                None => write!(f, " <invalid #{idx}>"),
+            },
+            Instr::EnumConst { enum_, value } => match names.enum_value(*enum_, *value) {
+                Some(symbol) => write!(f, " {symbol}"),
+                None => write!(f, " #{enum_} #{value}"),
+            },
+            Instr::StringConst(idx) => match names.bundle().strings().get(*idx) {
+                Some(str) => write!(f, " {}", Quoted(str)),
+                None => write!(f, " <invalid #{idx}>"),
+            },
+            Instr::TweakDbIdConst(idx) => match names.bundle().tdb_ids().get(*idx) {
+                Some(str) => write!(f, " {}", Quoted(str)),
+                None => write!(f, " <invalid #{idx}>"),
+            },
+            Instr::ResourceConst(idx) => match names.bundle().resources().get(*idx) {
+                Some(str) => write!(f, " {}", Quoted(str)),
+                None => write!(f, " <invalid #{idx}>"),
+            },
+            Instr::Breakpoint(bp) => {
+                write!(
+                    f,
+                    " l{} {} {} {} {}",
+                    bp.line(),
+                    bp.line_start(),
+                    bp.col(),
+                    bp.length(),
+                    bp.is_enabled()
+                )?;
+                match u64::from_be_bytes(bp.padding()) {
+                    0 => Ok(()),
+                    padding => write!(f, " {padding:016x}"),
+                }
+            }
+            Instr::Target(loc) => write!(f, " {}", self.label(offset, *loc)),
+            Instr::Local(idx) => write!(f, " {}", self.local(*idx)),
+            Instr::Param(idx) => write!(f, " {}", self.param(*idx)),
+            Instr::ObjectField(idx) | Instr::StructField(idx) => {
+                write!(f, " {}", names.field(*idx))
+            }
+            Instr::Switch(switch) => write!(
+                f,
+                " {} {}",
+                names.type_(switch.expr_type()),
+                self.label(offset, switch.first_case())
+            ),
+            Instr::SwitchLabel(label) => write!(
+                f,
+                " {} {}",
+                self.label(offset, label.next_case()),
+                self.label(offset, label.body())
+            ),
+            Instr::Jump(jump)
+            | Instr::JumpIfFalse(jump)
+            | Instr::Skip(jump)
+            | Instr::Context(jump) => {
+                write!(f, " {}", self.label(offset, jump.target()))
+            }
+            Instr::Conditional(cond) => write!(
+                f,
+                " {} {}",
+                self.label(offset, cond.false_label()),
+                self.label(offset, cond.exit())
+            ),
+            Instr::Construct { arg_count, class } => {
+                write!(f, " {arg_count} {}", names.class(*class))
+            }
+            Instr::InvokeStatic {
+                exit,
+                line,
+                function,
+                flags,
+            } => write!(
+                f,
+                " {} l{line} {} {flags}",
+                self.label(offset, exit.target()),
+                names.function(*function)
+            ),
+            Instr::InvokeVirtual {
+                exit,
+                line,
+                function,
+                flags,
+            } => write!(
+                f,
+                " {} l{line} {} {flags}",
+                self.label(offset, exit.target()),
+                Quoted(names.cname(*function))
+            ),
+            Instr::New(class) | Instr::DynamicCast { class, .. } => {
+                write!(f, " {}", names.class(*class))
+            }
+            Instr::Profile(profile) => write!(
+                f,
+                " \"{}\" {}",
+                profile.function().escape_ascii(),
+                profile.is_enabled()
+            ),
+            Instr::EnumToI32 { enum_type, size } | Instr::I32ToEnum { enum_type, size } => {
+                write!(f, " {} {size}", names.type_(*enum_type))
+            }
+            Instr::Equals(typ)
+            | Instr::RefStringEqualsString(typ)
+            | Instr::StringEqualsRefString(typ)
+            | Instr::NotEquals(typ)
+            | Instr::RefStringNotEqualsString(typ)
+            | Instr::StringNotEqualsRefString(typ)
+            | Instr::ArrayClear(typ)
+            | Instr::ArraySize(typ)
+            | Instr::ArrayResize(typ)
+            | Instr::ArrayFindFirst(typ)
+            | Instr::ArrayFindFirstFast(typ)
+            | Instr::ArrayFindLast(typ)
+            | Instr::ArrayFindLastFast(typ)
+            | Instr::ArrayContains(typ)
+            | Instr::ArrayContainsFast(typ)
+            | Instr::ArrayCount(typ)
+            | Instr::ArrayCountFast(typ)
+            | Instr::ArrayPush(typ)
+            | Instr::ArrayPop(typ)
+            | Instr::ArrayInsert(typ)
+            | Instr::ArrayRemove(typ)
+            | Instr::ArrayRemoveFast(typ)
+            | Instr::ArrayGrow(typ)
+            | Instr::ArrayErase(typ)
+            | Instr::ArrayEraseFast(typ)
+            | Instr::ArrayLast(typ)
+            | Instr::ArrayElement(typ)
+            | Instr::ArraySort(typ)
+            | Instr::ArraySortByPredicate(typ)
+            | Instr::StaticArraySize(typ)
+            | Instr::StaticArrayFindFirst(typ)
+            | Instr::StaticArrayFindFirstFast(typ)
+            | Instr::StaticArrayFindLast(typ)
+            | Instr::StaticArrayFindLastFast(typ)
+            | Instr::StaticArrayContains(typ)
+            | Instr::StaticArrayContainsFast(typ)
+            | Instr::StaticArrayCount(typ)
+            | Instr::StaticArrayCountFast(typ)
+            | Instr::StaticArrayLast(typ)
+            | Instr::StaticArrayElement(typ)
+            | Instr::ToString(typ)
+            | Instr::ToVariant(typ)
+            | Instr::FromVariant(typ)
+            | Instr::AsRef(typ)
+            | Instr::Deref(typ) => write!(f, " {}", names.type_(*typ)),
+            _ => Ok(()),
+        }
+    }
+
+    fn label(&self, offset: u32, rel: Offset) -> LabelRef {
+        absolute(offset, rel)
+            .and_then(|loc| self.labels.get(&loc))
+            .map_or(LabelRef::Relative(rel.into()), |&label| {
+                LabelRef::Label(label)
+            })
+    }
+
+    fn local(&self, index: LocalIndex) -> Symbol<'a> {
+        let locals = self.function.locals();
+        let name = |idx: LocalIndex| {
+            self.names
+                .bundle()
+                .get_item(idx)
+                .map(|local| self.names.cname(local.name()))
+        };
+        scoped(index, locals, name)
+    }
+
+    fn param(&self, index: ParameterIndex) -> Symbol<'a> {
+        let params = self.function.parameters();
+        let name = |idx: ParameterIndex| {
+            self.names
+                .bundle()
+                .get_item(idx)
+                .map(|param| self.names.cname(param.name()))
+        };
+        scoped(index, params, name)
+    }
+}
+
+impl fmt::Display for FunctionListing<'_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_header(f)?;
+        self.fmt_code(f)
+    }
+}
+
+#[derive(Debug)]
+pub struct CodeListing<'l, 'd, 'a, 'i>(&'l FunctionListing<'d, 'a, 'i>);
+
+impl fmt::Display for CodeListing<'_, '_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_code(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LabelRef {
+    Label(usize),
+    Relative(i16),
+}
+
+impl fmt::Display for LabelRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelRef::Label(label) => write!(f, "L{label}"),
+            LabelRef::Relative(offset) => write!(f, "{offset:+}"),
+        }
+    }
+}
+
+fn scoped<'a, I>(index: I, scope: &[I], name: impl Fn(I) -> Option<&'a str>) -> Symbol<'a>
+where
+    I: Copy + PartialEq + Into<u32>,
+{
+    let Some(str) = scope.contains(&index).then(|| name(index)).flatten() else {
+        return Symbol::Index(index.into());
+    };
+    let count = scope.iter().filter(|&&i| name(i) == Some(str)).count();
+    if count == 1 {
+        Symbol::Global(str)
+    } else {
+        Symbol::Index(index.into())
+    }
+}
+
+#[inline]
+fn absolute(offset: u32, rel: Offset) -> Option<u32> {
+    offset.checked_add_signed(i32::from(rel))
+}
+
+fn jump_offsets(instr: &Instr) -> [Option<Offset>; 2] {
+    match instr {
+        Instr::Target(loc) => [Some(*loc), None],
+        Instr::Switch(switch) => [Some(switch.first_case()), None],
+        Instr::SwitchLabel(label) => [Some(label.next_case()), Some(label.body())],
+        Instr::Jump(jump)
+        | Instr::JumpIfFalse(jump)
+        | Instr::Skip(jump)
+        | Instr::Context(jump)
+        | Instr::InvokeStatic { exit: jump, .. }
+        | Instr::InvokeVirtual { exit: jump, .. } => [Some(jump.target()), None],
+        Instr::Conditional(cond) => [Some(cond.false_label()), Some(cond.exit())],
+        _ => [None, None],
+    }
+}
+
+pub(crate) fn mnemonic<L>(instr: &Instr<L>) -> &'static str {
+    match instr {
+        Instr::Nop => "nop",
+        Instr::Null => "ref.null",
+        Instr::I32One => "i32.one",
+        Instr::I32Zero => "i32.zero",
+        Instr::I8Const(_) => "i8.const",
+        Instr::I16Const(_) => "i16.const",
+        Instr::I32Const(_) => "i32.const",
+        Instr::I64Const(_) => "i64.const",
+        Instr::U8Const(_) => "u8.const",
+        Instr::U16Const(_) => "u16.const",
+        Instr::U32Const(_) => "u32.const",
+        Instr::U64Const(_) => "u64.const",
+        Instr::F32Const(_) => "f32.const",
+        Instr::F64Const(_) => "f64.const",
+        Instr::CNameConst(_) => "cname.const",
+        Instr::EnumConst { .. } => "enum.const",
+        Instr::StringConst(_) => "string.const",
+        Instr::TweakDbIdConst(_) => "tweakdb.const",
+        Instr::ResourceConst(_) => "resource.const",
+        Instr::TrueConst => "true.const",
+        Instr::FalseConst => "false.const",
+        Instr::Breakpoint(_) => "breakpoint",
+        Instr::Assign => "assign",
+        Instr::Target(_) => "target",
+        Instr::Local(_) => "local",
+        Instr::Param(_) => "param",
+        Instr::ObjectField(_) => "object.field",
+        Instr::ExternalVar => "external",
+        Instr::Switch(_) => "switch",
+        Instr::SwitchLabel(_) => "switch.label",
+        Instr::SwitchDefault => "switch.default",
+        Instr::Jump(_) => "jump",
+        Instr::JumpIfFalse(_) => "jump.if_not",
+        Instr::Skip(_) => "skip",
+        Instr::Conditional(_) => "cond",
+        Instr::Construct { .. } => "struct.new",
+        Instr::InvokeStatic { .. } => "invoke.static",
+        Instr::InvokeVirtual { .. } => "invoke.virtual",
+        Instr::ParamEnd => "param.end",
+        Instr::Return => "return",
+        Instr::StructField(_) => "struct.field",
+        Instr::Context(_) => "ctx",
+        Instr::Equals(_) => "eq",
+        Instr::RefStringEqualsString(_) => "refstr.eq",
+        Instr::StringEqualsRefString(_) => "str.eq",
+        Instr::NotEquals(_) => "neq",
+        Instr::RefStringNotEqualsString(_) => "refstr.neq",
+        Instr::StringNotEqualsRefString(_) => "str.neq",
+        Instr::New(_) => "object.new",
+        Instr::Delete => "object.delete",
+        Instr::This => "this",
+        Instr::Profile(_) => "profile",
+        Instr::ArrayClear(_) => "array.clear",
+        Instr::ArraySize(_) => "array.size",
+        Instr::ArrayResize(_) => "array.resize",
+        Instr::ArrayFindFirst(_) => "array.find_first",
+        Instr::ArrayFindFirstFast(_) => "array.find_first_fast",
+        Instr::ArrayFindLast(_) => "array.find_last",
+        Instr::ArrayFindLastFast(_) => "array.find_last_fast",
+        Instr::ArrayContains(_) => "array.contains",
+        Instr::ArrayContainsFast(_) => "array.contains_fast",
+        Instr::ArrayCount(_) => "array.count",
+        Instr::ArrayCountFast(_) => "array.count_fast",
+        Instr::ArrayPush(_) => "array.push",
+        Instr::ArrayPop(_) => "array.pop",
+        Instr::ArrayInsert(_) => "array.insert",
+        Instr::ArrayRemove(_) => "array.remove",
+        Instr::ArrayRemoveFast(_) => "array.remove_fast",
+        Instr::ArrayGrow(_) => "array.grow",
+        Instr::ArrayErase(_) => "array.erase",
+        Instr::ArrayEraseFast(_) => "array.erase_fast",
+        Instr::ArrayLast(_) => "array.last",
+        Instr::ArrayElement(_) => "array.element",
+        Instr::ArraySort(_) => "array.sort",
+        Instr::ArraySortByPredicate(_) => "array.sort_by",
+        Instr::StaticArraySize(_) => "static_array.size",
+        Instr::StaticArrayFindFirst(_) => "static_array.find_first",
+        Instr::StaticArrayFindFirstFast(_) => "static_array.find_first_fast",
+        Instr::StaticArrayFindLast(_) => "static_array.find_last",
+        Instr::StaticArrayFindLastFast(_) => "static_array.find_last_fast",
+        Instr::StaticArrayContains(_) => "static_array.contains",
+        Instr::StaticArrayContainsFast(_) => "static_array.contains_fast",
+        Instr::StaticArrayCount(_) => "static_array.count",
+        Instr::StaticArrayCountFast(_) => "static_array.count_fast",
+        Instr::StaticArrayLast(_) => "static_array.last",
+        Instr::StaticArrayElement(_) => "static_array.element",
+        Instr::RefToBool => "ref.to_bool",
+        Instr::WeakRefToBool => "wref.to_bool",
+        Instr::EnumToI32 { .. } => "enum.to_int",
+        Instr::I32ToEnum { .. } => "enum.from_int",
+        Instr::DynamicCast { is_weak: true, .. } => "wref.dyncast",
+        Instr::DynamicCast { .. } => "ref.dyncast",
+        Instr::ToString(_) => "to_string",
+        Instr::ToVariant(_) => "variant.new",
+        Instr::FromVariant(_) => "variant.extract",
+        Instr::VariantIsDefined => "variant.is_defined",
+        Instr::VariantIsRef => "variant.is_ref",
+        Instr::VariantIsArray => "variant.is_array",
+        Instr::VariantTypeName => "variant.type_name",
+        Instr::VariantToString => "variant.to_string",
+        Instr::WeakRefToRef => "wref.to_ref",
+        Instr::RefToWeakRef => "ref.to_wref",
+        Instr::WeakRefNull => "wref.null",
+        Instr::AsRef(_) => "as_ref",
+        Instr::Deref(_) => "deref",
+    }
+}
@@ -0,0 +1,35 @@
+pub use assemble::Assembler;
+pub use disasm::{CodeListing, Disassembler, Filter, FunctionListing};
+use thiserror::Error;
+
+mod assemble;
+mod disasm;
+mod names;
+
+pub type Result<A, E = Error> = std::result::Result<A, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Function #{0} is not defined")]
+    UndefinedFunction(u32),
+    #[error("Invalid syntax at line {0}: {1}")]
+    InvalidSyntax(usize, &'static str),
+    #[error("Unknown instruction '{1}' at line {0}")]
+    UnknownMnemonic(usize, String),
+    #[error("Undefined label '{1}' at line {0}")]
+    UndefinedLabel(usize, String),
+    #[error("Unresolved {1} '{2}' at line {0}")]
+    UnresolvedSymbol(usize, &'static str, String),
+    #[error("Ambiguous {1} '{2}' at line {0}")]
+    AmbiguousSymbol(usize, &'static str, String),
+    #[error("Label is too far away from the instruction at line {0}")]
+    LabelTooDistant(usize),
+    #[error("Read error: {0}")]
+    ReadFailure(redscript_io::byte::Error),
+}
+
+impl From<redscript_io::byte::Error> for Error {
+    fn from(err: redscript_io::byte::Error) -> Self {
+        Error::ReadFailure(err)
+    }
+}
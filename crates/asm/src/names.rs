@@ -0,0 +1,221 @@
+use std::fmt;
+use std::hash::Hash;
+
+use hashbrown::HashMap;
+use redscript_io::{
+    CNameIndex, ClassIndex, EnumIndex, EnumValueIndex, FieldIndex, FunctionIndex,
+    IndexedDefinition, ScriptBundle, TypeIndex,
+};
+
+#[derive(Debug)]
+pub(crate) struct SymbolNames<'a, 'i> {
+    bundle: &'a ScriptBundle<'i>,
+    types: HashMap<&'a str, Lookup<TypeIndex>>,
+    classes: HashMap<&'a str, Lookup<ClassIndex>>,
+    enum_values: HashMap<(&'a str, &'a str), Lookup<EnumValueIndex>>,
+    functions: HashMap<(Option<&'a str>, &'a str), Lookup<FunctionIndex>>,
+    fields: HashMap<(&'a str, &'a str), Lookup<FieldIndex>>,
+    ambiguous_names: bool,
+}
+
+impl<'a, 'i> SymbolNames<'a, 'i> {
+    pub fn new(bundle: &'a ScriptBundle<'i>) -> Self {
+        let mut this = Self {
+            bundle,
+            types: HashMap::new(),
+            classes: HashMap::new(),
+            enum_values: HashMap::new(),
+            functions: HashMap::new(),
+            fields: HashMap::new(),
+            ambiguous_names: false,
+        };
+
+        for def in bundle.indexed_definitions() {
+            match def {
+                IndexedDefinition::Type(i, typ) => {
+                    let name = this.cname(typ.name());
+                    insert(&mut this.types, name, i);
+                }
+                IndexedDefinition::Class(i, class) => {
+                    let name = this.cname(class.name());
+                    insert(&mut this.classes, name, i);
+                }
+                IndexedDefinition::EnumMember(i, member) => {
+                    let Some(enum_) = this.enum_name(member.enum_()) else {
+                        continue;
+                    };
+                    let name = this.cname(member.name());
+                    insert(&mut this.enum_values, (enum_, name), i);
+                }
+                IndexedDefinition::Function(i, func) => {
+                    let class = match func.class() {
+                        Some(class) => match this.class_name(class) {
+                            Some(name) => Some(name),
+                            None => continue,
+                        },
+                        None => None,
+                    };
+                    let name = this.cname(func.name());
+                    insert(&mut this.functions, (class, name), i);
+                }
+                IndexedDefinition::Field(i, field) => {
+                    let Some(class) = this.class_name(field.class()) else {
+                        continue;
+                    };
+                    let name = this.cname(field.name());
+                    insert(&mut this.fields, (class, name), i);
+                }
+                _ => {}
+            }
+        }
+        this
+    }
+
+    /// Makes symbols that are not unique print their names instead of their indices.
+    #[inline]
+    pub fn set_ambiguous_names(&mut self, ambiguous_names: bool) {
+        self.ambiguous_names = ambiguous_names;
+    }
+
+    #[inline]
+    pub fn bundle(&self) -> &'a ScriptBundle<'i> {
+        self.bundle
+    }
+
+    #[inline]
+    pub fn cname(&self, index: CNameIndex) -> &'a str {
+        self.bundle.cnames().get(index).map_or("", |str| &**str)
+    }
+
+    pub fn class_name(&self, index: ClassIndex) -> Option<&'a str> {
+        Some(self.cname(self.bundle.get_item(index)?.name()))
+    }
+
+    pub fn enum_name(&self, index: EnumIndex) -> Option<&'a str> {
+        Some(self.cname(self.bundle.get_item(index)?.name()))
+    }
+
+    pub fn type_(&self, index: TypeIndex) -> Symbol<'a> {
+        self.bundle
+            .get_item(index)
+            .map(|typ| self.cname(typ.name()))
+            .filter(|&name| self.is_named(&self.types, &name, index))
+            .map_or(Symbol::Index(index.into()), Symbol::Global)
+    }
+
+    pub fn class(&self, index: ClassIndex) -> Symbol<'a> {
+        self.class_name(index)
+            .filter(|&name| self.is_named(&self.classes, &name, index))
+            .map_or(Symbol::Index(index.into()), Symbol::Global)
+    }
+
+    pub fn enum_value(&self, enum_: EnumIndex, index: EnumValueIndex) -> Option<Symbol<'a>> {
+        let member = self.bundle.get_item(index)?;
+        if member.enum_() != enum_ {
+            return None;
+        }
+        let key = (self.enum_name(enum_)?, self.cname(member.name()));
+        self.is_named(&self.enum_values, &key, index)
+            .then_some(Symbol::Member(key.0, key.1))
+    }
+
+    pub fn function(&self, index: FunctionIndex) -> Symbol<'a> {
+        let Some(func) = self.bundle.get_item(index) else {
+            return Symbol::Index(index.into());
+        };
+        let class = match func.class() {
+            Some(class) => match self.class_name(class) {
+                Some(name) => Some(name),
+                None => return Symbol::Index(index.into()),
+            },
+            None => None,
+        };
+        let key = (class, self.cname(func.name()));
+        match key {
+            _ if !self.is_named(&self.functions, &key, index) => Symbol::Index(index.into()),
+            (Some(class), name) => Symbol::Member(class, name),
+            (None, name) => Symbol::Global(name),
+        }
+    }
+
+    pub fn field(&self, index: FieldIndex) -> Symbol<'a> {
+        let Some(field) = self.bundle.get_item(index) else {
+            return Symbol::Index(index.into());
+        };
+        self.class_name(field.class())
+            .map(|class| (class, self.cname(field.name())))
+            .filter(|key| self.is_named(&self.fields, key, index))
+            .map_or(Symbol::Index(index.into()), |(class, name)| {
+                Symbol::Member(class, name)
+            })
+    }
+
+    fn is_named<K: Eq + Hash, I: PartialEq>(
+        &self,
+        map: &HashMap<K, Lookup<I>>,
+        key: &K,
+        index: I,
+    ) -> bool {
+        self.ambiguous_names || is_unique(map, key, index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lookup<I> {
+    Unique(I),
+    Ambiguous,
+}
+
+pub(crate) fn insert<K: Eq + Hash, I>(map: &mut HashMap<K, Lookup<I>>, key: K, index: I) {
+    map.entry(key)
+        .and_modify(|entry| *entry = Lookup::Ambiguous)
+        .or_insert(Lookup::Unique(index));
+}
+
+fn is_unique<K: Eq + Hash, I: PartialEq>(map: &HashMap<K, Lookup<I>>, key: &K, index: I) -> bool {
+    map.get(key) == Some(&Lookup::Unique(index))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Symbol<'a> {
+    Global(&'a str),
+    Member(&'a str, &'a str),
+    Index(u32),
+}
+
+impl fmt::Display for Symbol<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Symbol::Global(name) => write!(f, "{}", Ident(name)),
+            Symbol::Member(parent, name) => write!(f, "{}.{}", Ident(parent), Ident(name)),
+            Symbol::Index(index) => write!(f, "#{index}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ident<'a>(pub &'a str);
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.0.is_empty() && self.0.chars().all(is_ident_char) {
+            f.write_str(self.0)
+        } else {
+            write!(f, "{}", Quoted(self.0))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Quoted<'a>(pub &'a str);
+
+impl fmt::Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+#[inline]
+pub(crate) fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | ';')
+}
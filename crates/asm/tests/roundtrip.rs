@@ -0,0 +1,128 @@
+use redscript_asm::{Assembler, Disassembler, Error, Filter};
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Compilation, SourceMapExt, TypeInterner};
+use redscript_io::{
+    Breakpoint, Class, ClassFlags, Definition, Function, FunctionFlags, Instr, Profile,
+    ScriptBundle, Visibility,
+};
+
+#[test]
+fn roundtrip() {
+    insta::glob!("data/*.reds", |path| {
+        let sources = SourceMap::from_files([path]).unwrap();
+        sources.populate_boot_lib();
+
+        let interner = TypeInterner::default();
+        let mut bundle = ScriptBundle::default();
+        let name = bundle.cnames_mut().add("IScriptable");
+        bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+        let base = bundle.into_writeable().to_bytes().unwrap();
+
+        let (bundle, _, _) = Compilation::new(&base, &sources, &interner)
+            .unwrap()
+            .finish()
+            .unwrap_or_else(|err| panic!("{err:?}: {}", path.display()));
+        let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+        let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+        let disasm = Disassembler::new(&bundle);
+        let listing = disasm
+            .listings(&Filter::default())
+            .map(|listing| listing.unwrap().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // reset the functions to their bare definitions, so that their flags, visibility, source,
+        // parameters, locals, return type and code all have to be assembled from the listing
+        let mut bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+        for def in bundle.definitions_mut() {
+            if let Definition::Function(func) = def {
+                **func = Function::new(func.name(), Visibility::Public, FunctionFlags::default())
+                    .with_class(func.class())
+                    .with_base_method(func.base_method())
+                    .with_operator(func.operator())
+                    .with_cast_cost(func.cast_cost())
+                    .with_source(None);
+            }
+        }
+        Assembler::new(&mut bundle).assemble(&listing).unwrap();
+        let assembled = bundle.into_writeable().to_bytes().unwrap();
+
+        // the parameters and locals are defined anew, so the pools no longer line up with the
+        // original and the listings are compared instead
+        let bundle = ScriptBundle::from_bytes(&assembled).unwrap();
+        let reassembled = Disassembler::new(&bundle)
+            .listings(&Filter::default())
+            .map(|listing| listing.unwrap().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            reassembled,
+            listing,
+            "roundtrip mismatch: {}",
+            path.display()
+        );
+    });
+}
+
+#[test]
+fn roundtrip_debug_instrs() {
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("Test");
+    let code = vec![
+        Instr::Breakpoint(Box::new(
+            Breakpoint::new(1, 0, 2, 3, true).with_padding([1, 2, 3, 4, 5, 6, 7, 8]),
+        )),
+        Instr::Profile(Box::new(Profile::new(
+            b"Test \"loop\" \xff".to_vec(),
+            false,
+        ))),
+        Instr::Nop,
+    ];
+    let function = Function::new(name, Visibility::Public, FunctionFlags::default())
+        .with_source(None)
+        .with_code(code);
+    let index = bundle.define(function);
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let listing = Disassembler::new(&bundle)
+        .function(index)
+        .unwrap()
+        .to_string();
+
+    let mut bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    bundle[index].set_code(vec![]);
+    Assembler::new(&mut bundle).assemble(&listing).unwrap();
+    let assembled = bundle.into_writeable().to_bytes().unwrap();
+    assert!(assembled == bytes, "roundtrip mismatch:\n{listing}");
+}
+
+#[test]
+fn missing_pool_entries_are_not_assembled() {
+    let mut other = ScriptBundle::default();
+    other.strings_mut().add("unused");
+    let missing = other.strings_mut().add("missing");
+
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("Test");
+    let function = Function::new(name, Visibility::Public, FunctionFlags::default())
+        .with_source(None)
+        .with_code(vec![Instr::StringConst(missing)]);
+    let index = bundle.define(function);
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let listing = Disassembler::new(&bundle)
+        .function(index)
+        .unwrap()
+        .to_string();
+    assert!(
+        listing.contains(&format!("string.const <invalid #{missing}>")),
+        "{listing}"
+    );
+
+    let mut bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let err = Assembler::new(&mut bundle).assemble(&listing).unwrap_err();
+    assert!(matches!(err, Error::InvalidSyntax(_, _)), "{err}");
+}
@@ -6,15 +6,20 @@ use std::str::FromStr;
 
 use anyhow::Context;
 use argh::FromArgs;
-use hashbrown::HashMap;
 use mimalloc::MiMalloc;
-use redscript_compiler_api::ast::SourceMap;
+use redscript_asm::{Assembler, Disassembler, Filter};
+use redscript_compiler_api::ast::{self, SourceMap, Span};
+use redscript_compiler_api::cte::{self, Define};
 use redscript_compiler_api::{
-    Compilation, FlushError, ScriptBundle, SourceMapExt, TypeInterner, pass,
+    Compilation, CompileErrorReporter, DiagnosticFormat, FlushError, LintLevel, LintLevels,
+    Profiling, ScriptBundle, SourceMapExt, TypeInterner, configure_threads, parse_files, pass,
 };
-use redscript_decompiler::{Settings, decompile_all};
+use redscript_decompiler::{Settings, format_all};
+use redscript_diff::{BodyFormat, BundleDiff};
+use redscript_doc::{DocFormat, Documentation};
 use redscript_dotfile::Dotfile;
-use redscript_formatter::{FormatCtx, FormatSettings, SyntaxOps, format_document};
+use redscript_formatter::{FormatSettings, format_document};
+use redscript_interp::{Interpreter, Natives, Operators, Value};
 use vmap::Map;
 
 #[global_allocator]
@@ -31,8 +36,13 @@ struct Args {
 #[argh(subcommand)]
 enum Command {
     Decompile(DecompileOpts),
+    Disasm(DisasmOpts),
+    Asm(AsmOpts),
+    Diff(DiffOpts),
     Compile(CompileOpts),
     Lint(LintOpts),
+    Doc(DocOpts),
+    Test(TestOpts),
     Format(FormatOpts),
 }
 
@@ -72,6 +82,60 @@ struct DecompileOpts {
     max_sig_digits: Option<u8>,
 }
 
+/// print a bytecode listing of a .redscripts file
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "disasm")]
+struct DisasmOpts {
+    /// path to an input .redscripts file
+    #[argh(option, short = 'b')]
+    bundle: PathBuf,
+    /// path to an output file, the listing is printed to stdout if omitted
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+    /// only include methods of the class with this name
+    #[argh(option)]
+    class: Option<String>,
+    /// only include functions with this name
+    #[argh(option)]
+    function: Option<String>,
+}
+
+/// assemble a bytecode listing into a .redscripts file
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "asm")]
+struct AsmOpts {
+    /// path to an input listing file
+    #[argh(option, short = 'i')]
+    input: PathBuf,
+    /// path to a .redscripts file to assemble the listing into
+    #[argh(option, short = 'b')]
+    bundle: PathBuf,
+    /// path to an output .redscripts file
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+}
+
+/// compare the definitions of two .redscripts files
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "diff")]
+struct DiffOpts {
+    /// path to the old .redscripts file
+    #[argh(positional)]
+    old: PathBuf,
+    /// path to the new .redscripts file
+    #[argh(positional)]
+    new: PathBuf,
+    /// path to an output file, defaults to stdout
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+    /// compare function bodies as decompiled source instead of bytecode
+    #[argh(switch)]
+    source: bool,
+    /// write the report as JSON
+    #[argh(switch)]
+    json: bool,
+}
+
 /// compile redscript source code into a .redscripts file
 #[derive(Debug, FromArgs)]
 #[argh(subcommand, name = "compile")]
@@ -88,6 +152,36 @@ struct CompileOpts {
     /// enable specific warnings
     #[argh(option, short = 'W')]
     warn_on: Vec<WarnOn>,
+    /// silence diagnostics with the given code
+    #[argh(option)]
+    allow: Vec<String>,
+    /// report diagnostics with the given code as warnings
+    #[argh(option)]
+    warn: Vec<String>,
+    /// report diagnostics with the given code as errors
+    #[argh(option)]
+    deny: Vec<String>,
+    /// define a name for conditional compilation, written as NAME or NAME=value
+    #[argh(option, short = 'D')]
+    define: Vec<Define>,
+    /// the game version returned by GameVersion() in conditions, e.g. 2.12
+    #[argh(option)]
+    game_version: Option<cte::GameVersion>,
+    /// optimize the generated code
+    #[argh(switch, short = 'O')]
+    optimize: bool,
+    /// emit breakpoint instructions for script debuggers
+    #[argh(switch)]
+    breakpoints: bool,
+    /// emit profiling markers: off, functions or loops
+    #[argh(option, default = "Profiling::Disabled")]
+    profile: Profiling,
+    /// number of threads used for parsing, defaults to one per logical CPU
+    #[argh(option)]
+    threads: Option<usize>,
+    /// diagnostic output format: text, json or sarif
+    #[argh(option, default = "DiagnosticFormat::Text")]
+    format: DiagnosticFormat,
 }
 
 /// lint redscript source code
@@ -103,6 +197,57 @@ struct LintOpts {
     /// enable specific warnings
     #[argh(option, short = 'W')]
     warn_on: Vec<WarnOn>,
+    /// silence diagnostics with the given code
+    #[argh(option)]
+    allow: Vec<String>,
+    /// report diagnostics with the given code as warnings
+    #[argh(option)]
+    warn: Vec<String>,
+    /// report diagnostics with the given code as errors
+    #[argh(option)]
+    deny: Vec<String>,
+    /// define a name for conditional compilation, written as NAME or NAME=value
+    #[argh(option, short = 'D')]
+    define: Vec<Define>,
+    /// the game version returned by GameVersion() in conditions, e.g. 2.12
+    #[argh(option)]
+    game_version: Option<cte::GameVersion>,
+    /// diagnostic output format: text, json or sarif
+    #[argh(option, default = "DiagnosticFormat::Text")]
+    format: DiagnosticFormat,
+}
+
+/// generate API reference documentation
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "doc")]
+struct DocOpts {
+    /// path to an input source file or directory
+    #[argh(option, short = 's')]
+    src: Vec<PathBuf>,
+    /// path to a .redscripts file to document along with the sources
+    #[argh(option, short = 'b')]
+    bundle: PathBuf,
+    /// path to an output directory
+    #[argh(option, short = 'o')]
+    output: PathBuf,
+    /// output format: markdown or html
+    #[argh(option, default = "DocFormat::Markdown")]
+    format: DocFormat,
+}
+
+/// run functions annotated with @test in an interpreter
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "test")]
+struct TestOpts {
+    /// path to an input source file or directory
+    #[argh(option, short = 's')]
+    src: Vec<PathBuf>,
+    /// path to an input .redscripts file
+    #[argh(option, short = 'b')]
+    bundle: PathBuf,
+    /// only run tests with names containing this string
+    #[argh(option)]
+    filter: Option<String>,
 }
 
 /// format redscript source code
@@ -141,15 +286,39 @@ struct FormatOpts {
     max_sig_digits: Option<u8>,
 }
 
-#[derive(Debug)]
+impl Command {
+    fn diagnostic_format(&self) -> DiagnosticFormat {
+        match self {
+            Self::Compile(opts) => opts.format,
+            Self::Lint(opts) => opts.format,
+            _ => DiagnosticFormat::Text,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 enum WarnOn {
     UnusedLocals,
+    NullSafety,
+    Exhaustiveness,
 }
 
 impl WarnOn {
+    const ALL: [Self; 3] = [Self::UnusedLocals, Self::NullSafety, Self::Exhaustiveness];
+
+    fn codes(&self) -> &'static [&'static str] {
+        match self {
+            Self::UnusedLocals => &["UNUSED_LOCAL"],
+            Self::NullSafety => &["NULL_ACCESS"],
+            Self::Exhaustiveness => &["NON_EXHAUSTIVE_SWITCH", "DUP_CASE_LABEL"],
+        }
+    }
+
     pub fn to_pass<'ctx>(&self) -> Box<dyn pass::DiagnosticPass<'ctx>> {
         match self {
             Self::UnusedLocals => Box::new(pass::UnusedLocals),
+            Self::NullSafety => Box::new(pass::NullSafety),
+            Self::Exhaustiveness => Box::new(pass::SwitchExhaustiveness),
         }
     }
 }
@@ -160,49 +329,67 @@ impl FromStr for WarnOn {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "unused_locals" => Ok(Self::UnusedLocals),
+            "null_safety" => Ok(Self::NullSafety),
+            "exhaustiveness" => Ok(Self::Exhaustiveness),
             _ => Err(format!("unknown warning: {s}")),
         }
     }
 }
 
 fn main() -> anyhow::Result<ExitCode> {
-    let colors = fern::colors::ColoredLevelConfig::default();
-    fern::Dispatch::new()
-        .format(move |out, message, record| {
-            let level = colors.color(record.level());
-            out.finish(format_args!("[{level}] {message}"));
-        })
-        .chain(std::io::stdout())
-        .apply()?;
-
     let args: Args = argh::from_env();
 
+    let colors = fern::colors::ColoredLevelConfig::default();
+    let dispatch = fern::Dispatch::new().format(move |out, message, record| {
+        let level = colors.color(record.level());
+        out.finish(format_args!("[{level}] {message}"));
+    });
+    // keep stdout clean for machine-readable diagnostics
+    if args.command.diagnostic_format() == DiagnosticFormat::Text {
+        dispatch.chain(std::io::stdout()).apply()?;
+    } else {
+        dispatch.chain(std::io::stderr()).apply()?;
+    }
+
     match args.command {
         Command::Decompile(opts) => decompile(opts),
+        Command::Disasm(opts) => disasm(opts),
+        Command::Asm(opts) => asm(opts),
+        Command::Diff(opts) => diff(opts),
         Command::Compile(opts) => compile(opts),
         Command::Lint(opts) => lint(opts),
+        Command::Doc(opts) => doc(opts),
+        Command::Test(opts) => test(opts),
         Command::Format(opts) => format(opts),
     }
 }
 
 fn compile(opts: CompileOpts) -> anyhow::Result<ExitCode> {
+    if let Some(threads) = opts.threads {
+        configure_threads(threads)?;
+    }
+
     let (map, _f) = Map::with_options().open(opts.bundle)?;
     let interner = TypeInterner::default();
     let sources = load_sources(&opts.src)?;
-    let passes = opts
-        .warn_on
-        .into_iter()
-        .map(|w| w.to_pass())
-        .collect::<Vec<_>>();
-
-    match Compilation::new_with(&map, &sources, &interner, &passes)?.flush(opts.output) {
+    let levels = load_lint_levels(&opts.src, &opts.allow, &opts.warn, &opts.deny)?;
+    let passes = diagnostic_passes(&opts.warn_on, &levels);
+    let defines = load_defines(&opts.src, opts.define)?;
+    let conditions = cte::Conditions::new(defines, opts.game_version);
+
+    match Compilation::new_with(&map, &sources, &interner, &passes, &levels, &conditions)?
+        .with_optimizations(opts.optimize)
+        .with_breakpoints(opts.breakpoints)
+        .with_profiling(opts.profile)
+        .flush(opts.output)
+    {
         Ok((_, diagnostics)) => {
-            diagnostics.dump(&sources)?;
+            diagnostics.report(&sources, opts.format, std::io::stdout().lock())?;
             log::info!("Compilation successful");
             Ok(ExitCode::SUCCESS)
         }
         Err(FlushError::CompilationErrors(diagnostics)) => {
-            diagnostics.dump(&sources)?;
+            diagnostics.report(&sources, opts.format, std::io::stdout().lock())?;
             log::info!("Compilation failed");
             Ok(ExitCode::FAILURE)
         }
@@ -214,14 +401,14 @@ fn lint(opts: LintOpts) -> anyhow::Result<ExitCode> {
     let (map, _f) = Map::with_options().open(opts.bundle)?;
     let interner = TypeInterner::default();
     let sources = load_sources(&opts.src)?;
-    let passes = opts
-        .warn_on
-        .into_iter()
-        .map(|w| w.to_pass())
-        .collect::<Vec<_>>();
-
-    let comp = Compilation::new_with(&map, &sources, &interner, &passes)?;
-    comp.diagnostics().dump(&sources)?;
+    let levels = load_lint_levels(&opts.src, &opts.allow, &opts.warn, &opts.deny)?;
+    let passes = diagnostic_passes(&opts.warn_on, &levels);
+    let defines = load_defines(&opts.src, opts.define)?;
+    let conditions = cte::Conditions::new(defines, opts.game_version);
+
+    let comp = Compilation::new_with(&map, &sources, &interner, &passes, &levels, &conditions)?;
+    comp.diagnostics()
+        .report(&sources, opts.format, std::io::stdout().lock())?;
     if comp.diagnostics().has_fatal_errors() {
         log::info!("Compilation failed");
         Ok(ExitCode::FAILURE)
@@ -253,26 +440,283 @@ fn decompile(opts: DecompileOpts) -> anyhow::Result<ExitCode> {
     }
     settings.trunc_sig_digits = opts.max_sig_digits;
 
-    let prefix_map = HashMap::default();
-    let ctx = FormatCtx::new(&settings, &prefix_map);
-
     let (map, _f) = Map::with_options().open(opts.bundle)?;
     let bundle = ScriptBundle::from_bytes(&map)?;
 
     let mut output = BufWriter::new(File::create(opts.output)?);
-    let settings = Settings::default();
-    let settings = if opts.verbose {
-        settings.verbose()
+    let decompile_settings = Settings::default();
+    let decompile_settings = if opts.verbose {
+        decompile_settings.verbose()
+    } else {
+        decompile_settings
+    };
+    for item in format_all(&bundle, &decompile_settings, &settings) {
+        writeln!(output, "{}", item?)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn disasm(opts: DisasmOpts) -> anyhow::Result<ExitCode> {
+    let (map, _f) = Map::with_options().open(opts.bundle)?;
+    let bundle = ScriptBundle::from_bytes(&map)?;
+
+    let mut filter = Filter::default();
+    if let Some(class) = opts.class {
+        filter = filter.class(class);
+    }
+    if let Some(function) = opts.function {
+        filter = filter.function(function);
+    }
+
+    let mut output: Box<dyn Write> = match opts.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let disasm = Disassembler::new(&bundle);
+    for listing in disasm.listings(&filter) {
+        writeln!(output, "{}", listing?)?;
+    }
+    output.flush()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn asm(opts: AsmOpts) -> anyhow::Result<ExitCode> {
+    let (map, _f) = Map::with_options().open(opts.bundle)?;
+    let mut bundle = ScriptBundle::from_bytes(&map)?;
+    let listing = fs::read_to_string(opts.input)?;
+
+    let functions = Assembler::new(&mut bundle).assemble(&listing)?;
+    fs::write(opts.output, bundle.into_writeable().to_bytes()?)?;
+    log::info!("Assembled {} functions", functions.len());
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff(opts: DiffOpts) -> anyhow::Result<ExitCode> {
+    let (old_map, _f) = Map::with_options().open(opts.old)?;
+    let old = ScriptBundle::from_bytes(&old_map)?;
+    let (new_map, _f) = Map::with_options().open(opts.new)?;
+    let new = ScriptBundle::from_bytes(&new_map)?;
+
+    let format = if opts.source {
+        BodyFormat::Source
     } else {
-        settings
+        BodyFormat::Bytecode
+    };
+    let diff = BundleDiff::new(&old, &new, format)?;
+
+    let mut output: Box<dyn Write> = match opts.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
     };
-    for item in decompile_all(&bundle, &settings) {
-        writeln!(output, "{}", item?.as_fmt(ctx))?;
+    if opts.json {
+        serde_json::to_writer_pretty(&mut output, &diff)?;
+        writeln!(output)?;
+    } else {
+        write!(output, "{diff}")?;
+    }
+    output.flush()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn doc(opts: DocOpts) -> anyhow::Result<ExitCode> {
+    let (map, _f) = Map::with_options().open(opts.bundle)?;
+    let interner = TypeInterner::default();
+    let sources = load_sources(&opts.src)?;
+
+    let comp = Compilation::new(&map, &sources, &interner)?;
+    if comp.diagnostics().has_fatal_errors() {
+        comp.diagnostics().dump(&sources)?;
+        log::info!("Compilation failed");
+        return Ok(ExitCode::FAILURE);
     }
 
+    let docs = Documentation::new(comp.symbols(), &sources);
+    let pages = docs.write(opts.output, opts.format)?;
+    log::info!("Generated {pages} documentation pages");
+
     Ok(ExitCode::SUCCESS)
 }
 
+fn test(opts: TestOpts) -> anyhow::Result<ExitCode> {
+    let (map, _f) = Map::with_options().open(opts.bundle)?;
+    let interner = TypeInterner::default();
+    let sources = load_sources(&opts.src)?;
+
+    let bundle = match Compilation::new(&map, &sources, &interner)?.finish() {
+        Ok((bundle, _, _)) => bundle,
+        Err(FlushError::CompilationErrors(diagnostics)) => {
+            diagnostics.dump(&sources)?;
+            log::info!("Compilation failed");
+            return Ok(ExitCode::FAILURE);
+        }
+        Err(err) => anyhow::bail!("{err}"),
+    };
+
+    let results = run_tests(&bundle, &sources, opts.filter.as_deref());
+    let failed = results.iter().filter(|(_, res)| res.is_err()).count();
+    log::info!("{} passed, {failed} failed", results.len() - failed);
+    if failed > 0 {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Runs the tests found in the sources and returns their names along with the results.
+fn run_tests(
+    bundle: &ScriptBundle<'_>,
+    sources: &SourceMap,
+    filter: Option<&str>,
+) -> Vec<(String, Result<(), String>)> {
+    let mut interp = Interpreter::new(bundle, (TestNatives, Operators));
+    let mut results = vec![];
+
+    for test in find_tests(sources) {
+        if filter.is_some_and(|filter| !test.name.contains(filter)) {
+            continue;
+        }
+        let res = run_test(&mut interp, &test);
+        match &res {
+            Ok(()) => log::info!("{} passed", test.name),
+            Err(err) => {
+                let location = sources
+                    .get(test.span.file)
+                    .map(|file| {
+                        format!("{}:{}", file.path().display(), file.lookup(test.span.start))
+                    })
+                    .unwrap_or_default();
+                log::error!("{} failed at {location}: {err}", test.name);
+            }
+        }
+        results.push((test.name, res));
+    }
+    results
+}
+
+fn run_test<N: Natives>(
+    interp: &mut Interpreter<'_, '_, N>,
+    test: &TestCase,
+) -> Result<(), String> {
+    if test.has_params {
+        return Err("test functions must not take any parameters".to_owned());
+    }
+    let not_found = || format!("could not find '{}' in the compiled bundle", test.name);
+    let signature = format!("{};", test.function);
+
+    let res = match &test.class {
+        Some(class) => {
+            let class = interp.class(class).ok_or_else(not_found)?;
+            let method = interp.method(class, &signature).ok_or_else(not_found)?;
+            if test.is_static {
+                interp.call(method, vec![])
+            } else {
+                let this = interp.instantiate(class).map_err(|err| err.to_string())?;
+                interp.call_method(method, this, vec![])
+            }
+        }
+        None => {
+            let func = interp.function(&signature).ok_or_else(not_found)?;
+            interp.call(func, vec![])
+        }
+    };
+    match res {
+        Ok(Value::Bool(false)) => Err("returned false".to_owned()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// A function or method annotated with `@test`.
+#[derive(Debug)]
+struct TestCase {
+    /// The name used in reports, which includes the class for methods.
+    name: String,
+    /// The qualified name of the class for methods.
+    class: Option<String>,
+    /// The name of the function, qualified with the module for free functions.
+    function: String,
+    is_static: bool,
+    has_params: bool,
+    span: Span,
+}
+
+/// Returns all functions and methods annotated with `@test`.
+fn find_tests(sources: &SourceMap) -> Vec<TestCase> {
+    // the sources have already been reported on by the compilation
+    let mut reporter = CompileErrorReporter::default();
+    let mut tests = vec![];
+
+    for module in parse_files(sources, &mut reporter) {
+        let qualify = |name: &str| match &module.path {
+            Some(path) => format!("{}.{name}", path.segments.join(".")),
+            None => name.to_owned(),
+        };
+        for (decl, span) in &module.items {
+            match &decl.item {
+                ast::Item::Function(func) if is_test(decl) => {
+                    let (name, _) = func.name;
+                    let function = qualify(name);
+                    tests.push(TestCase {
+                        name: function.clone(),
+                        class: None,
+                        function,
+                        is_static: true,
+                        has_params: !func.params.is_empty(),
+                        span: *span,
+                    });
+                }
+                ast::Item::Class(aggregate) | ast::Item::Struct(aggregate) => {
+                    let (class_name, _) = aggregate.name;
+                    let class = qualify(class_name);
+                    for (decl, span) in &aggregate.items {
+                        let ast::Item::Function(func) = &decl.item else {
+                            continue;
+                        };
+                        if !is_test(decl) {
+                            continue;
+                        }
+                        let (name, _) = func.name;
+                        tests.push(TestCase {
+                            name: format!("{class}.{name}"),
+                            class: Some(class.clone()),
+                            function: name.to_owned(),
+                            is_static: decl.qualifiers.contains(ast::ItemQualifiers::STATIC),
+                            has_params: !func.params.is_empty(),
+                            span: *span,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    tests
+}
+
+fn is_test(decl: &ast::SourceItemDecl<'_>) -> bool {
+    decl.annotations.iter().any(|(ann, _)| ann.name == "test")
+}
+
+/// Natives available to tests, on top of the operators.
+#[derive(Debug)]
+struct TestNatives;
+
+impl Natives for TestNatives {
+    fn call(&mut self, name: &str, args: &mut [Value]) -> redscript_interp::Result<Value> {
+        match (name, args) {
+            ("Log" | "FTLog", [msg]) => {
+                log::info!("{msg}");
+                Ok(Value::Unit)
+            }
+            _ => Err(redscript_interp::Error::UnknownNative(name.to_owned())),
+        }
+    }
+}
+
 fn format(opts: FormatOpts) -> anyhow::Result<ExitCode> {
     let dotfile = Dotfile::load_or_default(&opts.src)?;
 
@@ -360,3 +804,168 @@ fn load_sources(src: &[PathBuf]) -> anyhow::Result<SourceMap> {
     sources.populate_boot_lib();
     Ok(sources)
 }
+
+/// Combines the diagnostic levels from the dotfiles with the ones passed on the command
+/// line, the latter take precedence. Codes that can't be assigned a level are skipped with
+/// a warning, the same way as in the annotations.
+fn load_lint_levels(
+    src: &[PathBuf],
+    allow: &[String],
+    warn: &[String],
+    deny: &[String],
+) -> anyhow::Result<LintLevels> {
+    let mut levels = LintLevels::default();
+    let mut set = |codes: &[String], level| {
+        for code in codes {
+            if LintLevels::is_known_code(code) {
+                levels.set(code.as_str(), level);
+            } else {
+                log::warn!(
+                    "Ignoring an unknown diagnostic code '{code}', expected one of: {}",
+                    LintLevels::KNOWN_CODES.join(", ")
+                );
+            }
+        }
+    };
+
+    for src in src {
+        let config = Dotfile::load_or_default(src)?.diagnostics;
+        set(&config.allow, LintLevel::Allow);
+        set(&config.warn, LintLevel::Warn);
+        set(&config.deny, LintLevel::Deny);
+    }
+    set(allow, LintLevel::Allow);
+    set(warn, LintLevel::Warn);
+    set(deny, LintLevel::Deny);
+    Ok(levels)
+}
+
+/// Returns the names defined in the dotfiles of the source directories followed by
+/// the ones passed on the command line, so that the latter take precedence.
+fn load_defines(src: &[PathBuf], defines: Vec<Define>) -> anyhow::Result<Vec<Define>> {
+    let mut all = vec![];
+    for src in src {
+        let dotfile = Dotfile::load_or_default(src)?;
+        all.extend(
+            dotfile
+                .defines
+                .into_iter()
+                .map(|(name, value)| Define::new(name, value.into())),
+        );
+    }
+    all.extend(defines);
+    Ok(all)
+}
+
+/// Returns the control flow pass, which always runs, and the optional passes enabled
+/// explicitly or implied by the level of their diagnostic.
+fn diagnostic_passes<'ctx>(
+    warn_on: &[WarnOn],
+    levels: &LintLevels,
+) -> Vec<Box<dyn pass::DiagnosticPass<'ctx>>> {
+    let mut passes: Vec<Box<dyn pass::DiagnosticPass<'ctx>>> = vec![Box::new(pass::ControlFlow)];
+    passes.extend(
+        WarnOn::ALL
+            .iter()
+            .filter(|w| {
+                warn_on.contains(w)
+                    || w.codes().iter().any(|code| {
+                        matches!(levels.get(code), Some(LintLevel::Warn | LintLevel::Deny))
+                    })
+            })
+            .map(WarnOn::to_pass),
+    );
+    passes
+}
+
+#[cfg(test)]
+mod tests {
+    use redscript_io::{Class, ClassFlags, Visibility};
+
+    use super::*;
+
+    const SOURCE: &str = r#"
+native func OperatorAdd(a: Int32, b: Int32) -> Int32
+native func OperatorEqual(a: Int32, b: Int32) -> Bool
+
+@test
+func Passes() -> Bool {
+  return 1 + 1 == 2;
+}
+
+@test
+func Fails() -> Bool {
+  return 1 + 1 == 3;
+}
+
+@test
+func TakesParams(a: Int32) {}
+
+class Suite {
+  let value: Int32;
+
+  @test
+  func OnInstance() -> Bool {
+    return this.value == 0;
+  }
+
+  @test
+  static func OnClass() {}
+}
+"#;
+
+    #[test]
+    fn runs_annotated_functions_and_methods() {
+        let sources = SourceMap::new();
+        sources.push_back("test.reds", SOURCE);
+        sources.populate_boot_lib();
+
+        let interner = TypeInterner::default();
+        let mut base = ScriptBundle::default();
+        let name = base.cnames_mut().add("IScriptable");
+        base.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+        let bytes = base.into_writeable().to_bytes().unwrap();
+
+        let (bundle, _, _) = Compilation::new(&bytes, &sources, &interner)
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        let results = run_tests(&bundle, &sources, None);
+        let results = results
+            .iter()
+            .map(|(name, res)| (name.as_str(), res.as_ref().map_err(String::as_str)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            [
+                ("Passes", Ok(())),
+                ("Fails", Err("returned false")),
+                (
+                    "TakesParams",
+                    Err("test functions must not take any parameters")
+                ),
+                ("Suite.OnInstance", Ok(())),
+                ("Suite.OnClass", Ok(())),
+            ]
+        );
+
+        let results = run_tests(&bundle, &sources, Some("Suite"));
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn unknown_lint_codes_are_ignored() {
+        let codes = |codes: &[&str]| codes.iter().map(|&c| c.to_owned()).collect::<Vec<_>>();
+
+        let levels = load_lint_levels(
+            &[],
+            &codes(&["UNUSED_LOCAL"]),
+            &[],
+            &codes(&["UNUSED_LOCALS"]),
+        )
+        .unwrap();
+        assert_eq!(levels.get("UNUSED_LOCAL"), Some(LintLevel::Allow));
+        assert_eq!(levels.get("UNUSED_LOCALS"), None);
+    }
+}
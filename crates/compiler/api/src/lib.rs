@@ -1,20 +1,24 @@
+mod report;
+
 use std::fmt;
 use std::path::Path;
 
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
 pub use redscript_ast as ast;
 use redscript_ast::SourceMap;
-pub use redscript_compiler_backend::CompilationInputs;
 use redscript_compiler_backend::{AssembleError, PoolError, PoolMappings};
+pub use redscript_compiler_backend::{CompilationInputs, ModConflict, ModConflicts, Profiling};
 use redscript_compiler_frontend::UnknownSource;
-use redscript_compiler_frontend::pass::{DiagnosticPass, UnusedLocals};
+use redscript_compiler_frontend::pass::{ControlFlow, DiagnosticPass, UnusedLocals};
 pub use redscript_compiler_frontend::{
-    Aggregate, CompileErrorReporter, Diagnostic, Enum, Evaluator, Field, FunctionType,
-    LoweredCompilationUnit, LoweredFunction, PolyType, Symbols, TypeId, TypeIndex, TypeInterner,
-    TypeSchema, TypeScope, infer_from_sources, ir, parse_file, parse_files, pass, process_sources,
-    types,
+    Aggregate, CompileErrorReporter, Diagnostic, Enum, Evaluator, Field, FunctionType, LintLevel,
+    LintLevels, LoweredCompilationUnit, LoweredFunction, PolyType, Symbols, TypeDef, TypeId,
+    TypeIndex, TypeInterner, TypeSchema, TypeScope, cte, infer_from_sources, ir, parse_file,
+    parse_files, pass, process_sources, types,
 };
 use redscript_io::byte;
 pub use redscript_io::{SaveError, ScriptBundle};
+pub use report::{DiagnosticFormat, DiagnosticReport, Position, ReportError, Severity};
 use thiserror::Error;
 
 pub struct Compilation<'ctx> {
@@ -24,6 +28,9 @@ pub struct Compilation<'ctx> {
     unit: LoweredCompilationUnit<'ctx>,
     bundle: ScriptBundle<'ctx>,
     diagnostics: Diagnostics<'ctx>,
+    optimize: bool,
+    breakpoints: bool,
+    profiling: Profiling,
 }
 
 impl<'ctx> Compilation<'ctx> {
@@ -32,14 +39,18 @@ impl<'ctx> Compilation<'ctx> {
         sources: &'ctx SourceMap,
         interner: &'ctx TypeInterner,
         passes: &[Box<dyn DiagnosticPass<'ctx>>],
+        levels: &LintLevels,
+        conditions: &cte::Conditions,
     ) -> Result<Self, Error> {
         let mut reporter = CompileErrorReporter::default();
         let bundle = ScriptBundle::from_bytes(bundle)?;
         let (symbols, mappings) = CompilationInputs::load(&bundle, interner)?.into_inner();
-        let (unit, symbols) = infer_from_sources(sources, symbols, &mut reporter, interner);
-        unit.run_diagnostics(passes, &mut reporter);
+        let mods = parse_files(sources, &mut reporter);
+        let evaluator = Evaluator::from_modules(&mods).with_conditions(conditions);
+        let (unit, symbols) = process_sources(mods, symbols, evaluator, &mut reporter, interner);
+        unit.run_diagnostics(passes, &symbols, &mut reporter);
 
-        let mut diagnostics = reporter.into_reported();
+        let mut diagnostics = levels.apply(reporter.into_reported(), &unit.lint_scopes);
         diagnostics.sort_by_key(Diagnostic::is_fatal);
 
         Ok(Self {
@@ -49,6 +60,9 @@ impl<'ctx> Compilation<'ctx> {
             unit,
             bundle,
             diagnostics: Diagnostics(diagnostics),
+            optimize: false,
+            breakpoints: false,
+            profiling: Profiling::Disabled,
         })
     }
 
@@ -57,18 +71,62 @@ impl<'ctx> Compilation<'ctx> {
         sources: &'ctx SourceMap,
         interner: &'ctx TypeInterner,
     ) -> Result<Self, Error> {
-        Self::new_with(bundle, sources, interner, &default_diagnostics())
+        Self::new_with(
+            bundle,
+            sources,
+            interner,
+            &default_diagnostics(),
+            &LintLevels::default(),
+            &cte::Conditions::default(),
+        )
+    }
+
+    /// Enables the optimization of the generated code.
+    pub fn with_optimizations(mut self, enabled: bool) -> Self {
+        self.optimize = enabled;
+        self
+    }
+
+    /// Enables the emission of breakpoint instructions for script debuggers.
+    pub fn with_breakpoints(mut self, enabled: bool) -> Self {
+        self.breakpoints = enabled;
+        self
+    }
+
+    /// Enables the emission of profiling markers for the in-game profiler.
+    pub fn with_profiling(mut self, profiling: Profiling) -> Self {
+        self.profiling = profiling;
+        self
     }
 
     pub fn flush(
-        mut self,
+        self,
         path: impl AsRef<Path>,
     ) -> Result<(Symbols<'ctx>, Diagnostics<'ctx>), FlushError<'ctx>> {
+        let (bundle, symbols, diagnostics) = self.finish()?;
+        bundle.into_writeable().save(path)?;
+        Ok((symbols, diagnostics))
+    }
+
+    /// Monomorphizes the compilation unit and returns the resulting bundle without
+    /// writing it to disk.
+    pub fn finish(
+        mut self,
+    ) -> Result<(ScriptBundle<'ctx>, Symbols<'ctx>, Diagnostics<'ctx>), FlushError<'ctx>> {
         if self.diagnostics.has_fatal_errors() {
             return Err(FlushError::CompilationErrors(self.diagnostics));
         }
 
-        let mut monomorph = self.mappings.into_monomorphizer(self.sources);
+        if self.optimize {
+            self.unit.optimize(&self.symbols);
+        }
+
+        let mut monomorph = self
+            .mappings
+            .into_monomorphizer(self.sources)
+            .with_optimizations(self.optimize)
+            .with_breakpoints(self.breakpoints)
+            .with_profiling(self.profiling);
         if let Err(err) = monomorph.monomorphize(&self.unit, &self.symbols, &mut self.bundle) {
             if let Some(span) = err.span() {
                 self.diagnostics
@@ -77,8 +135,7 @@ impl<'ctx> Compilation<'ctx> {
             }
         }
 
-        self.bundle.into_writeable().save(path)?;
-        Ok((self.symbols, self.diagnostics))
+        Ok((self.bundle, self.symbols, self.diagnostics))
     }
 
     pub fn symbols(&self) -> &Symbols<'ctx> {
@@ -96,6 +153,12 @@ impl<'ctx> Compilation<'ctx> {
     pub fn diagnostics(&self) -> &Diagnostics<'ctx> {
         &self.diagnostics
     }
+
+    /// Analyzes the annotations of the compilation unit for members targeted by more
+    /// than one mod.
+    pub fn conflicts(&self) -> ModConflicts {
+        ModConflicts::new(&self.unit, &self.symbols)
+    }
 }
 
 #[derive(Debug)]
@@ -182,6 +245,14 @@ impl<'ctx> From<AssembleError<'ctx>> for FlushError<'ctx> {
     }
 }
 
+/// Sets the number of threads used for compilation, zero means one thread per logical
+/// CPU. The thread pool is global and can only be configured once per process. Only
+/// parsing is spread across the threads, type inference and code generation always run
+/// on the calling thread.
+pub fn configure_threads(count: usize) -> Result<(), ThreadPoolBuildError> {
+    ThreadPoolBuilder::new().num_threads(count).build_global()
+}
+
 pub trait SourceMapExt {
     fn populate_boot_lib(&self);
 }
@@ -196,5 +267,5 @@ impl SourceMapExt for SourceMap {
 }
 
 fn default_diagnostics<'ctx>() -> Vec<Box<dyn DiagnosticPass<'ctx>>> {
-    vec![Box::new(UnusedLocals)]
+    vec![Box::new(ControlFlow), Box::new(UnusedLocals)]
 }
@@ -0,0 +1,188 @@
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use redscript_ast::SourceMap;
+use redscript_compiler_frontend::{Diagnostic, UnknownSource};
+use serde::Serialize;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::Diagnostics;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const TOOL_URI: &str = "https://github.com/jac3km4/redscript";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    #[default]
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for DiagnosticFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("unknown diagnostic format: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticReport<'a> {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub file: &'a Path,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl<'a> DiagnosticReport<'a> {
+    pub fn new(diagnostic: &Diagnostic<'_>, sources: &'a SourceMap) -> Result<Self, UnknownSource> {
+        let span = diagnostic.span();
+        let file = sources.get(span.file).ok_or(UnknownSource(span))?;
+        let start = file.lookup(span.start);
+        let end = file.lookup(span.end);
+
+        let severity = if diagnostic.is_fatal() {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        Ok(Self {
+            code: diagnostic.code(),
+            severity,
+            message: diagnostic.to_string(),
+            file: file.path(),
+            start: Position::new(start.line + 1, start.col + 1),
+            end: Position::new(end.line + 1, end.col + 1),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A 1-based line and column position in a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    #[inline]
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl Diagnostics<'_> {
+    pub fn reports<'a>(
+        &self,
+        sources: &'a SourceMap,
+    ) -> Result<Vec<DiagnosticReport<'a>>, UnknownSource> {
+        self.into_iter()
+            .map(|diagnostic| DiagnosticReport::new(diagnostic, sources))
+            .collect()
+    }
+
+    pub fn write_json(&self, sources: &SourceMap, out: impl io::Write) -> Result<(), ReportError> {
+        serde_json::to_writer_pretty(out, &self.reports(sources)?)?;
+        Ok(())
+    }
+
+    pub fn write_sarif(&self, sources: &SourceMap, out: impl io::Write) -> Result<(), ReportError> {
+        let reports = self.reports(sources)?;
+
+        let mut rules = reports.iter().map(|r| r.code).collect::<Vec<_>>();
+        rules.sort_unstable();
+        rules.dedup();
+
+        let results = reports
+            .iter()
+            .map(|report| {
+                json!({
+                    "ruleId": report.code,
+                    "level": report.severity,
+                    "message": { "text": report.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": report.file.to_string_lossy().replace('\\', "/"),
+                            },
+                            "region": {
+                                "startLine": report.start.line,
+                                "startColumn": report.start.column,
+                                "endLine": report.end.line,
+                                "endColumn": report.end.column,
+                            },
+                        },
+                    }],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": SARIF_VERSION,
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "redscript",
+                        "informationUri": TOOL_URI,
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                    },
+                },
+                "results": results,
+            }],
+        });
+        serde_json::to_writer_pretty(out, &log)?;
+        Ok(())
+    }
+
+    /// Writes the diagnostics to `out` in the given format. The text format is written
+    /// through the logger, in which case `out` is left untouched.
+    pub fn report(
+        &self,
+        sources: &SourceMap,
+        format: DiagnosticFormat,
+        mut out: impl io::Write,
+    ) -> Result<(), ReportError> {
+        match format {
+            DiagnosticFormat::Text => self.dump(sources)?,
+            DiagnosticFormat::Json => {
+                self.write_json(sources, &mut out)?;
+                writeln!(out)?;
+            }
+            DiagnosticFormat::Sarif => {
+                self.write_sarif(sources, &mut out)?;
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("{0}")]
+    UnknownSource(#[from] UnknownSource),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("write error: {0}")]
+    Write(#[from] io::Error),
+}
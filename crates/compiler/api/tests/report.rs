@@ -0,0 +1,42 @@
+use redscript_compiler_api::ast::{SourceMap, Span};
+use redscript_compiler_api::{Diagnostic, DiagnosticFormat, Diagnostics};
+
+const SOURCE: &str = "class Derived extends Missing {}\n\nfunc Test() {\n  let unused = 1;\n}\n";
+
+/// Returns an error and a warning reported for the test source.
+fn diagnostics(sources: &SourceMap) -> Diagnostics<'static> {
+    let file = sources.push_back("mod/test.reds", SOURCE);
+    Diagnostics::from(vec![
+        Diagnostic::InvalidBaseType(Span {
+            start: 22,
+            end: 29,
+            file,
+        }),
+        Diagnostic::UnusedLocal(Span {
+            start: 54,
+            end: 60,
+            file,
+        }),
+    ])
+}
+
+fn report(format: DiagnosticFormat) -> String {
+    let sources = SourceMap::new();
+    let mut out = vec![];
+    diagnostics(&sources)
+        .report(&sources, format, &mut out)
+        .unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .replace(env!("CARGO_PKG_VERSION"), "[version]")
+}
+
+#[test]
+fn json_report() {
+    insta::assert_snapshot!("json", report(DiagnosticFormat::Json));
+}
+
+#[test]
+fn sarif_report() {
+    insta::assert_snapshot!("sarif", report(DiagnosticFormat::Sarif));
+}
@@ -7,14 +7,14 @@ use redscript_compiler_frontend::{
 use redscript_io::{
     Conditional, FieldIndex as PoolFieldIndex, FunctionIndex as PoolFunctionIndex, Instr,
     InvokeFlags, Jump, Local as PoolLocal, LocalFlags as PoolLocalFlags,
-    LocalIndex as PoolLocalIndex, Offset, ParameterIndex as PoolParameterIndex, ScriptBundle,
-    Switch, SwitchLabel, TypeIndex as PoolTypeIndex, byte,
+    LocalIndex as PoolLocalIndex, Offset, ParameterIndex as PoolParameterIndex, Profile,
+    ScriptBundle, Switch, SwitchLabel, TypeIndex as PoolTypeIndex, byte,
 };
 use slab::Slab;
 use thiserror::Error;
 
 use crate::inputs::Signature;
-use crate::monomorph::{MethodWithReceiver, Monomorphizer};
+use crate::monomorph::{MethodWithReceiver, Monomorphizer, Profiling};
 
 mod closure;
 
@@ -32,7 +32,16 @@ pub fn assemble_block<'ctx>(
     let mut assembler = Assembler::new(
         index, locals, captures, symbols, type_env, bundle, monomorph,
     )?;
-    assembler.assemble_block(block, None)?;
+    if assembler.monomorph.profiling() == Profiling::Disabled {
+        assembler.assemble_block(block, None)?;
+    } else {
+        assembler.enter_profile_scope(assembler.function_name.clone());
+        assembler.assemble_block(block, None)?;
+        // returns close the scopes on their own
+        if !matches!(block.stmts.back(), Some(ir::Stmt::Return(..))) {
+            assembler.exit_profile_scope();
+        }
+    }
     assembler.into_code()
 }
 
@@ -42,9 +51,15 @@ struct Assembler<'scope, 'ctx> {
     params: Vec<(ir::Local, PoolParameterIndex)>,
     captures: Vec<(ir::Local, PoolFieldIndex)>,
     has_return_value: bool,
+    function: PoolFunctionIndex,
+    /// The mangled name of the function, used to name its profiling scopes.
+    function_name: String,
+    /// A local holding the return value while the profiling scopes are closed.
+    return_local: Option<PoolLocalIndex>,
 
     labels: Slab<u32>,
     virtual_offset: u32,
+    profile_scopes: Vec<String>,
 
     symbols: &'scope Symbols<'ctx>,
     type_env: &'scope ScopedMap<'scope, &'ctx str, MonoType<'ctx>>,
@@ -109,9 +124,13 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
             params: param_indices,
             captures: capture_indices,
             has_return_value: bundle[index].return_type().is_some(),
+            function: index,
+            function_name: bundle[bundle[index].name()].to_string(),
+            return_local: None,
 
             labels: Slab::new(),
             virtual_offset: 0,
+            profile_scopes: Vec::new(),
 
             symbols,
             bundle,
@@ -140,6 +159,51 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
         self.labels[usize::from(label)] = self.virtual_offset;
     }
 
+    fn enter_profile_scope(&mut self, name: String) {
+        self.emit(Instr::Profile(Box::new(Profile::new(
+            name.as_bytes(),
+            true,
+        ))));
+        self.profile_scopes.push(name);
+    }
+
+    fn exit_profile_scope(&mut self) {
+        if let Some(name) = self.profile_scopes.pop() {
+            self.emit(Instr::Profile(Box::new(Profile::new(
+                name.as_bytes(),
+                false,
+            ))));
+        }
+    }
+
+    /// Closes all open profiling scopes ahead of a return.
+    fn emit_profile_exits(&mut self) {
+        for i in (0..self.profile_scopes.len()).rev() {
+            let profile = Profile::new(self.profile_scopes[i].as_bytes(), false);
+            self.emit(Instr::Profile(Box::new(profile)));
+        }
+    }
+
+    fn return_local(&mut self) -> PoolLocalIndex {
+        if let Some(local) = self.return_local {
+            return local;
+        }
+        let typ = self.bundle[self.function]
+            .return_type()
+            .expect("function should have a return type");
+        let name = self.bundle.cnames_mut().add("ret");
+        let flags = PoolLocalFlags::default();
+        let local = self
+            .bundle
+            .define(PoolLocal::new(name, self.function, typ, flags));
+
+        let mut locals = self.bundle[self.function].locals().to_vec();
+        locals.push(local);
+        self.bundle[self.function].set_locals(locals);
+        self.return_local = Some(local);
+        local
+    }
+
     #[inline]
     fn assemble_block(
         &mut self,
@@ -157,6 +221,11 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
         stmt: &ir::Stmt<'ctx>,
         block_span: Option<BlockSpan>,
     ) -> Result<(), AssembleError<'ctx>> {
+        if self.monomorph.breakpoints_enabled() && !matches!(stmt, ir::Stmt::Block(..)) {
+            let breakpoint = self.monomorph.breakpoint(stmt.span());
+            self.emit(Instr::Breakpoint(Box::new(breakpoint)));
+        }
+
         match stmt {
             ir::Stmt::Expr(expr) => {
                 self.assemble_expr(expr)?;
@@ -164,16 +233,26 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
             ir::Stmt::Block(block, _) => {
                 self.assemble_block(block, block_span)?;
             }
-            ir::Stmt::While(block, _) => {
+            ir::Stmt::While(block, span) => {
                 let start = self.new_label();
                 let end = self.new_label();
 
+                let profile = self.monomorph.profiling() == Profiling::FunctionsAndLoops;
+                if profile {
+                    let line = self.monomorph.source_line(*span);
+                    self.enter_profile_scope(format!("{}:{line}", self.function_name));
+                }
+
                 self.mark_label(start);
                 self.emit(Instr::JumpIfFalse(Jump::new(end)));
                 self.assemble_expr(&block.condition)?;
                 self.assemble_block(&block.block, Some(BlockSpan::Loop { start, end }))?;
                 self.emit(Instr::Jump(Jump::new(start)));
                 self.mark_label(end);
+
+                if profile {
+                    self.exit_profile_scope();
+                }
             }
             ir::Stmt::Branches {
                 branches, default, ..
@@ -296,7 +375,21 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
                     return Err(AssembleError::InvalidControlFlow(*span));
                 }
             }
+            ir::Stmt::Return(Some(expr), _)
+                if self.has_return_value && !self.profile_scopes.is_empty() =>
+            {
+                // the value is computed before the scopes are closed, so that its cost is
+                // attributed to this function
+                let local = self.return_local();
+                self.emit(Instr::Assign);
+                self.emit(Instr::Local(local));
+                self.assemble_expr(expr)?;
+                self.emit_profile_exits();
+                self.emit(Instr::Return);
+                self.emit(Instr::Local(local));
+            }
             ir::Stmt::Return(expr, _) if self.has_return_value => {
+                self.emit_profile_exits();
                 self.emit(Instr::Return);
                 if let Some(expr) = expr {
                     self.assemble_expr(expr)?;
@@ -308,6 +401,7 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
                 if let Some(expr) = expr {
                     self.assemble_expr(expr)?;
                 }
+                self.emit_profile_exits();
                 self.emit(Instr::Return);
                 self.emit(Instr::Nop);
             }
@@ -766,7 +860,13 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
                 [arg],
             ) => {
                 let array_t = MonoType::new(predef::ARRAY, [arg.clone()]);
+                let fast = self.monomorph.optimizations_enabled()
+                    && has_bitwise_equality(arg, self.symbols);
                 let instr = match intrinsic {
+                    ir::Intrinsic::ArrayFindFirst if fast => Instr::ArrayFindFirstFast,
+                    ir::Intrinsic::ArrayFindLast if fast => Instr::ArrayFindLastFast,
+                    ir::Intrinsic::ArrayContains if fast => Instr::ArrayContainsFast,
+                    ir::Intrinsic::ArrayCount if fast => Instr::ArrayCountFast,
                     ir::Intrinsic::ArrayClear => Instr::ArrayClear,
                     ir::Intrinsic::ArraySize => Instr::ArraySize,
                     ir::Intrinsic::ArrayResize => Instr::ArrayResize,
@@ -921,6 +1021,28 @@ impl<'scope, 'ctx> Assembler<'scope, 'ctx> {
     }
 }
 
+/// Returns whether the values of a type can be compared by their bytes, which is
+/// required by the `Fast` variants of the array instructions.
+fn has_bitwise_equality(typ: &MonoType<'_>, symbols: &Symbols<'_>) -> bool {
+    let primitives = [
+        predef::BOOL,
+        predef::CNAME,
+        predef::TWEAK_DB_ID,
+        predef::INT8,
+        predef::INT16,
+        predef::INT32,
+        predef::INT64,
+        predef::UINT8,
+        predef::UINT16,
+        predef::UINT32,
+        predef::UINT64,
+    ];
+    primitives.contains(&typ.id())
+        || symbols
+            .get_type(typ.id())
+            .is_some_and(|def| matches!(def.schema(), TypeSchema::Enum(_)))
+}
+
 #[derive(Debug, Error)]
 pub enum AssembleError<'ctx> {
     #[error("label too distant")]
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use hashbrown::HashSet;
+use redscript_compiler_frontend::ast::{SourceMap, Span};
+use redscript_compiler_frontend::{AdditionKind, LoweredCompilationUnit, MethodId, Symbols};
+
+/// Conflicts between annotations that target the same members, typically coming from
+/// different mods.
+#[derive(Debug, Default)]
+pub struct ModConflicts {
+    conflicts: Vec<ModConflict>,
+}
+
+impl ModConflicts {
+    pub fn new(unit: &LoweredCompilationUnit<'_>, symbols: &Symbols<'_>) -> Self {
+        let mut conflicts = vec![];
+
+        for (&id, funcs) in &unit.method_replacements {
+            let replacements = funcs.iter().map(|func| func.span).collect::<Vec<_>>();
+            if replacements.len() > 1 {
+                conflicts.push(ModConflict::MultipleReplacements {
+                    target: method_name(id, symbols),
+                    replacements: replacements.clone(),
+                });
+            }
+
+            // wrappers defined next to the replacement are assumed to be intentional
+            let wrappers = unit
+                .method_wrappers
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .map(|func| func.span)
+                .filter(|wrapper| replacements.iter().all(|r| r.file != wrapper.file))
+                .collect::<Vec<_>>();
+            if !wrappers.is_empty() {
+                conflicts.push(ModConflict::ReplacedWrappedMethod {
+                    target: method_name(id, symbols),
+                    replacements,
+                    wrappers,
+                });
+            }
+        }
+
+        for addition in &unit.duplicate_additions {
+            conflicts.push(ModConflict::DuplicateAddition {
+                target: format!("{}.{}", addition.parent.as_str(), addition.name),
+                kind: addition.kind,
+                spans: [addition.original, addition.duplicate],
+            });
+        }
+
+        Self { conflicts }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.conflicts.len()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &ModConflict> {
+        self.conflicts.iter()
+    }
+
+    /// Returns a detailed report listing the location of every annotation involved.
+    pub fn report<'a>(&'a self, sources: &'a SourceMap) -> ConflictReport<'a> {
+        ConflictReport {
+            conflicts: self,
+            sources,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ModConflict {
+    /// More than one `@replaceMethod` targets the same method, only the last one takes
+    /// effect.
+    MultipleReplacements {
+        target: String,
+        replacements: Vec<Span>,
+    },
+    /// A method is replaced while it's also wrapped from another file, the wrappers will
+    /// be applied to the replacement rather than the original method.
+    ReplacedWrappedMethod {
+        target: String,
+        replacements: Vec<Span>,
+        wrappers: Vec<Span>,
+    },
+    /// Two `@addField` or `@addMethod` annotations add a member with the same name.
+    DuplicateAddition {
+        target: String,
+        kind: AdditionKind,
+        spans: [Span; 2],
+    },
+}
+
+impl ModConflict {
+    pub fn target(&self) -> &str {
+        match self {
+            Self::MultipleReplacements { target, .. }
+            | Self::ReplacedWrappedMethod { target, .. }
+            | Self::DuplicateAddition { target, .. } => target,
+        }
+    }
+
+    /// Returns the spans of all annotated items involved in the conflict.
+    pub fn spans(&self) -> impl Iterator<Item = Span> + '_ {
+        let (first, second) = match self {
+            Self::MultipleReplacements { replacements, .. } => (replacements.as_slice(), &[][..]),
+            Self::ReplacedWrappedMethod {
+                replacements,
+                wrappers,
+                ..
+            } => (replacements.as_slice(), wrappers.as_slice()),
+            Self::DuplicateAddition { spans, .. } => (spans.as_slice(), &[][..]),
+        };
+        first.iter().chain(second).copied()
+    }
+
+    /// Returns a one-line summary of the conflict that names the files involved.
+    pub fn summary<'a>(&'a self, sources: &'a SourceMap) -> ConflictSummary<'a> {
+        ConflictSummary {
+            conflict: self,
+            sources,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::MultipleReplacements { .. } => {
+                "is replaced more than once, only the last replacement takes effect"
+            }
+            Self::ReplacedWrappedMethod { .. } => "is both replaced and wrapped",
+            Self::DuplicateAddition {
+                kind: AdditionKind::Field,
+                ..
+            } => "is added as a field more than once",
+            Self::DuplicateAddition {
+                kind: AdditionKind::Method,
+                ..
+            } => "is added as a method more than once",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ConflictSummary<'a> {
+    conflict: &'a ModConflict,
+    sources: &'a SourceMap,
+}
+
+impl fmt::Display for ConflictSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut seen = HashSet::new();
+        let files = self
+            .conflict
+            .spans()
+            .filter(|span| seen.insert(span.file))
+            .filter_map(|span| Some(self.sources.get(span.file)?.path().display().to_string()))
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "{} {} ({})",
+            self.conflict.target(),
+            self.conflict.description(),
+            files.join(", ")
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ConflictReport<'a> {
+    conflicts: &'a ModConflicts,
+    sources: &'a SourceMap,
+}
+
+impl ConflictReport<'_> {
+    fn write_location(&self, f: &mut fmt::Formatter<'_>, label: &str, span: Span) -> fmt::Result {
+        match self.sources.get(span.file) {
+            Some(file) => writeln!(
+                f,
+                "  {label} at {}:{}",
+                file.path().display(),
+                file.lookup(span.start)
+            ),
+            None => writeln!(f, "  {label} in an unknown file"),
+        }
+    }
+}
+
+impl fmt::Display for ConflictReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Found {} annotation conflicts", self.conflicts.len())?;
+
+        for conflict in self.conflicts.iter() {
+            writeln!(f)?;
+            writeln!(f, "{} {}:", conflict.target(), conflict.description())?;
+
+            match conflict {
+                ModConflict::MultipleReplacements { replacements, .. } => {
+                    for &span in replacements {
+                        self.write_location(f, "replaced", span)?;
+                    }
+                }
+                ModConflict::ReplacedWrappedMethod {
+                    replacements,
+                    wrappers,
+                    ..
+                } => {
+                    for &span in replacements {
+                        self.write_location(f, "replaced", span)?;
+                    }
+                    for &span in wrappers {
+                        self.write_location(f, "wrapped", span)?;
+                    }
+                }
+                ModConflict::DuplicateAddition { spans, .. } => {
+                    for &span in spans {
+                        self.write_location(f, "added", span)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn method_name(id: MethodId<'_>, symbols: &Symbols<'_>) -> String {
+    match symbols.get_method(id) {
+        Some((name, _)) => format!("{}.{name}", id.parent().as_str()),
+        None => id.parent().as_str().to_owned(),
+    }
+}
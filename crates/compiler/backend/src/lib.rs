@@ -1,10 +1,12 @@
 mod assemble;
+mod conflicts;
 mod inputs;
 mod known_types;
 mod monomorph;
 
 pub use assemble::AssembleError;
+pub use conflicts::{ConflictReport, ConflictSummary, ModConflict, ModConflicts};
 pub use inputs::{CompilationInputs, Error as PoolError, PoolMappings};
-pub use monomorph::Monomorphizer;
+pub use monomorph::{Monomorphizer, Profiling};
 
 type IndexMap<K, V, S = hashbrown::DefaultHashBuilder> = indexmap::IndexMap<K, V, S>;
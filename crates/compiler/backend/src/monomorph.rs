@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::cell::Cell;
 use std::hash::{BuildHasher, Hash};
+use std::str::FromStr;
 use std::{fmt, mem, ops};
 
 use hashbrown::HashMap;
@@ -14,7 +15,7 @@ use redscript_compiler_frontend::{
     ir, predef,
 };
 use redscript_io::{
-    Class as PoolClass, ClassFlags as PoolClassFlags, ClassIndex as PoolClassIndex,
+    Breakpoint, Class as PoolClass, ClassFlags as PoolClassFlags, ClassIndex as PoolClassIndex,
     Enum as PoolEnum, EnumIndex as PoolEnumIndex, EnumMember as PoolEnumMember, Field as PoolField,
     FieldFlags as PoolFieldFlags, FieldIndex as PoolFieldIndex, Function as PoolFunction,
     FunctionFlags as PoolFunctionFlags, FunctionIndex as PoolFunctionIndex, Instr,
@@ -38,6 +39,10 @@ pub struct Monomorphizer<'ctx> {
     methods: IncrementalMap<Signature<'ctx, MethodWithReceiver<'ctx>>, PoolFunctionIndex>,
     functions: IncrementalMap<Signature<'ctx, FreeFunctionIndex>, PoolFunctionIndex>,
     types: IndexMap<MonoType<'ctx>, PoolTypeIndex>,
+
+    optimize: bool,
+    breakpoints: bool,
+    profiling: Profiling,
 }
 
 impl<'ctx> Monomorphizer<'ctx> {
@@ -57,9 +62,48 @@ impl<'ctx> Monomorphizer<'ctx> {
             methods: IncrementalMap::default(),
             functions: IncrementalMap::from_predefined(functions),
             types,
+
+            optimize: false,
+            breakpoints: false,
+            profiling: Profiling::Disabled,
         }
     }
 
+    /// Enables the use of specialized instructions where they are known to be equivalent.
+    pub fn with_optimizations(mut self, enabled: bool) -> Self {
+        self.optimize = enabled;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn optimizations_enabled(&self) -> bool {
+        self.optimize
+    }
+
+    /// Enables the emission of breakpoint instructions at statement boundaries, which
+    /// allows script debuggers to step through the code.
+    pub fn with_breakpoints(mut self, enabled: bool) -> Self {
+        self.breakpoints = enabled;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn breakpoints_enabled(&self) -> bool {
+        self.breakpoints
+    }
+
+    /// Enables the emission of profiling markers tagged with the mangled names of the
+    /// functions, which allows the in-game profiler to attribute time to script code.
+    pub fn with_profiling(mut self, profiling: Profiling) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
+    #[inline]
+    pub(crate) fn profiling(&self) -> Profiling {
+        self.profiling
+    }
+
     pub fn monomorphize(
         &mut self,
         unit: &LoweredCompilationUnit<'ctx>,
@@ -416,6 +460,24 @@ impl<'ctx> Monomorphizer<'ctx> {
         file.line_and_offset(span.start).0 as u16 + 1 // lines are 1-indexed
     }
 
+    pub(super) fn breakpoint(&self, span: Span) -> Breakpoint {
+        let file = self
+            .sources
+            .get(span.file)
+            .expect("source file should exist");
+        let (line, line_start) = file.line_and_offset(span.start);
+        let col = file.lookup(span.start).col;
+        let length = (span.end - span.start).min(u32::from(u16::MAX));
+        // breakpoints are disabled until a debugger attaches to them
+        Breakpoint::new(
+            line as u16 + 1,
+            line_start,
+            col as u16,
+            length as u16,
+            false,
+        )
+    }
+
     fn process_added_fields(
         &mut self,
         unit: &LoweredCompilationUnit<'ctx>,
@@ -478,7 +540,9 @@ impl<'ctx> Monomorphizer<'ctx> {
         bundle: &mut ScriptBundle<'ctx>,
     ) -> Result<(), AssembleError<'ctx>> {
         let env = ScopedMap::default();
-        for (id, func) in &unit.method_replacements {
+        for (id, funcs) in &unit.method_replacements {
+            // only the last replacement takes effect, the rest are reported as conflicts
+            let Some(func) = funcs.last() else { continue };
             let parent_t = MonoType::nullary(id.parent());
             let index = self.classes[&parent_t].methods()[&id.index()];
             self.function_body(index, func, &env, symbols, bundle)?;
@@ -817,6 +881,28 @@ fn define_predefs(bundle: &mut ScriptBundle<'_>) {
     ));
 }
 
+/// Determines which parts of the code are instrumented with profiling markers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Profiling {
+    #[default]
+    Disabled,
+    Functions,
+    FunctionsAndLoops,
+}
+
+impl FromStr for Profiling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Disabled),
+            "functions" => Ok(Self::Functions),
+            "loops" => Ok(Self::FunctionsAndLoops),
+            _ => Err(format!("unknown profiling mode: {s}")),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct MethodWithReceiver<'ctx> {
     receiver: MonoType<'ctx>,
@@ -1,10 +1,11 @@
 use std::cell::RefCell;
 use std::fmt;
+use std::path::Path;
 
 use indexmap::IndexSet;
 use redscript_compiler_api::ast::SourceMap;
 use redscript_compiler_api::{CompileErrorReporter, Diagnostics, SourceMapExt, TypeInterner};
-use redscript_compiler_backend::CompilationInputs;
+use redscript_compiler_backend::{CompilationInputs, Profiling};
 use redscript_compiler_frontend::infer_from_sources;
 use redscript_io::{
     CNameIndex, Class, ClassFlags, ClassIndex, EnumIndex, EnumValueIndex, FieldIndex,
@@ -17,37 +18,96 @@ fn bytecode() {
     insta::glob!("data/*.reds", |path| {
         let sources = SourceMap::from_files([path]).unwrap();
         sources.populate_boot_lib();
+        let interner = TypeInterner::default();
+
+        let (bundle, bundle_len) =
+            compile(path, &sources, &interner, false, false, Profiling::Disabled);
+        insta::assert_snapshot!(BytecodePrinter::new(&bundle, bundle_len));
+    });
+}
+
+#[test]
+fn optimized_bytecode() {
+    insta::glob!("data/optimized/*.reds", |path| {
+        let sources = SourceMap::from_files([path]).unwrap();
+        sources.populate_boot_lib();
+        let interner = TypeInterner::default();
+
+        let (bundle, bundle_len) =
+            compile(path, &sources, &interner, true, false, Profiling::Disabled);
+        insta::assert_snapshot!(BytecodePrinter::new(&bundle, bundle_len));
+    });
+}
+
+#[test]
+fn debug_bytecode() {
+    insta::glob!("data/debug/*.reds", |path| {
+        let sources = SourceMap::from_files([path]).unwrap();
+        sources.populate_boot_lib();
+        let interner = TypeInterner::default();
+
+        let (bundle, bundle_len) =
+            compile(path, &sources, &interner, false, true, Profiling::Disabled);
+        insta::assert_snapshot!(BytecodePrinter::new(&bundle, bundle_len));
+    });
+}
 
+#[test]
+fn profiled_bytecode() {
+    insta::glob!("data/profiled/*.reds", |path| {
+        let sources = SourceMap::from_files([path]).unwrap();
+        sources.populate_boot_lib();
         let interner = TypeInterner::default();
-        let mut bundle = ScriptBundle::default();
-
-        let name = bundle.cnames_mut().add("IScriptable");
-        bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
-
-        let (symbols, mappings) = CompilationInputs::load(&bundle, &interner)
-            .unwrap()
-            .into_inner();
-        let mut reporter = CompileErrorReporter::default();
-        let (unit, symbols) = infer_from_sources(&sources, symbols, &mut reporter, &interner);
-        let diagnostics = reporter.into_reported();
-        assert_eq!(
-            diagnostics.iter().filter(|d| d.is_fatal()).count(),
-            0,
-            "{}: {}",
-            Diagnostics::from(diagnostics),
-            path.display()
-        );
-
-        let bundle_len = bundle.definitions().len();
-
-        mappings
-            .into_monomorphizer(&sources)
-            .monomorphize(&unit, &symbols, &mut bundle)
-            .unwrap();
+
+        let profiling = Profiling::FunctionsAndLoops;
+        let (bundle, bundle_len) = compile(path, &sources, &interner, false, false, profiling);
         insta::assert_snapshot!(BytecodePrinter::new(&bundle, bundle_len));
     });
 }
 
+fn compile<'ctx>(
+    path: &Path,
+    sources: &'ctx SourceMap,
+    interner: &'ctx TypeInterner,
+    optimize: bool,
+    breakpoints: bool,
+    profiling: Profiling,
+) -> (ScriptBundle<'ctx>, usize) {
+    let mut bundle = ScriptBundle::default();
+
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+
+    let (symbols, mappings) = CompilationInputs::load(&bundle, interner)
+        .unwrap()
+        .into_inner();
+    let mut reporter = CompileErrorReporter::default();
+    let (mut unit, symbols) = infer_from_sources(sources, symbols, &mut reporter, interner);
+    let diagnostics = reporter.into_reported();
+    assert_eq!(
+        diagnostics.iter().filter(|d| d.is_fatal()).count(),
+        0,
+        "{}: {}",
+        Diagnostics::from(diagnostics),
+        path.display()
+    );
+
+    if optimize {
+        unit.optimize(&symbols);
+    }
+
+    let bundle_len = bundle.definitions().len();
+
+    mappings
+        .into_monomorphizer(sources)
+        .with_optimizations(optimize)
+        .with_breakpoints(breakpoints)
+        .with_profiling(profiling)
+        .monomorphize(&unit, &symbols, &mut bundle)
+        .unwrap();
+    (bundle, bundle_len)
+}
+
 #[derive(Debug)]
 struct BytecodePrinter<'a> {
     bundle: &'a ScriptBundle<'a>,
@@ -85,6 +145,18 @@ impl fmt::Display for BytecodePrinter<'_> {
                     Instr::CNameConst(idx) => {
                         writeln!(f, "cname.const {}", indices.name(idx))?;
                     }
+                    Instr::Breakpoint(bp) => {
+                        writeln!(f, "breakpoint l{} {}", bp.line(), bp.col())?;
+                    }
+                    Instr::Profile(profile) => {
+                        let kind = if profile.is_enabled() {
+                            "enter"
+                        } else {
+                            "exit"
+                        };
+                        let name = String::from_utf8_lossy(profile.function());
+                        writeln!(f, "profile.{kind} {name}")?;
+                    }
                     Instr::EnumConst { enum_, value } => {
                         writeln!(
                             f,
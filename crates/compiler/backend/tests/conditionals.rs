@@ -0,0 +1,188 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::cte::{Conditions, Define, Evaluator, Value};
+use redscript_compiler_api::{
+    Compilation, CompileErrorReporter, LintLevels, SourceMapExt, TypeInterner, parse_files,
+    process_sources,
+};
+use redscript_compiler_backend::CompilationInputs;
+use redscript_io::{Class, ClassFlags, Function, FunctionFlags, ScriptBundle, Visibility};
+
+const SOURCE: &str = r#"
+@if(TypeExists("IScriptable"))
+func TypeFound() {}
+
+@if(TypeExists("Missing"))
+func TypeMissing() {}
+
+@if(MethodExists("Derived", "Tick"))
+func InheritedMethodFound() {}
+
+@if(MethodExists("IScriptable", "Tock"))
+func MethodMissing() {}
+
+@if(FieldExists("IScriptable", "Tick"))
+func FieldMissing() {}
+
+@if(GameVersion() >= "2.12" && GameVersion() < "2.2")
+func VersionInRange() {}
+
+@if(GameVersion() <= "2.1")
+func VersionTooOld() {}
+
+@if(DEBUG && LEVEL > 1 && NAME == "test")
+func DefinesMatch() {}
+
+@if(!DEBUG || LEVEL != 2)
+func DefinesDoNotMatch() {}
+
+@if(DEBUG || UNDEFINED)
+func ShortCircuitOrMatch() {}
+
+@if(!DEBUG && UNDEFINED)
+func ShortCircuitAndDoesNotMatch() {}
+"#;
+
+#[test]
+fn conditional_compilation() {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", SOURCE);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+
+    let name = bundle.cnames_mut().add("IScriptable");
+    let base = bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let name = bundle.cnames_mut().add("Tick");
+    let method = Function::new(name, Visibility::Public, FunctionFlags::default());
+    let method = bundle.define(method.with_class(Some(base)));
+    bundle[base].add_method(method);
+
+    let name = bundle.cnames_mut().add("Derived");
+    let class = Class::new(name, Visibility::Public, ClassFlags::default()).with_base(Some(base));
+    bundle.define(class);
+
+    let (symbols, _) = CompilationInputs::load(&bundle, &interner)
+        .unwrap()
+        .into_inner();
+    let mut reporter = CompileErrorReporter::default();
+    let mods = parse_files(&sources, &mut reporter);
+    let defines = ["DEBUG", "LEVEL=2", "NAME=\"test\""].map(|str| str.parse().unwrap());
+    let evaluator = Evaluator::from_modules(&mods)
+        .with_defines(defines)
+        .with_game_version("2.12a".parse().unwrap());
+    let (_, symbols) = process_sources(mods, symbols, evaluator, &mut reporter, &interner);
+
+    let errors = reporter.into_reported();
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let mut included = symbols
+        .free_functions()
+        .filter_map(|entry| entry.name().as_single_component())
+        .filter(|name| {
+            ["Found", "Match", "Version"]
+                .iter()
+                .any(|s| name.contains(s))
+        })
+        .collect::<Vec<_>>();
+    included.sort_unstable();
+
+    assert_eq!(
+        included,
+        [
+            "DefinesMatch",
+            "InheritedMethodFound",
+            "ShortCircuitOrMatch",
+            "TypeFound",
+            "VersionInRange"
+        ]
+    );
+}
+
+#[test]
+fn game_version_is_passed_to_compilation() {
+    let sources = SourceMap::new();
+    sources.push_back(
+        "test.reds",
+        "@if(GameVersion() >= \"2.12\")\nfunc VersionFound() {}\n",
+    );
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let conditions = Conditions::new(vec![], Some("2.12a".parse().unwrap()));
+    let levels = LintLevels::default();
+    let comp =
+        Compilation::new_with(&bytes, &sources, &interner, &[], &levels, &conditions).unwrap();
+    assert!(
+        !comp.diagnostics().has_fatal_errors(),
+        "{}",
+        comp.diagnostics()
+    );
+    assert!(
+        comp.symbols()
+            .free_functions()
+            .any(|entry| entry.name().as_single_component() == Some("VersionFound"))
+    );
+
+    let comp = Compilation::new_with(
+        &bytes,
+        &sources,
+        &interner,
+        &[],
+        &levels,
+        &Conditions::default(),
+    )
+    .unwrap();
+    assert!(comp.diagnostics().has_fatal_errors());
+    assert!(
+        comp.diagnostics()
+            .to_string()
+            .contains("the game version is not known")
+    );
+}
+
+#[test]
+fn undefined_name_is_reported() {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", "@if(MISSING)\nfunc Test() {}\n");
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let bundle = ScriptBundle::default();
+    let (symbols, _) = CompilationInputs::load(&bundle, &interner)
+        .unwrap()
+        .into_inner();
+    let mut reporter = CompileErrorReporter::default();
+    let mods = parse_files(&sources, &mut reporter);
+    let evaluator = Evaluator::from_modules(&mods);
+    process_sources(mods, symbols, evaluator, &mut reporter, &interner);
+
+    let errors = reporter.into_reported();
+    let [error] = &errors[..] else {
+        panic!("expected a single error, got {errors:?}");
+    };
+    assert_eq!(error.code(), "CTE_ERR");
+    assert!(error.to_string().contains("'MISSING' is not defined"));
+}
+
+#[test]
+fn defines_are_parsed() {
+    let cases = [
+        ("DEBUG", Value::Bool(true)),
+        ("DEBUG=false", Value::Bool(false)),
+        ("LEVEL=-3", Value::Int(-3)),
+        ("NAME=test", Value::String("test".into())),
+        ("NAME=\"1\"", Value::String("1".into())),
+    ];
+    for (input, expected) in cases {
+        let define = input.parse::<Define>().unwrap();
+        assert_eq!(define.value(), &expected, "{input}");
+    }
+    assert!("1NAME".parse::<Define>().is_err());
+    assert!("=1".parse::<Define>().is_err());
+}
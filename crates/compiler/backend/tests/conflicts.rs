@@ -0,0 +1,87 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{CompileErrorReporter, SourceMapExt, TypeInterner};
+use redscript_compiler_backend::{CompilationInputs, ModConflict, ModConflicts};
+use redscript_compiler_frontend::infer_from_sources;
+use redscript_io::{Class, ClassFlags, Function, FunctionFlags, ScriptBundle, Visibility};
+
+const FIRST_MOD: &str = r#"
+@replaceMethod(IScriptable)
+func Tick() {}
+
+@wrapMethod(IScriptable)
+func Tock() {
+  wrappedMethod();
+}
+
+@addField(IScriptable)
+let counter: Int32;
+
+@addMethod(IScriptable)
+func Greet() {}
+"#;
+
+const SECOND_MOD: &str = r#"
+@replaceMethod(IScriptable)
+func Tick() {}
+
+@replaceMethod(IScriptable)
+func Tock() {}
+
+@addField(IScriptable)
+let counter: Int32;
+
+@addMethod(IScriptable)
+func Greet() {}
+"#;
+
+#[test]
+fn conflicting_annotations() {
+    let sources = SourceMap::new();
+    sources.push_back("first.reds", FIRST_MOD);
+    sources.push_back("second.reds", SECOND_MOD);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+
+    let name = bundle.cnames_mut().add("IScriptable");
+    let class = bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    for name in ["Tick", "Tock"] {
+        let name = bundle.cnames_mut().add(name);
+        let method = Function::new(name, Visibility::Public, FunctionFlags::default());
+        let method = bundle.define(method.with_class(Some(class)));
+        bundle[class].add_method(method);
+    }
+
+    let (symbols, _) = CompilationInputs::load(&bundle, &interner)
+        .unwrap()
+        .into_inner();
+    let mut reporter = CompileErrorReporter::default();
+    let (unit, symbols) = infer_from_sources(&sources, symbols, &mut reporter, &interner);
+
+    let conflicts = ModConflicts::new(&unit, &symbols);
+    let mut found = conflicts
+        .iter()
+        .map(|conflict| {
+            let kind = match conflict {
+                ModConflict::MultipleReplacements { .. } => "replace",
+                ModConflict::ReplacedWrappedMethod { .. } => "replace-wrap",
+                ModConflict::DuplicateAddition { .. } => "add",
+            };
+            (kind, conflict.target())
+        })
+        .collect::<Vec<_>>();
+    found.sort_unstable();
+
+    assert_eq!(
+        found,
+        [
+            ("add", "IScriptable.Greet"),
+            ("add", "IScriptable.counter"),
+            ("replace", "IScriptable.Tick"),
+            ("replace-wrap", "IScriptable.Tock"),
+        ],
+        "{}",
+        conflicts.report(&sources)
+    );
+}
@@ -1,15 +1,27 @@
-use hashbrown::HashSet;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use hashbrown::{HashMap, HashSet};
 use redscript_ast::{self as ast, Span, Spanned};
 use thiserror::Error;
 
+use crate::{Aggregate, Symbols, TypeId};
+
 #[derive(Debug)]
 pub struct Evaluator<'a> {
     modules: HashSet<Box<[&'a str]>>,
+    defines: HashMap<String, Value>,
+    game_version: Option<GameVersion>,
 }
 
 impl<'a> Evaluator<'a> {
     pub fn new(modules: HashSet<Box<[&'a str]>>) -> Self {
-        Self { modules }
+        Self {
+            modules,
+            defines: HashMap::new(),
+            game_version: None,
+        }
     }
 
     pub fn from_modules<'b>(modules: impl IntoIterator<Item = &'b ast::SourceModule<'a>>) -> Self
@@ -21,58 +33,391 @@ impl<'a> Evaluator<'a> {
             .filter_map(|m| m.path.as_ref())
             .map(|path| path.segments.clone())
             .collect::<HashSet<_>>();
-        Self { modules }
+        Self::new(modules)
+    }
+
+    /// Defines a name that can be referenced in the evaluated expressions.
+    pub fn with_define(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.defines.insert(name.into(), value);
+        self
+    }
+
+    /// Defines all of the provided names, later definitions override earlier ones.
+    pub fn with_defines(mut self, defines: impl IntoIterator<Item = Define>) -> Self {
+        self.defines
+            .extend(defines.into_iter().map(|def| (def.name, def.value)));
+        self
+    }
+
+    /// Sets the version returned by `GameVersion()`.
+    pub fn with_game_version(mut self, version: GameVersion) -> Self {
+        self.game_version = Some(version);
+        self
+    }
+
+    /// Applies the defines and the game version provided by the environment.
+    pub fn with_conditions(self, conditions: &Conditions) -> Self {
+        let evaluator = self.with_defines(conditions.defines.iter().cloned());
+        match &conditions.game_version {
+            Some(version) => evaluator.with_game_version(version.clone()),
+            None => evaluator,
+        }
     }
 
-    pub fn eval(&self, (expr, span): &Spanned<ast::SourceExpr<'_>>) -> Result<Value, Error> {
+    /// Evaluates an expression, the type and member predicates are resolved against
+    /// the provided symbols.
+    pub fn eval(
+        &self,
+        (expr, span): &Spanned<ast::SourceExpr<'_>>,
+        symbols: &Symbols<'_>,
+    ) -> Result<Value, Error> {
         let res = match expr {
-            ast::Expr::Constant(ast::Constant::Bool(b)) => Value::Bool(*b),
-            ast::Expr::BinOp { lhs, op, rhs } => match (self.eval(lhs)?, self.eval(rhs)?) {
-                (Value::Bool(lhs), Value::Bool(rhs)) => match op {
-                    ast::BinOp::And => Value::Bool(lhs && rhs),
-                    ast::BinOp::Or => Value::Bool(lhs || rhs),
-                    _ => return Err(Error::UnsupportedOperation(*span)),
-                },
-            },
-            ast::Expr::UnOp { op, expr } => match (op, self.eval(expr)?) {
+            ast::Expr::Constant(constant) => {
+                Value::from_constant(constant).ok_or(Error::UnsupportedOperation(*span))?
+            }
+            &ast::Expr::Ident(name) => self
+                .defines
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Error::UndefinedName(name.into(), *span))?,
+            ast::Expr::BinOp { lhs, op, rhs } => {
+                let lhs = self.eval(lhs, symbols)?;
+                // the right-hand side is skipped when it cannot change the result, so that
+                // it can refer to names that are only defined in some configurations
+                match (op, &lhs) {
+                    (ast::BinOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (ast::BinOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                match (op, lhs, self.eval(rhs, symbols)?) {
+                    (ast::BinOp::And | ast::BinOp::Or, Value::Bool(_), Value::Bool(rhs)) => {
+                        Value::Bool(rhs)
+                    }
+                    (op, lhs, rhs) => {
+                        let ordering = lhs.compare(&rhs, *span)?;
+                        let res = match op {
+                            ast::BinOp::Eq => ordering.is_eq(),
+                            ast::BinOp::Ne => ordering.is_ne(),
+                            ast::BinOp::Lt => ordering.is_lt(),
+                            ast::BinOp::Le => ordering.is_le(),
+                            ast::BinOp::Gt => ordering.is_gt(),
+                            ast::BinOp::Ge => ordering.is_ge(),
+                            _ => return Err(Error::UnsupportedOperation(*span)),
+                        };
+                        Value::Bool(res)
+                    }
+                }
+            }
+            ast::Expr::UnOp { op, expr } => match (op, self.eval(expr, symbols)?) {
                 (ast::UnOp::Not, Value::Bool(b)) => Value::Bool(!b),
+                (ast::UnOp::Neg, Value::Int(i)) => {
+                    Value::Int(i.checked_neg().ok_or(Error::UnsupportedOperation(*span))?)
+                }
                 _ => return Err(Error::UnsupportedOperation(*span)),
             },
             ast::Expr::Call {
                 expr,
                 type_args,
                 args,
-            } => match (&**expr, &**type_args, &**args) {
-                (
-                    (ast::Expr::Ident("ModuleExists"), _),
-                    [],
-                    [(ast::Expr::Constant(ast::Constant::String(str)), _)],
-                ) => {
-                    let path = str.split('.').collect::<Vec<_>>();
-                    Value::Bool(self.modules.contains(&path[..]))
+            } => {
+                let (ast::Expr::Ident(name), _) = &**expr else {
+                    return Err(Error::UnsupportedOperation(*span));
+                };
+                if !type_args.is_empty() {
+                    return Err(Error::UnsupportedOperation(*span));
                 }
-                _ => return Err(Error::UnsupportedOperation(*span)),
-            },
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg, symbols))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(name, &args, symbols, *span)?
+            }
             _ => return Err(Error::UnsupportedOperation(*span)),
         };
         Ok(res)
     }
+
+    fn call(
+        &self,
+        name: &str,
+        args: &[Value],
+        symbols: &Symbols<'_>,
+        span: Span,
+    ) -> Result<Value, Error> {
+        let res = match (name, args) {
+            ("ModuleExists", [Value::String(str)]) => {
+                let path = str.split('.').collect::<Vec<_>>();
+                self.modules.contains(&path[..])
+            }
+            ("TypeExists", [Value::String(name)]) => find_type(symbols, name).is_some(),
+            ("MethodExists", [Value::String(typ), Value::String(method)]) => {
+                aggregates(symbols, typ)
+                    .any(|agg| agg.methods().by_name(method.as_str()).next().is_some())
+            }
+            ("FieldExists", [Value::String(typ), Value::String(field)]) => {
+                aggregates(symbols, typ).any(|agg| agg.fields().by_name(field).is_some())
+            }
+            ("GameVersion", []) => {
+                let version = self.game_version.clone();
+                return version
+                    .map(Value::Version)
+                    .ok_or(Error::UnknownGameVersion(span));
+            }
+            _ => return Err(Error::UnsupportedOperation(span)),
+        };
+        Ok(Value::Bool(res))
+    }
 }
 
+fn find_type<'ctx>(symbols: &Symbols<'ctx>, name: &str) -> Option<TypeId<'ctx>> {
+    symbols
+        .types()
+        .find(|(id, _)| id.as_str() == name)
+        .map(|(id, _)| id)
+}
+
+/// Returns the aggregate with the given name followed by all of its base types.
+fn aggregates<'sym, 'ctx>(
+    symbols: &'sym Symbols<'ctx>,
+    name: &str,
+) -> impl Iterator<Item = &'sym Aggregate<'ctx>> {
+    find_type(symbols, name)
+        .into_iter()
+        .flat_map(|id| symbols.base_iter(id))
+        .filter_map(|(_, def)| def.schema().as_aggregate())
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Bool(bool),
+    Int(i64),
+    String(String),
+    Version(GameVersion),
+}
+
+impl Value {
+    fn from_constant(constant: &ast::Constant<'_>) -> Option<Self> {
+        let res = match constant {
+            ast::Constant::Bool(b) => Self::Bool(*b),
+            ast::Constant::I32(i, _) => Self::Int((*i).into()),
+            ast::Constant::I64(i, _) => Self::Int(*i),
+            ast::Constant::U32(i, _) => Self::Int((*i).into()),
+            ast::Constant::U64(i, _) => Self::Int(i64::try_from(*i).ok()?),
+            ast::Constant::String(str) => Self::String(str.clone().into_owned()),
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    fn compare(&self, other: &Self, span: Span) -> Result<Ordering, Error> {
+        let res = match (self, other) {
+            (Self::Bool(lhs), Self::Bool(rhs)) => lhs.cmp(rhs),
+            (Self::Int(lhs), Self::Int(rhs)) => lhs.cmp(rhs),
+            (Self::String(lhs), Self::String(rhs)) => lhs.cmp(rhs),
+            (Self::Version(lhs), Self::Version(rhs)) => lhs.cmp(rhs),
+            // versions are written as string literals, e.g. `GameVersion() >= "2.12"`
+            (Self::Version(lhs), Self::String(rhs)) => {
+                let rhs = rhs
+                    .parse::<GameVersion>()
+                    .map_err(|_| Error::InvalidGameVersion(span))?;
+                lhs.cmp(&rhs)
+            }
+            (Self::String(_), Self::Version(_)) => other.compare(self, span)?.reverse(),
+            _ => return Err(Error::IncomparableValues(span)),
+        };
+        Ok(res)
+    }
+}
+
+/// A name defined for conditional compilation, written as `NAME` or `NAME=value`.
+///
+/// A bare name is defined as `true`, otherwise the value is parsed as a boolean,
+/// an integer or a string, in that order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Define {
+    name: String,
+    value: Value,
+}
+
+impl Define {
+    pub fn new(name: impl Into<String>, value: Value) -> Self {
+        Self {
+            name: name.into(),
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+impl FromStr for Define {
+    type Err = InvalidDefine;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (s.trim(), None),
+        };
+
+        let mut chars = name.chars();
+        let is_ident = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_ident {
+            return Err(InvalidDefine(s.into()));
+        }
+
+        let value = match value {
+            None | Some("true") => Value::Bool(true),
+            Some("false") => Value::Bool(false),
+            Some(value) => match value.parse::<i64>() {
+                Ok(i) => Value::Int(i),
+                Err(_) => {
+                    let unquoted = value
+                        .strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .unwrap_or(value);
+                    Value::String(unquoted.to_owned())
+                }
+            },
+        };
+        Ok(Self::new(name, value))
+    }
+}
+
+/// The inputs of conditional compilation that come from outside of the sources, i.e. the
+/// defined names and the version of the game the sources are compiled against.
+#[derive(Debug, Default, Clone)]
+pub struct Conditions {
+    defines: Vec<Define>,
+    game_version: Option<GameVersion>,
+}
+
+impl Conditions {
+    pub fn new(defines: Vec<Define>, game_version: Option<GameVersion>) -> Self {
+        Self {
+            defines,
+            game_version,
+        }
+    }
+
+    pub fn defines(&self) -> &[Define] {
+        &self.defines
+    }
+
+    pub fn game_version(&self) -> Option<&GameVersion> {
+        self.game_version.as_ref()
+    }
+}
+
+/// A game version like `2.12` or `2.12a`.
+///
+/// The part after the dot is compared as a decimal fraction followed by an optional
+/// hotfix suffix, so that `2.01 < 2.1 < 2.1a < 2.12 < 2.12a < 2.2` and `2.10 == 2.1`.
+#[derive(Debug, Clone)]
+pub struct GameVersion {
+    major: u32,
+    minor: Box<str>,
+}
+
+impl GameVersion {
+    // trailing zeros of the fraction are insignificant, so with them removed the digits
+    // compare lexicographically as if both sides were padded to the same length
+    fn key(&self) -> (u32, &str, &str) {
+        let suffix_start = self
+            .minor
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(self.minor.len());
+        let (digits, suffix) = self.minor.split_at(suffix_start);
+        (self.major, digits.trim_end_matches('0'), suffix)
+    }
+}
+
+impl PartialEq for GameVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for GameVersion {}
+
+impl PartialOrd for GameVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GameVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = InvalidGameVersion;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.split_once('.').unwrap_or((s, ""));
+        let major = major.parse().map_err(|_| InvalidGameVersion)?;
+
+        let digits = minor.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(InvalidGameVersion);
+        }
+        Ok(Self {
+            major,
+            minor: minor.into(),
+        })
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.minor.is_empty() {
+            write!(f, "{}", self.major)
+        } else {
+            write!(f, "{}.{}", self.major, self.minor)
+        }
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("unsupported operation")]
     UnsupportedOperation(Span),
+    #[error("'{0}' is not defined, it can be set with -D or in the defines of a .redscript file")]
+    UndefinedName(Box<str>, Span),
+    #[error("these values cannot be compared")]
+    IncomparableValues(Span),
+    #[error("the game version is not known in this context")]
+    UnknownGameVersion(Span),
+    #[error("invalid game version, expected a version like \"2.12\"")]
+    InvalidGameVersion(Span),
 }
 
 impl Error {
     pub fn span(&self) -> Span {
         match self {
-            Self::UnsupportedOperation(span) => *span,
+            Self::UnsupportedOperation(span)
+            | Self::IncomparableValues(span)
+            | Self::UnknownGameVersion(span)
+            | Self::InvalidGameVersion(span)
+            | Self::UndefinedName(_, span) => *span,
         }
     }
 }
+
+#[derive(Debug, Error)]
+#[error("invalid game version, expected a version like \"2.12\"")]
+pub struct InvalidGameVersion;
+
+#[derive(Debug, Error)]
+#[error("invalid define '{0}', expected NAME or NAME=value")]
+pub struct InvalidDefine(Box<str>);
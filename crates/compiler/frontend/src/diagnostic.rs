@@ -9,8 +9,11 @@ use crate::stages::FunctionAnnotation;
 use crate::utils::fmt::{DisplayFn, lowercase, sep_by};
 use crate::{CoalesceError, LowerError, Param, PolyType, Type, TypeId, Variance, cte, predef};
 
+mod levels;
 pub mod pass;
 
+pub use levels::{LintLevel, LintLevels, LintScope};
+
 #[derive(Debug, Error)]
 pub enum Diagnostic<'ctx> {
     #[error("{0}")]
@@ -39,6 +42,8 @@ pub enum Diagnostic<'ctx> {
     InvalidBaseType(Span),
     #[error("'{0}' is not a valid annotation in this context")]
     UnknownAnnotation(&'ctx str, Span),
+    #[error("'{0}' is not a known diagnostic code, it will be ignored")]
+    UnknownLintCode(&'ctx str, Span),
     #[error("'{0}' could not be found")]
     ImportNotFound(&'ctx str, Span),
     #[error("this name is already defined in the scope")]
@@ -91,8 +96,31 @@ pub enum Diagnostic<'ctx> {
     InvalidImplType(Span),
     #[error("this implementation is a duplicate of a previous one")]
     DuplicateImpl(Span),
+    #[error(
+        "default values are not allowed on parameters of native functions or annotated methods"
+    )]
+    UnexpectedParamDefault(Span),
+    #[error("parameters with default values cannot be followed by required parameters")]
+    NonTrailingParamDefault(Span),
     #[error("unused variable")]
     UnusedLocal(Span),
+    #[error("this code is unreachable")]
+    UnreachableCode(Span),
+    #[error("this function does not return a value on all paths")]
+    MissingReturn(Span),
+    #[error("this reference may be null here, check it with IsDefined first")]
+    PossiblyNullAccess(Span),
+    #[error("this weak reference may be null here, check it with IsDefined first")]
+    PossiblyNullWeakAccess(Span),
+    #[error(
+        "this switch does not handle the following variants of {0}: {}",
+        sep_by(.1.iter(), ", ")
+    )]
+    NonExhaustiveSwitch(TypeId<'ctx>, Box<[&'ctx str]>, Span),
+    #[error("this case label repeats an earlier one and can never be matched")]
+    DuplicateCaseLabel(Span),
+    #[error("{0}")]
+    Denied(Box<Self>),
     #[error("{0}")]
     Other(Box<dyn std::error::Error + 'ctx>, Span),
 }
@@ -101,11 +129,18 @@ impl<'ctx> Diagnostic<'ctx> {
     pub fn is_fatal(&self) -> bool {
         match self {
             Self::TypeError(err) => err.is_fatal(),
+            Self::Denied(_) => true,
             Self::UnusedItemQualifiers(_, _)
             | Self::DuplicateVariantValue(_)
             | Self::DuplicateVariantName(_)
             | Self::FinalMethodOverride(_, _)
-            | Self::UnusedLocal(_) => false,
+            | Self::UnknownLintCode(_, _)
+            | Self::UnusedLocal(_)
+            | Self::UnreachableCode(_)
+            | Self::PossiblyNullAccess(_)
+            | Self::PossiblyNullWeakAccess(_)
+            | Self::NonExhaustiveSwitch(_, _, _)
+            | Self::DuplicateCaseLabel(_) => false,
             _ => true,
         }
     }
@@ -115,6 +150,7 @@ impl<'ctx> Diagnostic<'ctx> {
             Self::SyntaxError(err) => err.span(),
             Self::TypeError(err) => err.span(),
             Self::EvalFailed(err) => err.span(),
+            Self::Denied(inner) => inner.span(),
             Self::CoalesceError(_, span)
             | Self::DuplicateVariantName(span)
             | Self::DuplicateVariantValue(span)
@@ -126,6 +162,7 @@ impl<'ctx> Diagnostic<'ctx> {
             | Self::UnexpectedItem(span)
             | Self::InvalidBaseType(span)
             | Self::UnknownAnnotation(_, span)
+            | Self::UnknownLintCode(_, span)
             | Self::ImportNotFound(_, span)
             | Self::NameRedefinition(span)
             | Self::InvalidAnnotationType(_, span)
@@ -151,7 +188,15 @@ impl<'ctx> Diagnostic<'ctx> {
             | Self::InvalidImplName(span)
             | Self::InvalidImplType(span)
             | Self::DuplicateImpl(span)
+            | Self::UnexpectedParamDefault(span)
+            | Self::NonTrailingParamDefault(span)
             | Self::UnusedLocal(span)
+            | Self::UnreachableCode(span)
+            | Self::MissingReturn(span)
+            | Self::PossiblyNullAccess(span)
+            | Self::PossiblyNullWeakAccess(span)
+            | Self::NonExhaustiveSwitch(_, _, span)
+            | Self::DuplicateCaseLabel(span)
             | Self::Other(_, span) => *span,
         }
     }
@@ -178,6 +223,7 @@ impl<'ctx> Diagnostic<'ctx> {
             | Self::IncompatibleAnnotations(_)
             | Self::UserSymbolAnnotation(_)
             | Self::GenericMethodAnnotation(_) => "INVALID_ANN_USE",
+            Self::UnknownLintCode(_, _) => "UNKNOWN_LINT_CODE",
             Self::ImportNotFound(_, _) => "UNRESOLVED_IMPORT",
             Self::NameRedefinition(_) => "SYM_REDEFINITION",
             Self::MissingMethodImpls(_, _) => "MISSING_IMPL",
@@ -197,7 +243,16 @@ impl<'ctx> Diagnostic<'ctx> {
             Self::InvalidImplName(_) => "INVALID_IMPL_NAME",
             Self::InvalidImplType(_) => "INVALID_IMPL_TYPE",
             Self::DuplicateImpl(_) => "DUP_IMPL",
+            Self::UnexpectedParamDefault(_) | Self::NonTrailingParamDefault(_) => {
+                "INVALID_PARAM_DEFAULT"
+            }
             Self::UnusedLocal(_) => "UNUSED_LOCAL",
+            Self::UnreachableCode(_) => "UNREACHABLE_CODE",
+            Self::MissingReturn(_) => "MISSING_RETURN",
+            Self::PossiblyNullAccess(_) | Self::PossiblyNullWeakAccess(_) => "NULL_ACCESS",
+            Self::NonExhaustiveSwitch(_, _, _) => "NON_EXHAUSTIVE_SWITCH",
+            Self::DuplicateCaseLabel(_) => "DUP_CASE_LABEL",
+            Self::Denied(inner) => inner.code(),
             Self::Other(_, _) => "OTHER",
         }
     }
@@ -324,7 +379,7 @@ impl fmt::Display for MissingMethod<'_> {
 
 #[derive(Debug, Error)]
 #[error("the source of a diagnostic could not be determined (span: {0})")]
-pub struct UnknownSource(Span);
+pub struct UnknownSource(pub Span);
 
 pub trait ErrorWithSpan {
     type Result;
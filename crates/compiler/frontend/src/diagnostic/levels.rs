@@ -0,0 +1,105 @@
+use hashbrown::HashMap;
+use redscript_ast::Span;
+
+use super::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    /// The diagnostic is not reported.
+    Allow,
+    /// The diagnostic is reported as a warning.
+    Warn,
+    /// The diagnostic is reported as an error and fails the compilation.
+    Deny,
+}
+
+/// Levels assigned to diagnostic codes, keyed by codes like `UNUSED_LOCAL`.
+///
+/// Only the level of warnings can be lowered, errors are always reported regardless of
+/// their configured level.
+#[derive(Debug, Default, Clone)]
+pub struct LintLevels {
+    levels: HashMap<String, LintLevel>,
+}
+
+impl LintLevels {
+    /// Codes of the diagnostics that are reported as warnings, only these can be assigned
+    /// a level.
+    pub const KNOWN_CODES: &[&str] = &[
+        "DUP_VARIANT_NAME",
+        "DUP_VARIANT_VAL",
+        "UNUSED_ITEM_QUALIFIERS",
+        "FINAL_FN_OVERRIDE",
+        "DEPRECATED_SYNTAX",
+        "UNUSED_LOCAL",
+        "UNREACHABLE_CODE",
+        "NULL_ACCESS",
+        "NON_EXHAUSTIVE_SWITCH",
+        "DUP_CASE_LABEL",
+    ];
+
+    pub fn is_known_code(code: &str) -> bool {
+        Self::KNOWN_CODES.contains(&code)
+    }
+
+    pub fn set(&mut self, code: impl Into<String>, level: LintLevel) {
+        self.levels.insert(code.into(), level);
+    }
+
+    pub fn get(&self, code: &str) -> Option<LintLevel> {
+        self.levels.get(code).copied()
+    }
+
+    /// Applies the levels to the diagnostics, levels set on the items through annotations
+    /// take precedence over the global ones. Allowed warnings are removed and denied ones
+    /// become fatal.
+    pub fn apply<'ctx>(
+        &self,
+        diagnostics: Vec<Diagnostic<'ctx>>,
+        scopes: &[LintScope<'_>],
+    ) -> Vec<Diagnostic<'ctx>> {
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| {
+                if diagnostic.is_fatal() {
+                    return Some(diagnostic);
+                }
+                match self.resolve(&diagnostic, scopes) {
+                    Some(LintLevel::Allow) => None,
+                    Some(LintLevel::Deny) => Some(Diagnostic::Denied(diagnostic.into())),
+                    Some(LintLevel::Warn) | None => Some(diagnostic),
+                }
+            })
+            .collect()
+    }
+
+    fn resolve(&self, diagnostic: &Diagnostic<'_>, scopes: &[LintScope<'_>]) -> Option<LintLevel> {
+        let code = diagnostic.code();
+        let span = diagnostic.span();
+        scopes
+            .iter()
+            .filter(|scope| scope.code == code && scope.encloses(span))
+            .min_by_key(|scope| scope.span.end - scope.span.start)
+            .map(|scope| scope.level)
+            .or_else(|| self.get(code))
+    }
+}
+
+/// A level set for a diagnostic code with an annotation, it applies to all diagnostics
+/// within the span of the annotated item.
+#[derive(Debug, Clone)]
+pub struct LintScope<'ctx> {
+    code: &'ctx str,
+    level: LintLevel,
+    span: Span,
+}
+
+impl<'ctx> LintScope<'ctx> {
+    pub fn new(code: &'ctx str, level: LintLevel, span: Span) -> Self {
+        Self { code, level, span }
+    }
+
+    fn encloses(&self, span: Span) -> bool {
+        self.span.file == span.file && self.span.start <= span.start && span.end <= self.span.end
+    }
+}
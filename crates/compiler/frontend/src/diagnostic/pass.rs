@@ -1,9 +1,20 @@
-use crate::{CompileErrorReporter, LoweredFunction};
+use crate::{CompileErrorReporter, LoweredFunction, Symbols};
 
+mod control_flow;
+mod exhaustiveness;
+mod null_safety;
 mod unused_locals;
 
+pub use control_flow::ControlFlow;
+pub use exhaustiveness::SwitchExhaustiveness;
+pub use null_safety::NullSafety;
 pub use unused_locals::UnusedLocals;
 
 pub trait DiagnosticPass<'ctx> {
-    fn run(&self, func: &LoweredFunction<'ctx>, reporter: &mut CompileErrorReporter<'ctx>);
+    fn run(
+        &self,
+        func: &LoweredFunction<'ctx>,
+        symbols: &Symbols<'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    );
 }
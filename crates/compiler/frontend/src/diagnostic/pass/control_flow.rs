@@ -0,0 +1,156 @@
+use std::mem;
+
+use redscript_ast::Span;
+
+use super::DiagnosticPass;
+use crate::visitor::Visitor;
+use crate::{CompileErrorReporter, Diagnostic, LoweredFunction, Symbols, Type, ir, predef};
+
+/// Reports statements that can never be executed and non-void functions that can
+/// reach the end of their body without returning a value.
+#[derive(Debug, Default)]
+pub struct ControlFlow;
+
+impl<'ctx> DiagnosticPass<'ctx> for ControlFlow {
+    fn run(
+        &self,
+        func: &LoweredFunction<'ctx>,
+        _symbols: &Symbols<'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    ) {
+        let mut visitor = ControlFlowVisitor::default();
+        visitor.visit_block(&func.block);
+
+        for span in visitor.unreachable {
+            reporter.report(Diagnostic::UnreachableCode(span));
+        }
+
+        let returns_value = match &func.return_type {
+            Type::Data(app) => app.id() != predef::VOID,
+            Type::Nothing => false,
+            Type::Ctx(_) => true,
+        };
+        if visitor.reachable && returns_value {
+            reporter.report(Diagnostic::MissingReturn(func.span));
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ControlFlowVisitor {
+    /// Whether the statement being visited can be reached.
+    reachable: bool,
+    /// Enclosing loops and switches, with a flag set when a reachable `break` exits them.
+    break_targets: Vec<bool>,
+    unreachable: Vec<Span>,
+}
+
+impl Default for ControlFlowVisitor {
+    fn default() -> Self {
+        Self {
+            reachable: true,
+            break_targets: vec![],
+            unreachable: vec![],
+        }
+    }
+}
+
+impl<'ctx> Visitor<'ctx> for ControlFlowVisitor {
+    fn visit_block(&mut self, block: &ir::Block<'ctx>) {
+        for stmt in &block.stmts {
+            if !self.reachable {
+                // report the rest of the block at once, any breaks in it cannot be taken
+                let last = block.stmts.back().map_or(stmt.span(), ir::Stmt::span);
+                self.unreachable.push(stmt.span().merge(&last));
+                return;
+            }
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, while_loop: &ir::ConditionalBlock<'ctx>, _span: Span) {
+        self.visit_expr(&while_loop.condition);
+
+        self.break_targets.push(false);
+        self.visit_block(&while_loop.block);
+        let broken = self.break_targets.pop().unwrap_or_default();
+
+        let infinite = matches!(
+            while_loop.condition,
+            ir::Expr::Const(ir::Const::Bool(true), _)
+        );
+        self.reachable = !infinite || broken;
+    }
+
+    fn visit_branches(
+        &mut self,
+        branches: &[ir::ConditionalBlock<'ctx>],
+        default: Option<&ir::Block<'ctx>>,
+        _span: Span,
+    ) {
+        let mut completes = default.is_none();
+        for branch in branches {
+            self.reachable = true;
+            self.visit_expr(&branch.condition);
+            self.visit_block(&branch.block);
+            completes |= self.reachable;
+        }
+        if let Some(block) = default {
+            self.reachable = true;
+            self.visit_block(block);
+            completes |= self.reachable;
+        }
+        self.reachable = completes;
+    }
+
+    fn visit_switch(
+        &mut self,
+        scrutinee: &ir::Expr<'ctx>,
+        _scrutinee_type: &ir::Type<'ctx>,
+        branches: &[ir::Case<'ctx>],
+        default: Option<&ir::Block<'ctx>>,
+        _span: Span,
+    ) {
+        self.visit_expr(scrutinee);
+        self.break_targets.push(false);
+
+        // every case can be entered through its label, falling through from the previous
+        // case only matters for the last one, which falls out of the switch
+        for branch in branches {
+            self.reachable = true;
+            self.visit_expr(&branch.matcher);
+            self.visit_block(&branch.block);
+        }
+        if let Some(block) = default {
+            self.reachable = true;
+            self.visit_block(block);
+        }
+
+        let broken = self.break_targets.pop().unwrap_or_default();
+        self.reachable |= broken || default.is_none();
+    }
+
+    fn visit_break(&mut self, _span: Span) {
+        if let Some(broken) = self.break_targets.last_mut() {
+            *broken = true;
+        }
+        self.reachable = false;
+    }
+
+    fn visit_continue(&mut self, _span: Span) {
+        self.reachable = false;
+    }
+
+    fn visit_return(&mut self, expr: Option<&ir::Expr<'ctx>>, _span: Span) {
+        expr.inspect(|expr| self.visit_expr(expr));
+        self.reachable = false;
+    }
+
+    fn visit_new_closure(&mut self, closure: &ir::Closure<'ctx>, _span: Span) {
+        let reachable = mem::replace(&mut self.reachable, true);
+        let targets = mem::take(&mut self.break_targets);
+        self.visit_block(&closure.block);
+        self.reachable = reachable;
+        self.break_targets = targets;
+    }
+}
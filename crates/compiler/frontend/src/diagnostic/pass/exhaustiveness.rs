@@ -0,0 +1,101 @@
+use hashbrown::HashSet;
+use redscript_ast::Span;
+
+use super::DiagnosticPass;
+use crate::visitor::Visitor;
+use crate::{CompileErrorReporter, Diagnostic, LoweredFunction, Symbols, Type, ir};
+
+/// Reports `switch` statements over enums that neither handle every variant nor have
+/// a `default` case, along with case labels that repeat an earlier one.
+///
+/// Variants are compared by value, so a label also covers the other variants that share
+/// its value.
+#[derive(Debug, Default)]
+pub struct SwitchExhaustiveness;
+
+impl<'ctx> DiagnosticPass<'ctx> for SwitchExhaustiveness {
+    fn run(
+        &self,
+        func: &LoweredFunction<'ctx>,
+        symbols: &Symbols<'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    ) {
+        let mut visitor = SwitchVisitor { symbols, reporter };
+        visitor.visit_block(&func.block);
+    }
+}
+
+struct SwitchVisitor<'a, 'ctx> {
+    symbols: &'a Symbols<'ctx>,
+    reporter: &'a mut CompileErrorReporter<'ctx>,
+}
+
+impl<'ctx> SwitchVisitor<'_, 'ctx> {
+    fn check_switch(
+        &mut self,
+        scrutinee: &ir::Expr<'ctx>,
+        scrutinee_type: &ir::Type<'ctx>,
+        branches: &[ir::Case<'ctx>],
+        has_default: bool,
+    ) {
+        let Ok(Type::Data(app)) = scrutinee_type.coalesced(self.symbols) else {
+            return;
+        };
+        let Some(enum_) = self
+            .symbols
+            .get_type(app.id())
+            .and_then(|def| def.schema().as_enum())
+        else {
+            return;
+        };
+
+        let mut covered = HashSet::new();
+        for branch in branches {
+            let &ir::Expr::Const(ir::Const::EnumVariant(variant), span) = &branch.matcher else {
+                continue;
+            };
+            let Some((_, value)) = self.symbols.get_enum_variant(variant) else {
+                continue;
+            };
+            if !covered.insert(value) {
+                self.reporter.report(Diagnostic::DuplicateCaseLabel(span));
+            }
+        }
+
+        if has_default {
+            return;
+        }
+        let missing = enum_
+            .variants()
+            .filter(|(_, value)| !covered.contains(value))
+            .map(|(name, _)| name)
+            .collect::<Box<[_]>>();
+        if !missing.is_empty() {
+            self.reporter.report(Diagnostic::NonExhaustiveSwitch(
+                app.id(),
+                missing,
+                scrutinee.span(),
+            ));
+        }
+    }
+}
+
+impl<'ctx> Visitor<'ctx> for SwitchVisitor<'_, 'ctx> {
+    fn visit_switch(
+        &mut self,
+        scrutinee: &ir::Expr<'ctx>,
+        scrutinee_type: &ir::Type<'ctx>,
+        branches: &[ir::Case<'ctx>],
+        default: Option<&ir::Block<'ctx>>,
+        _span: Span,
+    ) {
+        self.check_switch(scrutinee, scrutinee_type, branches, default.is_some());
+
+        self.visit_expr(scrutinee);
+        for branch in branches {
+            self.visit_expr(&branch.matcher);
+            self.visit_block(&branch.block);
+        }
+        default.inspect(|block| self.visit_block(block));
+    }
+}
@@ -0,0 +1,475 @@
+use std::mem;
+
+use hashbrown::HashSet;
+use redscript_ast::{self as ast, Span};
+
+use super::DiagnosticPass;
+use crate::visitor::Visitor;
+use crate::{
+    CompileErrorReporter, Diagnostic, FieldId, FreeFunctionIndex, LoweredFunction, RefType,
+    Symbols, TypeId, ir, predef,
+};
+
+/// Reports member accesses on class references that may be null at the point of
+/// the access.
+///
+/// A reference is known to be defined after it has been checked with `IsDefined`,
+/// matched by an `if let` or `case let` pattern, or assigned a value that cannot be
+/// null, like a `new` expression or the result of a function annotated with `@nonNull`.
+/// A successful `as` cast in `IsDefined` also implies that the cast reference is defined,
+/// but the result of a cast on its own may always be null.
+/// Only locals and fields read directly from locals are tracked, a fact about a field
+/// is only invalidated when the field or its receiver is reassigned.
+#[derive(Debug, Default)]
+pub struct NullSafety;
+
+impl<'ctx> DiagnosticPass<'ctx> for NullSafety {
+    fn run(
+        &self,
+        func: &LoweredFunction<'ctx>,
+        symbols: &Symbols<'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    ) {
+        let mut visitor = NullSafetyVisitor::new(symbols);
+        visitor.visit_block(&func.block);
+
+        for access in visitor.accesses {
+            match access {
+                NullAccess::Strong(span) => reporter.report(Diagnostic::PossiblyNullAccess(span)),
+                NullAccess::Weak(span) => {
+                    reporter.report(Diagnostic::PossiblyNullWeakAccess(span));
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Place<'ctx> {
+    Local(ir::Local),
+    Field(ir::Local, FieldId<'ctx>),
+}
+
+impl<'ctx> Place<'ctx> {
+    fn of(expr: &ir::Expr<'ctx>) -> Option<Self> {
+        match expr {
+            ir::Expr::Local(local, _) => Some(Self::Local(*local)),
+            ir::Expr::Field {
+                receiver, field, ..
+            } => match **receiver {
+                ir::Expr::Local(local, _) => Some(Self::Field(local, *field)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a fact about `other` depends on the value stored in this place.
+    fn affects(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Local(local), Self::Field(receiver, _)) => local == receiver,
+            _ => self == other,
+        }
+    }
+}
+
+/// Places known to be defined, `None` when the current statement cannot be reached.
+type Facts<'ctx> = Option<HashSet<Place<'ctx>>>;
+
+#[derive(Debug, Clone, Copy)]
+enum NullAccess {
+    Strong(Span),
+    Weak(Span),
+}
+
+struct NullSafetyVisitor<'a, 'ctx> {
+    symbols: &'a Symbols<'ctx>,
+    defined: Facts<'ctx>,
+    /// Facts at the `break` statements of the enclosing loops and switches.
+    break_targets: Vec<Facts<'ctx>>,
+    accesses: Vec<NullAccess>,
+}
+
+impl<'a, 'ctx> NullSafetyVisitor<'a, 'ctx> {
+    fn new(symbols: &'a Symbols<'ctx>) -> Self {
+        Self {
+            symbols,
+            defined: Some(HashSet::new()),
+            break_targets: vec![],
+            accesses: vec![],
+        }
+    }
+
+    fn is_non_null(&self, expr: &ir::Expr<'ctx>) -> bool {
+        let expr = self.strip_ref_conversions(expr);
+        match expr {
+            ir::Expr::NewClass { .. } | ir::Expr::Local(ir::Local::This, _) => true,
+            ir::Expr::Local(_, _) | ir::Expr::Field { .. } => Place::of(expr)
+                .zip(self.defined.as_ref())
+                .is_some_and(|(place, defined)| defined.contains(&place)),
+            ir::Expr::Call { call, .. } => match call {
+                ir::Call::FreeFunction { function, .. } => {
+                    self.symbols[*function].flags().is_non_null()
+                }
+                ir::Call::Static { method, .. } | ir::Call::Instance { method, .. } => self
+                    .symbols
+                    .get_method(*method)
+                    .is_some_and(|(_, method)| method.flags().is_non_null()),
+                ir::Call::Closure { .. } => false,
+            },
+            ir::Expr::Conditional { then, else_, .. } => {
+                self.is_non_null(then) && self.is_non_null(else_)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the facts that hold when `condition` evaluates to `value`.
+    fn assume(&self, condition: &ir::Expr<'ctx>, value: bool, facts: &Facts<'ctx>) -> Facts<'ctx> {
+        let mut facts = facts.clone()?;
+        self.collect_facts(condition, value, &mut facts);
+        Some(facts)
+    }
+
+    fn collect_facts(
+        &self,
+        condition: &ir::Expr<'ctx>,
+        value: bool,
+        facts: &mut HashSet<Place<'ctx>>,
+    ) {
+        let ir::Expr::Call { call, .. } = condition else {
+            return;
+        };
+        match call {
+            ir::Call::FreeFunction { function, args, .. } => {
+                let func = &self.symbols[*function];
+                if func.intrinsic() == Some(ir::Intrinsic::IsDefined) {
+                    if let (true, [arg]) = (value, &args[..]) {
+                        facts.extend(Place::of(self.strip_casts(arg)));
+                    }
+                    return;
+                }
+                match (self.operator(*function), &args[..]) {
+                    (Some(Operator::Not), [arg]) => self.collect_facts(arg, !value, facts),
+                    (Some(Operator::And), [lhs, rhs]) if value => {
+                        self.collect_facts(lhs, true, facts);
+                        self.collect_facts(rhs, true, facts);
+                    }
+                    (Some(Operator::Or), [lhs, rhs]) if !value => {
+                        self.collect_facts(lhs, false, facts);
+                        self.collect_facts(rhs, false, facts);
+                    }
+                    _ => {}
+                }
+            }
+            // runtime type checks generated for patterns only succeed on defined references
+            ir::Call::Instance {
+                receiver, method, ..
+            } if value => {
+                if method.parent() == predef::ISCRIPTABLE
+                    && self
+                        .symbols
+                        .get_method(*method)
+                        .is_some_and(|(name, _)| name == "IsA")
+                {
+                    facts.extend(Place::of(self.strip_ref_conversions(receiver)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Skips the conversions between strong and weak references, they preserve whether
+    /// the reference is null.
+    fn strip_ref_conversions<'e>(&self, expr: &'e ir::Expr<'ctx>) -> &'e ir::Expr<'ctx> {
+        let ir::Expr::Call {
+            call: ir::Call::FreeFunction { function, args, .. },
+            ..
+        } = expr
+        else {
+            return expr;
+        };
+        match (self.symbols[*function].intrinsic(), &args[..]) {
+            (Some(ir::Intrinsic::WeakRefToRef | ir::Intrinsic::RefToWeakRef), [arg]) => {
+                self.strip_ref_conversions(arg)
+            }
+            _ => expr,
+        }
+    }
+
+    /// Skips the `as` casts along with the reference conversions, a cast only yields a
+    /// defined reference when its operand is defined.
+    fn strip_casts<'e>(&self, expr: &'e ir::Expr<'ctx>) -> &'e ir::Expr<'ctx> {
+        match self.strip_ref_conversions(expr) {
+            ir::Expr::DynCast { expr, .. } => self.strip_casts(expr),
+            expr => expr,
+        }
+    }
+
+    fn operator(&self, function: FreeFunctionIndex) -> Option<Operator> {
+        let (name, _) = self.symbols.get_free_function(function)?;
+        match name.as_single_component()? {
+            name if name == ast::UnOp::Not.name() => Some(Operator::Not),
+            name if ast::BinOp::And.name() == Some(name) => Some(Operator::And),
+            name if ast::BinOp::Or.name() == Some(name) => Some(Operator::Or),
+            _ => None,
+        }
+    }
+
+    fn check_access(
+        &mut self,
+        receiver: &ir::Expr<'ctx>,
+        receiver_ref: Option<RefType>,
+        parent: TypeId<'ctx>,
+    ) {
+        if self.defined.is_none() {
+            return;
+        }
+        let access = match receiver_ref {
+            Some(RefType::Weak) => NullAccess::Weak(receiver.span()),
+            None if self.is_class(parent) => NullAccess::Strong(receiver.span()),
+            _ => return,
+        };
+        if !self.is_non_null(receiver) {
+            self.accesses.push(access);
+        }
+    }
+
+    fn is_class(&self, id: TypeId<'ctx>) -> bool {
+        self.symbols
+            .get_type(id)
+            .and_then(|def| def.schema().as_aggregate())
+            .is_some_and(|agg| !agg.flags().is_struct())
+    }
+
+    fn invalidate(&mut self, place: Place<'ctx>) {
+        if let Some(defined) = &mut self.defined {
+            defined.retain(|fact| !place.affects(fact));
+        }
+    }
+
+    fn visit_with(&mut self, facts: Facts<'ctx>, block: &ir::Block<'ctx>) -> Facts<'ctx> {
+        self.defined = facts;
+        self.visit_block(block);
+        self.defined.take()
+    }
+}
+
+impl<'ctx> Visitor<'ctx> for NullSafetyVisitor<'_, 'ctx> {
+    fn visit_block(&mut self, block: &ir::Block<'ctx>) {
+        for stmt in &block.stmts {
+            if self.defined.is_none() {
+                return;
+            }
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_while(&mut self, while_loop: &ir::ConditionalBlock<'ctx>, _span: Span) {
+        // anything reassigned in the body may not hold by the time the loop repeats
+        let mut assigned = Assignments::default();
+        assigned.visit_block(&while_loop.block);
+        for place in assigned.places {
+            self.invalidate(place);
+        }
+
+        self.visit_expr(&while_loop.condition);
+        let head = self.defined.take();
+
+        self.break_targets.push(None);
+        let body = self.assume(&while_loop.condition, true, &head);
+        self.visit_with(body, &while_loop.block);
+        let broken = self.break_targets.pop().flatten();
+
+        let exit = match while_loop.condition {
+            ir::Expr::Const(ir::Const::Bool(true), _) => None,
+            _ => self.assume(&while_loop.condition, false, &head),
+        };
+        self.defined = join(exit, broken);
+    }
+
+    fn visit_branches(
+        &mut self,
+        branches: &[ir::ConditionalBlock<'ctx>],
+        default: Option<&ir::Block<'ctx>>,
+        _span: Span,
+    ) {
+        let mut exit = None;
+        for branch in branches {
+            self.visit_expr(&branch.condition);
+            let entry = self.defined.take();
+            let taken = self.assume(&branch.condition, true, &entry);
+            exit = join(exit, self.visit_with(taken, &branch.block));
+            self.defined = self.assume(&branch.condition, false, &entry);
+        }
+        let rest = self.defined.take();
+        let rest = match default {
+            Some(block) => self.visit_with(rest, block),
+            None => rest,
+        };
+        self.defined = join(exit, rest);
+    }
+
+    fn visit_switch(
+        &mut self,
+        scrutinee: &ir::Expr<'ctx>,
+        _scrutinee_type: &ir::Type<'ctx>,
+        branches: &[ir::Case<'ctx>],
+        default: Option<&ir::Block<'ctx>>,
+        _span: Span,
+    ) {
+        self.visit_expr(scrutinee);
+        let entry = self.defined.take();
+
+        self.break_targets.push(None);
+        let mut fallthrough = None;
+        for branch in branches {
+            self.defined.clone_from(&entry);
+            self.visit_expr(&branch.matcher);
+            let matched = self.assume(&branch.matcher, true, &entry);
+            fallthrough = self.visit_with(join(matched, fallthrough), &branch.block);
+        }
+        let exit = match default {
+            Some(block) => self.visit_with(join(entry, fallthrough), block),
+            None => join(entry, fallthrough),
+        };
+        let broken = self.break_targets.pop().flatten();
+        self.defined = join(exit, broken);
+    }
+
+    fn visit_init_default(&mut self, local: ir::Local, _typ: &ir::Type<'ctx>, _span: Span) {
+        self.invalidate(Place::Local(local));
+    }
+
+    fn visit_break(&mut self, _span: Span) {
+        let facts = self.defined.take();
+        if let Some(target) = self.break_targets.last_mut() {
+            *target = join(target.take(), facts);
+        }
+    }
+
+    fn visit_continue(&mut self, _span: Span) {
+        self.defined = None;
+    }
+
+    fn visit_return(&mut self, expr: Option<&ir::Expr<'ctx>>, _span: Span) {
+        expr.inspect(|expr| self.visit_expr(expr));
+        self.defined = None;
+    }
+
+    fn visit_new_closure(&mut self, closure: &ir::Closure<'ctx>, _span: Span) {
+        let defined = self.defined.replace(HashSet::new());
+        let targets = mem::take(&mut self.break_targets);
+        self.visit_block(&closure.block);
+        self.defined = defined;
+        self.break_targets = targets;
+    }
+
+    fn visit_call(&mut self, call: &ir::Call<'ctx>, _span: Span) {
+        match call {
+            ir::Call::FreeFunction { function, args, .. } => {
+                if let (Some(op @ (Operator::And | Operator::Or)), [lhs, rhs]) =
+                    (self.operator(*function), &args[..])
+                {
+                    // the right hand side is only evaluated when the left one did not
+                    // short-circuit
+                    self.visit_expr(lhs);
+                    let entry = self.defined.clone();
+                    self.defined = self.assume(lhs, op == Operator::And, &entry);
+                    self.visit_expr(rhs);
+                    self.defined = entry;
+                    return;
+                }
+            }
+            ir::Call::Instance {
+                receiver,
+                receiver_ref,
+                method,
+                ..
+            } => self.check_access(receiver, *receiver_ref, method.parent()),
+            _ => {}
+        }
+        call.receiver()
+            .inspect(|receiver| self.visit_expr(receiver));
+        call.args().iter().for_each(|arg| self.visit_expr(arg));
+    }
+
+    fn visit_assign(&mut self, place: &ir::Expr<'ctx>, value: &ir::Expr<'ctx>, _span: Span) {
+        match place {
+            ir::Expr::Local(_, _) => {}
+            other => self.visit_expr(other),
+        }
+        self.visit_expr(value);
+
+        if let Some(place) = Place::of(place) {
+            let non_null = self.is_non_null(value);
+            self.invalidate(place);
+            if let Some(defined) = self.defined.as_mut().filter(|_| non_null) {
+                defined.insert(place);
+            }
+        }
+    }
+
+    fn visit_field(
+        &mut self,
+        receiver: &ir::Expr<'ctx>,
+        _receiver_type: &ir::TypeApp<'ctx>,
+        receiver_ref: Option<RefType>,
+        field: FieldId<'ctx>,
+        _span: Span,
+    ) {
+        self.check_access(receiver, receiver_ref, field.parent());
+        self.visit_expr(receiver);
+    }
+
+    fn visit_conditional(
+        &mut self,
+        condition: &ir::Expr<'ctx>,
+        then_branch: &ir::Expr<'ctx>,
+        else_branch: &ir::Expr<'ctx>,
+        _span: Span,
+    ) {
+        self.visit_expr(condition);
+        let entry = self.defined.clone();
+        self.defined = self.assume(condition, true, &entry);
+        self.visit_expr(then_branch);
+        self.defined = self.assume(condition, false, &entry);
+        self.visit_expr(else_branch);
+        self.defined = entry;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Not,
+    And,
+    Or,
+}
+
+/// Collects the places assigned anywhere within a block.
+#[derive(Debug, Default)]
+struct Assignments<'ctx> {
+    places: Vec<Place<'ctx>>,
+}
+
+impl<'ctx> Visitor<'ctx> for Assignments<'ctx> {
+    fn visit_init_default(&mut self, local: ir::Local, _typ: &ir::Type<'ctx>, _span: Span) {
+        self.places.push(Place::Local(local));
+    }
+
+    fn visit_assign(&mut self, place: &ir::Expr<'ctx>, value: &ir::Expr<'ctx>, _span: Span) {
+        self.places.extend(Place::of(place));
+        self.visit_expr(place);
+        self.visit_expr(value);
+    }
+}
+
+fn join<'ctx>(lhs: Facts<'ctx>, rhs: Facts<'ctx>) -> Facts<'ctx> {
+    match (lhs, rhs) {
+        (Some(mut lhs), Some(rhs)) => {
+            lhs.retain(|place| rhs.contains(place));
+            Some(lhs)
+        }
+        (facts, None) | (None, facts) => facts,
+    }
+}
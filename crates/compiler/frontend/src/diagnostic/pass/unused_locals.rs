@@ -3,13 +3,18 @@ use redscript_ast::Span;
 
 use super::DiagnosticPass;
 use crate::visitor::Visitor;
-use crate::{CompileErrorReporter, Diagnostic, LoweredFunction, ir};
+use crate::{CompileErrorReporter, Diagnostic, LoweredFunction, Symbols, ir};
 
 #[derive(Debug, Default)]
 pub struct UnusedLocals;
 
 impl<'ctx> DiagnosticPass<'ctx> for UnusedLocals {
-    fn run(&self, func: &LoweredFunction<'ctx>, reporter: &mut CompileErrorReporter<'ctx>) {
+    fn run(
+        &self,
+        func: &LoweredFunction<'ctx>,
+        _symbols: &Symbols<'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    ) {
         let mut visitor = UnusedLocalVisitor::new(func);
         visitor.visit_block(&func.block);
 
@@ -485,11 +485,16 @@ impl<'ctx> From<&ast::Constant<'ctx>> for Const<'ctx> {
             ast::Constant::TweakDbId(s) => Self::TweakDbId(s.clone()),
             &ast::Constant::F32(f) => Self::F32(f),
             &ast::Constant::F64(f) => Self::F64(f),
-            &ast::Constant::I32(i) => Self::I32(i),
-            &ast::Constant::I64(i) => Self::I64(i),
-            &ast::Constant::U32(u) => Self::U32(u),
-            &ast::Constant::U64(u) => Self::U64(u),
+            &ast::Constant::I32(i, _) => Self::I32(i),
+            &ast::Constant::I64(i, _) => Self::I64(i),
+            &ast::Constant::U32(u, _) => Self::U32(u),
+            &ast::Constant::U64(u, _) => Self::U64(u),
             &ast::Constant::Bool(b) => Self::Bool(b),
+            // out of range literals are reported during lowering, a placeholder is used here
+            ast::Constant::OutOfRange(ast::IntType::I32, _) => Self::I32(0),
+            ast::Constant::OutOfRange(ast::IntType::I64, _) => Self::I64(0),
+            ast::Constant::OutOfRange(ast::IntType::U32, _) => Self::U32(0),
+            ast::Constant::OutOfRange(ast::IntType::U64, _) => Self::U64(0),
         }
     }
 }
@@ -1,8 +1,9 @@
-mod cte;
+pub mod cte;
 mod diagnostic;
 pub mod ir;
 mod lower;
 mod modules;
+mod optimize;
 mod stages;
 mod symbols;
 pub mod types;
@@ -10,11 +11,13 @@ pub mod utils;
 mod visitor;
 
 pub use cte::Evaluator;
-pub use diagnostic::{Diagnostic, Reporter, UnknownSource, pass};
+pub use diagnostic::{Diagnostic, LintLevel, LintLevels, LintScope, Reporter, UnknownSource, pass};
 pub use lower::{CoalesceError, Error as LowerError, PolyType, TypeRef};
 pub use redscript_ast as ast;
 use redscript_parser as parser;
-pub use stages::{LoweredClass, LoweredCompilationUnit, LoweredFunction};
+pub use stages::{
+    AdditionKind, DuplicateAddition, LoweredClass, LoweredCompilationUnit, LoweredFunction,
+};
 use stages::{NameResolution, Scope};
 pub use symbols::{
     Aggregate, AggregateFlags, Enum, Field, FieldFlags, FieldId, FieldIndex, FieldMap,
@@ -26,6 +26,10 @@ mod error;
 mod simplify;
 mod types;
 
+// the name the receiver of an optional member access is bound to while the member is resolved,
+// it's not a valid identifier so it can't clash with user code
+const OPTIONAL_RECEIVER: &str = "$receiver";
+
 #[derive(Debug)]
 pub struct Lower<'scope, 'ctx> {
     locals: Locals<'scope, 'ctx>,
@@ -268,13 +272,12 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                     span,
                 )?;
                 let index = ir::Expr::Const(ir::Const::I32(-index), span);
-                let (index, _) = self.new_free_function_call(
+                let (index, _) = self.new_operator_call(
                     ast::BinOp::Sub,
                     [
                         (ir::Expr::call(length, span), length_t),
                         (index, PolyType::nullary(predef::INT32)),
                     ],
-                    &[],
                     env,
                     span,
                 )?;
@@ -440,8 +443,8 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                     env,
                     *patterns_span,
                 )?;
-                let (size_check, _) = self.new_free_function_call(
-                    operator.name(),
+                let (size_check, _) = self.new_operator_call(
+                    operator,
                     [
                         (ir::Expr::call(length, *patterns_span), length_t.clone()),
                         (
@@ -449,7 +452,6 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                             PolyType::nullary(predef::INT32),
                         ),
                     ],
-                    &[],
                     env,
                     *patterns_span,
                 )?;
@@ -554,8 +556,7 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                 };
                 let typ = PolyType::nullary(predef::BOOL);
                 let args = [(acc, typ.clone()), (cond, typ)];
-                let (call, _) =
-                    self.new_free_function_call(ast::BinOp::And, args, &[], scope, *span)?;
+                let (call, _) = self.new_operator_call(ast::BinOp::And, args, scope, *span)?;
                 Ok(Some(ir::Expr::call(call, *span)))
             })?
             .unwrap_or(ir::Expr::Const(ir::Const::Bool(true), *span));
@@ -644,13 +645,8 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                             Some(lhs) => {
                                 let args =
                                     [lhs, elem].map(|e| (e, PolyType::nullary(predef::STRING)));
-                                let (call, _) = self.new_free_function_call(
-                                    ast::BinOp::Add,
-                                    args,
-                                    &[],
-                                    env,
-                                    *span,
-                                )?;
+                                let (call, _) =
+                                    self.new_operator_call(ast::BinOp::Add, args, env, *span)?;
                                 ir::Expr::call(call, *span)
                             }
                             None => elem,
@@ -677,17 +673,28 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                 (ir, PolyType::nullary(predef::VOID))
             }
             ast::Expr::BinOp { lhs, op, rhs } => {
+                let Some(name) = op.name() else {
+                    return self.lower_coalesce(lhs, rhs, env, *span);
+                };
+                if env
+                    .query_free_functions(name, self.symbols)
+                    .next()
+                    .is_none()
+                {
+                    if let Some(res) = self.lower_binop_fallback(lhs, *op, rhs, env, *span)? {
+                        return Ok(res);
+                    }
+                }
                 let lhs = self.lower_expr(lhs, env)?;
                 let rhs = self.lower_expr(rhs, env)?;
-                let (call, typ) =
-                    self.new_free_function_call(op.name(), [lhs, rhs], &[], env, *span)?;
+                let (call, typ) = self.new_free_function_call(name, [lhs, rhs], &[], env, *span)?;
                 (ir::Expr::call(call, *span), typ)
             }
             ast::Expr::UnOp { op, expr } => {
-                if let (ast::UnOp::Neg, &(ast::Expr::Constant(ast::Constant::I32(i)), span)) =
+                if let (ast::UnOp::Neg, &(ast::Expr::Constant(ast::Constant::I32(i, _)), span)) =
                     (op, &**expr)
                 {
-                    let const_ = self.lower_constant(&ast::Constant::I32(-i), hint, span);
+                    let const_ = self.lower_constant(&ast::Constant::I32(-i, None), hint, span);
                     let typ = PolyType::nullary(const_.type_id());
                     return Ok((ir::Expr::Const(const_, span), typ));
                 }
@@ -701,10 +708,17 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                 type_args,
                 args,
             } => {
+                if let (ast::Expr::OptionalMember { expr, member }, _) = &**expr {
+                    let call = Some((&type_args[..], &args[..]));
+                    return self.lower_optional_member(expr, member, call, hint, env, *span);
+                }
                 let (call, typ) = self.lower_call(expr, type_args, args, hint, env, *span)?;
                 (ir::Expr::call(call, *span), typ)
             }
             ast::Expr::Member { expr, member } => self.lower_member(expr, member, env, *span)?,
+            ast::Expr::OptionalMember { expr, member } => {
+                self.lower_optional_member(expr, member, None, hint, env, *span)?
+            }
             ast::Expr::Index { expr, index } => {
                 let expr @ (_, expr_span) = &**expr;
                 let elem_t = PolyType::fresh();
@@ -1331,6 +1345,173 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         Ok((expr, typ))
     }
 
+    // only the access right after `?.` is conditional, anything chained to it like the `c`
+    // in `a?.b.c` is applied to the result as usual
+    fn lower_optional_member(
+        &mut self,
+        receiver @ &(_, receiver_span): &Spanned<ast::SourceExpr<'ctx>>,
+        member: &'ctx str,
+        call: Option<(
+            &[Spanned<ast::SourceType<'ctx>>],
+            &[Spanned<ast::SourceExpr<'ctx>>],
+        )>,
+        hint: Option<&PolyType<'ctx>>,
+        env: &Env<'_, 'ctx>,
+        span: Span,
+    ) -> LowerResult<'ctx, (ir::Expr<'ctx>, PolyType<'ctx>)> {
+        let (receiver, receiver_t) = self.lower_expr(receiver, env)?;
+        let local = self.extract_local(receiver, &receiver_t, receiver_span);
+        let (check, _) = self.new_free_function_call(
+            ir::Intrinsic::IsDefined,
+            [(ir::Expr::Local(local, receiver_span), receiver_t.clone())],
+            &[],
+            env,
+            receiver_span,
+        )?;
+
+        // the member is resolved the same way as a regular access on the extracted receiver
+        let mut env = env.introduce_scope();
+        let info = ir::LocalInfo::new(local, receiver_t, Some(receiver_span));
+        env.define_local(OPTIONAL_RECEIVER, info);
+        let target = (
+            ast::Expr::Member {
+                expr: (ast::Expr::Ident(OPTIONAL_RECEIVER), receiver_span).into(),
+                member,
+            },
+            span,
+        );
+        let ((then, then_t), then_prefix) = self.lower_with_own_prefix(|this| match call {
+            Some((type_args, args)) => {
+                let (call, typ) = this.lower_call(&target, type_args, args, hint, &env, span)?;
+                Ok((ir::Expr::call(call, span), typ))
+            }
+            None => this.lower_expr(&target, &env),
+        })?;
+
+        // references fall back to null, void calls only need something to evaluate
+        let is_weak = then_t.ref_type(self.symbols) == Some(RefType::Weak);
+        let is_nullable = then_t.upper_bound(self.symbols).is_some_and(|typ| {
+            typ.id() == predef::VOID
+                || self
+                    .symbols
+                    .get_type(typ.id())
+                    .and_then(|def| def.schema().as_aggregate())
+                    .is_some_and(|agg| !agg.flags().is_struct())
+        });
+        // value types are default-initialized only when the receiver is null
+        let else_ = if is_weak || is_nullable {
+            (ir::Expr::Null { is_weak, span }, vec![])
+        } else {
+            let local = self.locals.add_var(then_t.clone(), span).id;
+            let init = ir::Stmt::InitDefault {
+                local,
+                typ: then_t.clone().into(),
+                span,
+            };
+            (ir::Expr::Local(local, span), vec![init])
+        };
+
+        let condition = ir::Expr::call(check, receiver_span);
+        let ir = self.new_conditional(condition, (then, then_prefix), else_, &then_t, span);
+        Ok((ir, then_t))
+    }
+
+    // the left operand is evaluated once and the right one only when the left one is null
+    fn lower_coalesce(
+        &mut self,
+        lhs: &Spanned<ast::SourceExpr<'ctx>>,
+        rhs: &Spanned<ast::SourceExpr<'ctx>>,
+        env: &Env<'_, 'ctx>,
+        span: Span,
+    ) -> LowerResult<'ctx, (ir::Expr<'ctx>, PolyType<'ctx>)> {
+        let (lhs, lhs_t) = self.lower_expr(lhs, env)?;
+        let lhs_span = lhs.span();
+        let local = self.extract_local(lhs, &lhs_t, lhs_span);
+        let ((rhs, rhs_t), rhs_prefix) =
+            self.lower_with_own_prefix(|this| this.lower_expr_with(rhs, Some(&lhs_t), env))?;
+        let (check, _) = self.new_free_function_call(
+            ir::Intrinsic::IsDefined,
+            [(ir::Expr::Local(local, lhs_span), lhs_t.clone())],
+            &[],
+            env,
+            lhs_span,
+        )?;
+
+        let typ = lhs_t.lub(&rhs_t, self.symbols).with_span(span)?;
+        let condition = ir::Expr::call(check, lhs_span);
+        let then = ir::Expr::Local(local, lhs_span);
+        let ir = self.new_conditional(condition, (then, vec![]), (rhs, rhs_prefix), &typ, span);
+        Ok((ir, typ))
+    }
+
+    /// Lowers an expression with a statement prefix frame of its own and returns the
+    /// statements it needs separately, so that they can be placed where it's evaluated.
+    fn lower_with_own_prefix<A>(
+        &mut self,
+        lower: impl FnOnce(&mut Self) -> LowerResult<'ctx, A>,
+    ) -> LowerResult<'ctx, (A, Vec<ir::Stmt<'ctx>>)> {
+        self.stmt_prefix.push(vec![]);
+        let res = lower(self);
+        let prefix = self.stmt_prefix.pop().unwrap_or_default();
+        Ok((res?, prefix))
+    }
+
+    /// Creates a conditional expression. If either of the branches needs statements of its
+    /// own, the conditional becomes a branch statement that stores the result in a local,
+    /// so that these statements only run when their branch is taken.
+    fn new_conditional(
+        &mut self,
+        condition: ir::Expr<'ctx>,
+        (then, then_prefix): (ir::Expr<'ctx>, Vec<ir::Stmt<'ctx>>),
+        (else_, else_prefix): (ir::Expr<'ctx>, Vec<ir::Stmt<'ctx>>),
+        typ: &PolyType<'ctx>,
+        span: Span,
+    ) -> ir::Expr<'ctx> {
+        if then_prefix.is_empty() && else_prefix.is_empty() {
+            return ir::Expr::Conditional {
+                condition: condition.into(),
+                then: then.into(),
+                else_: else_.into(),
+                span,
+            };
+        }
+
+        let is_void = typ
+            .upper_bound(self.symbols)
+            .is_some_and(|typ| typ.id() == predef::VOID);
+        let result = (!is_void).then(|| self.locals.add_var(typ.clone(), span).id);
+        let branch = |mut stmts: Vec<ir::Stmt<'ctx>>, expr: ir::Expr<'ctx>| {
+            match result {
+                Some(local) => stmts.push(
+                    ir::Expr::Assign {
+                        place: ir::Expr::Local(local, span).into(),
+                        expr: expr.into(),
+                        span,
+                    }
+                    .into(),
+                ),
+                None if matches!(expr, ir::Expr::Null { .. }) => {}
+                None => stmts.push(expr.into()),
+            }
+            ir::Block::new(stmts)
+        };
+        let then = branch(then_prefix, then);
+        let else_ = branch(else_prefix, else_);
+        self.push_prefix(ir::Stmt::Branches {
+            branches: [ir::ConditionalBlock::new(condition, then)].into(),
+            default: Some(else_),
+            span,
+        });
+
+        match result {
+            Some(local) => ir::Expr::Local(local, span),
+            None => ir::Expr::Null {
+                is_weak: false,
+                span,
+            },
+        }
+    }
+
     fn lower_for_in(
         &mut self,
         &(name, name_span): &Spanned<&'ctx str>,
@@ -1356,7 +1537,7 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         let elem = env.define_local(name, self.locals.add_var(elem_t, name_span).clone());
 
         let loop_body = {
-            let (increment, _) = self.new_free_function_call(
+            let (increment, _) = self.new_operator_call(
                 ast::BinOp::AssignAdd,
                 [
                     (ir::Expr::Local(counter, init_end_span), counter_t.clone()),
@@ -1365,7 +1546,6 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                         counter_t.clone(),
                     ),
                 ],
-                &[],
                 &env,
                 init_end_span,
             )?;
@@ -1397,13 +1577,12 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
             &env,
             iter_span,
         )?;
-        let (check, _) = self.new_free_function_call(
+        let (check, _) = self.new_operator_call(
             ast::BinOp::Lt,
             [
                 (ir::Expr::Local(counter, init_span), counter_t.clone()),
                 (ir::Expr::call(array_size, init_span), array_size_t),
             ],
-            &[],
             &env,
             init_span,
         )?;
@@ -1422,12 +1601,214 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         ]))
     }
 
+    // some operators are not defined by the game, they can be expressed with other operators
+    // in the cases handled here
+    fn lower_binop_fallback(
+        &mut self,
+        lhs: &Spanned<ast::SourceExpr<'ctx>>,
+        op: ast::BinOp,
+        rhs: &Spanned<ast::SourceExpr<'ctx>>,
+        env: &Env<'_, 'ctx>,
+        span: Span,
+    ) -> LowerResult<'ctx, Option<(ir::Expr<'ctx>, PolyType<'ctx>)>> {
+        let base_op = match op {
+            ast::BinOp::AssignMod => ast::BinOp::Mod,
+            ast::BinOp::AssignBitXor => ast::BinOp::BitXor,
+            ast::BinOp::Shl | ast::BinOp::Shr => {
+                // a shift of an unsigned integer by a constant is a multiplication or division
+                // by a power of two, there's no such equivalent for signed or floating point values
+                let &(ast::Expr::Constant(ast::Constant::I32(amount, _)), rhs_span) = rhs else {
+                    return Ok(None);
+                };
+                let (lhs, lhs_t) = self.lower_expr(lhs, env)?;
+                let factor = u32::try_from(amount).ok().and_then(|amount| {
+                    match lhs_t.upper_bound(self.symbols)?.id() {
+                        id if id == predef::UINT8 => 1u8.checked_shl(amount).map(ir::Const::U8),
+                        id if id == predef::UINT16 => 1u16.checked_shl(amount).map(ir::Const::U16),
+                        id if id == predef::UINT32 => 1u32.checked_shl(amount).map(ir::Const::U32),
+                        id if id == predef::UINT64 => 1u64.checked_shl(amount).map(ir::Const::U64),
+                        _ => None,
+                    }
+                });
+                let Some(factor) = factor else {
+                    let name = op.name().unwrap_or_default();
+                    return Err(Error::UnresolvedFunction(name, span));
+                };
+                let factor_t = PolyType::nullary(factor.type_id());
+                let base_op = if op == ast::BinOp::Shl {
+                    ast::BinOp::Mul
+                } else {
+                    ast::BinOp::Div
+                };
+                let args = [(lhs, lhs_t), (ir::Expr::Const(factor, rhs_span), factor_t)];
+                let (call, typ) = self.new_operator_call(base_op, args, env, span)?;
+                return Ok(Some((ir::Expr::call(call, span), typ)));
+            }
+            _ => return Ok(None),
+        };
+
+        let (place, place_t) = self.lower_expr(lhs, env)?;
+        if place.is_prvalue(self.symbols) {
+            self.reporter.report(Error::InvalidPlaceExpr(span));
+        }
+        // the place is read and then written, so anything it depends on is evaluated up front
+        let (place, current) = self.duplicate_place(place);
+        let current = (current, place_t.clone());
+        let rhs = self.lower_expr(rhs, env)?;
+        let (call, typ) = self.new_operator_call(base_op, [current, rhs], env, span)?;
+        let mut value = ir::Expr::call(call, span);
+        self.coerce(&mut value, typ, place_t, env, span)?;
+
+        let ir = ir::Expr::Assign {
+            place: place.into(),
+            expr: value.into(),
+            span,
+        };
+        Ok(Some((ir, PolyType::nullary(predef::VOID))))
+    }
+
+    fn duplicate_place(&mut self, place: ir::Expr<'ctx>) -> (ir::Expr<'ctx>, ir::Expr<'ctx>) {
+        match place {
+            ir::Expr::Local(local, span) => {
+                (ir::Expr::Local(local, span), ir::Expr::Local(local, span))
+            }
+            ir::Expr::Capture(local, span) => (
+                ir::Expr::Capture(local, span),
+                ir::Expr::Capture(local, span),
+            ),
+            ir::Expr::Field {
+                receiver,
+                receiver_type,
+                receiver_ref,
+                field,
+                span,
+            } => {
+                let (receiver, copy) = if receiver.is_prvalue(self.symbols) {
+                    let typ = Type::Data(receiver_type.clone());
+                    let typ = match receiver_ref {
+                        Some(RefType::Weak) => Type::app(predef::WREF, [typ]),
+                        Some(RefType::Script) => Type::app(predef::SCRIPT_REF, [typ]),
+                        None => typ,
+                    };
+                    let receiver_span = receiver.span();
+                    let local =
+                        self.extract_local(*receiver, &PolyType::from_type(&typ), receiver_span);
+                    (
+                        ir::Expr::Local(local, receiver_span),
+                        ir::Expr::Local(local, receiver_span),
+                    )
+                } else {
+                    self.duplicate_place(*receiver)
+                };
+                let copy = ir::Expr::Field {
+                    receiver: copy.into(),
+                    receiver_type: receiver_type.clone(),
+                    receiver_ref,
+                    field,
+                    span,
+                };
+                let place = ir::Expr::Field {
+                    receiver: receiver.into(),
+                    receiver_type,
+                    receiver_ref,
+                    field,
+                    span,
+                };
+                (place, copy)
+            }
+            ir::Expr::Index {
+                array,
+                array_type,
+                index,
+                span,
+            } => {
+                let (array, array_copy) = self.duplicate_place(*array);
+                let index_span = index.span();
+                let index =
+                    self.extract_local(*index, &PolyType::nullary(predef::INT32), index_span);
+                let copy = ir::Expr::Index {
+                    array: array_copy.into(),
+                    array_type: array_type.clone(),
+                    index: ir::Expr::Local(index, index_span).into(),
+                    span,
+                };
+                let place = ir::Expr::Index {
+                    array: array.into(),
+                    array_type,
+                    index: ir::Expr::Local(index, index_span).into(),
+                    span,
+                };
+                (place, copy)
+            }
+            ir::Expr::Call { call, span } => match *call {
+                ir::Call::FreeFunction {
+                    function,
+                    type_args,
+                    args,
+                } if self.symbols[function]
+                    .intrinsic()
+                    .is_some_and(|i| i == ir::Intrinsic::Deref) =>
+                {
+                    let (args, copies): (Vec<_>, Vec<_>) = args
+                        .into_vec()
+                        .into_iter()
+                        .map(|arg| self.duplicate_place(arg))
+                        .unzip();
+                    let copy = ir::Call::FreeFunction {
+                        function,
+                        type_args: type_args.clone(),
+                        args: copies.into(),
+                    };
+                    let place = ir::Call::FreeFunction {
+                        function,
+                        type_args,
+                        args: args.into(),
+                    };
+                    (ir::Expr::call(place, span), ir::Expr::call(copy, span))
+                }
+                call => {
+                    // not a place, this has been reported already
+                    let copy = ir::Expr::Null {
+                        is_weak: false,
+                        span,
+                    };
+                    (
+                        ir::Expr::Call {
+                            call: call.into(),
+                            span,
+                        },
+                        copy,
+                    )
+                }
+            },
+            place => {
+                let span = place.span();
+                let copy = ir::Expr::Null {
+                    is_weak: false,
+                    span,
+                };
+                (place, copy)
+            }
+        }
+    }
+
     fn lower_constant(
         &mut self,
         cn: &ast::Constant<'ctx>,
         hint: Option<&PolyType<'ctx>>,
         span: Span,
     ) -> ir::Const<'ctx> {
+        if let &ast::Constant::OutOfRange(typ, _) = cn {
+            let id = match typ {
+                ast::IntType::I32 => predef::INT32,
+                ast::IntType::I64 => predef::INT64,
+                ast::IntType::U32 => predef::UINT32,
+                ast::IntType::U64 => predef::UINT64,
+            };
+            self.reporter.report(Error::LiteralOutOfRange(id, span));
+            return ir::Const::from(cn);
+        }
+
         let Some(PolyType::Mono(Type::Data(app))) = hint else {
             return ir::Const::from(cn);
         };
@@ -1435,33 +1816,37 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         let res = match (cn, app.id()) {
             (ast::Constant::F32(f), id) if id == predef::DOUBLE => Some(ir::Const::F64(*f as f64)),
 
-            (ast::Constant::I32(i), id) if id == predef::FLOAT => Some(ir::Const::F32(*i as f32)),
-            (ast::Constant::I32(i), id) if id == predef::DOUBLE => Some(ir::Const::F64(*i as f64)),
+            (ast::Constant::I32(i, _), id) if id == predef::FLOAT => {
+                Some(ir::Const::F32(*i as f32))
+            }
+            (ast::Constant::I32(i, _), id) if id == predef::DOUBLE => {
+                Some(ir::Const::F64(*i as f64))
+            }
 
-            (ast::Constant::I32(i), id) if id == predef::INT8 => {
+            (ast::Constant::I32(i, _), id) if id == predef::INT8 => {
                 i8::try_from(*i).map(ir::Const::I8).ok()
             }
-            (ast::Constant::I32(i), id) if id == predef::INT16 => {
+            (ast::Constant::I32(i, _), id) if id == predef::INT16 => {
                 i16::try_from(*i).map(ir::Const::I16).ok()
             }
-            (ast::Constant::I32(i), id) if id == predef::INT64 => {
+            (ast::Constant::I32(i, _), id) if id == predef::INT64 => {
                 Some(ir::Const::I64(i64::from(*i)))
             }
 
-            (ast::Constant::I32(i), id) if id == predef::UINT8 => {
+            (ast::Constant::I32(i, _), id) if id == predef::UINT8 => {
                 u8::try_from(*i).map(ir::Const::U8).ok()
             }
-            (ast::Constant::I32(i), id) if id == predef::UINT16 => {
+            (ast::Constant::I32(i, _), id) if id == predef::UINT16 => {
                 u16::try_from(*i).map(ir::Const::U16).ok()
             }
-            (ast::Constant::I32(i), id) if id == predef::UINT32 => {
+            (ast::Constant::I32(i, _), id) if id == predef::UINT32 => {
                 u32::try_from(*i).map(ir::Const::U32).ok()
             }
-            (ast::Constant::I32(i), id) if id == predef::UINT64 => {
+            (ast::Constant::I32(i, _), id) if id == predef::UINT64 => {
                 u64::try_from(*i).map(ir::Const::U64).ok()
             }
 
-            (ast::Constant::U32(i), id) if id == predef::UINT64 => {
+            (ast::Constant::U32(i, _), id) if id == predef::UINT64 => {
                 Some(ir::Const::U64(u64::from(*i)))
             }
 
@@ -1579,6 +1964,8 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                 self.reporter.unwrap_err(res);
                 checked_args.push(expr);
             }
+            let defaults = self.default_args(primary.func().type_(), argc, &type_env, span)?;
+            checked_args.extend(defaults);
 
             let type_args = type_env
                 .pop_scope()
@@ -1621,7 +2008,7 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
     fn resolve_overload_typed<Key, Name, Func>(
         &mut self,
         name: &'ctx str,
-        args: &mut [ir::Expr<'ctx>],
+        args: &mut Vec<ir::Expr<'ctx>>,
         arg_types: Vec<PolyType<'ctx>>,
         type_args: &[PolyType<'ctx>],
         candidates: impl IntoIterator<Item = FunctionEntry<Key, Name, Func>>,
@@ -1700,6 +2087,8 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
                 .map_err(|var| Error::UnresolvedVar(var, span))?;
             self.new_arg(&mut *arg, typ.clone(), expected, param, env, span)?;
         }
+        let defaults = self.default_args(selected.func().type_(), args.len(), &type_env, span)?;
+        args.extend(defaults);
 
         let return_t =
             PolyType::from_type_with_env(selected.func().type_().return_type(), &type_env)
@@ -1820,6 +2209,17 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         Ok(res)
     }
 
+    fn new_operator_call(
+        &mut self,
+        op: ast::BinOp,
+        args: impl IntoIterator<Item = (ir::Expr<'ctx>, PolyType<'ctx>)>,
+        env: &Env<'_, 'ctx>,
+        span: Span,
+    ) -> LowerResult<'ctx, (ir::Call<'ctx>, PolyType<'ctx>)> {
+        let name = op.name().ok_or(Error::UnresolvedFunction("??", span))?;
+        self.new_free_function_call(name, args, &[], env, span)
+    }
+
     fn new_field_read(
         &mut self,
         ir: ir::Expr<'ctx>,
@@ -1877,7 +2277,7 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
             .symbols
             .query_methods_by_name(upper_bound.id(), "IsA")
             .filter(|entry| !entry.func().flags().is_static());
-        let mut args = [ir::Expr::call(name_of, span)];
+        let mut args = vec![ir::Expr::call(name_of, span)];
         let (call, _) = self
             .resolve_overload_typed(
                 "IsA",
@@ -1937,6 +2337,38 @@ impl<'scope, 'ctx> Lower<'scope, 'ctx> {
         Ok(map)
     }
 
+    // fills in the omitted parameters up to the last one that has a default value, optional
+    // parameters without a default in between are passed a default-initialized value
+    fn default_args(
+        &mut self,
+        func_t: &FunctionType<'ctx>,
+        provided: usize,
+        type_env: &ScopedMap<'_, &'ctx str, PolyType<'ctx>>,
+        span: Span,
+    ) -> LowerResult<'ctx, Vec<ir::Expr<'ctx>>> {
+        let params = func_t.params().get(provided..).unwrap_or_default();
+        let Some(last) = params.iter().rposition(|param| param.default().is_some()) else {
+            return Ok(vec![]);
+        };
+        params[..=last]
+            .iter()
+            .map(|param| {
+                if let Some(default) = param.default() {
+                    return Ok(ir::Expr::Const(default.clone(), span));
+                }
+                let typ = PolyType::from_type_with_env(param.type_(), type_env)
+                    .map_err(|var| Error::UnresolvedVar(var, span))?;
+                let local = self.locals.add_var(typ.clone(), span).id;
+                self.push_prefix(ir::Stmt::InitDefault {
+                    local,
+                    typ: typ.into(),
+                    span,
+                });
+                Ok(ir::Expr::Local(local, span))
+            })
+            .collect()
+    }
+
     fn extract_local(
         &mut self,
         expr: ir::Expr<'ctx>,
@@ -0,0 +1,470 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::mem;
+
+use hashbrown::HashSet;
+use redscript_ast as ast;
+
+use crate::visitor::Visitor;
+use crate::{LoweredFunction, Symbols, ir};
+
+/// Simplifies the IR of a function before it's assembled. Constant expressions are
+/// folded, branches with constant conditions are eliminated and the locals that are
+/// only ever assigned side-effect free values are removed.
+pub fn optimize<'ctx>(func: &mut LoweredFunction<'ctx>, symbols: &Symbols<'ctx>) {
+    ConstFolder { symbols }.fold_block(&mut func.block);
+    remove_unused_temporaries(func);
+}
+
+struct ConstFolder<'a, 'ctx> {
+    symbols: &'a Symbols<'ctx>,
+}
+
+impl<'ctx> ConstFolder<'_, 'ctx> {
+    fn fold_block(&self, block: &mut ir::Block<'ctx>) {
+        let stmts = mem::take(&mut block.stmts);
+        for stmt in stmts {
+            self.fold_stmt(stmt, &mut block.stmts);
+        }
+    }
+
+    fn fold_stmt(&self, stmt: ir::Stmt<'ctx>, out: &mut VecDeque<ir::Stmt<'ctx>>) {
+        match stmt {
+            ir::Stmt::Expr(mut expr) => {
+                self.fold_expr(&mut expr);
+                out.push_back(ir::Stmt::Expr(expr));
+            }
+            ir::Stmt::Block(mut block, span) => {
+                self.fold_block(&mut block);
+                out.push_back(ir::Stmt::Block(block, span));
+            }
+            ir::Stmt::While(mut while_loop, span) => {
+                self.fold_expr(&mut while_loop.condition);
+                if const_bool(&while_loop.condition) == Some(false) {
+                    return;
+                }
+                self.fold_block(&mut while_loop.block);
+                out.push_back(ir::Stmt::While(while_loop, span));
+            }
+            ir::Stmt::Branches {
+                branches,
+                mut default,
+                span,
+            } => {
+                let mut remaining = vec![];
+                for mut branch in branches {
+                    self.fold_expr(&mut branch.condition);
+                    match const_bool(&branch.condition) {
+                        Some(false) => {}
+                        // the branches that follow can never be taken
+                        Some(true) => {
+                            default = Some(branch.block);
+                            break;
+                        }
+                        None => remaining.push(branch),
+                    }
+                }
+
+                for branch in &mut remaining {
+                    self.fold_block(&mut branch.block);
+                }
+                if let Some(default) = &mut default {
+                    self.fold_block(default);
+                }
+
+                if !remaining.is_empty() {
+                    out.push_back(ir::Stmt::Branches {
+                        branches: remaining.into(),
+                        default,
+                        span,
+                    });
+                } else if let Some(default) = default {
+                    out.push_back(ir::Stmt::Block(default, span));
+                }
+            }
+            ir::Stmt::Switch {
+                mut scrutinee,
+                scrutinee_type,
+                mut branches,
+                mut default,
+                span,
+            } => {
+                self.fold_expr(&mut scrutinee);
+                for case in &mut branches {
+                    self.fold_expr(&mut case.matcher);
+                    self.fold_block(&mut case.block);
+                }
+                if let Some(default) = &mut default {
+                    self.fold_block(default);
+                }
+                out.push_back(ir::Stmt::Switch {
+                    scrutinee,
+                    scrutinee_type,
+                    branches,
+                    default,
+                    span,
+                });
+            }
+            ir::Stmt::InitArray {
+                local,
+                mut elements,
+                element_type,
+                span,
+            } => {
+                elements.iter_mut().for_each(|elem| self.fold_expr(elem));
+                out.push_back(ir::Stmt::InitArray {
+                    local,
+                    elements,
+                    element_type,
+                    span,
+                });
+            }
+            ir::Stmt::Return(mut expr, span) => {
+                if let Some(expr) = &mut expr {
+                    self.fold_expr(expr);
+                }
+                out.push_back(ir::Stmt::Return(expr, span));
+            }
+            stmt @ (ir::Stmt::InitDefault { .. } | ir::Stmt::Break(_) | ir::Stmt::Continue(_)) => {
+                out.push_back(stmt);
+            }
+        }
+    }
+
+    fn fold_expr(&self, expr: &mut ir::Expr<'ctx>) {
+        match expr {
+            ir::Expr::NewStruct { args, .. } => args.iter_mut().for_each(|arg| self.fold_expr(arg)),
+            ir::Expr::NewClosure { closure, .. } => self.fold_block(&mut closure.block),
+            ir::Expr::Call { call, span } => {
+                self.fold_call(call);
+                if let Some(const_) = self.eval_call(call) {
+                    *expr = ir::Expr::Const(const_, *span);
+                }
+            }
+            ir::Expr::Assign { place, expr, .. } => {
+                self.fold_expr(place);
+                self.fold_expr(expr);
+            }
+            ir::Expr::Field { receiver, .. } => self.fold_expr(receiver),
+            ir::Expr::Index { array, index, .. } => {
+                self.fold_expr(array);
+                self.fold_expr(index);
+            }
+            ir::Expr::Conditional {
+                condition,
+                then,
+                else_,
+                ..
+            } => {
+                self.fold_expr(condition);
+                self.fold_expr(then);
+                self.fold_expr(else_);
+
+                let Some(condition) = const_bool(condition) else {
+                    return;
+                };
+                let branch = if condition { then } else { else_ };
+                let span = branch.span();
+                *expr = mem::replace(&mut **branch, ir::Expr::null(span));
+            }
+            ir::Expr::DynCast { expr, .. } => self.fold_expr(expr),
+            ir::Expr::NewClass { .. }
+            | ir::Expr::Local(_, _)
+            | ir::Expr::Capture(_, _)
+            | ir::Expr::Const(_, _)
+            | ir::Expr::Null { .. } => {}
+        }
+    }
+
+    fn fold_call(&self, call: &mut ir::Call<'ctx>) {
+        let args = match call {
+            ir::Call::FreeFunction { args, .. } | ir::Call::Static { args, .. } => args,
+            ir::Call::Instance { receiver, args, .. } => {
+                self.fold_expr(receiver);
+                args
+            }
+            ir::Call::Closure { closure, args, .. } => {
+                self.fold_expr(closure);
+                args
+            }
+        };
+        args.iter_mut().for_each(|arg| self.fold_expr(arg));
+    }
+
+    /// Evaluates a call to a native operator with constant arguments.
+    fn eval_call(&self, call: &ir::Call<'ctx>) -> Option<ir::Const<'ctx>> {
+        let ir::Call::FreeFunction { function, args, .. } = call else {
+            return None;
+        };
+        let (name, func) = self.symbols.get_free_function(*function)?;
+        if !func.flags().is_native() {
+            return None;
+        }
+        let name = name.as_single_component()?;
+
+        let res = match &args[..] {
+            [arg] => eval_unop(ast::UnOp::from_name(name)?, self.const_arg(arg)?)?,
+            [lhs, rhs] => eval_binop(
+                ast::BinOp::from_name(name)?,
+                self.const_arg(lhs)?,
+                self.const_arg(rhs)?,
+            )?,
+            _ => return None,
+        };
+        // make sure that the operator does not do anything unusual with its result
+        let return_t = func.type_().return_type().upper_bound()?;
+        (return_t.id() == res.type_id()).then_some(res)
+    }
+
+    fn const_arg<'a>(&self, expr: &'a ir::Expr<'ctx>) -> Option<&'a ir::Const<'ctx>> {
+        match expr {
+            ir::Expr::Const(const_, _) => Some(const_),
+            // string operators take their arguments by reference
+            ir::Expr::Call { call, .. } => match &**call {
+                ir::Call::FreeFunction { function, args, .. }
+                    if self.symbols[*function].intrinsic() == Some(ir::Intrinsic::AsRef) =>
+                {
+                    match &args[..] {
+                        [arg] => self.const_arg(arg),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn eval_unop<'ctx>(op: ast::UnOp, arg: &ir::Const<'ctx>) -> Option<ir::Const<'ctx>> {
+    macro_rules! int_unop {
+        ($val:expr, $variant:ident) => {
+            match op {
+                ast::UnOp::Neg => $val.checked_neg().map(ir::Const::$variant),
+                ast::UnOp::BitNot => Some(ir::Const::$variant(!$val)),
+                ast::UnOp::Not => None,
+            }
+        };
+    }
+
+    match (op, arg) {
+        (ast::UnOp::Not, &ir::Const::Bool(b)) => Some(ir::Const::Bool(!b)),
+        (ast::UnOp::Neg, &ir::Const::F32(f)) => Some(ir::Const::F32(-f)),
+        (ast::UnOp::Neg, &ir::Const::F64(f)) => Some(ir::Const::F64(-f)),
+        (_, &ir::Const::I8(i)) => int_unop!(i, I8),
+        (_, &ir::Const::I16(i)) => int_unop!(i, I16),
+        (_, &ir::Const::I32(i)) => int_unop!(i, I32),
+        (_, &ir::Const::I64(i)) => int_unop!(i, I64),
+        (ast::UnOp::BitNot, &ir::Const::U8(i)) => Some(ir::Const::U8(!i)),
+        (ast::UnOp::BitNot, &ir::Const::U16(i)) => Some(ir::Const::U16(!i)),
+        (ast::UnOp::BitNot, &ir::Const::U32(i)) => Some(ir::Const::U32(!i)),
+        (ast::UnOp::BitNot, &ir::Const::U64(i)) => Some(ir::Const::U64(!i)),
+        _ => None,
+    }
+}
+
+fn eval_binop<'ctx>(
+    op: ast::BinOp,
+    lhs: &ir::Const<'ctx>,
+    rhs: &ir::Const<'ctx>,
+) -> Option<ir::Const<'ctx>> {
+    // overflows and divisions by zero are left to be handled at runtime
+    macro_rules! int_binop {
+        ($lhs:expr, $rhs:expr, $variant:ident) => {
+            match op {
+                ast::BinOp::Add => $lhs.checked_add($rhs).map(ir::Const::$variant),
+                ast::BinOp::Sub => $lhs.checked_sub($rhs).map(ir::Const::$variant),
+                ast::BinOp::Mul => $lhs.checked_mul($rhs).map(ir::Const::$variant),
+                ast::BinOp::Div => $lhs.checked_div($rhs).map(ir::Const::$variant),
+                ast::BinOp::Mod => $lhs.checked_rem($rhs).map(ir::Const::$variant),
+                ast::BinOp::BitAnd => Some(ir::Const::$variant($lhs & $rhs)),
+                ast::BinOp::BitOr => Some(ir::Const::$variant($lhs | $rhs)),
+                ast::BinOp::BitXor => Some(ir::Const::$variant($lhs ^ $rhs)),
+                op => compare(op, $lhs.cmp(&$rhs)),
+            }
+        };
+    }
+
+    macro_rules! float_binop {
+        ($lhs:expr, $rhs:expr, $variant:ident) => {
+            match op {
+                ast::BinOp::Add => Some(ir::Const::$variant($lhs + $rhs)),
+                ast::BinOp::Sub => Some(ir::Const::$variant($lhs - $rhs)),
+                ast::BinOp::Mul => Some(ir::Const::$variant($lhs * $rhs)),
+                ast::BinOp::Div if $rhs != 0.0 => Some(ir::Const::$variant($lhs / $rhs)),
+                op => compare(op, $lhs.partial_cmp(&$rhs)?),
+            }
+        };
+    }
+
+    match (lhs, rhs) {
+        (&ir::Const::I8(l), &ir::Const::I8(r)) => int_binop!(l, r, I8),
+        (&ir::Const::I16(l), &ir::Const::I16(r)) => int_binop!(l, r, I16),
+        (&ir::Const::I32(l), &ir::Const::I32(r)) => int_binop!(l, r, I32),
+        (&ir::Const::I64(l), &ir::Const::I64(r)) => int_binop!(l, r, I64),
+        (&ir::Const::U8(l), &ir::Const::U8(r)) => int_binop!(l, r, U8),
+        (&ir::Const::U16(l), &ir::Const::U16(r)) => int_binop!(l, r, U16),
+        (&ir::Const::U32(l), &ir::Const::U32(r)) => int_binop!(l, r, U32),
+        (&ir::Const::U64(l), &ir::Const::U64(r)) => int_binop!(l, r, U64),
+        (&ir::Const::F32(l), &ir::Const::F32(r)) => float_binop!(l, r, F32),
+        (&ir::Const::F64(l), &ir::Const::F64(r)) => float_binop!(l, r, F64),
+        (&ir::Const::Bool(l), &ir::Const::Bool(r)) => match op {
+            ast::BinOp::And => Some(ir::Const::Bool(l && r)),
+            ast::BinOp::Or => Some(ir::Const::Bool(l || r)),
+            ast::BinOp::Eq => Some(ir::Const::Bool(l == r)),
+            ast::BinOp::Ne => Some(ir::Const::Bool(l != r)),
+            _ => None,
+        },
+        (ir::Const::Str(l), ir::Const::Str(r)) => match op {
+            ast::BinOp::Add => Some(ir::Const::Str(format!("{l}{r}").into())),
+            ast::BinOp::Eq => Some(ir::Const::Bool(l == r)),
+            ast::BinOp::Ne => Some(ir::Const::Bool(l != r)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn compare<'ctx>(op: ast::BinOp, ordering: Ordering) -> Option<ir::Const<'ctx>> {
+    let res = match op {
+        ast::BinOp::Eq => ordering.is_eq(),
+        ast::BinOp::Ne => ordering.is_ne(),
+        ast::BinOp::Lt => ordering.is_lt(),
+        ast::BinOp::Le => ordering.is_le(),
+        ast::BinOp::Gt => ordering.is_gt(),
+        ast::BinOp::Ge => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(ir::Const::Bool(res))
+}
+
+fn const_bool(expr: &ir::Expr<'_>) -> Option<bool> {
+    match expr {
+        &ir::Expr::Const(ir::Const::Bool(b), _) => Some(b),
+        _ => None,
+    }
+}
+
+/// Removes the locals that are never read along with all of their assignments. Only
+/// the locals that are exclusively assigned side-effect free values are removed.
+fn remove_unused_temporaries(func: &mut LoweredFunction<'_>) {
+    loop {
+        let mut collector = LocalUseCollector::default();
+        collector.visit_block(&func.block);
+
+        let unused = func
+            .locals
+            .iter()
+            .map(|local| local.id)
+            .filter(|id| matches!(id, ir::Local::Var(_)) && !collector.used.contains(id))
+            .collect::<HashSet<_>>();
+        if unused.is_empty() {
+            break;
+        }
+
+        remove_writes(&mut func.block, &unused);
+        func.locals = func
+            .locals
+            .iter()
+            .filter(|local| !unused.contains(&local.id))
+            .cloned()
+            .collect();
+    }
+}
+
+fn remove_writes(block: &mut ir::Block<'_>, locals: &HashSet<ir::Local>) {
+    block.stmts.retain_mut(|stmt| {
+        match stmt {
+            ir::Stmt::Block(block, _) => remove_writes(block, locals),
+            ir::Stmt::While(while_loop, _) => remove_writes(&mut while_loop.block, locals),
+            ir::Stmt::Branches {
+                branches, default, ..
+            } => {
+                for branch in branches {
+                    remove_writes(&mut branch.block, locals);
+                }
+                if let Some(default) = default {
+                    remove_writes(default, locals);
+                }
+            }
+            ir::Stmt::Switch {
+                branches, default, ..
+            } => {
+                for case in branches {
+                    remove_writes(&mut case.block, locals);
+                }
+                if let Some(default) = default {
+                    remove_writes(default, locals);
+                }
+            }
+            _ => {}
+        }
+        !pure_write(stmt).is_some_and(|local| locals.contains(&local))
+    });
+}
+
+/// Returns the local written to by a statement that has no other effects.
+fn pure_write(stmt: &ir::Stmt<'_>) -> Option<ir::Local> {
+    match stmt {
+        ir::Stmt::Expr(expr) => match &**expr {
+            ir::Expr::Assign { place, expr, .. } if is_pure(expr) => match **place {
+                ir::Expr::Local(local, _) => Some(local),
+                _ => None,
+            },
+            _ => None,
+        },
+        ir::Stmt::InitArray {
+            local, elements, ..
+        } if elements.iter().all(is_pure) => Some(*local),
+        ir::Stmt::InitDefault { local, .. } => Some(*local),
+        _ => None,
+    }
+}
+
+fn is_pure(expr: &ir::Expr<'_>) -> bool {
+    matches!(
+        expr,
+        ir::Expr::Const(_, _)
+            | ir::Expr::Null { .. }
+            | ir::Expr::Local(_, _)
+            | ir::Expr::Capture(_, _)
+    )
+}
+
+/// Collects the locals that are used in any way other than a removable write.
+#[derive(Debug, Default)]
+struct LocalUseCollector {
+    used: HashSet<ir::Local>,
+}
+
+impl<'ctx> Visitor<'ctx> for LocalUseCollector {
+    fn visit_block(&mut self, block: &ir::Block<'ctx>) {
+        for stmt in &block.stmts {
+            if pure_write(stmt).is_none() {
+                self.visit_stmt(stmt);
+                continue;
+            }
+            // the written values can still read other locals
+            match stmt {
+                ir::Stmt::Expr(expr) => {
+                    if let ir::Expr::Assign { expr, .. } = &**expr {
+                        self.visit_expr(expr);
+                    }
+                }
+                ir::Stmt::InitArray { elements, .. } => {
+                    elements.iter().for_each(|elem| self.visit_expr(elem));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn visit_new_closure(&mut self, closure: &ir::Closure<'ctx>, _span: ast::Span) {
+        // closures have their own locals, only the captures refer to the enclosing function
+        self.used.extend(closure.captures.iter().copied());
+    }
+
+    fn visit_local(&mut self, local: ir::Local, _span: ast::Span) {
+        self.used.insert(local);
+    }
+}
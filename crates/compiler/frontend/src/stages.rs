@@ -1,5 +1,8 @@
 mod infer;
 mod resolution;
 
-pub use infer::{LoweredClass, LoweredCompilationUnit, LoweredFunction, TypeInference};
+pub use infer::{
+    AdditionKind, DuplicateAddition, LoweredClass, LoweredCompilationUnit, LoweredFunction,
+    TypeInference,
+};
 pub use resolution::{FunctionAnnotation, NameResolution, Scope};
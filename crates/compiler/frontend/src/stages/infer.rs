@@ -11,28 +11,46 @@ use crate::diagnostic::pass::DiagnosticPass;
 use crate::lower::{Env, Lower};
 use crate::symbols::FreeFunctionIndexes;
 use crate::{
-    CompileErrorReporter, FieldId, FieldIndex, FreeFunction, FreeFunctionIndex, FunctionIndex,
-    FunctionType, IndexMap, IndexSet, MethodId, PolyType, Symbols, Type, TypeId, TypeRef,
-    TypeScope, ir,
+    CompileErrorReporter, Diagnostic, FieldId, FieldIndex, FreeFunction, FreeFunctionIndex,
+    FunctionIndex, FunctionType, IndexMap, IndexSet, LintScope, MethodId, PolyType, Symbols, Type,
+    TypeId, TypeRef, TypeScope, ir, optimize,
 };
 
 #[derive(Debug)]
 pub struct TypeInference<'scope, 'ctx> {
     modules: Vec<InferStageModule<'scope, 'ctx>>,
     symbols: Symbols<'ctx>,
+    duplicate_additions: Vec<DuplicateAddition<'ctx>>,
+    lint_scopes: Vec<LintScope<'ctx>>,
 }
 
 impl<'scope, 'ctx> TypeInference<'scope, 'ctx> {
-    pub fn new(modules: Vec<InferStageModule<'scope, 'ctx>>, symbols: Symbols<'ctx>) -> Self {
-        Self { symbols, modules }
+    pub fn new(
+        modules: Vec<InferStageModule<'scope, 'ctx>>,
+        symbols: Symbols<'ctx>,
+        duplicate_additions: Vec<DuplicateAddition<'ctx>>,
+        lint_scopes: Vec<LintScope<'ctx>>,
+    ) -> Self {
+        Self {
+            symbols,
+            modules,
+            duplicate_additions,
+            lint_scopes,
+        }
     }
 
+    /// Lowers the modules one after another on the calling thread. This can't be spread
+    /// across threads yet: the types and symbols are shared through `Rc`, the type
+    /// references in scopes are resolved lazily through `RefCell`, the type interner is
+    /// not `Sync`, and wrapped methods register their aliases in the symbol table while
+    /// they're being lowered.
     pub fn finish(
         mut self,
         scope: &'scope Scope<'_, 'ctx>,
         reporter: &mut CompileErrorReporter<'ctx>,
     ) -> (LoweredCompilationUnit<'ctx>, Symbols<'ctx>) {
         let mut compiled = LoweredCompilationUnit::default();
+        self.lower_param_defaults(scope, reporter);
 
         for mod_ in mem::take(&mut self.modules) {
             let scope = scope.push(mod_.type_scope, mod_.func_scope);
@@ -120,7 +138,11 @@ impl<'scope, 'ctx> TypeInference<'scope, 'ctx> {
                             reporter,
                             func.span,
                         );
-                        compiled.method_replacements.insert(func.id, lowered);
+                        compiled
+                            .method_replacements
+                            .entry(func.id)
+                            .or_default()
+                            .push(lowered);
                     }
                     FuncItemKind::AddMethod(func) => {
                         let lowered = func.body.as_ref().map(|body| {
@@ -199,8 +221,91 @@ impl<'scope, 'ctx> TypeInference<'scope, 'ctx> {
             }
         }
 
+        compiled.duplicate_additions = self.duplicate_additions;
+        compiled.lint_scopes = self.lint_scopes;
         (compiled, self.symbols)
     }
+
+    // parameter defaults have to be known before any function body is lowered, because
+    // they are inserted at the call sites that omit them
+    fn lower_param_defaults(
+        &mut self,
+        scope: &'scope Scope<'_, 'ctx>,
+        reporter: &mut CompileErrorReporter<'ctx>,
+    ) {
+        for mod_ in &self.modules {
+            let scope = scope.push(mod_.type_scope.clone(), mod_.func_scope.clone());
+
+            for class in &mod_.classes {
+                let types = scope.types.push_scope(class.scope.clone());
+                for item in &class.methods {
+                    let id = MethodId::new(class.id, item.id);
+                    let types = types.push_scope(item.scope.clone());
+                    let env = Env::new(&types, &scope.funcs);
+                    let defaults = lower_defaults(
+                        self.symbols[id].type_(),
+                        self.symbols[id].flags().is_native(),
+                        &item.params,
+                        &env,
+                        &self.symbols,
+                        reporter,
+                    );
+                    let (_, method) = self
+                        .symbols
+                        .get_method_mut(id)
+                        .expect("method should exist");
+                    set_defaults(method.type_mut(), defaults);
+                }
+            }
+
+            for func in &mod_.functions {
+                match func {
+                    FuncItemKind::FreeFunction(func) => {
+                        let types = scope.types.push_scope(func.scope.clone());
+                        let env = Env::new(&types, &scope.funcs);
+                        let defaults = lower_defaults(
+                            self.symbols[func.id].type_(),
+                            self.symbols[func.id].flags().is_native(),
+                            &func.params,
+                            &env,
+                            &self.symbols,
+                            reporter,
+                        );
+                        let (_, free_func) = self
+                            .symbols
+                            .get_free_function_mut(func.id)
+                            .expect("free function should exist");
+                        set_defaults(free_func.type_mut(), defaults);
+                    }
+                    FuncItemKind::AddMethod(func) => {
+                        let types = scope.types.push_scope(func.scope.clone());
+                        let env = Env::new(&types, &scope.funcs);
+                        let defaults = lower_defaults(
+                            self.symbols[func.id].type_(),
+                            self.symbols[func.id].flags().is_native(),
+                            &func.params,
+                            &env,
+                            &self.symbols,
+                            reporter,
+                        );
+                        let (_, method) = self
+                            .symbols
+                            .get_method_mut(func.id)
+                            .expect("method should exist");
+                        set_defaults(method.type_mut(), defaults);
+                    }
+                    FuncItemKind::ReplaceMethod(func) | FuncItemKind::WrapMethod(func) => {
+                        // these share the signature of an existing method, which might be native
+                        for (param, _) in &func.params {
+                            if let Some((_, span)) = param.default.as_deref() {
+                                reporter.report(Diagnostic::UnexpectedParamDefault(*span));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -234,10 +339,54 @@ fn lower_function<'ctx>(
     LoweredFunction {
         block,
         locals,
+        return_type: func_type.return_type().clone(),
         span,
     }
 }
 
+fn lower_defaults<'ctx>(
+    func_type: &FunctionType<'ctx>,
+    is_native: bool,
+    params: &[Spanned<ast::SourceParam<'ctx>>],
+    env: &Env<'_, 'ctx>,
+    symbols: &Symbols<'ctx>,
+    reporter: &mut CompileErrorReporter<'ctx>,
+) -> Vec<(usize, ir::Const<'ctx>)> {
+    let mut defaults = vec![];
+    for (i, (param, (source, _))) in func_type.params().iter().zip(params).enumerate() {
+        let Some(default @ (_, span)) = source.default.as_deref() else {
+            continue;
+        };
+        // native functions are called by the game, which does not know about the defaults
+        if is_native {
+            reporter.report(Diagnostic::UnexpectedParamDefault(*span));
+            continue;
+        }
+        let is_trailing = params[i + 1..].iter().all(|(next, _)| {
+            next.default.is_some() || next.qualifiers.contains(ast::ParamQualifiers::OPTIONAL)
+        });
+        if !is_trailing {
+            reporter.report(Diagnostic::NonTrailingParamDefault(*span));
+            continue;
+        }
+
+        let expected = PolyType::from_type(param.type_());
+        let (value, errors) = Lower::constant(default, env, expected, symbols);
+        reporter.report_many(errors);
+        if let Some(value) = value {
+            defaults.push((i, value));
+        }
+    }
+    defaults
+}
+
+fn set_defaults<'ctx>(func_type: &mut FunctionType<'ctx>, defaults: Vec<(usize, ir::Const<'ctx>)>) {
+    let params = func_type.params_mut();
+    for (i, default) in defaults {
+        params[i].set_default(default);
+    }
+}
+
 fn lower_constant<'ctx>(
     id: FieldId<'ctx>,
     expr: &Spanned<ast::SourceExpr<'ctx>>,
@@ -408,6 +557,7 @@ pub enum FuncItemKind<'scope, 'ctx> {
 pub struct LoweredFunction<'ctx> {
     pub block: ir::Block<'ctx>,
     pub locals: Box<[ir::LocalInfo<'ctx>]>,
+    pub return_type: Type<'ctx>,
     pub span: Span,
 }
 
@@ -420,6 +570,41 @@ impl<'ctx> LoweredFunction<'ctx> {
     }
 }
 
+/// A field or method added through an annotation that has the same name as a member
+/// added by another annotation.
+#[derive(Debug, Clone, Copy)]
+pub struct DuplicateAddition<'ctx> {
+    pub parent: TypeId<'ctx>,
+    pub name: &'ctx str,
+    pub kind: AdditionKind,
+    pub original: Span,
+    pub duplicate: Span,
+}
+
+impl<'ctx> DuplicateAddition<'ctx> {
+    pub fn new(
+        parent: TypeId<'ctx>,
+        name: &'ctx str,
+        kind: AdditionKind,
+        original: Span,
+        duplicate: Span,
+    ) -> Self {
+        Self {
+            parent,
+            name,
+            kind,
+            original,
+            duplicate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdditionKind {
+    Field,
+    Method,
+}
+
 #[derive(Debug, Default)]
 pub struct LoweredCompilationUnit<'ctx> {
     pub classes: IndexMap<TypeId<'ctx>, LoweredClass<'ctx>, BuildIdentityHasher<usize>>,
@@ -428,8 +613,13 @@ pub struct LoweredCompilationUnit<'ctx> {
 
     pub added_fields: IndexMap<FieldId<'ctx>, Option<Box<ir::Const<'ctx>>>>,
     pub added_methods: IndexMap<MethodId<'ctx>, Option<LoweredFunction<'ctx>>>,
-    pub method_replacements: IndexMap<MethodId<'ctx>, LoweredFunction<'ctx>>,
+    /// Replacements of each method in the order they were encountered, the last one
+    /// takes effect.
+    pub method_replacements: IndexMap<MethodId<'ctx>, Vec<LoweredFunction<'ctx>>>,
     pub method_wrappers: IndexMap<MethodId<'ctx>, Vec<LoweredFunction<'ctx>>>,
+    pub duplicate_additions: Vec<DuplicateAddition<'ctx>>,
+    /// Diagnostic levels set on items with annotations.
+    pub lint_scopes: Vec<LintScope<'ctx>>,
 
     pub scopes: IndexMap<FileId, IndexMap<&'ctx str, TypeRef<'static, 'ctx>>>,
 }
@@ -441,13 +631,31 @@ impl<'ctx> LoweredCompilationUnit<'ctx> {
             .flat_map(|class| class.methods.values())
             .chain(self.functions.values())
             .chain(self.added_methods.values().flatten())
-            .chain(self.method_replacements.values())
+            .chain(self.method_replacements.values().flatten())
             .chain(self.method_wrappers.values().flatten())
     }
 
+    pub fn all_functions_mut(&mut self) -> impl Iterator<Item = &mut LoweredFunction<'ctx>> {
+        self.classes
+            .values_mut()
+            .flat_map(|class| class.methods.values_mut())
+            .chain(self.functions.values_mut())
+            .chain(self.added_methods.values_mut().flatten())
+            .chain(self.method_replacements.values_mut().flatten())
+            .chain(self.method_wrappers.values_mut().flatten())
+    }
+
+    /// Applies the IR optimizations to all functions in the unit.
+    pub fn optimize(&mut self, symbols: &Symbols<'ctx>) {
+        for func in self.all_functions_mut() {
+            optimize::optimize(func, symbols);
+        }
+    }
+
     pub fn run_diagnostics(
         &self,
         passes: &[Box<dyn DiagnosticPass<'ctx>>],
+        symbols: &Symbols<'ctx>,
         reporter: &mut CompileErrorReporter<'ctx>,
     ) {
         if passes.is_empty() {
@@ -456,7 +664,7 @@ impl<'ctx> LoweredCompilationUnit<'ctx> {
 
         for func in self.all_functions() {
             for pass in passes {
-                pass.run(func, reporter);
+                pass.run(func, symbols, reporter);
             }
         }
     }
@@ -9,7 +9,9 @@ use identity_hash::BuildIdentityHasher;
 use redscript_ast::{self as ast, Span, Spanned};
 
 use super::TypeInference;
-use super::infer::{ClassItem, FieldItem, FuncItem, FuncItemKind, InferStageModule};
+use super::infer::{
+    AdditionKind, ClassItem, DuplicateAddition, FieldItem, FuncItem, FuncItemKind, InferStageModule,
+};
 use crate::cte::{self, Evaluator};
 use crate::diagnostic::MissingMethod;
 use crate::lower::{InferredTypeApp, TypeEnv};
@@ -19,9 +21,9 @@ use crate::utils::{Lazy, ScopedMap};
 use crate::{
     Aggregate, AggregateFlags, CompileErrorReporter, CtxVar, Diagnostic, Enum, Field, FieldFlags,
     FieldId, FieldMap, FreeFunction, FreeFunctionFlags, FreeFunctionIndex, FunctionIndex,
-    FunctionType, IndexMap, IndexSet, LowerError, Method, MethodFlags, MethodId, MethodMap, Param,
-    ParamFlags, PolyType, QualifiedName, Symbols, Type, TypeApp, TypeDef, TypeId, TypeInterner,
-    TypeRef, TypeSchema, TypeScope, Variance, ir, predef,
+    FunctionType, IndexMap, IndexSet, LintLevel, LintLevels, LintScope, LowerError, Method,
+    MethodFlags, MethodId, MethodMap, Param, ParamFlags, PolyType, QualifiedName, Symbols, Type,
+    TypeApp, TypeDef, TypeId, TypeInterner, TypeRef, TypeSchema, TypeScope, Variance, ir, predef,
 };
 
 pub(super) const WRAP_METHOD_ANNOTATION: &str = "wrapMethod";
@@ -32,6 +34,11 @@ pub(super) const INTRINSIC_ANNOTATION: &str = "intrinsic";
 pub(super) const NEVER_REF_ANNOTATION: &str = "neverRef";
 pub(super) const NAME_IMPLEMENTATION_ANNOTATION: &str = "nameImplementation";
 pub(super) const RUNTIME_PROPERTY_ANNOTATION: &str = "runtimeProperty";
+pub(super) const TEST_ANNOTATION: &str = "test";
+pub(super) const ALLOW_ANNOTATION: &str = "allow";
+pub(super) const WARN_ANNOTATION: &str = "warn";
+pub(super) const DENY_ANNOTATION: &str = "deny";
+pub(super) const NON_NULL_ANNOTATION: &str = "nonNull";
 
 pub(super) const THIS_IDENT: &str = "this";
 pub(super) const WRAPPED_METHOD_IDENT: &str = "wrappedMethod";
@@ -43,6 +50,8 @@ pub struct NameResolution<'scope, 'ctx> {
     symbols: Symbols<'ctx>,
     module_map: ModuleMap<'ctx>,
     evaluator: Evaluator<'ctx>,
+    duplicate_additions: Vec<DuplicateAddition<'ctx>>,
+    lint_scopes: Vec<LintScope<'ctx>>,
     reporter: &'scope mut CompileErrorReporter<'ctx>,
 }
 
@@ -60,6 +69,8 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
             symbols,
             module_map: ModuleMap::default(),
             evaluator,
+            duplicate_additions: vec![],
+            lint_scopes: vec![],
             reporter,
         };
         for module in modules {
@@ -94,9 +105,20 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
             item_span,
         ) in module.items.into_vec()
         {
-            if !process_conditionals(&mut annotations, &self.evaluator, self.reporter) {
+            if !process_conditionals(
+                &mut annotations,
+                &self.evaluator,
+                &self.symbols,
+                self.reporter,
+            ) {
                 continue;
             }
+            process_lint_levels(
+                &mut annotations,
+                item_span,
+                &mut self.lint_scopes,
+                self.reporter,
+            );
 
             let meta = ParsedMeta {
                 annotations: annotations.into(),
@@ -113,8 +135,22 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                     });
                 }
                 ast::Item::Class(ref mut aggregate) | ast::Item::Struct(ref mut aggregate) => {
-                    aggregate.items.retain_mut(|(item, _)| {
-                        process_conditionals(&mut item.annotations, &self.evaluator, self.reporter)
+                    aggregate.items.retain_mut(|(item, span)| {
+                        let include = process_conditionals(
+                            &mut item.annotations,
+                            &self.evaluator,
+                            &self.symbols,
+                            self.reporter,
+                        );
+                        if include {
+                            process_lint_levels(
+                                &mut item.annotations,
+                                *span,
+                                &mut self.lint_scopes,
+                                self.reporter,
+                            );
+                        }
+                        include
                     });
 
                     let (name, name_span) = aggregate.name;
@@ -183,6 +219,10 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                             (WRAP_METHOD_ANNOTATION, &[(ast::Expr::Ident(name), span)]) => {
                                 annotation = Some(FunctionAnnotation::Wrap((name, span)));
                             }
+                            // test functions are compiled as usual and picked up by the test runner
+                            (TEST_ANNOTATION, &[]) => {}
+                            // picked up when the function flags are processed
+                            (NON_NULL_ANNOTATION, &[]) => {}
                             _ => {
                                 self.reporter
                                     .report(Diagnostic::UnknownAnnotation(ann.name, *ann_span));
@@ -345,7 +385,12 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
             .for_each(|module| self.validate_module(module));
         self.process_inheritance(results.iter().flat_map(InferStageModule::classes));
 
-        TypeInference::new(results, self.symbols)
+        TypeInference::new(
+            results,
+            self.symbols,
+            self.duplicate_additions,
+            self.lint_scopes,
+        )
     }
 
     fn process_aggregate<'a>(
@@ -437,7 +482,12 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                 ast::Item::Function(func) => {
                     let (name, name_span) = func.name;
                     let qs = item.qualifiers;
-                    let flags = self.process_method_flags(qs, &func, class_flags, name_span);
+                    let flags = self
+                        .process_method_flags(qs, &func, class_flags, name_span)
+                        .with_is_non_null(check_non_null_annotation(
+                            &item.annotations,
+                            self.reporter,
+                        ));
 
                     let (func_t, type_scope) = self.create_function_env(&func, &types);
                     if func.body.is_none()
@@ -577,7 +627,7 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
             }
         }
 
-        let schema = TypeSchema::Enum(Enum::new(by_name).into());
+        let schema = TypeSchema::Enum(Enum::new(by_name).with_span(Some(name_span)).into());
         self.symbols
             .add_type(entry.id, TypeDef::new([], schema, entry.meta.doc));
     }
@@ -621,7 +671,16 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                 });
                 let base = base.as_ref().map(FunctionEntry::key).copied();
 
-                if base.is_some_and(|base| base.parent() == id) {
+                if let Some(base) = base.filter(|base| base.parent() == id) {
+                    if let Some(original) = self.symbols[base].span() {
+                        self.duplicate_additions.push(DuplicateAddition::new(
+                            id,
+                            name,
+                            AdditionKind::Method,
+                            original,
+                            name_span,
+                        ));
+                    }
                     self.reporter
                         .report(Diagnostic::DuplicateMethodAnnotation(name_span));
                     return None;
@@ -639,7 +698,9 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                         .report(Diagnostic::UserSymbolAnnotation(name_span));
                 }
                 let qs = entry.meta.qualifiers;
-                let flags = self.process_method_flags(qs, &func, parent_flags, name_span);
+                let flags = self
+                    .process_method_flags(qs, &func, parent_flags, name_span)
+                    .with_is_non_null(has_non_null_annotation(&entry.meta.annotations));
 
                 if func.body.is_none() && !flags.is_native() {
                     self.reporter
@@ -688,7 +749,8 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
             {
                 let flags = FreeFunctionFlags::default()
                     .with_is_exec(qs.take_flag(ast::ItemQualifiers::EXEC))
-                    .with_is_native(qs.take_flag(ast::ItemQualifiers::NATIVE));
+                    .with_is_native(qs.take_flag(ast::ItemQualifiers::NATIVE))
+                    .with_is_non_null(has_non_null_annotation(&entry.meta.annotations));
                 let func = FreeFunction::new(flags, func_t, entry.meta.doc, Some(name_span));
                 (func, body)
             }
@@ -775,6 +837,20 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
                         mem::take(&mut doc),
                         Some(name_span),
                     );
+                    let original = self.symbols[parent_t]
+                        .schema()
+                        .as_aggregate()
+                        .and_then(|agg| agg.fields().by_name(name))
+                        .and_then(|(_, field)| field.span());
+                    if let Some(original) = original {
+                        self.duplicate_additions.push(DuplicateAddition::new(
+                            parent_t,
+                            name,
+                            AdditionKind::Field,
+                            original,
+                            name_span,
+                        ));
+                    }
                     let res = self.symbols[parent_t]
                         .schema_mut()
                         .as_aggregate_mut()
@@ -1383,13 +1459,14 @@ impl<'scope, 'ctx> NameResolution<'scope, 'ctx> {
 fn process_conditionals<'ctx>(
     annotations: &mut Vec<Spanned<ast::SourceAnnotation<'ctx>>>,
     evaluator: &Evaluator<'ctx>,
+    symbols: &Symbols<'ctx>,
     reporter: &mut CompileErrorReporter<'ctx>,
 ) -> bool {
     let mut include = true;
 
     annotations.retain(|(ann, _)| match (ann.name, &*ann.args) {
         ("if", [arg]) => {
-            let result = reporter.unwrap_err(evaluator.eval(arg));
+            let result = reporter.unwrap_err(evaluator.eval(arg, symbols));
             include = include && !matches!(result, Some(cte::Value::Bool(false)));
             false
         }
@@ -1399,6 +1476,57 @@ fn process_conditionals<'ctx>(
     include
 }
 
+fn process_lint_levels<'ctx>(
+    annotations: &mut Vec<Spanned<ast::SourceAnnotation<'ctx>>>,
+    item_span: Span,
+    scopes: &mut Vec<LintScope<'ctx>>,
+    reporter: &mut CompileErrorReporter<'ctx>,
+) {
+    annotations.retain(|(ann, ann_span)| {
+        let level = match ann.name {
+            ALLOW_ANNOTATION => LintLevel::Allow,
+            WARN_ANNOTATION => LintLevel::Warn,
+            DENY_ANNOTATION => LintLevel::Deny,
+            _ => return true,
+        };
+        if ann.args.is_empty() {
+            reporter.report(Diagnostic::UnknownAnnotation(ann.name, *ann_span));
+        }
+        for arg in &ann.args {
+            match arg {
+                (ast::Expr::Ident(code), _) if LintLevels::is_known_code(code) => {
+                    scopes.push(LintScope::new(*code, level, item_span));
+                }
+                (ast::Expr::Ident(code), code_span) => {
+                    reporter.report(Diagnostic::UnknownLintCode(code, *code_span));
+                }
+                _ => reporter.report(Diagnostic::UnknownAnnotation(ann.name, *ann_span)),
+            }
+        }
+        false
+    });
+}
+
+fn has_non_null_annotation(annotations: &[Spanned<ast::SourceAnnotation<'_>>]) -> bool {
+    annotations
+        .iter()
+        .any(|(ann, _)| ann.name == NON_NULL_ANNOTATION && ann.args.is_empty())
+}
+
+/// Like [`has_non_null_annotation`], but also reports `@nonNull` annotations with
+/// arguments, used for class methods whose annotations are not validated elsewhere.
+fn check_non_null_annotation<'ctx>(
+    annotations: &[Spanned<ast::SourceAnnotation<'ctx>>],
+    reporter: &mut CompileErrorReporter<'ctx>,
+) -> bool {
+    for (ann, ann_span) in annotations {
+        if ann.name == NON_NULL_ANNOTATION && !ann.args.is_empty() {
+            reporter.report(Diagnostic::UnknownAnnotation(ann.name, *ann_span));
+        }
+    }
+    has_non_null_annotation(annotations)
+}
+
 #[derive(Debug)]
 struct ResolutionStageModule<'ctx> {
     imports: Vec<ParsedImport<'ctx>>,
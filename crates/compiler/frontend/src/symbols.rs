@@ -114,6 +114,13 @@ impl<'ctx> Symbols<'ctx> {
         self.free_funcs.by_index(index.0)
     }
 
+    pub fn get_free_function_mut(
+        &mut self,
+        index: FreeFunctionIndex,
+    ) -> Option<(&QualifiedName<'ctx>, &mut FreeFunction<'ctx>)> {
+        self.free_funcs.by_index_mut(index.0)
+    }
+
     pub fn add_free_function(
         &mut self,
         name: impl Into<QualifiedName<'ctx>>,
@@ -403,6 +410,7 @@ impl<'ctx> TypeSchema<'ctx> {
 #[derive(Debug, Default, Clone)]
 pub struct Enum<'ctx> {
     variants: IndexMap<&'ctx str, i64>,
+    span: Option<Span>,
 }
 
 impl<'ctx> Enum<'ctx> {
@@ -410,9 +418,22 @@ impl<'ctx> Enum<'ctx> {
     pub fn new(variants: impl Into<IndexMap<&'ctx str, i64>>) -> Self {
         Self {
             variants: variants.into(),
+            span: None,
         }
     }
 
+    #[inline]
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Returns the span of the enum name for enums defined in sources.
+    #[inline]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
     #[inline]
     pub fn variant_by_name(&self, name: &str) -> Option<(FieldIndex, i64)> {
         let (index, _, value) = self.variants.get_full(name)?;
@@ -751,6 +772,11 @@ impl<'ctx> FunctionType<'ctx> {
         &self.params
     }
 
+    #[inline]
+    pub fn params_mut(&mut self) -> &mut [Param<'ctx>] {
+        &mut self.params
+    }
+
     #[inline]
     pub fn param_types(&self) -> impl ExactSizeIterator<Item = &Type<'ctx>> + use<'_, 'ctx> {
         self.params.iter().map(Param::type_)
@@ -772,7 +798,7 @@ impl<'ctx> FunctionType<'ctx> {
         self.params
             .iter()
             .rev()
-            .skip_while(|p| p.flags.is_optional())
+            .skip_while(|p| p.is_omittable())
             .count()
     }
 
@@ -855,6 +881,11 @@ impl<'ctx> FreeFunction<'ctx> {
         &self.type_
     }
 
+    #[inline]
+    pub fn type_mut(&mut self) -> &mut FunctionType<'ctx> {
+        &mut self.type_
+    }
+
     #[inline]
     pub fn intrinsic(&self) -> Option<ir::Intrinsic> {
         self.intrinsic
@@ -913,6 +944,11 @@ impl<'ctx> Method<'ctx> {
         &self.typ
     }
 
+    #[inline]
+    pub fn type_mut(&mut self) -> &mut FunctionType<'ctx> {
+        &mut self.typ
+    }
+
     #[inline]
     pub fn overloaded(&self) -> Option<MethodId<'ctx>> {
         self.overloaded
@@ -1041,6 +1077,7 @@ pub struct Param<'ctx, K: TypeKind = Immutable> {
     flags: ParamFlags,
     typ: K::Type<'ctx>,
     span: Option<Span>,
+    default: Option<ir::Const<'ctx>>,
 }
 
 impl<'ctx, K: TypeKind> Param<'ctx, K> {
@@ -1051,6 +1088,7 @@ impl<'ctx, K: TypeKind> Param<'ctx, K> {
             flags,
             typ,
             span,
+            default: None,
         }
     }
 
@@ -1073,6 +1111,23 @@ impl<'ctx, K: TypeKind> Param<'ctx, K> {
     pub fn span(&self) -> Option<Span> {
         self.span
     }
+
+    /// Returns the value passed for this parameter when a call site omits it.
+    #[inline]
+    pub fn default(&self) -> Option<&ir::Const<'ctx>> {
+        self.default.as_ref()
+    }
+
+    #[inline]
+    pub fn set_default(&mut self, default: ir::Const<'ctx>) {
+        self.default = Some(default);
+    }
+
+    /// Returns whether the parameter can be omitted at call sites.
+    #[inline]
+    pub fn is_omittable(&self) -> bool {
+        self.flags.is_optional() || self.default.is_some()
+    }
 }
 
 impl<K: TypeKind> fmt::Display for Param<'_, K> {
@@ -1184,7 +1239,8 @@ pub struct AggregateFlags {
 pub struct FreeFunctionFlags {
     pub is_exec: bool,
     pub is_native: bool,
-    #[bits(6)]
+    pub is_non_null: bool,
+    #[bits(5)]
     __: u8,
 }
 
@@ -1195,7 +1251,8 @@ pub struct MethodFlags {
     pub is_native: bool,
     pub is_callback: bool,
     pub is_unimplemented: bool,
-    #[bits(3)]
+    pub is_non_null: bool,
+    #[bits(2)]
     __: u8,
 }
 
@@ -0,0 +1,71 @@
+#![allow(dead_code)]
+
+use redscript_ast::SourceMap;
+use redscript_compiler_api::{
+    CompileErrorReporter, Diagnostic, LintLevels, LoweredCompilationUnit, SourceMapExt, Symbols,
+    TypeInterner,
+};
+use redscript_compiler_frontend::infer_from_sources;
+use redscript_compiler_frontend::pass::DiagnosticPass;
+
+/// A diagnostic reported for a test source.
+#[derive(Debug)]
+pub struct Reported {
+    pub code: &'static str,
+    pub message: String,
+    pub is_fatal: bool,
+    pub line: usize,
+}
+
+/// Lowers the source along with the boot library and passes the result to `f` along with
+/// the diagnostics reported while lowering.
+pub fn lower<R>(
+    source: &str,
+    f: impl for<'ctx> FnOnce(
+        &SourceMap,
+        LoweredCompilationUnit<'ctx>,
+        Symbols<'ctx>,
+        Vec<Diagnostic<'ctx>>,
+    ) -> R,
+) -> R {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", source);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let symbols = Symbols::with_default_types();
+    let mut reporter = CompileErrorReporter::default();
+    let (unit, symbols) = infer_from_sources(&sources, symbols, &mut reporter, &interner);
+    f(&sources, unit, symbols, reporter.into_reported())
+}
+
+/// Lowers the source, runs the diagnostic pass on it and applies the levels. Returns
+/// everything that was reported ordered by line, with lines starting at 1.
+pub fn check(
+    source: &str,
+    pass: impl for<'ctx> DiagnosticPass<'ctx> + 'static,
+    levels: &LintLevels,
+) -> Vec<Reported> {
+    lower(source, |sources, unit, symbols, mut diagnostics| {
+        let mut reporter = CompileErrorReporter::default();
+        let passes: [Box<dyn DiagnosticPass<'_>>; 1] = [Box::new(pass)];
+        unit.run_diagnostics(&passes, &symbols, &mut reporter);
+        diagnostics.extend(reporter.into_reported());
+
+        let mut reported = levels
+            .apply(diagnostics, &unit.lint_scopes)
+            .iter()
+            .map(|d| {
+                let file = sources.get(d.span().file).unwrap();
+                Reported {
+                    code: d.code(),
+                    message: d.to_string(),
+                    is_fatal: d.is_fatal(),
+                    line: file.lookup(d.span().start).line + 1,
+                }
+            })
+            .collect::<Vec<_>>();
+        reported.sort_by_key(|d| d.line);
+        reported
+    })
+}
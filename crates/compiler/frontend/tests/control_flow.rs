@@ -0,0 +1,107 @@
+mod common;
+
+use redscript_compiler_api::LintLevels;
+use redscript_compiler_frontend::pass::ControlFlow;
+
+const SOURCE: &str = r#"
+func AfterReturn() -> Int32 {
+  return 1;
+  let unreachable = 2;
+}
+
+func AfterBreak() {
+  while true {
+    break;
+    let unreachable = 1;
+  }
+}
+
+func AllBranchesReturn(a: Bool) -> Int32 {
+  if a {
+    return 1;
+  } else {
+    return 2;
+  }
+}
+
+func MissingElse(a: Bool) -> Int32 {
+  if a {
+    return 1;
+  }
+}
+
+func InfiniteLoop() -> Int32 {
+  while true {}
+}
+
+func LoopWithBreak() -> Int32 {
+  while true {
+    break;
+  }
+}
+
+func SwitchFallthrough(a: Int32) -> Int32 {
+  switch a {
+    case 1:
+    case 2:
+      return 1;
+    default:
+      return 2;
+  }
+}
+
+func SwitchWithBreak(a: Int32) -> Int32 {
+  switch a {
+    case 1:
+      break;
+    default:
+      return 2;
+  }
+}
+
+func CaseLet(a: array<Int32>) -> Int32 {
+  switch a {
+    case let [x]:
+      return x;
+    case let [x, y, ..]:
+      return y;
+  }
+}
+
+func CaseLetWithDefault(a: array<Int32>) -> Int32 {
+  switch a {
+    case let [x]:
+      return x;
+    default:
+      return 0;
+  }
+}
+
+func ClosureReturns() -> Int32 {
+  let f = (x: Int32) -> {
+    return x;
+    let unreachable = 1;
+  };
+  return f(1);
+}
+"#;
+
+#[test]
+fn control_flow_diagnostics() {
+    let found = common::check(SOURCE, ControlFlow, &LintLevels::default())
+        .iter()
+        .map(|d| (d.code, d.line))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            ("UNREACHABLE_CODE", 4),
+            ("UNREACHABLE_CODE", 10),
+            ("MISSING_RETURN", 22),
+            ("MISSING_RETURN", 32),
+            ("MISSING_RETURN", 48),
+            ("MISSING_RETURN", 57),
+            ("UNREACHABLE_CODE", 78),
+        ]
+    );
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use redscript_compiler_api::{LintLevel, LintLevels};
+use redscript_compiler_frontend::pass::SwitchExhaustiveness;
+
+const SOURCE: &str = r#"
+enum Color {
+  Red = 0,
+  Green = 1,
+  Blue = 2,
+}
+
+func Missing(color: Color) -> Int32 {
+  switch color {
+    case Color.Red:
+      return 0;
+    case Color.Green:
+      return 1;
+  }
+  return 2;
+}
+
+func WithDefault(color: Color) -> Int32 {
+  switch color {
+    case Color.Red:
+      return 0;
+    default:
+      return 1;
+  }
+}
+
+func Exhaustive(color: Color) -> Int32 {
+  switch color {
+    case Color.Red:
+    case Color.Green:
+      return 0;
+    case Color.Blue:
+      return 1;
+  }
+  return 2;
+}
+
+func Duplicate(color: Color) -> Int32 {
+  switch color {
+    case Color.Red:
+      return 0;
+    case Color.Red:
+      return 1;
+    default:
+      return 2;
+  }
+}
+
+func Nested(color: Color, value: Int32) -> Int32 {
+  switch value {
+    case 0:
+      switch color {
+        case Color.Blue:
+          return 0;
+      }
+  }
+  return 1;
+}
+"#;
+
+#[test]
+fn switch_exhaustiveness_diagnostics() {
+    let mut levels = LintLevels::default();
+    levels.set("NON_EXHAUSTIVE_SWITCH", LintLevel::Deny);
+
+    let found = common::check(SOURCE, SwitchExhaustiveness, &levels)
+        .into_iter()
+        .map(|d| (d.message, d.is_fatal, d.line))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [
+            (
+                "this switch does not handle the following variants of Color: Blue".to_owned(),
+                true,
+                9
+            ),
+            (
+                "this case label repeats an earlier one and can never be matched".to_owned(),
+                false,
+                42
+            ),
+            (
+                "this switch does not handle the following variants of Color: Red, Green"
+                    .to_owned(),
+                true,
+                52
+            ),
+        ]
+    );
+}
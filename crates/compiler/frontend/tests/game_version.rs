@@ -0,0 +1,32 @@
+use redscript_compiler_frontend::cte::GameVersion;
+
+fn version(str: &str) -> GameVersion {
+    str.parse().unwrap()
+}
+
+#[test]
+fn fraction_digits_compare_as_padded() {
+    assert!(version("2.01") < version("2.1"));
+    assert!(version("2.1") < version("2.12"));
+    assert!(version("2.12") < version("2.2"));
+    assert!(version("2.10a") < version("2.9"));
+    assert!(version("2") < version("2.01"));
+    assert!(version("2.99") < version("3"));
+    assert_eq!(version("2.10"), version("2.1"));
+    assert_eq!(version("2.0"), version("2"));
+}
+
+#[test]
+fn suffix_compares_after_digits() {
+    assert!(version("2.1a") < version("2.12"));
+    assert!(version("2.12") < version("2.12a"));
+    assert!(version("2.12a") < version("2.12b"));
+    assert!(version("2.12a") < version("2.2"));
+    assert_eq!(version("2.10a"), version("2.1a"));
+}
+
+#[test]
+fn invalid_versions_are_rejected() {
+    assert!("2.1x2".parse::<GameVersion>().is_err());
+    assert!("two".parse::<GameVersion>().is_err());
+}
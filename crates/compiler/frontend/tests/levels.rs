@@ -0,0 +1,74 @@
+mod common;
+
+use redscript_compiler_api::{LintLevel, LintLevels};
+use redscript_compiler_frontend::pass::UnusedLocals;
+
+const SOURCE: &str = r#"
+enum Denied {
+  One = 1,
+  Two = 1,
+}
+
+@allow(DUP_VARIANT_VAL)
+enum Allowed {
+  One = 1,
+  Two = 1,
+}
+
+@warn(DUP_VARIANT_VAL)
+enum Warned {
+  One = 1,
+  Two = 1,
+}
+
+class Locals {
+  func Denied() {
+    let a = 1;
+  }
+
+  @allow(UNUSED_LOCAL)
+  func Allowed() {
+    let b = 1;
+  }
+}
+"#;
+
+#[test]
+fn annotated_and_global_levels() {
+    let mut levels = LintLevels::default();
+    levels.set("DUP_VARIANT_VAL", LintLevel::Deny);
+    levels.set("UNUSED_LOCAL", LintLevel::Deny);
+
+    let found = common::check(SOURCE, UnusedLocals, &levels)
+        .iter()
+        .map(|d| (d.code, d.is_fatal))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [
+            ("DUP_VARIANT_VAL", true),
+            ("DUP_VARIANT_VAL", false),
+            ("UNUSED_LOCAL", true),
+        ]
+    );
+}
+
+#[test]
+fn unknown_annotated_codes_are_reported() {
+    let source = r#"
+        @allow(UNUSED_LOCALS)
+        func Typo() {
+          let a = 1;
+        }
+    "#;
+    let found = common::check(source, UnusedLocals, &LintLevels::default())
+        .iter()
+        .map(|d| (d.code, d.is_fatal, d.line))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        found,
+        [("UNKNOWN_LINT_CODE", false, 2), ("UNUSED_LOCAL", false, 4)]
+    );
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use redscript_compiler_api::Symbols;
+use redscript_compiler_frontend::{LoweredCompilationUnit, LoweredFunction, ir};
+
+const SOURCE: &str = r#"
+class Node {
+  let value: Int32;
+  let next: ref<Node>;
+
+  func Value() -> Int32 {
+    return this.value;
+  }
+
+  func Next() -> ref<Node> {
+    return this.next;
+  }
+
+  func Touch() {}
+}
+
+native func Make() -> ref<Node>
+native func Fallback() -> ref<Node>
+
+func ValueOrDefault(node: ref<Node>) -> Int32 = node?.Value()
+
+func NextOrNull(node: ref<Node>) -> ref<Node> = node?.Next()
+
+func TouchIfDefined(node: ref<Node>) {
+  node?.Touch();
+}
+
+func MadeOrFallback() -> ref<Node> = Make() ?? Fallback()
+
+func NodeOrMade(node: ref<Node>) -> ref<Node> = node ?? Make()?.next
+"#;
+
+#[test]
+fn null_operators_are_lowered() {
+    common::lower(SOURCE, |_, unit, symbols, errors| {
+        assert!(errors.is_empty(), "{errors:?}");
+
+        // a value type falls back to a local of the member type that's default-initialized
+        // only when the receiver is null
+        let func = function(&unit, &symbols, "ValueOrDefault");
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [
+            ir::Stmt::Branches {
+                branches,
+                default: Some(default),
+                ..
+            },
+            ir::Stmt::Return(Some(ret), _),
+        ] = &stmts[..]
+        else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        let [branch] = &branches[..] else {
+            panic!("expected a single branch, got {branches:?}");
+        };
+        assert_eq!(callee(&symbols, &branch.condition), Some("IsDefined"));
+        assert!(!format!("{:?}", branch.block).contains("InitDefault"));
+        let fallback = default.stmts.iter().collect::<Vec<_>>();
+        let [
+            ir::Stmt::InitDefault { local, typ, .. },
+            ir::Stmt::Expr(assign),
+        ] = &fallback[..]
+        else {
+            panic!("unexpected fallback: {default:?}");
+        };
+        assert_eq!(typ.to_string(), "Int32");
+        assert!(matches!(
+            &**assign,
+            ir::Expr::Assign { expr, .. } if matches!(**expr, ir::Expr::Local(value, _) if value == *local)
+        ));
+        assert!(matches!(**ret, ir::Expr::Local(..)));
+
+        // a reference falls back to null
+        let func = function(&unit, &symbols, "NextOrNull");
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [ir::Stmt::Return(Some(ret), _)] = &stmts[..] else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        assert!(matches!(
+            &**ret,
+            ir::Expr::Conditional { else_, .. } if matches!(**else_, ir::Expr::Null { is_weak: false, .. })
+        ));
+
+        // a void call is only evaluated when the receiver is defined
+        let func = function(&unit, &symbols, "TouchIfDefined");
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [ir::Stmt::Expr(expr)] = &stmts[..] else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        let ir::Expr::Conditional {
+            condition,
+            then,
+            else_,
+            ..
+        } = &**expr
+        else {
+            panic!("expected a conditional, got {expr:?}");
+        };
+        assert_eq!(callee(&symbols, condition), Some("IsDefined"));
+        assert!(
+            matches!(&**then, ir::Expr::Call { call, .. } if matches!(**call, ir::Call::Instance { .. }))
+        );
+        assert!(matches!(**else_, ir::Expr::Null { .. }));
+
+        // the left operand is evaluated once into a local that's checked and returned
+        let func = function(&unit, &symbols, "MadeOrFallback");
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [ir::Stmt::Expr(assign), ir::Stmt::Return(Some(ret), _)] = &stmts[..] else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        let ir::Expr::Assign { place, expr, .. } = &**assign else {
+            panic!("expected an assignment, got {assign:?}");
+        };
+        let &ir::Expr::Local(lhs, _) = &**place else {
+            panic!("expected a local, got {place:?}");
+        };
+        assert_eq!(callee(&symbols, expr), Some("Make"));
+        let ir::Expr::Conditional { then, else_, .. } = &**ret else {
+            panic!("expected a conditional, got {ret:?}");
+        };
+        assert!(matches!(**then, ir::Expr::Local(local, _) if local == lhs));
+        assert_eq!(callee(&symbols, else_), Some("Fallback"));
+
+        // statements needed by the right operand only run when the left one is null
+        let func = function(&unit, &symbols, "NodeOrMade");
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [
+            ir::Stmt::Branches {
+                branches,
+                default: Some(default),
+                ..
+            },
+            ir::Stmt::Return(Some(ret), _),
+        ] = &stmts[..]
+        else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        let [branch] = &branches[..] else {
+            panic!("expected a single branch, got {branches:?}");
+        };
+        assert_eq!(callee(&symbols, &branch.condition), Some("IsDefined"));
+        assert!(!format!("{:?}", branch.block).contains("FreeFunction"));
+        let Some(ir::Stmt::Expr(first)) = default.stmts.front() else {
+            panic!("unexpected fallback: {default:?}");
+        };
+        assert!(
+            matches!(&**first, ir::Expr::Assign { expr, .. } if callee(&symbols, expr) == Some("Make"))
+        );
+        assert!(matches!(**ret, ir::Expr::Local(..)));
+    });
+}
+
+fn function<'a, 'ctx>(
+    unit: &'a LoweredCompilationUnit<'ctx>,
+    symbols: &Symbols<'ctx>,
+    name: &str,
+) -> &'a LoweredFunction<'ctx> {
+    let entry = symbols
+        .free_functions()
+        .find(|entry| entry.name().as_single_component() == Some(name))
+        .unwrap();
+    &unit.functions[entry.key()]
+}
+
+/// Returns the name of the free function called by the expression.
+fn callee<'ctx>(symbols: &Symbols<'ctx>, expr: &ir::Expr<'ctx>) -> Option<&'ctx str> {
+    let ir::Expr::Call { call, .. } = expr else {
+        return None;
+    };
+    let ir::Call::FreeFunction { function, .. } = &**call else {
+        return None;
+    };
+    symbols
+        .free_functions()
+        .find(|entry| entry.key() == function)
+        .and_then(|entry| entry.name().as_single_component())
+}
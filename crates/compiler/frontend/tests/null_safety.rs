@@ -0,0 +1,167 @@
+mod common;
+
+use redscript_compiler_api::LintLevels;
+use redscript_compiler_frontend::pass::NullSafety;
+
+const SOURCE: &str = r#"
+class Node {
+  let next: Node;
+  let parent: wref<Node>;
+
+  func Value() -> Int32 {
+    return 1;
+  }
+
+  @nonNull
+  func Child() -> Node {
+    return new Node();
+  }
+}
+
+class Leaf extends Node {}
+
+class Checker {
+  func IsA(name: CName) -> Bool {
+    return true;
+  }
+}
+
+@addMethod(IScriptable)
+public func IsA(name: CName) -> Bool {
+  return true;
+}
+
+native func OperatorLogicNot(a: Bool) -> Bool;
+native func OperatorLogicAnd(a: Bool, b: Bool) -> Bool;
+
+@nonNull
+func MakeNode() -> Node {
+  return new Node();
+}
+
+func Unchecked(node: Node) -> Int32 {
+  return node.Value();
+}
+
+func Checked(node: Node) -> Int32 {
+  if IsDefined(node) {
+    return node.Value();
+  }
+  return 0;
+}
+
+func EarlyReturn(node: Node) -> Int32 {
+  if !IsDefined(node) {
+    return 0;
+  }
+  return node.Value();
+}
+
+func CheckedField(node: Node) -> Int32 {
+  if IsDefined(node) && IsDefined(node.next) {
+    return node.next.Value();
+  }
+  return 0;
+}
+
+func Constructed() -> Int32 {
+  let node = new Node();
+  let other = MakeNode();
+  node.Value();
+  node.Child().Value();
+  return other.Value();
+}
+
+func Reassigned(node: Node) -> Int32 {
+  let current = node;
+  if IsDefined(current) {
+    current = current.next;
+    return current.Value();
+  }
+  return 0;
+}
+
+func Loop(node: Node) {
+  let current = node;
+  while IsDefined(current) {
+    current = current.next;
+  }
+}
+
+func Weak(node: Node) -> Int32 {
+  if IsDefined(node) {
+    return node.parent.Value();
+  }
+  return 0;
+}
+
+func Patterns(node: Node, parent: wref<Node>) -> Int32 {
+  if let leaf as Leaf = node {
+    return leaf.Value();
+  }
+  if let parent? = parent {
+    return parent.Value();
+  }
+  return 0;
+}
+
+func Casts(node: Node) -> Int32 {
+  if IsDefined(node as Leaf) {
+    return node.Value();
+  }
+  let leaf = node as Leaf;
+  return leaf.Value();
+}
+
+func UnrelatedIsA(checker: Checker) -> Bool {
+  if checker.IsA(n"Checker") {
+    return checker.IsA(n"Node");
+  }
+  return false;
+}
+
+func OptionalChaining(node: Node, fallback: Node) -> Int32 {
+  let next = node?.next ?? fallback;
+  next?.Value();
+  return node?.parent?.Value();
+}
+"#;
+
+#[test]
+fn null_safety_diagnostics() {
+    let found = common::check(SOURCE, NullSafety, &LintLevels::default())
+        .into_iter()
+        .map(|d| (d.message, d.line))
+        .collect::<Vec<_>>();
+
+    let strong = "this reference may be null here, check it with IsDefined first";
+    let weak = "this weak reference may be null here, check it with IsDefined first";
+    assert_eq!(
+        found,
+        [
+            (strong.to_owned(), 38),
+            (strong.to_owned(), 74),
+            (weak.to_owned(), 88),
+            (strong.to_owned(), 108),
+            (strong.to_owned(), 112),
+            (strong.to_owned(), 113),
+        ]
+    );
+}
+
+#[test]
+fn non_null_methods_reject_arguments() {
+    let source = r#"
+class Node {
+  @nonNull(Node)
+  func Child() -> Node {
+    return new Node();
+  }
+}
+"#;
+    let found = common::check(source, NullSafety, &LintLevels::default())
+        .into_iter()
+        .map(|d| (d.code, d.line))
+        .collect::<Vec<_>>();
+    assert_eq!(found, [("INVALID_ANN_USE", 3)]);
+}
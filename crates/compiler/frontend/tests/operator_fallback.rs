@@ -0,0 +1,61 @@
+mod common;
+
+use redscript_compiler_frontend::ir;
+
+const SOURCE: &str = r#"
+native func Next() -> Int32
+native func OperatorModulo(a: Int32, b: Int32) -> Int32
+native func OperatorMultiply(a: Uint32, b: Uint32) -> Uint32
+
+func ModuloAt(arr: [Int32]) {
+  arr[Next()] %= 2;
+}
+
+func ShiftUnsigned(a: Uint32) -> Uint32 = a << 2
+
+func ShiftSigned(a: Int32) -> Int32 = a << 2
+"#;
+
+#[test]
+fn operator_fallbacks() {
+    // only the shift of a signed integer has no fallback
+    common::lower(SOURCE, |_, unit, symbols, errors| {
+        let [error] = &errors[..] else {
+            panic!("expected a single error, got {errors:?}");
+        };
+        assert_eq!(error.code(), "UNRESOLVED_FN");
+
+        // the index of a compound assignment is evaluated once and reused for the read and write
+        let entry = symbols
+            .free_functions()
+            .find(|entry| entry.name().as_single_component() == Some("ModuloAt"))
+            .unwrap();
+        let func = &unit.functions[entry.key()];
+        let stmts = func.block.stmts.iter().collect::<Vec<_>>();
+        let [ir::Stmt::Expr(extract), ir::Stmt::Expr(assign)] = &stmts[..] else {
+            panic!("unexpected lowering: {:?}", func.block);
+        };
+        let ir::Expr::Assign { place, .. } = &**extract else {
+            panic!("expected an assignment, got {extract:?}");
+        };
+        let &ir::Expr::Local(index, _) = &**place else {
+            panic!("expected a local, got {place:?}");
+        };
+
+        let ir::Expr::Assign { place, expr, .. } = &**assign else {
+            panic!("expected an assignment, got {assign:?}");
+        };
+        let ir::Expr::Call { call, .. } = &**expr else {
+            panic!("expected a call, got {expr:?}");
+        };
+        let [current, _] = call.args() else {
+            panic!("unexpected arguments: {call:?}");
+        };
+        for place in [&**place, current] {
+            assert!(matches!(
+                place,
+                ir::Expr::Index { index: idx, .. } if matches!(**idx, ir::Expr::Local(local, _) if local == index)
+            ));
+        }
+    });
+}
@@ -1,5 +1,6 @@
 use std::cell::Cell;
 use std::hash::{Hash, Hasher};
+use std::mem;
 use std::rc::Rc;
 
 use hashbrown::{HashMap, HashSet};
@@ -18,18 +19,36 @@ pub enum Verbosity {
     Verbose,
 }
 
+/// Comments to be written before the decompiled statements, keyed by the statement.
+pub type Comments = HashMap<ast::NodeId, Vec<String>>;
+
 pub fn decompile_block<'ctx: 'i, 'i>(
     function: &'ctx Function<'i>,
     bundle: &'ctx ScriptBundle<'i>,
     code: &[Instr],
     block: &ControlFlowBlock,
     verbosity: Verbosity,
+    comments: &mut Comments,
 ) -> Result<ast::Block<'i>> {
     let mut decomp = Decompiler::new(function, code, block, bundle, verbosity);
-    let block = decomp.consume_block(block, Location::MAX)?;
-    let mut stmts = decomp.into_prelude()?;
-    stmts.extend(block.stmts);
-    Ok(ast::Block::new(stmts))
+    let (mut stmts, mut commented) = decomp.consume_stmts(block, Location::MAX)?;
+    if !decomp.pending_comments.is_empty() {
+        // markers at the end of the function are attached to an explicit return
+        commented.push((stmts.len(), mem::take(&mut decomp.pending_comments)));
+        stmts.push(ast::Stmt::Return(None));
+    }
+    comments.extend(mem::take(&mut decomp.comments));
+
+    let prelude = decomp.into_prelude()?;
+    let offset = prelude.len();
+    let block = ast::Block::new(prelude.into_iter().chain(stmts).collect::<Vec<_>>());
+    for (index, stmt_comments) in commented {
+        comments.insert(
+            ast::NodeId::stmt(&block.stmts[index + offset]),
+            stmt_comments,
+        );
+    }
+    Ok(block)
 }
 
 struct Decompiler<'scope, 'ctx, 'i> {
@@ -40,6 +59,8 @@ struct Decompiler<'scope, 'ctx, 'i> {
     liveness: HashMap<LocalIndex, Bounds>,
     declared: HashSet<Declaration>,
     verbosity: Verbosity,
+    pending_comments: Vec<String>,
+    comments: Comments,
 }
 
 impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
@@ -59,6 +80,8 @@ impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
             liveness: calculate_liveness(code),
             declared: HashSet::new(),
             verbosity,
+            pending_comments: vec![],
+            comments: Comments::new(),
         }
     }
 
@@ -75,21 +98,70 @@ impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
         cf_block: &ControlFlowBlock,
         end: Location,
     ) -> Result<ast::Block<'i>> {
+        let (stmts, commented) = self.consume_stmts(cf_block, end)?;
+        let block = ast::Block::new(stmts);
+        for (index, comments) in commented {
+            self.comments
+                .insert(ast::NodeId::stmt(&block.stmts[index]), comments);
+        }
+        Ok(block)
+    }
+
+    /// Consumes statements up to `end`, returning them along with the comments that precede
+    /// them, keyed by the statement index.
+    fn consume_stmts(
+        &mut self,
+        cf_block: &ControlFlowBlock,
+        end: Location,
+    ) -> Result<(Vec<ast::Stmt<'i>>, Vec<(usize, Vec<String>)>)> {
         let mut stmts = vec![];
+        let mut commented = vec![];
         while self.position() < end {
-            match self.consume_stmt(cf_block, end)? {
-                Some(stmt @ ast::Stmt::Return(_)) => {
-                    stmts.push(stmt);
-                    // stop on first return statement
-                    break;
-                }
-                Some(stmt) => {
-                    stmts.push(stmt);
+            self.consume_markers(end)?;
+            if self.position() >= end {
+                break;
+            }
+            let comments = mem::take(&mut self.pending_comments);
+            let Some(stmt) = self.consume_stmt(cf_block, end)? else {
+                self.pending_comments.splice(0..0, comments);
+                break;
+            };
+            if !comments.is_empty() {
+                commented.push((stmts.len(), comments));
+            }
+            let is_return = matches!(stmt, ast::Stmt::Return(_));
+            stmts.push(stmt);
+            if is_return {
+                // stop on first return statement
+                break;
+            }
+        }
+        Ok((stmts, commented))
+    }
+
+    /// Consumes the breakpoint and profiling markers, which have no source form. In verbose mode,
+    /// the profiling markers are kept as comments for the next statement.
+    fn consume_markers(&mut self, end: Location) -> Result<()> {
+        while self.position() < end {
+            match self.code.clone().next() {
+                Some(Instr::Breakpoint(_)) => {}
+                Some(Instr::Profile(profile)) => {
+                    if self.verbosity == Verbosity::Verbose {
+                        let kind = if profile.is_enabled() {
+                            "enter"
+                        } else {
+                            "exit"
+                        };
+                        let function = String::from_utf8_lossy(profile.function());
+                        self.pending_comments
+                            .push(format!("// profile {kind} {function}"));
+                    }
                 }
-                None => break,
+                _ => break,
             }
+            self.consume_instr()?;
         }
-        Ok(ast::Block::new(stmts))
+        Ok(())
     }
 
     fn consume_stmt(
@@ -261,6 +333,13 @@ impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
                 self.consume_instr()?;
                 return Ok(None);
             }
+            Instr::Breakpoint(_) | Instr::Profile(_) => {
+                self.consume_markers(end)?;
+                if self.position() >= end {
+                    return Ok(None);
+                }
+                return self.consume_stmt(cf_block, end);
+            }
             _ => self.consume_expr()?.into_stmt(),
         };
         Ok(Some(stmt))
@@ -275,16 +354,16 @@ impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
         let verbose = self.verbosity == Verbosity::Verbose;
         let expr = match self.consume_instr()? {
             Instr::Null | Instr::WeakRefNull => ast::Expr::Null,
-            Instr::I32One => ast::Expr::Constant(ast::Constant::I32(1)),
-            Instr::I32Zero => ast::Expr::Constant(ast::Constant::I32(0)),
-            &Instr::I8Const(val) => ast::Expr::Constant(ast::Constant::I32(val.into())),
-            &Instr::I16Const(val) => ast::Expr::Constant(ast::Constant::I32(val.into())),
-            &Instr::I32Const(val) => ast::Expr::Constant(ast::Constant::I32(val)),
-            &Instr::I64Const(val) => ast::Expr::Constant(ast::Constant::I64(val)),
-            &Instr::U8Const(val) => ast::Expr::Constant(ast::Constant::U32(val.into())),
-            &Instr::U16Const(val) => ast::Expr::Constant(ast::Constant::U32(val.into())),
-            &Instr::U32Const(val) => ast::Expr::Constant(ast::Constant::U32(val)),
-            &Instr::U64Const(val) => ast::Expr::Constant(ast::Constant::U64(val)),
+            Instr::I32One => ast::Expr::Constant(ast::Constant::I32(1, None)),
+            Instr::I32Zero => ast::Expr::Constant(ast::Constant::I32(0, None)),
+            &Instr::I8Const(val) => ast::Expr::Constant(ast::Constant::I32(val.into(), None)),
+            &Instr::I16Const(val) => ast::Expr::Constant(ast::Constant::I32(val.into(), None)),
+            &Instr::I32Const(val) => ast::Expr::Constant(ast::Constant::I32(val, None)),
+            &Instr::I64Const(val) => ast::Expr::Constant(ast::Constant::I64(val, None)),
+            &Instr::U8Const(val) => ast::Expr::Constant(ast::Constant::U32(val.into(), None)),
+            &Instr::U16Const(val) => ast::Expr::Constant(ast::Constant::U32(val.into(), None)),
+            &Instr::U32Const(val) => ast::Expr::Constant(ast::Constant::U32(val, None)),
+            &Instr::U64Const(val) => ast::Expr::Constant(ast::Constant::U64(val, None)),
             &Instr::F32Const(val) => ast::Expr::Constant(ast::Constant::F32(val)),
             &Instr::F64Const(val) => ast::Expr::Constant(ast::Constant::F64(val)),
             &Instr::CNameConst(idx) => {
@@ -369,11 +448,12 @@ impl<'scope, 'ctx: 'i, 'i> Decompiler<'scope, 'ctx, 'i> {
                     member: self.bundle.try_get_item_hint(field.name(), "field name")?,
                 }
             }
-            Instr::Conditional(_) => ast::Expr::Conditional {
-                cond: self.consume_expr()?.into_expr().into(),
-                then: self.consume_expr()?.into_expr().into(),
-                else_: self.consume_expr()?.into_expr().into(),
-            },
+            Instr::Conditional(_) => {
+                let cond = self.consume_expr()?.into_expr();
+                let then = self.consume_expr()?.into_expr();
+                let else_ = self.consume_expr()?.into_expr();
+                null_checked_conditional(cond, then, else_)
+            }
             &Instr::Construct { arg_count, class } => {
                 let class = self.bundle.try_get_item(class)?;
                 let args = (0..arg_count)
@@ -710,3 +790,54 @@ fn calculate_liveness(code: &[Instr]) -> HashMap<LocalIndex, Bounds> {
 
     liveness
 }
+
+/// Turns the conditionals generated for `a?.b`, `a?.b()` and `a ?? b` back into the
+/// operators, anything else is kept as a regular conditional.
+fn null_checked_conditional<'i>(
+    cond: ast::Expr<'i>,
+    then: ast::Expr<'i>,
+    else_: ast::Expr<'i>,
+) -> ast::Expr<'i> {
+    let is_checked = |expr: &ast::Expr<'i>| match &cond {
+        ast::Expr::Call {
+            expr: func, args, ..
+        } if **func == ast::Expr::Ident("IsDefined") => {
+            matches!(&args[..], [arg @ (ast::Expr::Ident(_) | ast::Expr::This)] if arg == expr)
+        }
+        _ => false,
+    };
+
+    match (then, else_) {
+        (then, else_) if is_checked(&then) => ast::Expr::BinOp {
+            lhs: then.into(),
+            op: ast::BinOp::Coalesce,
+            rhs: else_.into(),
+        },
+        (ast::Expr::Member { expr, member }, ast::Expr::Null) if is_checked(&expr) => {
+            ast::Expr::OptionalMember { expr, member }
+        }
+        (
+            ast::Expr::Call {
+                expr,
+                type_args,
+                args,
+            },
+            ast::Expr::Null,
+        ) if matches!(&*expr, ast::Expr::Member { expr, .. } if is_checked(expr)) => {
+            let expr = match *expr {
+                ast::Expr::Member { expr, member } => ast::Expr::OptionalMember { expr, member },
+                expr => expr,
+            };
+            ast::Expr::Call {
+                expr: expr.into(),
+                type_args,
+                args,
+            }
+        }
+        (then, else_) => ast::Expr::Conditional {
+            cond: cond.into(),
+            then: then.into(),
+            else_: else_.into(),
+        },
+    }
+}
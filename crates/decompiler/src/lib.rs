@@ -2,9 +2,11 @@ use std::borrow::Cow;
 
 use control_flow::build_control_flow;
 pub use decompiler::Verbosity;
-use decompiler::decompile_block;
+use decompiler::{Comments, decompile_block};
 use error::{Error, Result};
+use hashbrown::HashMap;
 use redscript_ast as ast;
+use redscript_formatter::{FormatCtx, FormatSettings, Prefix, SyntaxOps};
 use redscript_io::{
     Class, CodeIter, Definition, Enum, Field, Function, Parameter, PoolItemIndex, ScriptBundle,
     TypeIndex, TypeKind, Visibility,
@@ -37,20 +39,68 @@ pub fn decompile_all<'ctx: 'i, 'i>(
     bundle: &'ctx ScriptBundle<'i>,
     settings: &Settings,
 ) -> impl Iterator<Item = Result<ast::ItemDecl<'i>>> {
-    bundle.definitions().filter_map(move |def| match def {
-        Definition::Class(class) => Some(decompile_class(class, bundle, settings)),
-        Definition::Function(function) if function.class().is_none() => {
-            Some(decompile_function(function, bundle, settings))
-        }
+    bundle
+        .definitions()
+        .filter_map(move |def| decompile_definition(def, bundle, settings, &mut Comments::new()))
+}
+
+/// Decompiles all definitions in the bundle and formats them as source. In verbose mode,
+/// the profiling markers that have no source form are written as comments.
+pub fn format_all<'ctx: 'i, 'i>(
+    bundle: &'ctx ScriptBundle<'i>,
+    settings: &'ctx Settings,
+    format_settings: &'ctx FormatSettings,
+) -> impl Iterator<Item = Result<String>> {
+    bundle.definitions().filter_map(move |def| {
+        let mut comments = Comments::new();
+        let item = match decompile_definition(def, bundle, settings, &mut comments)? {
+            Ok(item) => item,
+            Err(err) => return Some(Err(err)),
+        };
+        let prefixes = comments
+            .iter()
+            .map(|(&id, comments)| {
+                let prefixes = comments
+                    .iter()
+                    .map(|c| Prefix::LineComment(c.as_str()))
+                    .collect();
+                (id, prefixes)
+            })
+            .collect::<HashMap<_, _>>();
+        let ctx = FormatCtx::new(format_settings, &prefixes);
+        Some(Ok(item.as_fmt(ctx).to_string()))
+    })
+}
+
+fn decompile_definition<'ctx: 'i, 'i>(
+    def: &'ctx Definition<'i>,
+    bundle: &'ctx ScriptBundle<'i>,
+    settings: &Settings,
+    comments: &mut Comments,
+) -> Option<Result<ast::ItemDecl<'i>>> {
+    match def {
+        Definition::Class(class) => Some(decompile_class_with(class, bundle, settings, comments)),
+        Definition::Function(function) if function.class().is_none() => Some(
+            decompile_function_with(function, bundle, settings, comments),
+        ),
         Definition::Enum(enum_) => Some(decompile_enum(enum_, bundle)),
         _ => None,
-    })
+    }
 }
 
 pub fn decompile_class<'ctx: 'i, 'i>(
     class: &'ctx Class,
     bundle: &'ctx ScriptBundle<'i>,
     settings: &Settings,
+) -> Result<ast::ItemDecl<'i>> {
+    decompile_class_with(class, bundle, settings, &mut Comments::new())
+}
+
+fn decompile_class_with<'ctx: 'i, 'i>(
+    class: &'ctx Class,
+    bundle: &'ctx ScriptBundle<'i>,
+    settings: &Settings,
+    comments: &mut Comments,
 ) -> Result<ast::ItemDecl<'i>> {
     let name = extract_mangled_name(expect_borrowed(
         bundle.try_get_item_hint(class.name(), "class name")?,
@@ -72,10 +122,9 @@ pub fn decompile_class<'ctx: 'i, 'i>(
         .iter()
         .map(|&field_idx| decompile_field(bundle.try_get_item(field_idx)?, bundle));
 
-    let methods = class
-        .methods()
-        .iter()
-        .map(|&func_idx| decompile_function(bundle.try_get_item(func_idx)?, bundle, settings));
+    let methods = class.methods().iter().map(|&func_idx| {
+        decompile_function_with(bundle.try_get_item(func_idx)?, bundle, settings, comments)
+    });
 
     let members = fields.chain(methods).collect::<Result<Vec<_>>>()?;
     let aggregate = ast::Aggregate::new(name, [], base, members);
@@ -119,6 +168,15 @@ pub fn decompile_function<'ctx: 'i, 'i>(
     func: &'ctx Function<'i>,
     bundle: &'ctx ScriptBundle<'i>,
     settings: &Settings,
+) -> Result<ast::ItemDecl<'i>> {
+    decompile_function_with(func, bundle, settings, &mut Comments::new())
+}
+
+fn decompile_function_with<'ctx: 'i, 'i>(
+    func: &'ctx Function<'i>,
+    bundle: &'ctx ScriptBundle<'i>,
+    settings: &Settings,
+    comments: &mut Comments,
 ) -> Result<ast::ItemDecl<'i>> {
     let demangled_name = extract_mangled_name(expect_borrowed(
         bundle.try_get_item_hint(func.name(), "function name")?,
@@ -131,7 +189,7 @@ pub fn decompile_function<'ctx: 'i, 'i>(
     } else {
         let code = func.body().code_iter().collect::<Result<Vec<_>, _>>()?;
         let cf_block = build_control_flow(CodeIter::new(&code))?;
-        let block = decompile_block(func, bundle, &code, &cf_block, settings.verbosity)?;
+        let block = decompile_block(func, bundle, &code, &cf_block, settings.verbosity, comments)?;
         Some(ast::FunctionBody::Block(block))
     };
 
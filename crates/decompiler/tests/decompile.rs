@@ -0,0 +1,108 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Compilation, Profiling, SourceMapExt, TypeInterner};
+use redscript_decompiler::{Settings, decompile_all, format_all};
+use redscript_formatter::{FormatCtx, FormatSettings, SyntaxOps};
+use redscript_io::{Class, ClassFlags, ScriptBundle, Visibility};
+
+const SOURCE: &str = r#"
+class Node {
+  let next: ref<Node>;
+
+  func Next() -> ref<Node> {
+    return this.next;
+  }
+}
+
+native func Fallback() -> ref<Node>
+
+func OptionalField(node: ref<Node>) -> ref<Node> = node?.next
+
+func OptionalCall(node: ref<Node>) -> ref<Node> = node?.Next()
+
+func Coalesce(node: ref<Node>) -> ref<Node> = node ?? Fallback()
+
+func Ternary(node: ref<Node>, other: ref<Node>) -> ref<Node> = IsDefined(node) ? other : node
+"#;
+
+#[test]
+fn null_checked_conditionals_round_trip() {
+    let bytes = compile(SOURCE, Profiling::Disabled);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+
+    let settings = FormatSettings::default();
+    let prefixes = Default::default();
+    let ctx = FormatCtx::new(&settings, &prefixes);
+    let output = decompile_all(&bundle, &Settings::default())
+        .map(|item| item.unwrap().as_fmt(ctx).to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for expected in [
+        "return node?.next;",
+        "return node?.Next();",
+        "return node ?? Fallback();",
+        "return IsDefined(node) ? other : node;",
+    ] {
+        assert!(
+            output.contains(expected),
+            "{expected} not found in:\n{output}"
+        );
+    }
+}
+
+#[test]
+fn profiling_markers_written_as_comments_in_verbose_mode() {
+    let bytes = compile(PROFILED_SOURCE, Profiling::Functions);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let format_settings = FormatSettings::default();
+
+    let settings = Settings::default().verbose();
+    let output = format_all(&bundle, &settings, &format_settings)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+        .join("\n");
+
+    let enter = output.find("// profile enter Profiled;Int32");
+    let exit = output.find("// profile exit Profiled;Int32");
+    let ret = output.find("return ");
+    assert!(
+        matches!((enter, exit, ret), (Some(enter), Some(exit), Some(ret)) if enter < exit && exit < ret),
+        "profiling comments not found in order in:\n{output}"
+    );
+
+    let output = format_all(&bundle, &Settings::default(), &format_settings)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+        .join("\n");
+    assert!(
+        !output.contains("// profile"),
+        "unexpected comments in:\n{output}"
+    );
+}
+
+const PROFILED_SOURCE: &str = r#"
+func Profiled(a: Int32) -> Int32 {
+  let b = a + 1;
+  return b;
+}
+"#;
+
+fn compile(source: &str, profiling: Profiling) -> Vec<u8> {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", source);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let (bundle, _, _) = Compilation::new(&bytes, &sources, &interner)
+        .unwrap()
+        .with_profiling(profiling)
+        .finish()
+        .unwrap();
+    // the decompiler expects the names to be borrowed from the file it reads
+    bundle.into_writeable().to_bytes().unwrap()
+}
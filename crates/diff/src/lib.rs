@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use hashbrown::HashMap;
+use redscript_asm::Disassembler;
+use redscript_decompiler::{Settings, decompile_function};
+use redscript_formatter::{FormatCtx, FormatSettings, SyntaxOps};
+use redscript_io::{
+    CNameIndex, ClassIndex, EnumIndex, FieldIndex, FunctionIndex, IndexedDefinition, ScriptBundle,
+    TypeIndex, Visibility,
+};
+use serde::Serialize;
+use similar::TextDiff;
+use thiserror::Error;
+
+pub type Result<A, E = Error> = std::result::Result<A, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Disassembly failed: {0}")]
+    Disassembly(#[from] redscript_asm::Error),
+    #[error("Decompilation of {0} failed: {1}")]
+    Decompilation(String, String),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[default]
+    Bytecode,
+    Source,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BundleDiff {
+    pub classes: Changes,
+    pub enums: Changes,
+    pub fields: Changes,
+    pub functions: Changes,
+    pub bodies: Vec<BodyChange>,
+}
+
+impl BundleDiff {
+    pub fn new(old: &ScriptBundle<'_>, new: &ScriptBundle<'_>, format: BodyFormat) -> Result<Self> {
+        let old = Side::new(old);
+        let new = Side::new(new);
+
+        let classes = compare(
+            &old.classes,
+            &new.classes,
+            |i| old.class_signature(i),
+            |i| new.class_signature(i),
+        );
+        let enums = compare(
+            &old.enums,
+            &new.enums,
+            |i| old.enum_signature(i),
+            |i| new.enum_signature(i),
+        );
+        let fields = compare(
+            &old.fields,
+            &new.fields,
+            |i| old.field_signature(i),
+            |i| new.field_signature(i),
+        );
+
+        let mut functions = Changes::default();
+        let mut pairs = vec![];
+        let mut removed: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (name, &index) in &old.functions {
+            match new.functions.get(name) {
+                Some(&other) => pairs.push((name.clone(), index, other)),
+                None => {
+                    removed
+                        .entry(unmangled(name))
+                        .or_default()
+                        .push((name, index));
+                }
+            }
+        }
+        let mut added: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (name, &index) in &new.functions {
+            if !old.functions.contains_key(name) {
+                added
+                    .entry(unmangled(name))
+                    .or_default()
+                    .push((name, index));
+            }
+        }
+        for (key, old_entries) in &removed {
+            match (&old_entries[..], added.get(key).map(Vec::as_slice)) {
+                ([(_, old_index)], Some([(name, new_index)])) => {
+                    pairs.push(((*name).clone(), *old_index, *new_index));
+                }
+                _ => functions
+                    .removed
+                    .extend(old_entries.iter().map(|(name, _)| (*name).clone())),
+            }
+        }
+        for (key, new_entries) in &added {
+            if !matches!(
+                (removed.get(key).map(Vec::as_slice), &new_entries[..]),
+                (Some([_]), [_])
+            ) {
+                functions
+                    .added
+                    .extend(new_entries.iter().map(|(name, _)| (*name).clone()));
+            }
+        }
+        pairs.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+        let mut bodies = vec![];
+        for (name, old_index, new_index) in pairs {
+            let (old_sig, new_sig) = (
+                old.function_signature(old_index),
+                new.function_signature(new_index),
+            );
+            if old_sig != new_sig {
+                functions.changed.push(SignatureChange {
+                    name: name.clone(),
+                    old: old_sig,
+                    new: new_sig,
+                });
+            }
+
+            let old_body = old.body(&name, old_index, format)?;
+            let new_body = new.body(&name, new_index, format)?;
+            if old_body != new_body {
+                let diff = TextDiff::from_lines(&old_body, &new_body)
+                    .unified_diff()
+                    .header(&format!("old/{name}"), &format!("new/{name}"))
+                    .to_string();
+                bodies.push(BodyChange { name, diff });
+            }
+        }
+
+        Ok(Self {
+            classes,
+            enums,
+            fields,
+            functions,
+            bodies,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+            && self.enums.is_empty()
+            && self.fields.is_empty()
+            && self.functions.is_empty()
+            && self.bodies.is_empty()
+    }
+}
+
+impl fmt::Display for BundleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("classes", &self.classes),
+            ("enums", &self.enums),
+            ("fields", &self.fields),
+            ("functions", &self.functions),
+        ];
+        for (title, changes) in sections {
+            writeln!(
+                f,
+                "{title}: {} added, {} removed, {} changed",
+                changes.added.len(),
+                changes.removed.len(),
+                changes.changed.len()
+            )?;
+        }
+        writeln!(f, "function bodies: {} changed", self.bodies.len())?;
+
+        for (_, changes) in sections {
+            for name in &changes.added {
+                writeln!(f, "+ {name}")?;
+            }
+            for name in &changes.removed {
+                writeln!(f, "- {name}")?;
+            }
+            for change in &changes.changed {
+                writeln!(f, "~ {}", change.name)?;
+                writeln!(f, "    - {}", change.old)?;
+                writeln!(f, "    + {}", change.new)?;
+            }
+        }
+        for body in &self.bodies {
+            writeln!(f)?;
+            write!(f, "{}", body.diff)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<SignatureChange>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BodyChange {
+    pub name: String,
+    pub diff: String,
+}
+
+struct Side<'a> {
+    bundle: &'a ScriptBundle<'a>,
+    disasm: Disassembler<'a, 'a>,
+    classes: BTreeMap<String, ClassIndex>,
+    enums: BTreeMap<String, EnumIndex>,
+    fields: BTreeMap<String, FieldIndex>,
+    functions: BTreeMap<String, FunctionIndex>,
+}
+
+impl<'a> Side<'a> {
+    fn new(bundle: &'a ScriptBundle<'a>) -> Self {
+        let mut this = Self {
+            bundle,
+            disasm: Disassembler::new(bundle).with_ambiguous_names(),
+            classes: BTreeMap::new(),
+            enums: BTreeMap::new(),
+            fields: BTreeMap::new(),
+            functions: BTreeMap::new(),
+        };
+
+        for def in bundle.indexed_definitions() {
+            match def {
+                IndexedDefinition::Class(i, class) => {
+                    let name = this.cname(class.name()).to_owned();
+                    this.classes.entry(name).or_insert(i);
+                }
+                IndexedDefinition::Enum(i, enum_) => {
+                    let name = this.cname(enum_.name()).to_owned();
+                    this.enums.entry(name).or_insert(i);
+                }
+                IndexedDefinition::Field(i, field) => {
+                    let class = this.class_name(field.class());
+                    let name = format!("{class}.{}", this.cname(field.name()));
+                    this.fields.entry(name).or_insert(i);
+                }
+                IndexedDefinition::Function(i, func) => {
+                    let name = match func.class() {
+                        Some(class) => {
+                            format!("{}.{}", this.class_name(class), this.cname(func.name()))
+                        }
+                        None => this.cname(func.name()).to_owned(),
+                    };
+                    this.functions.entry(name).or_insert(i);
+                }
+                _ => {}
+            }
+        }
+        this
+    }
+
+    fn body(&self, name: &str, index: FunctionIndex, format: BodyFormat) -> Result<String> {
+        match format {
+            BodyFormat::Bytecode => Ok(self.disasm.function(index)?.code().to_string()),
+            BodyFormat::Source => {
+                let item =
+                    decompile_function(&self.bundle[index], self.bundle, &Settings::default())
+                        .map_err(|err| Error::Decompilation(name.into(), err.to_string()))?;
+                let settings = FormatSettings::default();
+                let prefixes = HashMap::default();
+                Ok(item
+                    .as_fmt(FormatCtx::new(&settings, &prefixes))
+                    .to_string())
+            }
+        }
+    }
+
+    fn class_signature(&self, index: ClassIndex) -> String {
+        let class = &self.bundle[index];
+        let flags = class.flags();
+        let qualifiers = [
+            (flags.is_abstract(), "abstract"),
+            (flags.is_final(), "final"),
+            (flags.is_native(), "native"),
+            (flags.is_import_only(), "importonly"),
+            (flags.is_test_only(), "testonly"),
+        ];
+        let kind = if flags.is_struct() { "struct" } else { "class" };
+        let mut sig = visibility(class.visibility());
+        sig.push_str(&qualified(&qualifiers));
+        sig.push_str(&format!("{kind} {}", self.cname(class.name())));
+        if let Some(base) = class.base() {
+            sig.push_str(&format!(" extends {}", self.class_name(base)));
+        }
+        sig
+    }
+
+    fn enum_signature(&self, index: EnumIndex) -> String {
+        let enum_ = &self.bundle[index];
+        let values = enum_
+            .values()
+            .iter()
+            .filter_map(|&value| self.bundle.get_item(value))
+            .map(|value| format!("{} = {}", self.cname(value.name()), value.value()))
+            .collect::<Vec<_>>();
+        format!(
+            "{}{}enum {} {{ {} }}",
+            visibility(enum_.visibility()),
+            qualified(&[(enum_.is_native(), "native")]),
+            self.cname(enum_.name()),
+            values.join(", ")
+        )
+    }
+
+    fn field_signature(&self, index: FieldIndex) -> String {
+        let field = &self.bundle[index];
+        let flags = field.flags();
+        let qualifiers = [
+            (flags.is_native(), "native"),
+            (flags.is_const(), "const"),
+            (flags.is_persistent(), "persistent"),
+            (flags.is_editable(), "editable"),
+            (flags.is_inline(), "inline"),
+            (flags.is_replicated(), "replicated"),
+            (flags.is_instance_editable(), "instance_editable"),
+            (flags.is_browsable(), "browsable"),
+            (flags.is_test_only(), "testonly"),
+        ];
+        format!(
+            "{}{}let {}: {}",
+            visibility(field.visibility()),
+            qualified(&qualifiers),
+            self.cname(field.name()),
+            self.type_name(field.type_())
+        )
+    }
+
+    fn function_signature(&self, index: FunctionIndex) -> String {
+        let func = &self.bundle[index];
+        let flags = func.flags();
+        let qualifiers = [
+            (flags.is_static(), "static"),
+            (flags.is_final(), "final"),
+            (flags.is_native(), "native"),
+            (flags.is_exec(), "exec"),
+            (flags.is_timer(), "timer"),
+            (flags.is_callback(), "cb"),
+            (flags.is_implicit_cast(), "implicit_cast"),
+            (flags.is_const(), "const"),
+            (flags.is_thread_safe(), "threadsafe"),
+            (flags.is_quest(), "quest"),
+        ];
+        let params = func
+            .parameters()
+            .iter()
+            .filter_map(|&param| self.bundle.get_item(param))
+            .map(|param| {
+                let flags = param.flags();
+                let qualifiers = [
+                    (flags.is_optional(), "opt"),
+                    (flags.is_out(), "out"),
+                    (flags.is_short_circuit(), "short_circuit"),
+                    (flags.is_const(), "const"),
+                ];
+                format!(
+                    "{}{}: {}",
+                    qualified(&qualifiers),
+                    self.cname(param.name()),
+                    self.type_name(param.type_())
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut sig = format!(
+            "{}{}func {}({})",
+            visibility(func.visibility()),
+            qualified(&qualifiers),
+            self.cname(func.name()),
+            params.join(", ")
+        );
+        if let Some(typ) = func.return_type() {
+            let const_ = if func.is_const_return() { "const " } else { "" };
+            sig.push_str(&format!(" -> {const_}{}", self.type_name(typ)));
+        }
+        sig
+    }
+
+    fn cname(&self, index: CNameIndex) -> &'a str {
+        self.bundle.cnames().get(index).map_or("", |str| &**str)
+    }
+
+    fn class_name(&self, index: ClassIndex) -> &'a str {
+        self.bundle
+            .get_item(index)
+            .map_or("", |class| self.cname(class.name()))
+    }
+
+    fn type_name(&self, index: TypeIndex) -> &'a str {
+        self.bundle
+            .get_item(index)
+            .map_or("", |typ| self.cname(typ.name()))
+    }
+}
+
+fn compare<I: Copy>(
+    old: &BTreeMap<String, I>,
+    new: &BTreeMap<String, I>,
+    old_signature: impl Fn(I) -> String,
+    new_signature: impl Fn(I) -> String,
+) -> Changes {
+    let mut changes = Changes::default();
+    for (name, &index) in old {
+        match new.get(name) {
+            Some(&other) => {
+                let (old, new) = (old_signature(index), new_signature(other));
+                if old != new {
+                    changes.changed.push(SignatureChange {
+                        name: name.clone(),
+                        old,
+                        new,
+                    });
+                }
+            }
+            None => changes.removed.push(name.clone()),
+        }
+    }
+    changes
+        .added
+        .extend(new.keys().filter(|name| !old.contains_key(*name)).cloned());
+    changes
+}
+
+fn unmangled(name: &str) -> &str {
+    name.split_once(';').map_or(name, |(name, _)| name)
+}
+
+fn visibility(visibility: Visibility) -> String {
+    match visibility {
+        Visibility::Public => "public ".into(),
+        Visibility::Protected => "protected ".into(),
+        Visibility::Private => "private ".into(),
+    }
+}
+
+fn qualified(qualifiers: &[(bool, &str)]) -> String {
+    qualifiers
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| format!("{name} "))
+        .collect()
+}
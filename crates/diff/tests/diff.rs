@@ -0,0 +1,130 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Compilation, SourceMapExt, TypeInterner};
+use redscript_diff::{BodyFormat, BundleDiff};
+use redscript_io::{Class, ClassFlags, Function, FunctionFlags, Instr, ScriptBundle, Visibility};
+
+const OLD: &str = r#"
+class Foo {
+  let a: Int32;
+
+  func Bar() -> Int32 {
+    return 1;
+  }
+
+  func Baz(x: Int32) {}
+}
+
+func Removed() {}
+
+enum Color {
+  Red = 0,
+  Green = 1,
+}
+"#;
+
+const NEW: &str = r#"
+class Foo {
+  let a: Float;
+  let b: Bool;
+
+  func Bar() -> Int32 {
+    return 2;
+  }
+
+  func Baz(x: Float) {}
+}
+
+func Added() {}
+
+enum Color {
+  Red = 0,
+  Green = 1,
+  Blue = 2,
+}
+"#;
+
+#[test]
+fn structural_changes() {
+    let old = compile(OLD);
+    let new = compile(NEW);
+    let old = ScriptBundle::from_bytes(&old).unwrap();
+    let new = ScriptBundle::from_bytes(&new).unwrap();
+
+    let diff = BundleDiff::new(&old, &new, BodyFormat::Bytecode).unwrap();
+
+    assert!(diff.classes.is_empty(), "{diff}");
+    assert_eq!(diff.fields.added, ["Foo.b"], "{diff}");
+    assert_eq!(names(&diff.fields.changed), ["Foo.a"], "{diff}");
+    assert_eq!(names(&diff.enums.changed), ["Color"], "{diff}");
+    assert_eq!(diff.functions.added, ["Added;"], "{diff}");
+    assert_eq!(diff.functions.removed, ["Removed;"], "{diff}");
+    assert_eq!(names(&diff.functions.changed), ["Foo.Baz;Float"], "{diff}");
+    let bodies = diff
+        .bodies
+        .iter()
+        .map(|body| body.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(bodies, ["Foo.Bar;"], "{diff}");
+}
+
+#[test]
+fn identical_bundles() {
+    let bytes = compile(OLD);
+    let old = ScriptBundle::from_bytes(&bytes).unwrap();
+    let new = ScriptBundle::from_bytes(&bytes).unwrap();
+
+    let diff = BundleDiff::new(&old, &new, BodyFormat::Source).unwrap();
+    assert!(diff.is_empty(), "{diff}");
+}
+
+#[test]
+fn ambiguous_symbols_are_matched_by_name() {
+    // the same code referring to a class that shares its name with another one, with
+    // the pool indices shifted in the new bundle
+    let old = bundle_with_ambiguous_class(false);
+    let new = bundle_with_ambiguous_class(true);
+
+    let diff = BundleDiff::new(&old, &new, BodyFormat::Bytecode).unwrap();
+    assert!(diff.functions.is_empty(), "{diff}");
+    assert!(diff.bodies.is_empty(), "{diff}");
+}
+
+fn bundle_with_ambiguous_class(shifted: bool) -> ScriptBundle<'static> {
+    let mut bundle = ScriptBundle::default();
+    if shifted {
+        let name = bundle.cnames_mut().add("Padding");
+        bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    }
+    let name = bundle.cnames_mut().add("Duplicate");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let class = bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+
+    let name = bundle.cnames_mut().add("Make;");
+    bundle.define(
+        Function::new(name, Visibility::Public, FunctionFlags::default())
+            .with_code(vec![Instr::Return, Instr::New(class)]),
+    );
+    bundle
+}
+
+fn names(changes: &[redscript_diff::SignatureChange]) -> Vec<&str> {
+    changes.iter().map(|change| change.name.as_str()).collect()
+}
+
+fn compile(source: &str) -> Vec<u8> {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", source);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let (bundle, _, _) = Compilation::new(&bytes, &sources, &interner)
+        .unwrap()
+        .finish()
+        .unwrap();
+    bundle.into_writeable().to_bytes().unwrap()
+}
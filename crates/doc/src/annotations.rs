@@ -0,0 +1,96 @@
+use hashbrown::HashMap;
+use redscript_compiler_api::ast::{self, SourceMap, Span};
+use redscript_compiler_api::{CompileErrorReporter, Symbols, TypeId, parse_files};
+
+const MOD_ANNOTATIONS: [&str; 4] = ["wrapMethod", "replaceMethod", "addMethod", "addField"];
+
+/// A mod annotation that targets a type, e.g. `@wrapMethod(PlayerPuppet)`.
+#[derive(Debug, Clone, Copy)]
+pub struct ModAnnotation<'ctx> {
+    pub name: &'ctx str,
+    pub member: &'ctx str,
+    pub span: Span,
+}
+
+/// Collects the mod annotations found in `sources` grouped by the type they target.
+/// Annotation targets are resolved against the module of the annotated item and its
+/// imports, which mirrors how the compiler looks them up.
+pub(crate) fn collect<'ctx>(
+    symbols: &Symbols<'ctx>,
+    sources: &'ctx SourceMap,
+) -> HashMap<TypeId<'ctx>, Vec<ModAnnotation<'ctx>>> {
+    let ids = symbols
+        .types()
+        .map(|(id, _)| (id.as_str(), id))
+        .collect::<HashMap<_, _>>();
+
+    // the sources have already been reported on by the compilation
+    let mut reporter = CompileErrorReporter::default();
+    let mut annotations: HashMap<_, Vec<_>> = HashMap::new();
+
+    for module in parse_files(sources, &mut reporter) {
+        let imports = module
+            .items
+            .iter()
+            .filter_map(|(decl, _)| match &decl.item {
+                ast::Item::Import(import) => Some(import),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (decl, _) in &module.items {
+            let (member, _) = match &decl.item {
+                ast::Item::Function(func) => func.name,
+                ast::Item::Let(field) => field.name,
+                _ => continue,
+            };
+            for (annotation, span) in &decl.annotations {
+                if !MOD_ANNOTATIONS.contains(&annotation.name) {
+                    continue;
+                }
+                let [(ast::Expr::Ident(target), _)] = &annotation.args[..] else {
+                    continue;
+                };
+                let Some(id) = candidates(target, module.path.as_ref(), &imports)
+                    .find_map(|name| ids.get(name.as_str()).copied())
+                else {
+                    continue;
+                };
+                annotations.entry(id).or_default().push(ModAnnotation {
+                    name: annotation.name,
+                    member,
+                    span: *span,
+                });
+            }
+        }
+    }
+    annotations
+}
+
+fn candidates<'a>(
+    name: &'a str,
+    module: Option<&'a ast::Path<'_>>,
+    imports: &'a [&ast::Import<'_>],
+) -> impl Iterator<Item = String> {
+    let local = module.map(|path| qualified(path, name));
+    let imported = imports.iter().filter_map(move |import| match import {
+        ast::Import::Exact(path) if path.segments.last() == Some(&name) => {
+            Some(path.segments.join("."))
+        }
+        ast::Import::Select(path, names) if names.iter().any(|n| *n == name) => {
+            Some(qualified(path, name))
+        }
+        ast::Import::All(path) => Some(qualified(path, name)),
+        _ => None,
+    });
+    local.into_iter().chain(imported).chain([name.to_owned()])
+}
+
+fn qualified(path: &ast::Path<'_>, name: &str) -> String {
+    path.segments
+        .iter()
+        .copied()
+        .chain([name])
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io};
+
+use hashbrown::HashMap;
+use redscript_compiler_api::ast::{SourceMap, Span};
+use redscript_compiler_api::types::{Type, predef};
+use redscript_compiler_api::{Aggregate, FunctionType, Symbols, TypeDef, TypeId, TypeSchema};
+
+mod annotations;
+mod render;
+
+pub use annotations::ModAnnotation;
+pub use render::Page;
+use render::{Block, Inline};
+
+const GLOBAL_MODULE: &str = "global";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+impl FromStr for DocFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            _ => Err(format!("unknown documentation format: {s}")),
+        }
+    }
+}
+
+/// API reference documentation generated from the symbols of a compilation.
+pub struct Documentation<'a, 'ctx> {
+    symbols: &'a Symbols<'ctx>,
+    sources: &'ctx SourceMap,
+    annotations: HashMap<TypeId<'ctx>, Vec<ModAnnotation<'ctx>>>,
+}
+
+impl<'a, 'ctx> Documentation<'a, 'ctx> {
+    pub fn new(symbols: &'a Symbols<'ctx>, sources: &'ctx SourceMap) -> Self {
+        Self {
+            symbols,
+            sources,
+            annotations: annotations::collect(symbols, sources),
+        }
+    }
+
+    /// Writes all pages to `dir` and returns the number of pages written.
+    pub fn write(&self, dir: impl AsRef<Path>, format: DocFormat) -> io::Result<usize> {
+        let dir = dir.as_ref();
+        let pages = self.pages();
+        for page in &pages {
+            let path = dir.join(format!("{}.{}", page.path(), format.extension()));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, page.render(format))?;
+        }
+        Ok(pages.len())
+    }
+
+    pub fn pages(&self) -> Vec<Page> {
+        let mut modules: BTreeMap<String, ModuleItems<'_, 'ctx>> = BTreeMap::new();
+
+        for (id, def) in self.symbols.types() {
+            if matches!(def.schema(), TypeSchema::Primitive) && def.doc().is_empty() {
+                continue;
+            }
+            let (module, _) = split_name(id.as_str());
+            modules
+                .entry(module.unwrap_or(GLOBAL_MODULE).to_owned())
+                .or_default()
+                .types
+                .push((id, def));
+        }
+
+        for entry in self.symbols.free_functions() {
+            let segments: &[&str] = entry.name().as_ref();
+            let Some((&name, path)) = segments.split_last() else {
+                continue;
+            };
+            let module = if path.is_empty() {
+                GLOBAL_MODULE.to_owned()
+            } else {
+                path.join(".")
+            };
+            let func = entry.func();
+            let mut qualifiers = vec![];
+            if func.flags().is_native() {
+                qualifiers.push("native");
+            }
+            if func.flags().is_exec() {
+                qualifiers.push("exec");
+            }
+            modules.entry(module).or_default().functions.push(ItemDoc {
+                signature: function_signature(&qualifiers, name, func.type_()),
+                doc: doc_text(func.doc()),
+                location: func.span().and_then(|span| self.location(span)),
+            });
+        }
+
+        let mut pages = vec![self.index_page(&modules)];
+        for (module, items) in &mut modules {
+            items.types.sort_by_key(|(id, _)| id.as_str());
+            items
+                .functions
+                .sort_by(|a, b| a.signature.cmp(&b.signature));
+
+            pages.push(self.module_page(module, items));
+            pages.extend(
+                items
+                    .types
+                    .iter()
+                    .map(|&(id, def)| self.type_page(module, id, def)),
+            );
+        }
+        pages
+    }
+
+    fn index_page(&self, modules: &BTreeMap<String, ModuleItems<'_, 'ctx>>) -> Page {
+        let mut page = Page::new("index", "API reference");
+        let items = modules
+            .iter()
+            .map(|(name, items)| {
+                vec![
+                    Inline::link(name, module_path(name)),
+                    Inline::text(format!(
+                        " - {} types, {} functions",
+                        items.types.len(),
+                        items.functions.len()
+                    )),
+                ]
+            })
+            .collect();
+        page.push(Block::Heading(2, vec![Inline::text("Modules")]));
+        page.push(Block::List(items));
+        page
+    }
+
+    fn module_page(&self, module: &str, items: &ModuleItems<'_, 'ctx>) -> Page {
+        let mut page = Page::new(module_path(module), format!("module {module}"));
+        page.push(Block::Paragraph(vec![Inline::link("Index", "index")]));
+
+        if !items.types.is_empty() {
+            page.push(Block::Heading(2, vec![Inline::text("Types")]));
+            let types = items
+                .types
+                .iter()
+                .map(|&(id, def)| {
+                    let mut item = vec![
+                        Inline::code(type_kind(def)),
+                        Inline::text(" "),
+                        Inline::link(id.as_str(), type_path(id)),
+                    ];
+                    if let Some(summary) = doc_text(def.doc())
+                        .as_deref()
+                        .and_then(|doc| doc.lines().next())
+                    {
+                        item.push(Inline::text(format!(" - {summary}")));
+                    }
+                    item
+                })
+                .collect();
+            page.push(Block::List(types));
+        }
+
+        if !items.functions.is_empty() {
+            page.push(Block::Heading(2, vec![Inline::text("Functions")]));
+            for func in &items.functions {
+                func.push_to(&mut page);
+            }
+        }
+        page
+    }
+
+    fn type_page(&self, module: &str, id: TypeId<'ctx>, def: &TypeDef<'ctx>) -> Page {
+        let mut page = Page::new(type_path(id), format!("{} {id}", type_kind(def)));
+        page.push(Block::Paragraph(vec![
+            Inline::text("Module: "),
+            Inline::link(module, module_path(module)),
+        ]));
+        page.push(Block::Code(self.type_signature(id, def)));
+        if let Some(doc) = doc_text(def.doc()) {
+            page.push(Block::Doc(doc));
+        }
+        if let Some(location) = def.span().and_then(|span| self.location(span)) {
+            page.push(Block::Paragraph(vec![
+                Inline::text("Defined at "),
+                Inline::code(location),
+            ]));
+        }
+
+        match def.schema() {
+            TypeSchema::Aggregate(agg) => self.push_aggregate(id, agg, &mut page),
+            TypeSchema::Enum(enum_) => {
+                page.push(Block::Heading(2, vec![Inline::text("Variants")]));
+                let variants = enum_
+                    .variants()
+                    .map(|(name, value)| vec![Inline::code(format!("{name} = {value}"))])
+                    .collect();
+                page.push(Block::List(variants));
+            }
+            TypeSchema::Primitive => {}
+        }
+
+        if let Some(annotations) = self.annotations.get(&id) {
+            page.push(Block::Heading(2, vec![Inline::text("Mod annotations")]));
+            let items = annotations
+                .iter()
+                .map(|annotation| {
+                    let mut item = vec![
+                        Inline::code(format!("@{}({})", annotation.name, id)),
+                        Inline::text(" "),
+                        Inline::code(annotation.member),
+                    ];
+                    if let Some(location) = self.location(annotation.span) {
+                        item.push(Inline::text(format!(" at {location}")));
+                    }
+                    item
+                })
+                .collect();
+            page.push(Block::List(items));
+        }
+        page
+    }
+
+    fn push_aggregate(&self, id: TypeId<'ctx>, agg: &Aggregate<'ctx>, page: &mut Page) {
+        let chain = self
+            .symbols
+            .base_iter(id)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if chain.len() > 1 {
+            page.push(Block::Heading(2, vec![Inline::text("Inheritance")]));
+            let mut inlines = vec![];
+            for (i, &base) in chain.iter().enumerate() {
+                if i > 0 {
+                    inlines.push(Inline::text(" → "));
+                }
+                inlines.push(Inline::link(base.as_str(), type_path(base)));
+            }
+            page.push(Block::Paragraph(inlines));
+        }
+
+        if !agg.fields().is_empty() {
+            page.push(Block::Heading(2, vec![Inline::text("Fields")]));
+            for entry in agg.fields().iter() {
+                let field = entry.field();
+                let flags = field.flags();
+                let qualifiers = [
+                    (flags.is_native(), "native"),
+                    (flags.is_const(), "const"),
+                    (flags.is_persistent(), "persistent"),
+                    (flags.is_editable(), "edit"),
+                    (flags.is_inline(), "inline"),
+                ];
+                let item = ItemDoc {
+                    signature: format!(
+                        "{}let {}: {}",
+                        qualified(&qualifiers),
+                        entry.name(),
+                        field.type_()
+                    ),
+                    doc: doc_text(field.doc()),
+                    location: field.span().and_then(|span| self.location(span)),
+                };
+                item.push_to(page);
+            }
+        }
+
+        let mut methods = agg.methods().iter().peekable();
+        if methods.peek().is_some() {
+            page.push(Block::Heading(2, vec![Inline::text("Methods")]));
+            for entry in methods {
+                let method = entry.func();
+                let flags = method.flags();
+                let qualifiers = [
+                    (flags.is_static(), "static"),
+                    (flags.is_final(), "final"),
+                    (flags.is_native(), "native"),
+                    (flags.is_callback(), "cb"),
+                ];
+                let qualifiers = qualifiers
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|&(_, name)| name)
+                    .collect::<Vec<_>>();
+                let item = ItemDoc {
+                    signature: function_signature(&qualifiers, entry.name(), method.type_()),
+                    doc: doc_text(method.doc()),
+                    location: method.span().and_then(|span| self.location(span)),
+                };
+                item.push_to(page);
+            }
+        }
+    }
+
+    fn type_signature(&self, id: TypeId<'ctx>, def: &TypeDef<'ctx>) -> String {
+        let vars = def.vars().collect::<Vec<_>>();
+        let params = if vars.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", vars.join(", "))
+        };
+
+        match def.schema() {
+            TypeSchema::Aggregate(agg) => {
+                let flags = agg.flags();
+                let qualifiers = [
+                    (flags.is_native(), "native"),
+                    (flags.is_import_only(), "importonly"),
+                    (flags.is_abstract(), "abstract"),
+                    (flags.is_final(), "final"),
+                ];
+                let mut sig = format!("{}{} {id}{params}", qualified(&qualifiers), type_kind(def));
+                if let Some(base) = agg.base() {
+                    sig.push_str(&format!(" extends {base}"));
+                }
+                sig
+            }
+            TypeSchema::Enum(_) => format!("enum {id}"),
+            TypeSchema::Primitive => format!("{id}{params}"),
+        }
+    }
+
+    fn location(&self, span: Span) -> Option<String> {
+        let file = self.sources.get(span.file)?;
+        let loc = file.lookup(span.start);
+        Some(format!("{}:{}", file.path().display(), loc.line + 1))
+    }
+}
+
+#[derive(Debug, Default)]
+struct ModuleItems<'a, 'ctx> {
+    types: Vec<(TypeId<'ctx>, &'a TypeDef<'ctx>)>,
+    functions: Vec<ItemDoc>,
+}
+
+#[derive(Debug)]
+struct ItemDoc {
+    signature: String,
+    doc: Option<String>,
+    location: Option<String>,
+}
+
+impl ItemDoc {
+    fn push_to(&self, page: &mut Page) {
+        page.push(Block::Heading(3, vec![Inline::code(&self.signature)]));
+        if let Some(doc) = &self.doc {
+            page.push(Block::Doc(doc.clone()));
+        }
+        if let Some(location) = &self.location {
+            page.push(Block::Paragraph(vec![
+                Inline::text("Defined at "),
+                Inline::code(location),
+            ]));
+        }
+    }
+}
+
+fn function_signature(qualifiers: &[&str], name: &str, typ: &FunctionType<'_>) -> String {
+    let mut sig = String::new();
+    for qualifier in qualifiers {
+        sig.push_str(qualifier);
+        sig.push(' ');
+    }
+    sig.push_str("func ");
+    sig.push_str(name);
+
+    let vars = typ.type_vars().collect::<Vec<_>>();
+    if !vars.is_empty() {
+        sig.push_str(&format!("<{}>", vars.join(", ")));
+    }
+    let params = typ
+        .params()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    sig.push_str(&format!("({})", params.join(", ")));
+
+    if !matches!(typ.return_type(), Type::Data(app) if app.id() == predef::VOID) {
+        sig.push_str(&format!(" -> {}", typ.return_type()));
+    }
+    sig
+}
+
+fn type_kind(def: &TypeDef<'_>) -> &'static str {
+    match def.schema() {
+        TypeSchema::Aggregate(agg) if agg.flags().is_struct() => "struct",
+        TypeSchema::Aggregate(_) => "class",
+        TypeSchema::Enum(_) => "enum",
+        TypeSchema::Primitive => "primitive",
+    }
+}
+
+fn qualified(qualifiers: &[(bool, &str)]) -> String {
+    qualifiers
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| format!("{name} "))
+        .collect()
+}
+
+/// Joins the lines of a doc comment, stripping the comment markers.
+fn doc_text(doc: &[&str]) -> Option<String> {
+    let text = doc
+        .iter()
+        .map(|line| {
+            line.strip_prefix("///")
+                .map_or(*line, |line| line.strip_prefix(' ').unwrap_or(line))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_owned())
+}
+
+fn split_name(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once('.') {
+        Some((module, name)) => (Some(module), name),
+        None => (None, name),
+    }
+}
+
+fn module_path(module: &str) -> String {
+    format!("modules/{}", file_name(module))
+}
+
+fn type_path(id: TypeId<'_>) -> String {
+    format!("types/{}", file_name(id.as_str()))
+}
+
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
@@ -0,0 +1,212 @@
+use std::fmt::{self, Write};
+
+use crate::DocFormat;
+
+/// A single documentation page, identified by its path relative to the output
+/// directory without an extension.
+#[derive(Debug, Clone)]
+pub struct Page {
+    path: String,
+    title: String,
+    blocks: Vec<Block>,
+}
+
+impl Page {
+    pub(crate) fn new(path: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            title: title.into(),
+            blocks: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub(crate) fn push(&mut self, block: Block) {
+        self.blocks.push(block);
+    }
+
+    pub fn render(&self, format: DocFormat) -> String {
+        let mut out = String::new();
+        let res = match format {
+            DocFormat::Markdown => Markdown(self).render(&mut out),
+            DocFormat::Html => Html(self).render(&mut out),
+        };
+        res.expect("writing to a string should not fail");
+        out
+    }
+
+    fn link_prefix(&self) -> String {
+        "../".repeat(self.path.matches('/').count())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Block {
+    Heading(u8, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    /// Text taken verbatim from doc comments.
+    Doc(String),
+    Code(String),
+    List(Vec<Vec<Inline>>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Inline {
+    Text(String),
+    Code(String),
+    /// A link to another page, identified by its path.
+    Link(String, String),
+}
+
+impl Inline {
+    #[inline]
+    pub fn text(str: impl Into<String>) -> Self {
+        Self::Text(str.into())
+    }
+
+    #[inline]
+    pub fn code(str: impl Into<String>) -> Self {
+        Self::Code(str.into())
+    }
+
+    #[inline]
+    pub fn link(text: impl Into<String>, page: impl Into<String>) -> Self {
+        Self::Link(text.into(), page.into())
+    }
+}
+
+struct Markdown<'a>(&'a Page);
+
+impl Markdown<'_> {
+    fn render(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "# {}", self.0.title)?;
+        for block in &self.0.blocks {
+            writeln!(out)?;
+            match block {
+                Block::Heading(level, inlines) => {
+                    write!(out, "{} ", "#".repeat(usize::from(*level)))?;
+                    self.inlines(inlines, out)?;
+                    writeln!(out)?;
+                }
+                Block::Paragraph(inlines) => {
+                    self.inlines(inlines, out)?;
+                    writeln!(out)?;
+                }
+                Block::Doc(text) => writeln!(out, "{}", text.trim_end())?,
+                Block::Code(code) => writeln!(out, "```\n{}\n```", code.trim_end())?,
+                Block::List(items) => {
+                    for item in items {
+                        write!(out, "- ")?;
+                        self.inlines(item, out)?;
+                        writeln!(out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn inlines(&self, inlines: &[Inline], out: &mut String) -> fmt::Result {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => out.push_str(text),
+                Inline::Code(code) => write!(out, "`{code}`")?,
+                Inline::Link(text, page) => {
+                    write!(out, "[{text}]({}{page}.md)", self.0.link_prefix())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Html<'a>(&'a Page);
+
+impl Html<'_> {
+    fn render(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{}</title>", Escaped(&self.0.title))?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>{}</h1>", Escaped(&self.0.title))?;
+        for block in &self.0.blocks {
+            match block {
+                Block::Heading(level, inlines) => {
+                    write!(out, "<h{level}>")?;
+                    self.inlines(inlines, out)?;
+                    writeln!(out, "</h{level}>")?;
+                }
+                Block::Paragraph(inlines) => {
+                    write!(out, "<p>")?;
+                    self.inlines(inlines, out)?;
+                    writeln!(out, "</p>")?;
+                }
+                Block::Doc(text) => {
+                    for paragraph in text.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                        writeln!(out, "<p>{}</p>", Escaped(paragraph.trim()))?;
+                    }
+                }
+                Block::Code(code) => {
+                    writeln!(out, "<pre><code>{}</code></pre>", Escaped(code.trim_end()))?;
+                }
+                Block::List(items) => {
+                    writeln!(out, "<ul>")?;
+                    for item in items {
+                        write!(out, "<li>")?;
+                        self.inlines(item, out)?;
+                        writeln!(out, "</li>")?;
+                    }
+                    writeln!(out, "</ul>")?;
+                }
+            }
+        }
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")
+    }
+
+    fn inlines(&self, inlines: &[Inline], out: &mut String) -> fmt::Result {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => write!(out, "{}", Escaped(text))?,
+                Inline::Code(code) => write!(out, "<code>{}</code>", Escaped(code))?,
+                Inline::Link(text, page) => write!(
+                    out,
+                    "<a href=\"{}{}.html\">{}</a>",
+                    self.0.link_prefix(),
+                    Escaped(page),
+                    Escaped(text)
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{
+    CompilationInputs, CompileErrorReporter, SourceMapExt, TypeInterner, infer_from_sources,
+};
+use redscript_doc::{DocFormat, Documentation};
+use redscript_io::{Class, ClassFlags, ScriptBundle, Visibility};
+
+const SOURCE: &str = r#"
+module Test.Docs
+
+/// A documented class.
+public class Foo {
+  /// A documented field.
+  let count: Int32;
+
+  /// Returns the count.
+  func Count() -> Int32 = this.count
+}
+
+@addField(Foo)
+let extra: Bool;
+
+@addMethod(Foo)
+func Extra() -> Bool = this.extra
+"#;
+
+#[test]
+fn type_pages() {
+    let sources = SourceMap::new();
+    sources.push_back("docs.reds", SOURCE);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+
+    let (symbols, _) = CompilationInputs::load(&bundle, &interner)
+        .unwrap()
+        .into_inner();
+    let mut reporter = CompileErrorReporter::default();
+    let (_, symbols) = infer_from_sources(&sources, symbols, &mut reporter, &interner);
+
+    let docs = Documentation::new(&symbols, &sources);
+    let pages = docs.pages();
+    let page = pages
+        .iter()
+        .find(|page| page.path() == "types/Test.Docs.Foo")
+        .expect("class page should be generated");
+
+    let markdown = page.render(DocFormat::Markdown);
+    assert!(markdown.contains("A documented class."), "{markdown}");
+    assert!(markdown.contains("`let count: Int32`"), "{markdown}");
+    assert!(markdown.contains("A documented field."), "{markdown}");
+    assert!(markdown.contains("`func Count() -> Int32`"), "{markdown}");
+    assert!(markdown.contains("@addField(Test.Docs.Foo)"), "{markdown}");
+    assert!(markdown.contains("@addMethod(Test.Docs.Foo)"), "{markdown}");
+    assert!(
+        markdown.contains("[Test.Docs](../modules/Test.Docs.md)"),
+        "{markdown}"
+    );
+
+    let html = page.render(DocFormat::Html);
+    assert!(
+        html.contains("<code>func Count() -&gt; Int32</code>"),
+        "{html}"
+    );
+
+    assert!(
+        pages.iter().any(|page| page.path() == "modules/Test.Docs"),
+        "module page should be generated"
+    );
+}
@@ -3,10 +3,11 @@ use std::{env, fs};
 
 use hashbrown::HashMap;
 use leon::{Template, vals};
+use redscript_compiler_frontend::cte;
 use serde::Deserialize;
 use toml::Value;
 
-const DOTFILE_NAME: &str = ".redscript";
+pub const DOTFILE_NAME: &str = ".redscript";
 
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -16,6 +17,10 @@ pub struct Dotfile {
     #[serde(default)]
     pub format: FormatConfig,
     #[serde(default)]
+    pub diagnostics: DiagnosticsConfig,
+    #[serde(default)]
+    pub defines: HashMap<String, DefineValue>,
+    #[serde(default)]
     pub tools: HashMap<String, Value>,
 }
 
@@ -50,6 +55,8 @@ impl Default for Dotfile {
         Self {
             source_roots: default_roots(),
             format: FormatConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            defines: HashMap::new(),
             tools: HashMap::new(),
         }
     }
@@ -65,6 +72,37 @@ pub struct FormatConfig {
     pub max_chain_total: Option<u8>,
 }
 
+/// Diagnostic codes to silence, report as warnings or report as errors.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiagnosticsConfig {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub warn: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// A value of a name defined for conditional compilation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum DefineValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl From<DefineValue> for cte::Value {
+    fn from(value: DefineValue) -> Self {
+        match value {
+            DefineValue::Bool(b) => Self::Bool(b),
+            DefineValue::Int(i) => Self::Int(i),
+            DefineValue::String(s) => Self::String(s),
+        }
+    }
+}
+
 fn default_roots() -> Vec<String> {
     vec![String::from(".")]
 }
@@ -89,6 +127,8 @@ mod tests {
                     indent: Some(4),
                     ..Default::default()
                 },
+                diagnostics: DiagnosticsConfig::default(),
+                defines: HashMap::new(),
                 tools: HashMap::new(),
             }
         )
@@ -121,11 +161,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_dotfile_with_diagnostics() {
+        let content = r#"
+            [diagnostics]
+            allow = ["DUP_VARIANT_VAL"]
+            deny = ["UNUSED_LOCAL", "FINAL_FN_OVERRIDE"]
+        "#;
+
+        let dotfile: Dotfile = toml::from_str(content).unwrap();
+        assert_eq!(
+            dotfile.diagnostics,
+            DiagnosticsConfig {
+                allow: vec![String::from("DUP_VARIANT_VAL")],
+                warn: vec![],
+                deny: vec![
+                    String::from("UNUSED_LOCAL"),
+                    String::from("FINAL_FN_OVERRIDE")
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_dotfile_with_defines() {
+        let content = r#"
+            [defines]
+            DEBUG = true
+            LEVEL = 2
+            NAME = "test"
+        "#;
+
+        let dotfile: Dotfile = toml::from_str(content).unwrap();
+        assert_eq!(
+            dotfile.defines,
+            HashMap::from_iter([
+                (String::from("DEBUG"), DefineValue::Bool(true)),
+                (String::from("LEVEL"), DefineValue::Int(2)),
+                (
+                    String::from("NAME"),
+                    DefineValue::String(String::from("test"))
+                ),
+            ])
+        );
+    }
+
     #[test]
     fn expanded_source_roots() {
         let dotfile = Dotfile {
             source_roots: vec![String::from("{CARGO_MANIFEST_DIR}/src")],
             format: FormatConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            defines: HashMap::new(),
             tools: HashMap::new(),
         };
 
@@ -141,6 +228,8 @@ mod tests {
         let dotfile = Dotfile {
             source_roots: vec![String::from("{CARGO_MANIFEST_DIR}\\src")],
             format: FormatConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            defines: HashMap::new(),
             tools: HashMap::new(),
         };
 
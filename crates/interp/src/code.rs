@@ -0,0 +1,560 @@
+use std::rc::Weak;
+
+use hashbrown::HashMap;
+use redscript_io::{
+    CNameIndex, ClassIndex, FieldIndex, FunctionIndex, Instr, LocalIndex, Offset, ParameterIndex,
+    ScriptBundle, TypeIndex,
+};
+
+use crate::{Error, Result, Value};
+
+/// The body of a scripted function, translated from the tree-shaped bytecode into a flat
+/// sequence of operations on a value stack.
+#[derive(Debug)]
+pub(crate) struct Code {
+    ops: Vec<Op>,
+}
+
+impl Code {
+    pub fn compile(
+        bundle: &ScriptBundle<'_>,
+        methods: &HashMap<CNameIndex, FunctionIndex>,
+        function: FunctionIndex,
+    ) -> Result<Self> {
+        let func = bundle
+            .get_item(function)
+            .ok_or(Error::UndefinedFunction(function))?;
+        let mut instrs = vec![];
+        let mut offsets = vec![];
+        let mut iter = func.body().code_iter();
+        loop {
+            offsets.push(iter.virtual_offset());
+            match iter.next() {
+                Some(instr) => instrs.push(instr?),
+                None => break,
+            }
+        }
+
+        let mut compiler = Compiler {
+            bundle,
+            methods,
+            instrs,
+            offsets,
+            pos: 0,
+            ops: vec![],
+            statements: HashMap::new(),
+            cases: HashMap::new(),
+            fixups: vec![],
+        };
+        compiler.body()?;
+        Ok(Self { ops: compiler.ops })
+    }
+
+    #[inline]
+    pub fn get(&self, pc: usize) -> Option<&Op> {
+        self.ops.get(pc)
+    }
+}
+
+/// An operation of the interpreter. Operands are popped from the value stack and
+/// every operation apart from jumps, returns and stack manipulation pushes one result.
+#[derive(Debug, Clone)]
+pub(crate) enum Op {
+    Push(Value),
+    /// Pushes the default value of a type, used for omitted arguments.
+    Default(TypeIndex),
+    This,
+    Local(LocalIndex),
+    Param(ParameterIndex),
+    LocalPlace(LocalIndex),
+    ParamPlace(ParameterIndex),
+    /// Pops an object and pushes the value of its field.
+    Field(FieldIndex),
+    FieldPlace(FieldIndex),
+    /// Pops a struct and pushes the value of its field.
+    StructField(FieldIndex),
+    StructFieldPlace(FieldIndex),
+    /// Pops an index and an array and pushes the element.
+    Element,
+    ElementPlace,
+    /// Checks that the object on top of the stack is not null.
+    Receiver,
+    /// Pops a value and a place and writes the value to the place.
+    Store,
+    Pop,
+    /// Removes the slot below the top of the stack.
+    Nip,
+    Dup,
+    Equal,
+    NotEqual,
+    Jump(usize),
+    JumpIfFalse(usize),
+    /// Jumps to `target` with a copy of the top of the stack pushed as the next argument
+    /// when the top of the stack is `value`, which means that the preceding argument
+    /// already determines the result of a logical operator.
+    ShortCircuit {
+        value: bool,
+        target: usize,
+    },
+    Return,
+    /// Pops the arguments followed by `this` when `has_this` is set and invokes the
+    /// function. Arguments passed as places are written back when the call returns.
+    Call {
+        function: FunctionIndex,
+        arg_count: usize,
+        has_this: bool,
+    },
+    /// Pops the arguments followed by the receiver and invokes the method with
+    /// this name on the receiver's class.
+    CallVirtual {
+        name: CNameIndex,
+        arg_count: usize,
+    },
+    Construct {
+        class: ClassIndex,
+        arg_count: usize,
+    },
+    New(ClassIndex),
+    /// Pops the operands listed by `operands` and applies the instruction to them.
+    Intrinsic(Instr),
+}
+
+/// Whether an expression is evaluated for its value or for a location to write to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Value,
+    Place,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Label {
+    /// The start of the statement at an instruction position.
+    Statement(usize),
+    /// The switch case at an instruction position, which is tested against the
+    /// scrutinee on top of the stack.
+    Case(usize),
+}
+
+struct Compiler<'a, 'i> {
+    bundle: &'a ScriptBundle<'i>,
+    methods: &'a HashMap<CNameIndex, FunctionIndex>,
+    instrs: Vec<Instr>,
+    /// Virtual offsets of the instructions, followed by the offset of the end.
+    offsets: Vec<u32>,
+    pos: usize,
+    ops: Vec<Op>,
+    statements: HashMap<usize, usize>,
+    cases: HashMap<usize, usize>,
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Compiler<'_, '_> {
+    fn body(&mut self) -> Result<()> {
+        while self.pos < self.instrs.len() {
+            self.statement()?;
+        }
+        self.statements.insert(self.pos, self.ops.len());
+        self.ops.push(Op::Push(Value::Unit));
+        self.ops.push(Op::Return);
+
+        for (op, label) in std::mem::take(&mut self.fixups) {
+            let target = self.resolve(label)?;
+            match &mut self.ops[op] {
+                Op::Jump(dest) | Op::JumpIfFalse(dest) => *dest = target,
+                _ => unreachable!("only jumps are fixed up"),
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<()> {
+        let at = self.pos;
+        self.statements.insert(at, self.ops.len());
+        match self.next()? {
+            Instr::Jump(jump) => {
+                let target = self.target(at, jump.target())?;
+                self.jump(Op::Jump, Label::Statement(target));
+            }
+            Instr::JumpIfFalse(jump) => {
+                let target = self.target(at, jump.target())?;
+                self.expr(Mode::Value)?;
+                self.jump(Op::JumpIfFalse, Label::Statement(target));
+            }
+            Instr::Return => {
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Return);
+            }
+            Instr::Switch(switch) => {
+                let first_case = self.target(at, switch.first_case())?;
+                self.expr(Mode::Value)?;
+                self.jump(Op::Jump, Label::Case(first_case));
+            }
+            Instr::SwitchLabel(label) => {
+                let body = self.target(at, label.body())?;
+                let next_case = self.target(at, label.next_case())?;
+                // falling through from the previous case goes straight to the body
+                self.jump(Op::Jump, Label::Statement(body));
+
+                self.cases.insert(at, self.ops.len());
+                self.ops.push(Op::Dup);
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Equal);
+                self.jump(Op::JumpIfFalse, Label::Case(next_case));
+                self.ops.push(Op::Pop);
+                self.jump(Op::Jump, Label::Statement(body));
+            }
+            Instr::SwitchDefault => {
+                self.jump(Op::Jump, Label::Statement(self.pos));
+                self.cases.insert(at, self.ops.len());
+                self.ops.push(Op::Pop);
+            }
+            Instr::Nop | Instr::Breakpoint(_) | Instr::Profile(_) | Instr::Target(_) => {}
+            _ => {
+                self.pos = at;
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Pop);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn expr(&mut self, mode: Mode) -> Result<()> {
+        self.member(mode, false)
+    }
+
+    /// Compiles an expression, `has_receiver` is set when it's the member part of a context
+    /// expression and the object it's evaluated on is on top of the stack.
+    fn member(&mut self, mode: Mode, has_receiver: bool) -> Result<()> {
+        let bundle = self.bundle;
+        match self.next()? {
+            Instr::Nop => self.ops.push(Op::Push(Value::Unit)),
+            Instr::Null => self.ops.push(Op::Push(Value::Null)),
+            Instr::I32One => self.ops.push(Op::Push(Value::I32(1))),
+            Instr::I32Zero => self.ops.push(Op::Push(Value::I32(0))),
+            Instr::I8Const(i) => self.ops.push(Op::Push(Value::I8(i))),
+            Instr::I16Const(i) => self.ops.push(Op::Push(Value::I16(i))),
+            Instr::I32Const(i) => self.ops.push(Op::Push(Value::I32(i))),
+            Instr::I64Const(i) => self.ops.push(Op::Push(Value::I64(i))),
+            Instr::U8Const(i) => self.ops.push(Op::Push(Value::U8(i))),
+            Instr::U16Const(i) => self.ops.push(Op::Push(Value::U16(i))),
+            Instr::U32Const(i) => self.ops.push(Op::Push(Value::U32(i))),
+            Instr::U64Const(i) => self.ops.push(Op::Push(Value::U64(i))),
+            Instr::F32Const(f) => self.ops.push(Op::Push(Value::F32(f))),
+            Instr::F64Const(f) => self.ops.push(Op::Push(Value::F64(f))),
+            Instr::CNameConst(idx) => {
+                self.ops
+                    .push(Op::Push(Value::CName(bundle[idx].to_string())));
+            }
+            Instr::StringConst(idx) => {
+                self.ops
+                    .push(Op::Push(Value::String(bundle[idx].to_string())));
+            }
+            Instr::TweakDbIdConst(idx) => {
+                self.ops
+                    .push(Op::Push(Value::TweakDbId(bundle[idx].to_string())));
+            }
+            Instr::ResourceConst(idx) => {
+                self.ops
+                    .push(Op::Push(Value::Resource(bundle[idx].to_string())));
+            }
+            Instr::EnumConst { enum_, value } => {
+                self.ops
+                    .push(Op::Push(Value::Enum(enum_, bundle[value].value())));
+            }
+            Instr::TrueConst => self.ops.push(Op::Push(Value::Bool(true))),
+            Instr::FalseConst => self.ops.push(Op::Push(Value::Bool(false))),
+            Instr::WeakRefNull => self.ops.push(Op::Push(Value::WeakRef(Weak::new()))),
+            Instr::This => self.ops.push(Op::This),
+            Instr::Assign => {
+                self.expr(Mode::Place)?;
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Store);
+            }
+            Instr::Local(idx) => self.ops.push(match mode {
+                Mode::Value => Op::Local(idx),
+                Mode::Place => Op::LocalPlace(idx),
+            }),
+            Instr::Param(idx) => self.ops.push(match mode {
+                Mode::Value => Op::Param(idx),
+                Mode::Place => Op::ParamPlace(idx),
+            }),
+            Instr::ObjectField(field) => {
+                if !has_receiver {
+                    self.ops.push(Op::This);
+                }
+                self.ops.push(match mode {
+                    Mode::Value => Op::Field(field),
+                    Mode::Place => Op::FieldPlace(field),
+                });
+                return Ok(());
+            }
+            Instr::StructField(field) => {
+                self.expr(mode)?;
+                self.ops.push(match mode {
+                    Mode::Value => Op::StructField(field),
+                    Mode::Place => Op::StructFieldPlace(field),
+                });
+            }
+            Instr::ArrayElement(_) | Instr::StaticArrayElement(_) => {
+                self.expr(mode)?;
+                self.expr(Mode::Value)?;
+                self.ops.push(match mode {
+                    Mode::Value => Op::Element,
+                    Mode::Place => Op::ElementPlace,
+                });
+            }
+            Instr::Context(_) => {
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Receiver);
+                self.member(mode, true)?;
+            }
+            Instr::Conditional(_) => {
+                self.expr(Mode::Value)?;
+                let if_false = self.ops.len();
+                self.ops.push(Op::JumpIfFalse(0));
+                self.expr(Mode::Value)?;
+                let exit = self.ops.len();
+                self.ops.push(Op::Jump(0));
+                self.ops[if_false] = Op::JumpIfFalse(self.ops.len());
+                self.expr(Mode::Value)?;
+                self.ops[exit] = Op::Jump(self.ops.len());
+            }
+            Instr::Construct { arg_count, class } => {
+                for _ in 0..arg_count {
+                    self.expr(Mode::Value)?;
+                }
+                self.ops.push(Op::Construct {
+                    class,
+                    arg_count: arg_count.into(),
+                });
+            }
+            Instr::New(class) => self.ops.push(Op::New(class)),
+            Instr::InvokeStatic { function, .. } => {
+                let func = bundle
+                    .get_item(function)
+                    .ok_or(Error::UndefinedFunction(function))?;
+                let has_this = !func.flags().is_static();
+                match (has_this, has_receiver) {
+                    (true, false) => self.ops.push(Op::This),
+                    (false, true) => self.ops.push(Op::Pop),
+                    _ => {}
+                }
+                let arg_count = self.args(function)?;
+                self.ops.push(Op::Call {
+                    function,
+                    arg_count,
+                    has_this,
+                });
+                return Ok(());
+            }
+            Instr::InvokeVirtual { function: name, .. } => {
+                if !has_receiver {
+                    self.ops.push(Op::This);
+                }
+                // overrides share the signature, so any of them describes the parameters
+                let &method = self
+                    .methods
+                    .get(&name)
+                    .ok_or_else(|| Error::UndefinedMethod(bundle[name].to_string()))?;
+                let arg_count = self.args(method)?;
+                self.ops.push(Op::CallVirtual { name, arg_count });
+                return Ok(());
+            }
+            Instr::Equals(_)
+            | Instr::RefStringEqualsString(_)
+            | Instr::StringEqualsRefString(_) => {
+                self.expr(Mode::Value)?;
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::Equal);
+            }
+            Instr::NotEquals(_)
+            | Instr::RefStringNotEqualsString(_)
+            | Instr::StringNotEqualsRefString(_) => {
+                self.expr(Mode::Value)?;
+                self.expr(Mode::Value)?;
+                self.ops.push(Op::NotEqual);
+            }
+            Instr::AsRef(_) | Instr::Deref(_) => self.expr(mode)?,
+            Instr::Breakpoint(_) | Instr::Profile(_) | Instr::Target(_) => {
+                return self.member(mode, has_receiver);
+            }
+            instr => {
+                let Some(operands) = operands(&instr) else {
+                    return Err(Error::UnexpectedInstr(instr.to_string()));
+                };
+                for &mode in operands {
+                    self.expr(mode)?;
+                }
+                self.ops.push(Op::Intrinsic(instr));
+            }
+        }
+        if has_receiver {
+            // the receiver is only used by field accesses and calls
+            self.ops.push(Op::Nip);
+        }
+        Ok(())
+    }
+
+    /// Compiles the arguments of a call up to and including the parameter end marker,
+    /// returns the number of arguments pushed.
+    fn args(&mut self, function: FunctionIndex) -> Result<usize> {
+        let bundle = self.bundle;
+        let func = bundle
+            .get_item(function)
+            .ok_or(Error::UndefinedFunction(function))?;
+        let name = &*bundle[func.name()];
+        let short_circuit = match name.split_once(';').map_or(name, |(name, _)| name) {
+            "OperatorLogicAnd" => Some(false),
+            "OperatorLogicOr" => Some(true),
+            _ => None,
+        };
+
+        let mut count = 0;
+        for &param in func.parameters() {
+            let param = &bundle[param];
+            let is_skippable = match self.instrs.get(self.pos) {
+                Some(Instr::ParamEnd) | None => break,
+                Some(Instr::Nop) => {
+                    self.pos += 1;
+                    self.ops.push(Op::Default(param.type_()));
+                    count += 1;
+                    continue;
+                }
+                Some(Instr::Skip(_)) => {
+                    self.pos += 1;
+                    true
+                }
+                Some(_) => false,
+            };
+
+            let mode = if param.flags().is_out() {
+                Mode::Place
+            } else {
+                Mode::Value
+            };
+            match short_circuit {
+                Some(value) if is_skippable => {
+                    let skip = self.ops.len();
+                    self.ops.push(Op::ShortCircuit { value, target: 0 });
+                    self.expr(mode)?;
+                    self.ops[skip] = Op::ShortCircuit {
+                        value,
+                        target: self.ops.len(),
+                    };
+                }
+                _ => self.expr(mode)?,
+            }
+            count += 1;
+        }
+        match self.next()? {
+            Instr::ParamEnd => Ok(count),
+            other => Err(Error::UnexpectedInstr(other.to_string())),
+        }
+    }
+
+    fn next(&mut self) -> Result<Instr> {
+        let instr = self
+            .instrs
+            .get(self.pos)
+            .cloned()
+            .ok_or(Error::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(instr)
+    }
+
+    fn jump(&mut self, op: fn(usize) -> Op, label: Label) {
+        self.fixups.push((self.ops.len(), label));
+        self.ops.push(op(0));
+    }
+
+    /// Returns the position of the instruction at an offset relative to the instruction
+    /// at `origin`.
+    fn target(&self, origin: usize, offset: Offset) -> Result<usize> {
+        let target = i64::from(self.offsets[origin]) + i64::from(i16::from(offset));
+        u32::try_from(target)
+            .ok()
+            .and_then(|target| self.offsets.binary_search(&target).ok())
+            .ok_or(Error::InvalidJump(target))
+    }
+
+    fn resolve(&mut self, label: Label) -> Result<usize> {
+        match label {
+            Label::Statement(pos) => self.statement_start(pos),
+            Label::Case(pos) => {
+                if let Some(&case) = self.cases.get(&pos) {
+                    return Ok(case);
+                }
+                // no case matched, so the scrutinee is dropped before leaving the switch
+                let target = self.statement_start(pos)?;
+                let trampoline = self.ops.len();
+                self.ops.push(Op::Pop);
+                self.ops.push(Op::Jump(target));
+                Ok(trampoline)
+            }
+        }
+    }
+
+    fn statement_start(&self, pos: usize) -> Result<usize> {
+        self.statements
+            .get(&pos)
+            .copied()
+            .ok_or_else(|| Error::InvalidJump(self.offsets[pos].into()))
+    }
+}
+
+/// Returns the modes in which the operands of an intrinsic instruction are evaluated,
+/// or `None` if the instruction is not an intrinsic.
+fn operands(instr: &Instr) -> Option<&'static [Mode]> {
+    use Mode::{Place, Value};
+
+    let res: &[Mode] = match instr {
+        Instr::RefToBool
+        | Instr::WeakRefToBool
+        | Instr::WeakRefToRef
+        | Instr::RefToWeakRef
+        | Instr::DynamicCast { .. }
+        | Instr::EnumToI32 { .. }
+        | Instr::I32ToEnum { .. }
+        | Instr::ToString(_)
+        | Instr::ToVariant(_)
+        | Instr::FromVariant(_)
+        | Instr::VariantIsDefined
+        | Instr::VariantIsRef
+        | Instr::VariantIsArray
+        | Instr::VariantTypeName
+        | Instr::VariantToString
+        | Instr::ArraySize(_)
+        | Instr::StaticArraySize(_)
+        | Instr::ArrayLast(_)
+        | Instr::StaticArrayLast(_) => &[Value],
+        Instr::ArrayFindFirst(_)
+        | Instr::ArrayFindFirstFast(_)
+        | Instr::StaticArrayFindFirst(_)
+        | Instr::StaticArrayFindFirstFast(_)
+        | Instr::ArrayFindLast(_)
+        | Instr::ArrayFindLastFast(_)
+        | Instr::StaticArrayFindLast(_)
+        | Instr::StaticArrayFindLastFast(_)
+        | Instr::ArrayContains(_)
+        | Instr::ArrayContainsFast(_)
+        | Instr::StaticArrayContains(_)
+        | Instr::StaticArrayContainsFast(_)
+        | Instr::ArrayCount(_)
+        | Instr::ArrayCountFast(_)
+        | Instr::StaticArrayCount(_)
+        | Instr::StaticArrayCountFast(_) => &[Value, Value],
+        Instr::ArrayClear(_) | Instr::ArrayPop(_) | Instr::ArraySort(_) => &[Place],
+        Instr::ArrayResize(_)
+        | Instr::ArrayGrow(_)
+        | Instr::ArrayPush(_)
+        | Instr::ArrayRemove(_)
+        | Instr::ArrayRemoveFast(_)
+        | Instr::ArrayErase(_)
+        | Instr::ArrayEraseFast(_)
+        | Instr::ArraySortByPredicate(_) => &[Place, Value],
+        Instr::ArrayInsert(_) => &[Place, Value, Value],
+        _ => return None,
+    };
+    Some(res)
+}
@@ -0,0 +1,899 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use hashbrown::HashMap;
+use redscript_io::{
+    CNameIndex, ClassIndex, EnumIndex, FieldIndex, FunctionIndex, IndexedDefinition, Instr,
+    LocalIndex, ParameterIndex, ScriptBundle, TypeIndex, TypeKind,
+};
+
+use crate::code::{Code, Op};
+use crate::{Error, Natives, Object, ObjectRef, Result, Struct, Value};
+
+const MAX_CALL_DEPTH: usize = 256;
+
+/// A stack-based interpreter for the bytecode of scripted functions.
+///
+/// Function bodies are translated into flat sequences of operations the first time they
+/// are called. The operations work on a value stack, and calls between scripted functions
+/// push frames onto a call stack instead of recursing, so the depth of script calls is
+/// not bounded by the native stack. Natives that call back into scripts, like the
+/// predicate of `ArraySortByPredicate`, run a nested dispatch loop.
+pub struct Interpreter<'a, 'i, N> {
+    bundle: &'a ScriptBundle<'i>,
+    natives: N,
+    classes: HashMap<&'a str, ClassIndex>,
+    enums: HashMap<&'a str, EnumIndex>,
+    functions: HashMap<&'a str, FunctionIndex>,
+    methods: HashMap<CNameIndex, FunctionIndex>,
+    code: HashMap<FunctionIndex, Rc<Code>>,
+    stack: Vec<Slot>,
+    frames: Vec<Frame>,
+}
+
+impl<'a, 'i, N: Natives> Interpreter<'a, 'i, N> {
+    pub fn new(bundle: &'a ScriptBundle<'i>, natives: N) -> Self {
+        let mut classes = HashMap::new();
+        let mut enums = HashMap::new();
+        let mut functions = HashMap::new();
+        let mut methods = HashMap::new();
+
+        for def in bundle.indexed_definitions() {
+            match def {
+                IndexedDefinition::Class(idx, class) => {
+                    classes.insert(&*bundle[class.name()], idx);
+                }
+                IndexedDefinition::Enum(idx, enum_) => {
+                    enums.insert(&*bundle[enum_.name()], idx);
+                }
+                IndexedDefinition::Function(idx, func) if func.class().is_none() => {
+                    functions.insert(&*bundle[func.name()], idx);
+                }
+                IndexedDefinition::Function(idx, func) => {
+                    methods.entry(func.name()).or_insert(idx);
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            bundle,
+            natives,
+            classes,
+            enums,
+            functions,
+            methods,
+            code: HashMap::new(),
+            stack: vec![],
+            frames: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn natives(&self) -> &N {
+        &self.natives
+    }
+
+    #[inline]
+    pub fn natives_mut(&mut self) -> &mut N {
+        &mut self.natives
+    }
+
+    /// Looks up a free function by its full name as stored in the bundle,
+    /// e.g. `Add;Int32Int32`.
+    pub fn function(&self, name: &str) -> Option<FunctionIndex> {
+        self.functions.get(name).copied()
+    }
+
+    /// Looks up a class by its name.
+    pub fn class(&self, name: &str) -> Option<ClassIndex> {
+        self.classes.get(name).copied()
+    }
+
+    /// Looks up a method of a class or one of its bases by its full name as stored in
+    /// the bundle, e.g. `GetName;`.
+    pub fn method(&self, class: ClassIndex, name: &str) -> Option<FunctionIndex> {
+        self.find_method(class, |idx| &*self.bundle[self.bundle[idx].name()] == name)
+    }
+
+    /// Calls a static or free function with the given arguments.
+    pub fn call(&mut self, function: FunctionIndex, args: Vec<Value>) -> Result<Value> {
+        self.execute(function, None, args)
+    }
+
+    /// Calls an instance method on `this` with the given arguments.
+    pub fn call_method(
+        &mut self,
+        function: FunctionIndex,
+        this: ObjectRef,
+        args: Vec<Value>,
+    ) -> Result<Value> {
+        self.execute(function, Some(this), args)
+    }
+
+    /// Creates a new instance of a class with all of its fields set to their defaults.
+    pub fn instantiate(&self, class: ClassIndex) -> Result<ObjectRef> {
+        let mut fields = HashMap::new();
+        let mut current = Some(class);
+        while let Some(idx) = current {
+            let def = self
+                .bundle
+                .get_item(idx)
+                .ok_or(Error::UndefinedClass(idx))?;
+            for &field in def.fields() {
+                fields.insert(field, self.default_value(self.bundle[field].type_())?);
+            }
+            current = def.base();
+        }
+        Ok(Rc::new(RefCell::new(Object::new(class, fields))))
+    }
+
+    /// Returns the default value of a type, as used to initialize locals and fields.
+    pub fn default_value(&self, typ: TypeIndex) -> Result<Value> {
+        let typ = self.bundle.get_item(typ).ok_or(Error::UndefinedType(typ))?;
+        let name = &*self.bundle[typ.name()];
+        let res = match typ.kind() {
+            TypeKind::Primitive => match name {
+                "Bool" => Value::Bool(false),
+                "Int8" => Value::I8(0),
+                "Int16" => Value::I16(0),
+                "Int32" => Value::I32(0),
+                "Int64" => Value::I64(0),
+                "Uint8" => Value::U8(0),
+                "Uint16" => Value::U16(0),
+                "Uint32" => Value::U32(0),
+                "Uint64" => Value::U64(0),
+                "Float" => Value::F32(0.0),
+                "Double" => Value::F64(0.0),
+                "String" => Value::String(String::new()),
+                "CName" => Value::CName(String::new()),
+                "TweakDBID" => Value::TweakDbId(String::new()),
+                "ResRef" => Value::Resource(String::new()),
+                "Variant" => Value::Variant(None),
+                _ => Value::Unit,
+            },
+            TypeKind::Class => match (self.enums.get(name), self.classes.get(name)) {
+                (Some(&enum_), _) => Value::Enum(enum_, 0),
+                (_, Some(&class)) if self.bundle[class].flags().is_struct() => {
+                    Value::Struct(self.default_struct(class)?)
+                }
+                _ => Value::Null,
+            },
+            TypeKind::Ref(_) => Value::Null,
+            TypeKind::WeakRef(_) => Value::WeakRef(Weak::new()),
+            TypeKind::Array(_) => Value::Array(vec![]),
+            TypeKind::StaticArray { element_type, size } => {
+                let elem = self.default_value(element_type)?;
+                Value::Array(vec![elem; size as usize])
+            }
+            TypeKind::ScriptRef(inner) => self.default_value(inner)?,
+        };
+        Ok(res)
+    }
+
+    fn default_struct(&self, class: ClassIndex) -> Result<Struct> {
+        let fields = self.bundle[class]
+            .fields()
+            .iter()
+            .map(|&field| Ok((field, self.default_value(self.bundle[field].type_())?)))
+            .collect::<Result<_>>()?;
+        Ok(Struct::new(class, fields))
+    }
+
+    /// Runs a function to completion. The stacks are restored to their previous state
+    /// when the function fails.
+    fn execute(
+        &mut self,
+        function: FunctionIndex,
+        this: Option<ObjectRef>,
+        args: Vec<Value>,
+    ) -> Result<Value> {
+        let depth = self.frames.len();
+        let height = self.stack.len();
+        let res = self.invoke(function, this, args, vec![]).and_then(|()| {
+            if self.frames.len() > depth {
+                self.run(depth)
+            } else {
+                self.pop_value()
+            }
+        });
+        if res.is_err() {
+            self.frames.truncate(depth);
+            self.stack.truncate(height);
+        }
+        res
+    }
+
+    /// Invokes a function. Natives are called immediately and their result is pushed
+    /// onto the stack, while scripted functions get a new frame that runs next. The
+    /// arguments at the positions listed in `outs` are written back to their places
+    /// when the function returns.
+    fn invoke(
+        &mut self,
+        function: FunctionIndex,
+        this: Option<ObjectRef>,
+        mut args: Vec<Value>,
+        outs: Vec<(usize, Place)>,
+    ) -> Result<()> {
+        let func = self
+            .bundle
+            .get_item(function)
+            .ok_or(Error::UndefinedFunction(function))?;
+
+        if func.flags().is_native() {
+            let name = &*self.bundle[func.name()];
+            let name = name.split_once(';').map_or(name, |(name, _)| name);
+            let res = self.natives.call(name, &mut args)?;
+            for (i, place) in outs {
+                self.write(&place, args[i].clone())?;
+            }
+            self.push(res);
+            return Ok(());
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Error::StackOverflow);
+        }
+
+        let code = self.code(function)?;
+        let mut params = HashMap::new();
+        let mut args = args.into_iter();
+        for &param in func.parameters() {
+            let value = match args.next() {
+                Some(arg) => arg,
+                None => self.default_value(self.bundle[param].type_())?,
+            };
+            params.insert(param, value);
+        }
+        let mut locals = HashMap::new();
+        for &local in func.locals() {
+            locals.insert(local, self.default_value(self.bundle[local].type_())?);
+        }
+        let outs = outs
+            .into_iter()
+            .map(|(i, place)| (func.parameters()[i], place))
+            .collect();
+
+        self.frames.push(Frame {
+            code,
+            pc: 0,
+            base: self.stack.len(),
+            this,
+            params,
+            locals,
+            outs,
+        });
+        Ok(())
+    }
+
+    fn code(&mut self, function: FunctionIndex) -> Result<Rc<Code>> {
+        if let Some(code) = self.code.get(&function) {
+            return Ok(code.clone());
+        }
+        let code = Rc::new(Code::compile(self.bundle, &self.methods, function)?);
+        self.code.insert(function, code.clone());
+        Ok(code)
+    }
+
+    /// Executes operations until the frame at `depth` returns.
+    fn run(&mut self, depth: usize) -> Result<Value> {
+        loop {
+            let frame = self.frame_mut();
+            let code = frame.code.clone();
+            let op = code.get(frame.pc).ok_or(Error::UnexpectedEnd)?;
+            frame.pc += 1;
+
+            match op {
+                Op::Push(value) => self.push(value.clone()),
+                Op::Default(typ) => {
+                    let value = self.default_value(*typ)?;
+                    self.push(value);
+                }
+                Op::This => {
+                    let this = self.frame().this.clone();
+                    self.push(this.map_or(Value::Null, Value::Ref));
+                }
+                Op::Local(idx) => {
+                    let value = self.frame().locals.get(idx).cloned().unwrap_or_default();
+                    self.push(value);
+                }
+                Op::Param(idx) => {
+                    let value = self.frame().params.get(idx).cloned().unwrap_or_default();
+                    self.push(value);
+                }
+                Op::LocalPlace(idx) => self.stack.push(Slot::Place(Place::Local(*idx))),
+                Op::ParamPlace(idx) => self.stack.push(Slot::Place(Place::Param(*idx))),
+                Op::Field(field) => {
+                    let obj = self.pop_object()?;
+                    let value = obj.borrow().field(*field).cloned().unwrap_or_default();
+                    self.push(value);
+                }
+                Op::FieldPlace(field) => {
+                    let obj = self.pop_object()?;
+                    self.stack.push(Slot::Place(Place::Field(obj, *field)));
+                }
+                Op::StructField(field) => match self.pop_value()? {
+                    Value::Struct(struct_) => {
+                        self.push(struct_.field(*field).cloned().unwrap_or_default());
+                    }
+                    other => return Err(Error::TypeMismatch("struct", other.type_name())),
+                },
+                Op::StructFieldPlace(field) => {
+                    let inner = self.pop_place();
+                    let place = Place::StructField(Box::new(inner), *field);
+                    self.stack.push(Slot::Place(place));
+                }
+                Op::Element => {
+                    let index = self.pop_value()?.as_index()?;
+                    let array = self.pop_value()?.into_array()?;
+                    let elem = array
+                        .into_iter()
+                        .nth(index)
+                        .ok_or(Error::IndexOutOfBounds(index as i128))?;
+                    self.push(elem);
+                }
+                Op::ElementPlace => {
+                    let index = self.pop_value()?.as_index()?;
+                    let array = self.pop_place();
+                    let place = Place::Element(Box::new(array), index);
+                    self.stack.push(Slot::Place(place));
+                }
+                Op::Receiver => {
+                    let obj = self.pop_object()?;
+                    self.push(Value::Ref(obj));
+                }
+                Op::Store => {
+                    let value = self.pop_value()?;
+                    let place = self.pop_place();
+                    self.write(&place, value)?;
+                    self.push(Value::Unit);
+                }
+                Op::Pop => {
+                    self.pop()?;
+                }
+                Op::Nip => {
+                    let top = self.pop()?;
+                    self.pop()?;
+                    self.stack.push(top);
+                }
+                Op::Dup => {
+                    let top = self.stack.last().cloned().ok_or(Error::UnexpectedEnd)?;
+                    self.stack.push(top);
+                }
+                Op::Equal => {
+                    let rhs = self.pop_value()?;
+                    let lhs = self.pop_value()?;
+                    self.push(Value::Bool(lhs == rhs));
+                }
+                Op::NotEqual => {
+                    let rhs = self.pop_value()?;
+                    let lhs = self.pop_value()?;
+                    self.push(Value::Bool(lhs != rhs));
+                }
+                Op::Jump(target) => self.frame_mut().pc = *target,
+                Op::JumpIfFalse(target) => {
+                    if !self.pop_value()?.as_bool()? {
+                        self.frame_mut().pc = *target;
+                    }
+                }
+                &Op::ShortCircuit { value, target } => {
+                    if matches!(self.stack.last(), Some(Slot::Value(Value::Bool(b))) if *b == value)
+                    {
+                        self.push(Value::Bool(value));
+                        self.frame_mut().pc = target;
+                    }
+                }
+                Op::Return => {
+                    let value = self.pop_value()?;
+                    let frame = self.frames.pop().ok_or(Error::UnexpectedEnd)?;
+                    self.stack.truncate(frame.base);
+                    if self.frames.len() == depth {
+                        return Ok(value);
+                    }
+                    for (param, place) in frame.outs {
+                        let arg = frame.params.get(&param).cloned().unwrap_or_default();
+                        self.write(&place, arg)?;
+                    }
+                    self.push(value);
+                }
+                &Op::Call {
+                    function,
+                    arg_count,
+                    has_this,
+                } => {
+                    let (args, outs) = self.pop_args(arg_count)?;
+                    let this = if has_this {
+                        self.pop_value()?.as_object()?
+                    } else {
+                        None
+                    };
+                    self.invoke(function, this, args, outs)?;
+                }
+                &Op::CallVirtual { name, arg_count } => {
+                    let (args, outs) = self.pop_args(arg_count)?;
+                    let this = self.pop_object()?;
+                    let class = this.borrow().class();
+                    let method = self
+                        .resolve_virtual(class, name)
+                        .ok_or_else(|| Error::UndefinedMethod(self.bundle[name].to_string()))?;
+                    self.invoke(method, Some(this), args, outs)?;
+                }
+                &Op::Construct { class, arg_count } => {
+                    let (args, _) = self.pop_args(arg_count)?;
+                    let mut res = self.default_struct(class)?;
+                    for (&field, arg) in self.bundle[class].fields().iter().zip(args) {
+                        res.set_field(field, arg);
+                    }
+                    self.push(Value::Struct(res));
+                }
+                Op::New(class) => {
+                    let obj = self.instantiate(*class)?;
+                    self.push(Value::Ref(obj));
+                }
+                Op::Intrinsic(instr) => {
+                    let value = self.intrinsic(instr)?;
+                    self.push(value);
+                }
+            }
+        }
+    }
+
+    fn intrinsic(&mut self, instr: &Instr) -> Result<Value> {
+        let res = match *instr {
+            Instr::RefToBool | Instr::WeakRefToBool => {
+                Value::Bool(self.pop_value()?.as_object()?.is_some())
+            }
+            Instr::WeakRefToRef => self
+                .pop_value()?
+                .as_object()?
+                .map_or(Value::Null, Value::Ref),
+            Instr::RefToWeakRef => {
+                let obj = self.pop_value()?.as_object()?;
+                Value::WeakRef(obj.as_ref().map_or_else(Weak::new, Rc::downgrade))
+            }
+            Instr::DynamicCast { class, is_weak } => {
+                let obj = self
+                    .pop_value()?
+                    .as_object()?
+                    .filter(|obj| self.is_subclass(obj.borrow().class(), class));
+                match (obj, is_weak) {
+                    (Some(obj), true) => Value::WeakRef(Rc::downgrade(&obj)),
+                    (Some(obj), false) => Value::Ref(obj),
+                    (None, true) => Value::WeakRef(Weak::new()),
+                    (None, false) => Value::Null,
+                }
+            }
+            Instr::EnumToI32 { size, .. } => {
+                let value = self.pop_value()?;
+                let Value::Enum(_, int) = value else {
+                    return Err(Error::TypeMismatch("enum", value.type_name()));
+                };
+                match size {
+                    1 => Value::I8(int as i8),
+                    2 => Value::I16(int as i16),
+                    4 => Value::I32(int as i32),
+                    _ => Value::I64(int),
+                }
+            }
+            Instr::I32ToEnum { enum_type, .. } => {
+                let value = self.pop_value()?;
+                let int = value
+                    .as_integer()
+                    .ok_or_else(|| Error::TypeMismatch("integer", value.type_name()))?;
+                let name = &*self.bundle[self.bundle[enum_type].name()];
+                let enum_ = *self
+                    .enums
+                    .get(name)
+                    .ok_or(Error::UndefinedType(enum_type))?;
+                Value::Enum(enum_, int as i64)
+            }
+            Instr::ToString(_) => {
+                let value = self.pop_value()?;
+                Value::String(self.to_string(&value))
+            }
+            Instr::ToVariant(typ) => Value::Variant(Some(Box::new((typ, self.pop_value()?)))),
+            Instr::FromVariant(typ) => match self.pop_value()? {
+                Value::Variant(Some(inner)) if inner.0 == typ => inner.1,
+                _ => self.default_value(typ)?,
+            },
+            Instr::VariantIsDefined => Value::Bool(self.pop_variant()?.is_some()),
+            Instr::VariantIsRef => {
+                let kind = self.pop_variant()?.map(|(typ, _)| self.bundle[typ].kind());
+                Value::Bool(matches!(
+                    kind,
+                    Some(TypeKind::Ref(_) | TypeKind::WeakRef(_))
+                ))
+            }
+            Instr::VariantIsArray => {
+                let kind = self.pop_variant()?.map(|(typ, _)| self.bundle[typ].kind());
+                Value::Bool(matches!(
+                    kind,
+                    Some(TypeKind::Array(_) | TypeKind::StaticArray { .. })
+                ))
+            }
+            Instr::VariantTypeName => match self.pop_variant()? {
+                Some((typ, _)) => Value::CName(self.bundle[self.bundle[typ].name()].to_string()),
+                None => Value::CName(String::new()),
+            },
+            Instr::VariantToString => match self.pop_variant()? {
+                Some((_, value)) => Value::String(self.to_string(&value)),
+                None => Value::String(String::new()),
+            },
+            Instr::ArraySize(_) | Instr::StaticArraySize(_) => {
+                let array = self.pop_value()?.into_array()?;
+                Value::I32(array.len() as i32)
+            }
+            Instr::ArrayLast(_) | Instr::StaticArrayLast(_) => {
+                let mut array = self.pop_value()?.into_array()?;
+                array.pop().ok_or(Error::IndexOutOfBounds(-1))?
+            }
+            Instr::ArrayFindFirst(_)
+            | Instr::ArrayFindFirstFast(_)
+            | Instr::StaticArrayFindFirst(_)
+            | Instr::StaticArrayFindFirstFast(_) => {
+                let needle = self.pop_value()?;
+                let array = self.pop_value()?.into_array()?;
+                let pos = array.iter().position(|elem| *elem == needle);
+                Value::I32(pos.map_or(-1, |pos| pos as i32))
+            }
+            Instr::ArrayFindLast(_)
+            | Instr::ArrayFindLastFast(_)
+            | Instr::StaticArrayFindLast(_)
+            | Instr::StaticArrayFindLastFast(_) => {
+                let needle = self.pop_value()?;
+                let array = self.pop_value()?.into_array()?;
+                let pos = array.iter().rposition(|elem| *elem == needle);
+                Value::I32(pos.map_or(-1, |pos| pos as i32))
+            }
+            Instr::ArrayContains(_)
+            | Instr::ArrayContainsFast(_)
+            | Instr::StaticArrayContains(_)
+            | Instr::StaticArrayContainsFast(_) => {
+                let needle = self.pop_value()?;
+                let array = self.pop_value()?.into_array()?;
+                Value::Bool(array.contains(&needle))
+            }
+            Instr::ArrayCount(_)
+            | Instr::ArrayCountFast(_)
+            | Instr::StaticArrayCount(_)
+            | Instr::StaticArrayCountFast(_) => {
+                let needle = self.pop_value()?;
+                let array = self.pop_value()?.into_array()?;
+                Value::I32(array.iter().filter(|&elem| *elem == needle).count() as i32)
+            }
+            Instr::ArrayClear(_) => {
+                let place = self.pop_place();
+                self.write(&place, Value::Array(vec![]))?;
+                Value::Unit
+            }
+            Instr::ArrayResize(typ) => {
+                let size = self.pop_value()?.as_index()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                array.resize(size, self.default_element(typ)?);
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            Instr::ArrayGrow(typ) => {
+                let size = self.pop_value()?.as_index()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                array.resize(array.len() + size, self.default_element(typ)?);
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            Instr::ArrayPush(_) => {
+                let value = self.pop_value()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                array.push(value);
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            Instr::ArrayPop(typ) => {
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                let res = match array.pop() {
+                    Some(value) => value,
+                    None => self.default_element(typ)?,
+                };
+                self.write(&place, Value::Array(array))?;
+                res
+            }
+            Instr::ArrayInsert(_) => {
+                let value = self.pop_value()?;
+                let index = self.pop_value()?.as_index()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                if index > array.len() {
+                    return Err(Error::IndexOutOfBounds(index as i128));
+                }
+                array.insert(index, value);
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            Instr::ArrayRemove(_) | Instr::ArrayRemoveFast(_) => {
+                let value = self.pop_value()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                let pos = array.iter().position(|elem| *elem == value);
+                if let Some(pos) = pos {
+                    array.remove(pos);
+                    self.write(&place, Value::Array(array))?;
+                }
+                Value::Bool(pos.is_some())
+            }
+            Instr::ArrayErase(_) | Instr::ArrayEraseFast(_) => {
+                let index = self.pop_value()?.as_index()?;
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                let erased = index < array.len();
+                if erased {
+                    array.remove(index);
+                    self.write(&place, Value::Array(array))?;
+                }
+                Value::Bool(erased)
+            }
+            Instr::ArraySort(_) => {
+                let place = self.pop_place();
+                let mut array = self.read(&place)?.into_array()?;
+                array.sort_by(|a, b| a.compare(b).unwrap_or(std::cmp::Ordering::Equal));
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            Instr::ArraySortByPredicate(_) => {
+                let predicate = self.pop_object()?;
+                let place = self.pop_place();
+                let array = self.read(&place)?.into_array()?;
+                let array = self.sort_by_predicate(array, &predicate)?;
+                self.write(&place, Value::Array(array))?;
+                Value::Unit
+            }
+            ref other => return Err(Error::UnexpectedInstr(other.to_string())),
+        };
+        Ok(res)
+    }
+
+    #[inline]
+    fn frame(&self) -> &Frame {
+        self.frames
+            .last()
+            .expect("operations should only run inside of a frame")
+    }
+
+    #[inline]
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("operations should only run inside of a frame")
+    }
+
+    #[inline]
+    fn push(&mut self, value: Value) {
+        self.stack.push(Slot::Value(value));
+    }
+
+    fn pop(&mut self) -> Result<Slot> {
+        self.stack.pop().ok_or(Error::UnexpectedEnd)
+    }
+
+    fn pop_value(&mut self) -> Result<Value> {
+        match self.pop()? {
+            Slot::Value(value) => Ok(value),
+            Slot::Place(place) => self.read(&place),
+        }
+    }
+
+    /// Pops a place, values that are not places are treated as temporaries.
+    fn pop_place(&mut self) -> Place {
+        match self.stack.pop() {
+            Some(Slot::Place(place)) => place,
+            Some(Slot::Value(value)) => Place::Value(value),
+            None => Place::Value(Value::Unit),
+        }
+    }
+
+    fn pop_object(&mut self) -> Result<ObjectRef> {
+        self.pop_value()?.as_object()?.ok_or(Error::NullReference)
+    }
+
+    fn pop_variant(&mut self) -> Result<Option<(TypeIndex, Value)>> {
+        match self.pop_value()? {
+            Value::Variant(inner) => Ok(inner.map(|inner| *inner)),
+            other => Err(Error::TypeMismatch("Variant", other.type_name())),
+        }
+    }
+
+    /// Pops the arguments of a call, along with the places that arguments passed to
+    /// `out` parameters have to be written back to.
+    fn pop_args(&mut self, count: usize) -> Result<(Vec<Value>, Vec<(usize, Place)>)> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(count)
+            .ok_or(Error::UnexpectedEnd)?;
+        let mut args = Vec::with_capacity(count);
+        let mut outs = vec![];
+        for (i, slot) in self.stack.split_off(start).into_iter().enumerate() {
+            match slot {
+                Slot::Value(value) => args.push(value),
+                Slot::Place(place) => {
+                    args.push(self.read(&place)?);
+                    outs.push((i, place));
+                }
+            }
+        }
+        Ok((args, outs))
+    }
+
+    fn read(&self, place: &Place) -> Result<Value> {
+        match place {
+            Place::Local(idx) => Ok(self.frame().locals.get(idx).cloned().unwrap_or_default()),
+            Place::Param(idx) => Ok(self.frame().params.get(idx).cloned().unwrap_or_default()),
+            Place::Field(obj, field) => Ok(obj.borrow().field(*field).cloned().unwrap_or_default()),
+            Place::StructField(inner, field) => match self.read(inner)? {
+                Value::Struct(struct_) => Ok(struct_.field(*field).cloned().unwrap_or_default()),
+                other => Err(Error::TypeMismatch("struct", other.type_name())),
+            },
+            Place::Element(inner, index) => {
+                let array = self.read(inner)?.into_array()?;
+                array
+                    .into_iter()
+                    .nth(*index)
+                    .ok_or(Error::IndexOutOfBounds(*index as i128))
+            }
+            Place::Value(value) => Ok(value.clone()),
+        }
+    }
+
+    fn write(&mut self, place: &Place, value: Value) -> Result<()> {
+        match place {
+            Place::Local(idx) => {
+                self.frame_mut().locals.insert(*idx, value);
+            }
+            Place::Param(idx) => {
+                self.frame_mut().params.insert(*idx, value);
+            }
+            Place::Field(obj, field) => obj.borrow_mut().set_field(*field, value),
+            Place::StructField(inner, field) => match self.read(inner)? {
+                Value::Struct(mut struct_) => {
+                    struct_.set_field(*field, value);
+                    self.write(inner, Value::Struct(struct_))?;
+                }
+                other => return Err(Error::TypeMismatch("struct", other.type_name())),
+            },
+            Place::Element(inner, index) => {
+                let mut array = self.read(inner)?.into_array()?;
+                let elem = array
+                    .get_mut(*index)
+                    .ok_or(Error::IndexOutOfBounds(*index as i128))?;
+                *elem = value;
+                self.write(inner, Value::Array(array))?;
+            }
+            // writes to temporaries are discarded
+            Place::Value(_) => {}
+        }
+        Ok(())
+    }
+
+    fn default_element(&self, array_type: TypeIndex) -> Result<Value> {
+        match self.bundle[array_type].kind() {
+            TypeKind::Array(elem)
+            | TypeKind::StaticArray {
+                element_type: elem, ..
+            } => self.default_value(elem),
+            _ => Ok(Value::Unit),
+        }
+    }
+
+    fn sort_by_predicate(
+        &mut self,
+        array: Vec<Value>,
+        predicate: &ObjectRef,
+    ) -> Result<Vec<Value>> {
+        let class = predicate.borrow().class();
+        let call = self
+            .resolve_call(class)
+            .ok_or_else(|| Error::UndefinedMethod("Call".to_owned()))?;
+
+        // insertion sort, since the predicate can fail
+        let mut sorted: Vec<Value> = Vec::with_capacity(array.len());
+        for elem in array {
+            let mut pos = sorted.len();
+            while pos > 0 {
+                let args = vec![elem.clone(), sorted[pos - 1].clone()];
+                let less = self.execute(call, Some(predicate.clone()), args)?;
+                if !less.as_bool()? {
+                    break;
+                }
+                pos -= 1;
+            }
+            sorted.insert(pos, elem);
+        }
+        Ok(sorted)
+    }
+
+    fn resolve_virtual(&self, class: ClassIndex, name: CNameIndex) -> Option<FunctionIndex> {
+        self.find_method(class, |idx| self.bundle[idx].name() == name)
+    }
+
+    fn resolve_call(&self, class: ClassIndex) -> Option<FunctionIndex> {
+        self.find_method(class, |idx| {
+            let name = &*self.bundle[self.bundle[idx].name()];
+            name.split_once(';').map_or(name, |(name, _)| name) == "Call"
+        })
+    }
+
+    fn find_method(
+        &self,
+        class: ClassIndex,
+        pred: impl Fn(FunctionIndex) -> bool,
+    ) -> Option<FunctionIndex> {
+        let mut current = Some(class);
+        while let Some(idx) = current {
+            let def = self.bundle.get_item(idx)?;
+            if let Some(&method) = def.methods().iter().find(|&&method| pred(method)) {
+                return Some(method);
+            }
+            current = def.base();
+        }
+        None
+    }
+
+    fn is_subclass(&self, class: ClassIndex, base: ClassIndex) -> bool {
+        let mut current = Some(class);
+        while let Some(idx) = current {
+            if idx == base {
+                return true;
+            }
+            current = self.bundle.get_item(idx).and_then(|def| def.base());
+        }
+        false
+    }
+
+    fn to_string(&self, value: &Value) -> String {
+        match value {
+            &Value::Enum(enum_, int) => self.bundle[enum_]
+                .values()
+                .iter()
+                .map(|&member| &self.bundle[member])
+                .find(|member| member.value() == int)
+                .map_or_else(
+                    || int.to_string(),
+                    |member| self.bundle[member.name()].to_string(),
+                ),
+            Value::Ref(obj) => self.bundle[self.bundle[obj.borrow().class()].name()].to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    code: Rc<Code>,
+    pc: usize,
+    /// The height of the value stack when the frame was entered.
+    base: usize,
+    this: Option<ObjectRef>,
+    params: HashMap<ParameterIndex, Value>,
+    locals: HashMap<LocalIndex, Value>,
+    /// Places in the caller that the `out` parameters are written back to on return.
+    outs: Vec<(ParameterIndex, Place)>,
+}
+
+/// An entry of the value stack.
+#[derive(Debug, Clone)]
+enum Slot {
+    Value(Value),
+    Place(Place),
+}
+
+/// A location that can be read from and written to.
+#[derive(Debug, Clone)]
+enum Place {
+    Local(LocalIndex),
+    Param(ParameterIndex),
+    Field(ObjectRef, FieldIndex),
+    StructField(Box<Place>, FieldIndex),
+    Element(Box<Place>, usize),
+    Value(Value),
+}
@@ -0,0 +1,50 @@
+pub use interpreter::Interpreter;
+pub use natives::{Natives, Operators};
+use redscript_io::{ClassIndex, FunctionIndex, TypeIndex};
+use thiserror::Error;
+pub use value::{Object, ObjectRef, Struct, Value};
+
+mod code;
+mod interpreter;
+mod natives;
+mod value;
+
+pub type Result<A, E = Error> = std::result::Result<A, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Function #{0} is not defined")]
+    UndefinedFunction(FunctionIndex),
+    #[error("Class #{0} is not defined")]
+    UndefinedClass(ClassIndex),
+    #[error("Type #{0} is not defined")]
+    UndefinedType(TypeIndex),
+    #[error("Method '{0}' could not be resolved")]
+    UndefinedMethod(String),
+    #[error("Native function '{0}' is not implemented")]
+    UnknownNative(String),
+    #[error("Type mismatch, expected {0} but found {1}")]
+    TypeMismatch(&'static str, &'static str),
+    #[error("Index {0} is out of bounds")]
+    IndexOutOfBounds(i128),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Null reference")]
+    NullReference,
+    #[error("Stack overflow")]
+    StackOverflow,
+    #[error("Invalid jump to offset {0}")]
+    InvalidJump(i64),
+    #[error("Unexpected end of function body")]
+    UnexpectedEnd,
+    #[error("Unexpected instruction '{0}'")]
+    UnexpectedInstr(String),
+    #[error("Read error: {0}")]
+    ReadFailure(redscript_io::byte::Error),
+}
+
+impl From<redscript_io::byte::Error> for Error {
+    fn from(err: redscript_io::byte::Error) -> Self {
+        Error::ReadFailure(err)
+    }
+}
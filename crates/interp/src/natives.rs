@@ -0,0 +1,171 @@
+use crate::{Error, Result, Value};
+
+/// A provider of native functions. The interpreter calls into it for every function
+/// marked as native in the bundle.
+pub trait Natives {
+    /// Invokes the native function `name`, which is the name of the function without
+    /// its signature suffix. Arguments passed to `out` parameters can be modified in
+    /// place and are written back to the caller afterwards.
+    ///
+    /// Implementations should return [`Error::UnknownNative`] for functions they do
+    /// not provide, which allows natives to be chained with a tuple.
+    fn call(&mut self, name: &str, args: &mut [Value]) -> Result<Value>;
+}
+
+impl<N: Natives + ?Sized> Natives for &mut N {
+    #[inline]
+    fn call(&mut self, name: &str, args: &mut [Value]) -> Result<Value> {
+        (**self).call(name, args)
+    }
+}
+
+impl<A: Natives, B: Natives> Natives for (A, B) {
+    fn call(&mut self, name: &str, args: &mut [Value]) -> Result<Value> {
+        match self.0.call(name, args) {
+            Err(Error::UnknownNative(_)) => self.1.call(name, args),
+            res => res,
+        }
+    }
+}
+
+/// Implementations of the operators that the game exposes as native functions, for
+/// all of the numeric types, booleans and strings.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Operators;
+
+impl Natives for Operators {
+    fn call(&mut self, name: &str, args: &mut [Value]) -> Result<Value> {
+        let res = match (name, &mut *args) {
+            ("OperatorAdd", [lhs, rhs]) => arithmetic(Arithmetic::Add, lhs, rhs)?,
+            ("OperatorSubtract", [lhs, rhs]) => arithmetic(Arithmetic::Sub, lhs, rhs)?,
+            ("OperatorMultiply", [lhs, rhs]) => arithmetic(Arithmetic::Mul, lhs, rhs)?,
+            ("OperatorDivide", [lhs, rhs]) => arithmetic(Arithmetic::Div, lhs, rhs)?,
+            ("OperatorModulo", [lhs, rhs]) => arithmetic(Arithmetic::Mod, lhs, rhs)?,
+            ("OperatorAnd", [lhs, rhs]) => bitwise(Bitwise::And, lhs, rhs),
+            ("OperatorOr", [lhs, rhs]) => bitwise(Bitwise::Or, lhs, rhs),
+            ("OperatorXor", [lhs, rhs]) => bitwise(Bitwise::Xor, lhs, rhs),
+            ("OperatorAssignAdd", [lhs, rhs]) => {
+                let res = arithmetic(Arithmetic::Add, lhs, rhs)?;
+                assign(lhs, res)
+            }
+            ("OperatorAssignSubtract", [lhs, rhs]) => {
+                let res = arithmetic(Arithmetic::Sub, lhs, rhs)?;
+                assign(lhs, res)
+            }
+            ("OperatorAssignMultiply", [lhs, rhs]) => {
+                let res = arithmetic(Arithmetic::Mul, lhs, rhs)?;
+                assign(lhs, res)
+            }
+            ("OperatorAssignDivide", [lhs, rhs]) => {
+                let res = arithmetic(Arithmetic::Div, lhs, rhs)?;
+                assign(lhs, res)
+            }
+            ("OperatorAssignAnd", [lhs, rhs]) => {
+                let res = bitwise(Bitwise::And, lhs, rhs);
+                assign(lhs, res)
+            }
+            ("OperatorAssignOr", [lhs, rhs]) => {
+                let res = bitwise(Bitwise::Or, lhs, rhs);
+                assign(lhs, res)
+            }
+            ("OperatorEqual", [lhs, rhs]) => Some(Value::Bool(lhs == rhs)),
+            ("OperatorNotEqual", [lhs, rhs]) => Some(Value::Bool(lhs != rhs)),
+            ("OperatorLess", [lhs, rhs]) => lhs.compare(rhs).map(|o| o.is_lt().into()),
+            ("OperatorLessEqual", [lhs, rhs]) => lhs.compare(rhs).map(|o| o.is_le().into()),
+            ("OperatorGreater", [lhs, rhs]) => lhs.compare(rhs).map(|o| o.is_gt().into()),
+            ("OperatorGreaterEqual", [lhs, rhs]) => lhs.compare(rhs).map(|o| o.is_ge().into()),
+            ("OperatorLogicAnd", [Value::Bool(lhs), Value::Bool(rhs)]) => {
+                Some(Value::Bool(*lhs && *rhs))
+            }
+            ("OperatorLogicOr", [Value::Bool(lhs), Value::Bool(rhs)]) => {
+                Some(Value::Bool(*lhs || *rhs))
+            }
+            ("OperatorLogicNot", [Value::Bool(val)]) => Some(Value::Bool(!*val)),
+            ("OperatorNeg", [val]) => match (val.as_integer(), val.as_float()) {
+                (Some(int), _) => val.with_integer(-int),
+                (_, Some(float)) => val.with_float(-float),
+                _ => None,
+            },
+            ("OperatorBitNot", [val]) => val.as_integer().and_then(|int| val.with_integer(!int)),
+            _ => None,
+        };
+        res.ok_or_else(|| Error::UnknownNative(name.to_owned()))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Bitwise {
+    And,
+    Or,
+    Xor,
+}
+
+fn arithmetic(op: Arithmetic, lhs: &Value, rhs: &Value) -> Result<Option<Value>> {
+    match (op, lhs, rhs) {
+        (Arithmetic::Add, Value::String(lhs), rhs) => {
+            return Ok(Some(format!("{lhs}{rhs}").into()));
+        }
+        (Arithmetic::Add, lhs, Value::String(rhs)) => {
+            return Ok(Some(format!("{lhs}{rhs}").into()));
+        }
+        _ if !lhs.is_same_type(rhs) => return Ok(None),
+        _ => {}
+    }
+
+    if let (Some(a), Some(b)) = (lhs.as_integer(), rhs.as_integer()) {
+        let res = match op {
+            Arithmetic::Add => a + b,
+            Arithmetic::Sub => a - b,
+            Arithmetic::Mul => a.wrapping_mul(b),
+            Arithmetic::Div | Arithmetic::Mod if b == 0 => return Err(Error::DivisionByZero),
+            Arithmetic::Div => a / b,
+            Arithmetic::Mod => a % b,
+        };
+        return Ok(lhs.with_integer(res));
+    }
+
+    let (Some(a), Some(b)) = (lhs.as_float(), rhs.as_float()) else {
+        return Ok(None);
+    };
+    let res = match op {
+        Arithmetic::Add => a + b,
+        Arithmetic::Sub => a - b,
+        Arithmetic::Mul => a * b,
+        Arithmetic::Div => a / b,
+        Arithmetic::Mod => a % b,
+    };
+    Ok(lhs.with_float(res))
+}
+
+fn bitwise(op: Bitwise, lhs: &Value, rhs: &Value) -> Option<Value> {
+    match (op, lhs, rhs) {
+        (Bitwise::And, Value::Bool(a), Value::Bool(b)) => return Some(Value::Bool(a & b)),
+        (Bitwise::Or, Value::Bool(a), Value::Bool(b)) => return Some(Value::Bool(a | b)),
+        (Bitwise::Xor, Value::Bool(a), Value::Bool(b)) => return Some(Value::Bool(a ^ b)),
+        _ if !lhs.is_same_type(rhs) => return None,
+        _ => {}
+    }
+
+    let (a, b) = (lhs.as_integer()?, rhs.as_integer()?);
+    let res = match op {
+        Bitwise::And => a & b,
+        Bitwise::Or => a | b,
+        Bitwise::Xor => a ^ b,
+    };
+    lhs.with_integer(res)
+}
+
+fn assign(lhs: &mut Value, res: Option<Value>) -> Option<Value> {
+    let res = res?;
+    *lhs = res.clone();
+    Some(res)
+}
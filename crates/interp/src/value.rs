@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use hashbrown::HashMap;
+use redscript_io::{ClassIndex, EnumIndex, FieldIndex, TypeIndex};
+
+use crate::{Error, Result};
+
+pub type ObjectRef = Rc<RefCell<Object>>;
+
+/// A runtime value. Arrays, structs and strings have value semantics, while objects
+/// are shared through reference counted pointers.
+#[derive(Debug, Clone, Default)]
+pub enum Value {
+    #[default]
+    Unit,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    CName(String),
+    TweakDbId(String),
+    Resource(String),
+    Enum(EnumIndex, i64),
+    Null,
+    Ref(ObjectRef),
+    WeakRef(Weak<RefCell<Object>>),
+    Struct(Struct),
+    Array(Vec<Value>),
+    Variant(Option<Box<(TypeIndex, Value)>>),
+}
+
+impl Value {
+    #[inline]
+    pub fn string(str: impl Into<String>) -> Self {
+        Self::String(str.into())
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        match self {
+            &Self::Bool(b) => Ok(b),
+            _ => Err(Error::TypeMismatch("Bool", self.type_name())),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str> {
+        match self {
+            Self::String(str) | Self::CName(str) | Self::TweakDbId(str) | Self::Resource(str) => {
+                Ok(str)
+            }
+            _ => Err(Error::TypeMismatch("String", self.type_name())),
+        }
+    }
+
+    pub fn as_object(&self) -> Result<Option<ObjectRef>> {
+        match self {
+            Self::Ref(obj) => Ok(Some(obj.clone())),
+            Self::WeakRef(obj) => Ok(obj.upgrade()),
+            Self::Null => Ok(None),
+            _ => Err(Error::TypeMismatch("ref", self.type_name())),
+        }
+    }
+
+    pub fn into_array(self) -> Result<Vec<Value>> {
+        match self {
+            Self::Array(array) => Ok(array),
+            _ => Err(Error::TypeMismatch("array", self.type_name())),
+        }
+    }
+
+    pub(crate) fn as_index(&self) -> Result<usize> {
+        let int = self
+            .as_integer()
+            .ok_or_else(|| Error::TypeMismatch("integer", self.type_name()))?;
+        usize::try_from(int).map_err(|_| Error::IndexOutOfBounds(int))
+    }
+
+    /// Returns the value of any integer or enum as a wide integer.
+    pub(crate) fn as_integer(&self) -> Option<i128> {
+        match *self {
+            Self::I8(i) => Some(i.into()),
+            Self::I16(i) => Some(i.into()),
+            Self::I32(i) => Some(i.into()),
+            Self::I64(i) | Self::Enum(_, i) => Some(i.into()),
+            Self::U8(i) => Some(i.into()),
+            Self::U16(i) => Some(i.into()),
+            Self::U32(i) => Some(i.into()),
+            Self::U64(i) => Some(i.into()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_float(&self) -> Option<f64> {
+        match *self {
+            Self::F32(f) => Some(f.into()),
+            Self::F64(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Creates an integer of the same type as `self`, wrapping around on overflow.
+    pub(crate) fn with_integer(&self, int: i128) -> Option<Self> {
+        let res = match self {
+            Self::I8(_) => Self::I8(int as i8),
+            Self::I16(_) => Self::I16(int as i16),
+            Self::I32(_) => Self::I32(int as i32),
+            Self::I64(_) => Self::I64(int as i64),
+            Self::U8(_) => Self::U8(int as u8),
+            Self::U16(_) => Self::U16(int as u16),
+            Self::U32(_) => Self::U32(int as u32),
+            Self::U64(_) => Self::U64(int as u64),
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Creates a float of the same type as `self`.
+    pub(crate) fn with_float(&self, float: f64) -> Option<Self> {
+        match self {
+            Self::F32(_) => Some(Self::F32(float as f32)),
+            Self::F64(_) => Some(Self::F64(float)),
+            _ => None,
+        }
+    }
+
+    /// Compares two values of the same primitive type.
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => Some(a.cmp(b)),
+            (Self::String(a), Self::String(b))
+            | (Self::CName(a), Self::CName(b))
+            | (Self::TweakDbId(a), Self::TweakDbId(b))
+            | (Self::Resource(a), Self::Resource(b)) => Some(a.cmp(b)),
+            _ if !self.is_same_type(other) => None,
+            _ => match (self.as_integer(), other.as_integer()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => self.as_float()?.partial_cmp(&other.as_float()?),
+            },
+        }
+    }
+
+    pub(crate) fn is_same_type(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Unit => "Void",
+            Self::Bool(_) => "Bool",
+            Self::I8(_) => "Int8",
+            Self::I16(_) => "Int16",
+            Self::I32(_) => "Int32",
+            Self::I64(_) => "Int64",
+            Self::U8(_) => "Uint8",
+            Self::U16(_) => "Uint16",
+            Self::U32(_) => "Uint32",
+            Self::U64(_) => "Uint64",
+            Self::F32(_) => "Float",
+            Self::F64(_) => "Double",
+            Self::String(_) => "String",
+            Self::CName(_) => "CName",
+            Self::TweakDbId(_) => "TweakDBID",
+            Self::Resource(_) => "ResRef",
+            Self::Enum(_, _) => "enum",
+            Self::Null | Self::Ref(_) => "ref",
+            Self::WeakRef(_) => "wref",
+            Self::Struct(_) => "struct",
+            Self::Array(_) => "array",
+            Self::Variant(_) => "Variant",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unit, Self::Unit) | (Self::Null, Self::Null) => true,
+            (Self::Ref(a), Self::Ref(b)) => Rc::ptr_eq(a, b),
+            (Self::WeakRef(a), Self::WeakRef(b)) => a.ptr_eq(b),
+            (Self::Enum(a, x), Self::Enum(b, y)) => a == b && x == y,
+            (Self::Struct(a), Self::Struct(b)) => a == b,
+            (Self::Array(a), Self::Array(b)) => a == b,
+            (Self::Variant(a), Self::Variant(b)) => a == b,
+            _ => self.compare(other) == Some(Ordering::Equal),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    #[inline]
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Self::I32(value)
+    }
+}
+
+impl From<f32> for Value {
+    #[inline]
+    fn from(value: f32) -> Self {
+        Self::F32(value)
+    }
+}
+
+impl From<String> for Value {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => Ok(()),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::I8(i) => write!(f, "{i}"),
+            Self::I16(i) => write!(f, "{i}"),
+            Self::I32(i) => write!(f, "{i}"),
+            Self::I64(i) | Self::Enum(_, i) => write!(f, "{i}"),
+            Self::U8(i) => write!(f, "{i}"),
+            Self::U16(i) => write!(f, "{i}"),
+            Self::U32(i) => write!(f, "{i}"),
+            Self::U64(i) => write!(f, "{i}"),
+            Self::F32(x) => write!(f, "{x}"),
+            Self::F64(x) => write!(f, "{x}"),
+            Self::String(str) | Self::CName(str) | Self::TweakDbId(str) | Self::Resource(str) => {
+                f.write_str(str)
+            }
+            Self::Null => f.write_str("null"),
+            Self::Ref(_) => f.write_str("ref"),
+            Self::WeakRef(_) => f.write_str("wref"),
+            Self::Struct(_) => f.write_str("struct"),
+            Self::Array(elems) => {
+                f.write_str("[")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{elem}")?;
+                }
+                f.write_str("]")
+            }
+            Self::Variant(Some(inner)) => write!(f, "{}", inner.1),
+            Self::Variant(None) => f.write_str("None"),
+        }
+    }
+}
+
+/// An instance of a scripted or native class.
+#[derive(Debug)]
+pub struct Object {
+    class: ClassIndex,
+    fields: HashMap<FieldIndex, Value>,
+}
+
+impl Object {
+    #[inline]
+    pub fn new(class: ClassIndex, fields: HashMap<FieldIndex, Value>) -> Self {
+        Self { class, fields }
+    }
+
+    #[inline]
+    pub fn class(&self) -> ClassIndex {
+        self.class
+    }
+
+    #[inline]
+    pub fn field(&self, field: FieldIndex) -> Option<&Value> {
+        self.fields.get(&field)
+    }
+
+    #[inline]
+    pub fn set_field(&mut self, field: FieldIndex, value: Value) {
+        self.fields.insert(field, value);
+    }
+}
+
+/// An instance of a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    class: ClassIndex,
+    fields: HashMap<FieldIndex, Value>,
+}
+
+impl Struct {
+    #[inline]
+    pub fn new(class: ClassIndex, fields: HashMap<FieldIndex, Value>) -> Self {
+        Self { class, fields }
+    }
+
+    #[inline]
+    pub fn class(&self) -> ClassIndex {
+        self.class
+    }
+
+    #[inline]
+    pub fn field(&self, field: FieldIndex) -> Option<&Value> {
+        self.fields.get(&field)
+    }
+
+    #[inline]
+    pub fn set_field(&mut self, field: FieldIndex, value: Value) {
+        self.fields.insert(field, value);
+    }
+}
@@ -0,0 +1,253 @@
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Compilation, SourceMapExt, TypeInterner};
+use redscript_interp::{Error, Interpreter, Natives, Operators, Value};
+use redscript_io::{Class, ClassFlags, ScriptBundle, Visibility};
+
+const SOURCE: &str = r#"
+native func OperatorAdd(a: Int32, b: Int32) -> Int32
+native func OperatorSubtract(a: Int32, b: Int32) -> Int32
+native func OperatorMultiply(a: Int32, b: Int32) -> Int32
+native func OperatorLess(a: Int32, b: Int32) -> Bool
+native func OperatorLessEqual(a: Int32, b: Int32) -> Bool
+native func Record(value: Int32)
+
+func Factorial(n: Int32) -> Int32 {
+  let res = 1;
+  let i = 1;
+  while i <= n {
+    res = res * i;
+    i = i + 1;
+  }
+  return res;
+}
+
+func Countdown(n: Int32) {
+  while 0 < n {
+    Record(n);
+    n = n - 1;
+  }
+}
+
+abstract class Shape {
+  func Area() -> Int32 {
+    return 0;
+  }
+}
+
+class Square extends Shape {
+  let side: Int32;
+
+  func Area() -> Int32 {
+    return this.side * this.side;
+  }
+}
+
+func SquareArea(side: Int32) -> Int32 {
+  let square = new Square();
+  square.side = side;
+  let shape: ref<Shape> = square;
+  return shape.Area();
+}
+
+struct Point {
+  let x: Int32;
+  let y: Int32;
+}
+
+func SumPoints() -> Int32 {
+  let points: array<Point>;
+  ArrayPush(points, new Point(1, 2));
+  ArrayPush(points, new Point(3, 4));
+  points[0].x = 10;
+
+  let sum = 0;
+  let i = 0;
+  while i < ArraySize(points) {
+    sum = sum + points[i].x + points[i].y;
+    i = i + 1;
+  }
+  return sum;
+}
+
+func ApplyClosure(x: Int32) -> Int32 {
+  let offset = 2;
+  let f = (y: Int32) -> y * offset;
+  return f(x);
+}
+
+func Classify(n: Int32) -> Int32 {
+  switch n {
+    case 0:
+    case 1:
+      return 10;
+    case 2:
+      n = 5;
+    default:
+      n = n + 1;
+  }
+  return n;
+}
+
+func SumTo(n: Int32) -> Int32 {
+  if n <= 0 {
+    return 0;
+  }
+  return n + SumTo(n - 1);
+}
+
+func Increment(out x: Int32) {
+  x = x + 1;
+}
+
+func IncrementTwice() -> Int32 {
+  let x = 1;
+  Increment(x);
+  Increment(x);
+  return x;
+}
+
+func CallUndefined() -> Int32 {
+  return Undefined();
+}
+
+native func Undefined() -> Int32
+"#;
+
+#[test]
+fn loops_and_locals() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("Factorial;Int32").unwrap();
+    let res = interp.call(func, vec![Value::I32(5)]).unwrap();
+    assert_eq!(res, Value::I32(120));
+}
+
+#[test]
+fn stubbed_natives() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, (Recorder::default(), Operators));
+
+    let func = interp.function("Countdown;Int32").unwrap();
+    interp.call(func, vec![Value::I32(3)]).unwrap();
+    assert_eq!(
+        interp.natives().0.values,
+        [Value::I32(3), Value::I32(2), Value::I32(1)]
+    );
+
+    let func = interp.function("CallUndefined;").unwrap();
+    let err = interp.call(func, vec![]).unwrap_err();
+    assert!(matches!(err, Error::UnknownNative(name) if name == "Undefined"));
+}
+
+#[test]
+fn virtual_methods() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("SquareArea;Int32").unwrap();
+    let res = interp.call(func, vec![Value::I32(4)]).unwrap();
+    assert_eq!(res, Value::I32(16));
+}
+
+#[test]
+fn structs_and_arrays() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("SumPoints;").unwrap();
+    let res = interp.call(func, vec![]).unwrap();
+    assert_eq!(res, Value::I32(19));
+}
+
+#[test]
+fn closures() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("ApplyClosure;Int32").unwrap();
+    let res = interp.call(func, vec![Value::I32(21)]).unwrap();
+    assert_eq!(res, Value::I32(42));
+}
+
+#[test]
+fn switches() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("Classify;Int32").unwrap();
+    for (input, expected) in [(0, 10), (1, 10), (2, 6), (7, 8)] {
+        let res = interp.call(func, vec![Value::I32(input)]).unwrap();
+        assert_eq!(res, Value::I32(expected), "Classify({input})");
+    }
+}
+
+#[test]
+fn recursion() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("SumTo;Int32").unwrap();
+    let res = interp.call(func, vec![Value::I32(100)]).unwrap();
+    assert_eq!(res, Value::I32(5050));
+
+    let err = interp.call(func, vec![Value::I32(1000)]).unwrap_err();
+    assert!(matches!(err, Error::StackOverflow));
+
+    // the interpreter is still usable after a failed call
+    let res = interp.call(func, vec![Value::I32(3)]).unwrap();
+    assert_eq!(res, Value::I32(6));
+}
+
+#[test]
+fn out_params() {
+    let bytes = compile(SOURCE);
+    let bundle = ScriptBundle::from_bytes(&bytes).unwrap();
+    let mut interp = Interpreter::new(&bundle, Operators);
+
+    let func = interp.function("IncrementTwice;").unwrap();
+    let res = interp.call(func, vec![]).unwrap();
+    assert_eq!(res, Value::I32(3));
+}
+
+#[derive(Debug, Default)]
+struct Recorder {
+    values: Vec<Value>,
+}
+
+impl Natives for Recorder {
+    fn call(&mut self, name: &str, args: &mut [Value]) -> redscript_interp::Result<Value> {
+        match (name, args) {
+            ("Record", [value]) => {
+                self.values.push(value.clone());
+                Ok(Value::Unit)
+            }
+            _ => Err(Error::UnknownNative(name.to_owned())),
+        }
+    }
+}
+
+fn compile(source: &str) -> Vec<u8> {
+    let sources = SourceMap::new();
+    sources.push_back("test.reds", source);
+    sources.populate_boot_lib();
+
+    let interner = TypeInterner::default();
+    let mut bundle = ScriptBundle::default();
+    let name = bundle.cnames_mut().add("IScriptable");
+    bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+    let bytes = bundle.into_writeable().to_bytes().unwrap();
+
+    let (bundle, _, _) = Compilation::new(&bytes, &sources, &interner)
+        .unwrap()
+        .finish()
+        .unwrap();
+    bundle.into_writeable().to_bytes().unwrap()
+}
@@ -165,6 +165,26 @@ impl<'i> ScriptBundle<'i> {
         }
     }
 
+    #[inline]
+    pub fn cnames(&self) -> &StringPool<'i, index::types::CName> {
+        &self.cnames
+    }
+
+    #[inline]
+    pub fn tdb_ids(&self) -> &StringPool<'i, index::types::TweakDbId> {
+        &self.tdb_ids
+    }
+
+    #[inline]
+    pub fn resources(&self) -> &StringPool<'i, index::types::Resource> {
+        &self.resources
+    }
+
+    #[inline]
+    pub fn strings(&self) -> &StringPool<'i, index::types::String> {
+        &self.strings
+    }
+
     #[inline]
     pub fn cnames_mut(&mut self) -> &mut StringPool<'i, index::types::CName> {
         &mut self.cnames
@@ -676,6 +676,12 @@ impl<'i> Function<'i> {
         self
     }
 
+    #[inline]
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
     #[inline]
     pub fn with_flags(mut self, flags: FunctionFlags) -> Self {
         self.flags = flags;
@@ -701,14 +707,16 @@ impl<'i> Function<'i> {
     #[inline]
     pub fn with_return_type(mut self, return_type: Option<TypeIndex>) -> Self {
         self.return_type = return_type;
+        self.is_const_return = false;
         self.flags.set_has_return_value(return_type.is_some());
         self
     }
 
     #[inline]
-    pub fn with_const_return_type(mut self, return_type: TypeIndex) -> Self {
-        self.is_const_return = true;
-        self.with_return_type(Some(return_type))
+    pub fn with_const_return_type(self, return_type: TypeIndex) -> Self {
+        let mut this = self.with_return_type(Some(return_type));
+        this.is_const_return = true;
+        this
     }
 
     #[inline]
@@ -130,7 +130,7 @@ impl<A> NzPoolIndex<A> {
     pub(crate) const ONE: Self = Self(NonZeroU32::MIN, PhantomData);
 
     #[inline]
-    pub(crate) const fn new(index: u32) -> Option<Self> {
+    pub const fn new(index: u32) -> Option<Self> {
         match NonZeroU32::new(index) {
             Some(index) => Some(NzPoolIndex(index, PhantomData)),
             None => None,
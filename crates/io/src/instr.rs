@@ -769,6 +769,56 @@ pub struct Breakpoint {
     padding: [u8; 8],
 }
 
+impl Breakpoint {
+    #[inline]
+    pub fn new(line: u16, line_start: u32, col: u16, length: u16, enabled: bool) -> Self {
+        Self {
+            line,
+            line_start,
+            col,
+            length,
+            enabled,
+            padding: [0; 8],
+        }
+    }
+
+    #[inline]
+    pub fn line(&self) -> u16 {
+        self.line
+    }
+
+    #[inline]
+    pub fn line_start(&self) -> u32 {
+        self.line_start
+    }
+
+    #[inline]
+    pub fn col(&self) -> u16 {
+        self.col
+    }
+
+    #[inline]
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn padding(&self) -> [u8; 8] {
+        self.padding
+    }
+
+    #[inline]
+    pub fn with_padding(mut self, padding: [u8; 8]) -> Self {
+        self.padding = padding;
+        self
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, TryRead, TryWrite, Measure)]
 pub struct Profile {
     #[byte(ctx = Prefixed(ctx))]
@@ -776,6 +826,26 @@ pub struct Profile {
     enabled: bool,
 }
 
+impl Profile {
+    #[inline]
+    pub fn new(function: impl Into<Vec<u8>>, enabled: bool) -> Self {
+        Self {
+            function: function.into(),
+            enabled,
+        }
+    }
+
+    #[inline]
+    pub fn function(&self) -> &[u8] {
+        &self.function
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TryRead, TryWrite, Measure)]
 pub struct Offset {
     value: i16,
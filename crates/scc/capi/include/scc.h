@@ -13,6 +13,7 @@ typedef struct SccSettings SccSettings;
 typedef struct SccOutput SccOutput;
 typedef struct SccResult SccResult;
 typedef struct SccSourceRef SccSourceRef;
+typedef struct SccDiagnostic SccDiagnostic;
 
 typedef struct StrWithLen {
   const char *str;
@@ -27,6 +28,17 @@ enum SccSourceRefType {
   SCC_SOURCE_REF_TYPE_ENUM = 4,
 };
 
+enum SccSourceRefAnnotation {
+  SCC_SOURCE_REF_ANNOTATION_NONE = 0,
+  SCC_SOURCE_REF_ANNOTATION_ADD_METHOD = 1,
+  SCC_SOURCE_REF_ANNOTATION_ADD_FIELD = 2,
+};
+
+enum SccDiagnosticSeverity {
+  SCC_DIAGNOSTIC_SEVERITY_WARNING = 0,
+  SCC_DIAGNOSTIC_SEVERITY_ERROR = 1,
+};
+
 typedef SccSettings *scc_settings_new(const char *r6_dir);
 
 typedef void scc_settings_set_custom_cache_file(SccSettings *settings,
@@ -40,6 +52,58 @@ typedef void scc_settings_add_script_path(SccSettings *settings,
 
 typedef void scc_settings_disable_error_popup(SccSettings *settings);
 
+typedef void scc_settings_add_define(SccSettings *settings, const char *define);
+
+typedef void scc_settings_enable_optimizations(SccSettings *settings);
+
+typedef void scc_settings_set_thread_count(SccSettings *settings, size_t count);
+
+typedef void scc_settings_enable_safe_mode(SccSettings *settings);
+
+typedef SccDiagnostic *scc_result_get_diagnostic(SccResult *result,
+                                                 size_t index);
+
+typedef size_t scc_result_diagnostic_count(SccResult *result);
+
+typedef uint8_t scc_diagnostic_severity(SccResult *result,
+                                        SccDiagnostic *diagnostic);
+
+typedef StrWithLen scc_diagnostic_code(SccResult *result,
+                                       SccDiagnostic *diagnostic);
+
+typedef StrWithLen scc_diagnostic_message(SccResult *result,
+                                          SccDiagnostic *diagnostic);
+
+typedef StrWithLen scc_diagnostic_path(SccResult *result,
+                                       SccDiagnostic *diagnostic);
+
+typedef size_t scc_diagnostic_line(SccResult *result,
+                                   SccDiagnostic *diagnostic);
+
+typedef size_t scc_diagnostic_column(SccResult *result,
+                                     SccDiagnostic *diagnostic);
+
+typedef size_t scc_diagnostic_end_line(SccResult *result,
+                                       SccDiagnostic *diagnostic);
+
+typedef size_t scc_diagnostic_end_column(SccResult *result,
+                                         SccDiagnostic *diagnostic);
+
+typedef StrWithLen scc_diagnostic_hint(SccResult *result,
+                                       SccDiagnostic *diagnostic);
+
+typedef size_t scc_source_ref_column(SccOutput *output, SccSourceRef *ref);
+
+typedef uint8_t scc_source_ref_annotation(SccOutput *output,
+                                          SccSourceRef *ref);
+
+typedef void scc_settings_enable_breakpoints(SccSettings *settings);
+
+typedef void scc_settings_enable_profiling(SccSettings *settings);
+
+typedef void scc_settings_set_game_version(SccSettings *settings,
+                                           const char *version);
+
 typedef SccResult *scc_compile(SccSettings *settings);
 
 typedef void scc_free_result(SccResult *result);
@@ -118,7 +182,9 @@ typedef struct SccApi {
    */
   scc_output_get_source_ref *output_get_source_ref;
   /**
-   * Returns the total number of source references in the output.
+   * Returns the total number of source references in the output. Since
+   * redscript 1.0.0 this includes the entities defined in scripts, earlier
+   * versions only reference the native ones.
    */
   scc_output_source_ref_count *output_source_ref_count;
   /**
@@ -126,7 +192,8 @@ typedef struct SccApi {
    */
   scc_source_ref_type *source_ref_type;
   /**
-   * Returns whether the entity behind the reference is native.
+   * Returns whether the entity behind the reference is native. References to
+   * scripted entities are only available since redscript 1.0.0.
    */
   scc_source_ref_is_native *source_ref_is_native;
   /**
@@ -147,6 +214,181 @@ typedef struct SccApi {
    * is defined.
    */
   scc_source_ref_line *source_ref_line;
+  /**
+   * Defines a name for conditional compilation, written as NAME or NAME=value.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_add_define *settings_add_define;
+  /**
+   * Enables the optimization of the generated bytecode.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_enable_optimizations *settings_enable_optimizations;
+  /**
+   * Sets the number of threads used for compilation, zero means one thread per
+   * logical CPU. Only parsing is spread across the threads, type checking and
+   * code generation always run on a single thread.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_set_thread_count *settings_set_thread_count;
+  /**
+   * Enables the safe mode, in which mods with fatal errors are disabled and the
+   * rest of the scripts are compiled without them.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_enable_safe_mode *settings_enable_safe_mode;
+  /**
+   * Returns the diagnostic at the given index. Diagnostics are available for
+   * both successful and failed compilations. Returns null if the index is out
+   * of bounds.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_result_get_diagnostic *result_get_diagnostic;
+  /**
+   * Returns the total number of diagnostics in the result.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_result_diagnostic_count *result_diagnostic_count;
+  /**
+   * Returns the severity of the diagnostic, one of SccDiagnosticSeverity.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_severity *diagnostic_severity;
+  /**
+   * Returns the code of the diagnostic, e.g. UNUSED_LOCAL.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_code *diagnostic_code;
+  /**
+   * Returns the message of the diagnostic.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_message *diagnostic_message;
+  /**
+   * Returns the path to the file the diagnostic was reported in.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_path *diagnostic_path;
+  /**
+   * Returns the 1-based line where the diagnostic starts.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_line *diagnostic_line;
+  /**
+   * Returns the 1-based column where the diagnostic starts.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_column *diagnostic_column;
+  /**
+   * Returns the 1-based line where the diagnostic ends.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_end_line *diagnostic_end_line;
+  /**
+   * Returns the 1-based column where the diagnostic ends.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_end_column *diagnostic_end_column;
+  /**
+   * Returns the message of a user hint that matched the diagnostic, or a null
+   * string if there is none.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_diagnostic_hint *diagnostic_hint;
+  /**
+   * Returns the zero-based column in the source code where the entity behind
+   * the reference is defined.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_source_ref_column *source_ref_column;
+  /**
+   * Returns the annotation that the entity behind the reference was added
+   * with, one of SccSourceRefAnnotation.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_source_ref_annotation *source_ref_annotation;
+  /**
+   * Enables the emission of breakpoint instructions, which are used by script
+   * debuggers.
+   *
+   * Breakpoints are disabled in newly created settings. The scc executable
+   * calls this function unless it's invoked with -no-breakpoint, so callers
+   * that want the same behavior have to call it themselves.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_enable_breakpoints *settings_enable_breakpoints;
+  /**
+   * Enables the emission of profiling markers around function bodies, tagged
+   * with the mangled function names, for the in-game profiler.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_enable_profiling *settings_enable_profiling;
+  /**
+   * Sets the version of the game the scripts are compiled against, e.g.
+   * "2.12a". It's returned by GameVersion() in conditional compilation.
+   *
+   * Added in redscript 1.0.0. It will be null if the loaded library version is
+   * older. The caller should do a null check if they want to maintain backward
+   * compatibility.
+   */
+  scc_settings_set_game_version *settings_set_game_version;
 } SccApi;
 
 #if defined(_WIN32) && !defined(BINDING_TEST)
@@ -178,6 +420,34 @@ inline SccApi scc_load_api(HMODULE module) {
           module, "scc_source_ref_parent_name"),
       (scc_source_ref_path *)GetProcAddress(module, "scc_source_ref_path"),
       (scc_source_ref_line *)GetProcAddress(module, "scc_source_ref_line"),
+      (scc_settings_add_define *)GetProcAddress(module,
+                                                "scc_settings_add_define"),
+      (scc_settings_enable_optimizations *)GetProcAddress(
+          module, "scc_settings_enable_optimizations"),
+      (scc_settings_set_thread_count *)GetProcAddress(
+          module, "scc_settings_set_thread_count"),
+      (scc_settings_enable_safe_mode *)GetProcAddress(
+          module, "scc_settings_enable_safe_mode"),
+      (scc_result_get_diagnostic *)GetProcAddress(module, "scc_result_get_diagnostic"),
+      (scc_result_diagnostic_count *)GetProcAddress(module, "scc_result_diagnostic_count"),
+      (scc_diagnostic_severity *)GetProcAddress(module, "scc_diagnostic_severity"),
+      (scc_diagnostic_code *)GetProcAddress(module, "scc_diagnostic_code"),
+      (scc_diagnostic_message *)GetProcAddress(module, "scc_diagnostic_message"),
+      (scc_diagnostic_path *)GetProcAddress(module, "scc_diagnostic_path"),
+      (scc_diagnostic_line *)GetProcAddress(module, "scc_diagnostic_line"),
+      (scc_diagnostic_column *)GetProcAddress(module, "scc_diagnostic_column"),
+      (scc_diagnostic_end_line *)GetProcAddress(module, "scc_diagnostic_end_line"),
+      (scc_diagnostic_end_column *)GetProcAddress(module, "scc_diagnostic_end_column"),
+      (scc_diagnostic_hint *)GetProcAddress(module, "scc_diagnostic_hint"),
+      (scc_source_ref_column *)GetProcAddress(module, "scc_source_ref_column"),
+      (scc_source_ref_annotation *)GetProcAddress(module,
+                                                  "scc_source_ref_annotation"),
+      (scc_settings_enable_breakpoints *)GetProcAddress(
+          module, "scc_settings_enable_breakpoints"),
+      (scc_settings_enable_profiling *)GetProcAddress(
+          module, "scc_settings_enable_profiling"),
+      (scc_settings_set_game_version *)GetProcAddress(
+          module, "scc_settings_set_game_version"),
   };
   return api;
 }
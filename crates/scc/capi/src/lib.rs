@@ -4,7 +4,10 @@ use std::path::{Path, PathBuf};
 use std::ptr;
 
 use mimalloc::MiMalloc;
-use scc_shared::{SccOutput, SccSettings, SourceRef, SourceRefType};
+use scc_shared::{
+    CompilationFailure, DiagnosticSeverity, SccDiagnostic, SccOutput, SccSettings, SourceRef,
+    SourceRefAnnotation, SourceRefType,
+};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -15,7 +18,7 @@ static GLOBAL: MiMalloc = MiMalloc;
 pub unsafe extern "C" fn scc_settings_new(r6_dir: *const i8) -> Box<SccSettings> {
     let r6_dir = unsafe { c_path(r6_dir) };
     let root_dir = r6_dir.parent().map(Path::to_owned).unwrap_or(r6_dir);
-    Box::new(SccSettings::new(root_dir, None, None, vec![], true))
+    Box::new(SccSettings::new(root_dir, None, None, vec![], vec![], true))
 }
 
 /// # Safety
@@ -48,11 +51,57 @@ pub unsafe extern "C" fn scc_settings_add_script_path(settings: &mut SccSettings
     settings.add_script_path(unsafe { c_path(path) });
 }
 
+/// # Safety
+/// The caller must ensure that `settings` is a valid pointer to a `SccSettings` struct and
+/// `define` is a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn scc_settings_add_define(settings: &mut SccSettings, define: *const i8) {
+    let cstr = unsafe { CStr::from_ptr(define) };
+    settings.add_define(cstr.to_string_lossy().into_owned());
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn scc_settings_disable_error_popup(settings: &mut SccSettings) {
     settings.set_show_error_report(false);
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_settings_enable_optimizations(settings: &mut SccSettings) {
+    settings.set_optimize(true);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_settings_set_thread_count(settings: &mut SccSettings, count: usize) {
+    settings.set_thread_count(count);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_settings_enable_safe_mode(settings: &mut SccSettings) {
+    settings.set_safe_mode(true);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_settings_enable_breakpoints(settings: &mut SccSettings) {
+    settings.set_breakpoints(true);
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_settings_enable_profiling(settings: &mut SccSettings) {
+    settings.set_profile(true);
+}
+
+/// # Safety
+/// The caller must ensure that `settings` is a valid pointer to a `SccSettings` struct and
+/// `version` is a valid null-terminated UTF-8 string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn scc_settings_set_game_version(
+    settings: &mut SccSettings,
+    version: *const i8,
+) {
+    let cstr = unsafe { CStr::from_ptr(version) };
+    settings.set_game_version(cstr.to_string_lossy().into_owned());
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn scc_compile(settings: Box<SccSettings>) -> Box<SccResult> {
     let res = match scc_shared::compile(&settings) {
@@ -112,8 +161,8 @@ pub extern "C" fn scc_source_ref_type(_output: &SccOutput, link: &SourceRef) ->
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn scc_source_ref_is_native(_output: &SccOutput, _link: &SourceRef) -> bool {
-    true
+pub extern "C" fn scc_source_ref_is_native(_output: &SccOutput, link: &SourceRef) -> bool {
+    link.is_native()
 }
 
 #[unsafe(no_mangle)]
@@ -149,6 +198,98 @@ pub extern "C" fn scc_source_ref_line(output: &SccOutput, link: &SourceRef) -> u
     output.line(link).unwrap_or(0)
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_source_ref_column(output: &SccOutput, link: &SourceRef) -> usize {
+    output.column(link).unwrap_or(0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_source_ref_annotation(
+    _output: &SccOutput,
+    link: &SourceRef,
+) -> SourceRefAnnotation {
+    link.annotation()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_result_get_diagnostic(result: &SccResult, i: usize) -> *const SccDiagnostic {
+    result
+        .diagnostics()
+        .get(i)
+        .map_or(ptr::null(), ptr::from_ref)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_result_diagnostic_count(result: &SccResult) -> usize {
+    result.diagnostics().len()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_severity(
+    _result: &SccResult,
+    diagnostic: &SccDiagnostic,
+) -> DiagnosticSeverity {
+    diagnostic.severity()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_code<'a>(
+    _result: &'a SccResult,
+    diagnostic: &'a SccDiagnostic,
+) -> StrWithLen<'a> {
+    diagnostic.code().into()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_message<'a>(
+    _result: &'a SccResult,
+    diagnostic: &'a SccDiagnostic,
+) -> StrWithLen<'a> {
+    diagnostic.message().into()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_path<'a>(
+    _result: &'a SccResult,
+    diagnostic: &'a SccDiagnostic,
+) -> StrWithLen<'a> {
+    diagnostic.path().to_str().unwrap_or_default().into()
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_line(_result: &SccResult, diagnostic: &SccDiagnostic) -> usize {
+    diagnostic.start().line
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_column(_result: &SccResult, diagnostic: &SccDiagnostic) -> usize {
+    diagnostic.start().column
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_end_line(
+    _result: &SccResult,
+    diagnostic: &SccDiagnostic,
+) -> usize {
+    diagnostic.end().line
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_end_column(
+    _result: &SccResult,
+    diagnostic: &SccDiagnostic,
+) -> usize {
+    diagnostic.end().column
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn scc_diagnostic_hint<'a>(
+    _result: &'a SccResult,
+    diagnostic: &'a SccDiagnostic,
+) -> StrWithLen<'a> {
+    diagnostic.hint().map(Into::into).unwrap_or_default()
+}
+
 unsafe fn c_path(r6_dir: *const i8) -> PathBuf {
     let cstr = unsafe { CStr::from_ptr(r6_dir) };
     PathBuf::from(cstr.to_string_lossy().as_ref())
@@ -159,6 +300,18 @@ pub enum SccResult {
     Error(anyhow::Error),
 }
 
+impl SccResult {
+    fn diagnostics(&self) -> &[SccDiagnostic] {
+        match self {
+            Self::Success(output) => output.diagnostics(),
+            Self::Error(err) => err
+                .downcast_ref::<CompilationFailure>()
+                .map(CompilationFailure::diagnostics)
+                .unwrap_or_default(),
+        }
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct StrWithLen<'a> {
@@ -212,4 +365,17 @@ mod tests {
         assert_eq!(SourceRefType::Function as u8, 3);
         assert_eq!(SourceRefType::Enum as u8, 4);
     }
+
+    #[test]
+    fn source_ref_annotation_is_stable() {
+        assert_eq!(SourceRefAnnotation::None as u8, 0);
+        assert_eq!(SourceRefAnnotation::AddMethod as u8, 1);
+        assert_eq!(SourceRefAnnotation::AddField as u8, 2);
+    }
+
+    #[test]
+    fn diagnostic_severity_is_stable() {
+        assert_eq!(DiagnosticSeverity::Warning as u8, 0);
+        assert_eq!(DiagnosticSeverity::Error as u8, 1);
+    }
 }
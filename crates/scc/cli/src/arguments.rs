@@ -17,6 +17,8 @@ pub struct Arguments {
     pub cache_file: Option<PathBuf>,
     pub no_exec: bool,
     pub no_debug: bool,
+    pub defines: Vec<String>,
+    pub safe_mode: bool,
 }
 
 impl Arguments {
@@ -42,6 +44,8 @@ impl Arguments {
                 let mut cache_file = None;
                 let mut no_exec = false;
                 let mut no_debug = false;
+                let mut defines = Vec::new();
+                let mut safe_mode = false;
 
                 for arg in args {
                     match arg {
@@ -54,6 +58,7 @@ impl Arguments {
                                     .map_err(|_| OptsError::InvalidArgument("threads"))?;
                             }
                             "compilePathsFile" => script_paths_file = Some(PathBuf::from(value)),
+                            "D" => defines.push(value.to_owned()),
                             _ => {}
                         },
                         Arg::Flag { name } => match name {
@@ -63,6 +68,7 @@ impl Arguments {
                             "no-breakpoint" => no_breakpoint = true,
                             "no-exec" => no_exec = true,
                             "no-debug" => no_debug = true,
+                            "safe-mode" => safe_mode = true,
                             _ => {}
                         },
                         Arg::Positional { value } => {
@@ -83,6 +89,8 @@ impl Arguments {
                     cache_file,
                     no_exec,
                     no_debug,
+                    defines,
+                    safe_mode,
                 })
             }
         }
@@ -123,13 +131,21 @@ fn fixed_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
 fn arg_parser<'a>() -> impl Parser<'a, &'a [&'a str], Arg<'a>, extra::Err<Rich<'a, &'a str>>> {
     let switch = select! { str if str::starts_with(str, "-") => &str[1..] }.labelled("switch");
     let value = any().and_is(switch.not()).labelled("value");
+    // some switches are passed with their value attached, e.g. `-profile=on`
+    let assignment = select! {
+        str if str::starts_with(str, "-") && str::contains(str, '=') => {
+            let (name, value) = str[1..].split_once('=').unwrap_or_default();
+            Arg::Named { name, value }
+        }
+    };
 
-    switch
-        .then(value.or_not())
-        .map(|(name, value)| match value {
-            Some(value) => Arg::Named { name, value },
-            None => Arg::Flag { name },
-        })
+    assignment
+        .or(switch
+            .then(value.or_not())
+            .map(|(name, value)| match value {
+                Some(value) => Arg::Named { name, value },
+                None => Arg::Flag { name },
+            }))
         .or(value.map(|value| Arg::Positional { value }))
 }
 
@@ -189,6 +205,11 @@ mod tests {
             "-no-debug",
             "-profile=off",
             "-optimize",
+            "-D",
+            "DEBUG",
+            "-D",
+            "LEVEL=2",
+            "-safe-mode",
             "-compilePathsFile D:\\Games\\Cyberpunk 2077\\red4ext\\redscript_paths.txt",
         ]
         .into_iter()
@@ -213,6 +234,11 @@ mod tests {
         );
         assert_eq!(opts.no_exec, true);
         assert_eq!(opts.no_debug, true);
+        assert_eq!(
+            opts.defines,
+            vec!["DEBUG".to_string(), "LEVEL=2".to_string()]
+        );
+        assert_eq!(opts.safe_mode, true);
     }
 
     #[test]
@@ -246,6 +272,30 @@ mod tests {
         assert_eq!(opts.no_debug, true);
     }
 
+    #[test]
+    fn from_env_args_splits_attached_values() {
+        let args = [
+            "D:\\Games\\Cyberpunk 2077\\engine\\tools\\scc.exe",
+            "-compile",
+            "D:\\Games\\Cyberpunk 2077\\r6\\scripts",
+            "-profile=on",
+            "D:\\Games\\Cyberpunk 2077\\r6\\cache\\final.redscripts",
+            "-threads=2",
+        ]
+        .into_iter()
+        .map(ToOwned::to_owned);
+
+        let opts = Arguments::from_env_args(args).unwrap();
+        assert_eq!(opts.profile, true);
+        assert_eq!(opts.threads, 2);
+        assert_eq!(
+            opts.cache_file,
+            Some(PathBuf::from(
+                "D:\\Games\\Cyberpunk 2077\\r6\\cache\\final.redscripts"
+            ))
+        );
+    }
+
     #[test]
     fn fix_args_corrects_broken_args() {
         let broken_args = [
@@ -23,6 +23,28 @@ pub(super) fn load() -> anyhow::Result<raw::SccApi> {
             source_ref_parent_name: lib.sym("scc_source_ref_parent_name\0")?,
             source_ref_path: lib.sym("scc_source_ref_path\0")?,
             source_ref_line: lib.sym("scc_source_ref_line\0")?,
+            // functions added after the initial release are optional, so that older
+            // libraries can still be loaded as long as the options using them are not set
+            settings_add_define: lib.sym("scc_settings_add_define\0").ok(),
+            settings_enable_optimizations: lib.sym("scc_settings_enable_optimizations\0").ok(),
+            settings_set_thread_count: lib.sym("scc_settings_set_thread_count\0").ok(),
+            settings_enable_safe_mode: lib.sym("scc_settings_enable_safe_mode\0").ok(),
+            result_get_diagnostic: lib.sym("scc_result_get_diagnostic\0").ok(),
+            result_diagnostic_count: lib.sym("scc_result_diagnostic_count\0").ok(),
+            diagnostic_severity: lib.sym("scc_diagnostic_severity\0").ok(),
+            diagnostic_code: lib.sym("scc_diagnostic_code\0").ok(),
+            diagnostic_message: lib.sym("scc_diagnostic_message\0").ok(),
+            diagnostic_path: lib.sym("scc_diagnostic_path\0").ok(),
+            diagnostic_line: lib.sym("scc_diagnostic_line\0").ok(),
+            diagnostic_column: lib.sym("scc_diagnostic_column\0").ok(),
+            diagnostic_end_line: lib.sym("scc_diagnostic_end_line\0").ok(),
+            diagnostic_end_column: lib.sym("scc_diagnostic_end_column\0").ok(),
+            diagnostic_hint: lib.sym("scc_diagnostic_hint\0").ok(),
+            source_ref_column: lib.sym("scc_source_ref_column\0").ok(),
+            source_ref_annotation: lib.sym("scc_source_ref_annotation\0").ok(),
+            settings_enable_breakpoints: lib.sym("scc_settings_enable_breakpoints\0").ok(),
+            settings_enable_profiling: lib.sym("scc_settings_enable_profiling\0").ok(),
+            settings_set_game_version: lib.sym("scc_settings_set_game_version\0").ok(),
         }
     })
 }
@@ -57,6 +79,26 @@ mod tests {
             source_ref_parent_name: None,
             source_ref_path: None,
             source_ref_line: None,
+            settings_add_define: None,
+            settings_enable_optimizations: None,
+            settings_set_thread_count: None,
+            settings_enable_safe_mode: None,
+            result_get_diagnostic: None,
+            result_diagnostic_count: None,
+            diagnostic_severity: None,
+            diagnostic_code: None,
+            diagnostic_message: None,
+            diagnostic_path: None,
+            diagnostic_line: None,
+            diagnostic_column: None,
+            diagnostic_end_line: None,
+            diagnostic_end_column: None,
+            diagnostic_hint: None,
+            source_ref_column: None,
+            source_ref_annotation: None,
+            settings_enable_breakpoints: None,
+            settings_enable_profiling: None,
+            settings_set_game_version: None,
         };
 
         let _settings_new: Option<unsafe extern "C" fn(*const i8) -> *mut SccSettings> =
@@ -101,6 +143,58 @@ mod tests {
         let _source_ref_line: Option<
             unsafe extern "C" fn(*mut SccOutput, *mut SccSourceRef) -> usize,
         > = api.source_ref_line;
+        let _settings_add_define: Option<unsafe extern "C" fn(*mut SccSettings, *const i8)> =
+            api.settings_add_define;
+        let _settings_enable_optimizations: Option<unsafe extern "C" fn(*mut SccSettings)> =
+            api.settings_enable_optimizations;
+        let _settings_set_thread_count: Option<unsafe extern "C" fn(*mut SccSettings, usize)> =
+            api.settings_set_thread_count;
+        let _settings_enable_safe_mode: Option<unsafe extern "C" fn(*mut SccSettings)> =
+            api.settings_enable_safe_mode;
+        let _result_get_diagnostic: Option<
+            unsafe extern "C" fn(*mut SccResult, usize) -> *mut SccDiagnostic,
+        > = api.result_get_diagnostic;
+        let _result_diagnostic_count: Option<unsafe extern "C" fn(*mut SccResult) -> usize> =
+            api.result_diagnostic_count;
+        let _diagnostic_severity: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> u8,
+        > = api.diagnostic_severity;
+        let _diagnostic_code: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> StrWithLen,
+        > = api.diagnostic_code;
+        let _diagnostic_message: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> StrWithLen,
+        > = api.diagnostic_message;
+        let _diagnostic_path: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> StrWithLen,
+        > = api.diagnostic_path;
+        let _diagnostic_line: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> usize,
+        > = api.diagnostic_line;
+        let _diagnostic_column: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> usize,
+        > = api.diagnostic_column;
+        let _diagnostic_end_line: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> usize,
+        > = api.diagnostic_end_line;
+        let _diagnostic_end_column: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> usize,
+        > = api.diagnostic_end_column;
+        let _diagnostic_hint: Option<
+            unsafe extern "C" fn(*mut SccResult, *mut SccDiagnostic) -> StrWithLen,
+        > = api.diagnostic_hint;
+        let _source_ref_column: Option<
+            unsafe extern "C" fn(*mut SccOutput, *mut SccSourceRef) -> usize,
+        > = api.source_ref_column;
+        let _source_ref_annotation: Option<
+            unsafe extern "C" fn(*mut SccOutput, *mut SccSourceRef) -> u8,
+        > = api.source_ref_annotation;
+        let _settings_enable_breakpoints: Option<unsafe extern "C" fn(*mut SccSettings)> =
+            api.settings_enable_breakpoints;
+        let _settings_enable_profiling: Option<unsafe extern "C" fn(*mut SccSettings)> =
+            api.settings_enable_profiling;
+        let _settings_set_game_version: Option<unsafe extern "C" fn(*mut SccSettings, *const i8)> =
+            api.settings_set_game_version;
     }
 
     #[test]
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::env;
 use std::ffi::CString;
 use std::fs::File;
@@ -47,12 +48,22 @@ fn run(r6_dir: &Path, args: Option<Arguments>) -> anyhow::Result<()> {
         .context("missing 'settings_add_script_path'")?;
     let compile = api.compile.context("missing 'compile'")?;
     let free_result = api.free_result.context("missing 'free_result'")?;
+    // diagnostics are only reported by libraries that expose them
+    let diagnostics = DiagnosticsApi::new(&api);
 
     let root = c_path(r6_dir)?;
 
     unsafe {
         let settings = settings_new(root.as_ptr());
 
+        // breakpoints are emitted unless they're explicitly disabled, libraries that
+        // predate them never emit any
+        if let Some(enable_breakpoints) = api.settings_enable_breakpoints {
+            if args.as_ref().is_none_or(|args| !args.no_breakpoint) {
+                enable_breakpoints(settings);
+            }
+        }
+
         if let Some(args) = args {
             if let Some(cache_file) = args.cache_file {
                 let cache_file = c_path(&cache_file)?;
@@ -69,15 +80,122 @@ fn run(r6_dir: &Path, args: Option<Arguments>) -> anyhow::Result<()> {
                     add_script_path(settings, script_path.as_ptr());
                 }
             }
+
+            if !args.defines.is_empty() {
+                let add_define = api
+                    .settings_add_define
+                    .context("missing 'settings_add_define'")?;
+                for define in args.defines {
+                    let define = CString::new(define)?;
+                    add_define(settings, define.as_ptr());
+                }
+            }
+
+            if args.optimize {
+                let enable_optimizations = api
+                    .settings_enable_optimizations
+                    .context("missing 'settings_enable_optimizations'")?;
+                enable_optimizations(settings);
+            }
+
+            // libraries that predate the setting always compile on a single thread
+            if let Some(set_thread_count) = api.settings_set_thread_count {
+                set_thread_count(settings, args.threads.into());
+            }
+
+            if args.safe_mode {
+                let enable_safe_mode = api
+                    .settings_enable_safe_mode
+                    .context("missing 'settings_enable_safe_mode'")?;
+                enable_safe_mode(settings);
+            }
+
+            if args.profile {
+                let enable_profiling = api
+                    .settings_enable_profiling
+                    .context("missing 'settings_enable_profiling'")?;
+                enable_profiling(settings);
+            }
         }
 
         let res = compile(settings);
+        if let Some(diagnostics) = &diagnostics {
+            diagnostics.print(res);
+        }
         free_result(res);
     }
 
     Ok(())
 }
 
+struct DiagnosticsApi {
+    get: unsafe extern "C" fn(*mut raw::SccResult, usize) -> *mut raw::SccDiagnostic,
+    count: unsafe extern "C" fn(*mut raw::SccResult) -> usize,
+    severity: unsafe extern "C" fn(*mut raw::SccResult, *mut raw::SccDiagnostic) -> u8,
+    code: DiagnosticStrFn,
+    message: DiagnosticStrFn,
+    path: DiagnosticStrFn,
+    line: DiagnosticPosFn,
+    column: DiagnosticPosFn,
+    hint: DiagnosticStrFn,
+}
+
+type DiagnosticStrFn =
+    unsafe extern "C" fn(*mut raw::SccResult, *mut raw::SccDiagnostic) -> raw::StrWithLen;
+type DiagnosticPosFn = unsafe extern "C" fn(*mut raw::SccResult, *mut raw::SccDiagnostic) -> usize;
+
+impl DiagnosticsApi {
+    fn new(api: &raw::SccApi) -> Option<Self> {
+        Some(Self {
+            get: api.result_get_diagnostic?,
+            count: api.result_diagnostic_count?,
+            severity: api.diagnostic_severity?,
+            code: api.diagnostic_code?,
+            message: api.diagnostic_message?,
+            path: api.diagnostic_path?,
+            line: api.diagnostic_line?,
+            column: api.diagnostic_column?,
+            hint: api.diagnostic_hint?,
+        })
+    }
+
+    /// Prints the diagnostics of a compilation result to the standard error.
+    unsafe fn print(&self, res: *mut raw::SccResult) {
+        unsafe {
+            for i in 0..(self.count)(res) {
+                let diagnostic = (self.get)(res, i);
+                let severity = if (self.severity)(res, diagnostic)
+                    == raw::SccDiagnosticSeverity_SCC_DIAGNOSTIC_SEVERITY_ERROR as u8
+                {
+                    "error"
+                } else {
+                    "warning"
+                };
+                eprintln!(
+                    "{}:{}:{}: {severity}[{}]: {}",
+                    to_str((self.path)(res, diagnostic)),
+                    (self.line)(res, diagnostic),
+                    (self.column)(res, diagnostic),
+                    to_str((self.code)(res, diagnostic)),
+                    to_str((self.message)(res, diagnostic)),
+                );
+                let hint = to_str((self.hint)(res, diagnostic));
+                if !hint.is_empty() {
+                    eprintln!("  hint: {hint}");
+                }
+            }
+        }
+    }
+}
+
+unsafe fn to_str<'a>(str: raw::StrWithLen) -> Cow<'a, str> {
+    if str.str_.is_null() {
+        return Cow::Borrowed("");
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(str.str_.cast::<u8>(), str.len) };
+    String::from_utf8_lossy(bytes)
+}
+
 fn c_path(path: &Path) -> anyhow::Result<CString> {
     Ok(CString::new(path.to_string_lossy().as_ref())?)
 }
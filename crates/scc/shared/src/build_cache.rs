@@ -0,0 +1,480 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use hashbrown::HashMap;
+use redscript_compiler_api::ast::{SourceMap, Span};
+use redscript_compiler_api::{Position, TypeInterner};
+use xxhash_rust::xxh3::{Xxh3Default, xxh3_64};
+
+use crate::diagnostic::{DiagnosticSeverity, SccDiagnostic};
+use crate::output::{SccOutput, SourceRef, SourceRefAnnotation, SourceSymbol};
+
+const MAGIC: [u8; 4] = *b"RSBC";
+const FORMAT_VERSION: u8 = 5;
+
+/// A record of the inputs and the output of the last successful compilation. It's used
+/// to skip the compilation when neither the scripts nor the base bundle have changed.
+#[derive(Debug, PartialEq)]
+pub struct BuildCache {
+    inputs: u64,
+    output: u64,
+    refs: Vec<CachedRef>,
+    diagnostics: Vec<SccDiagnostic>,
+    conflict_report: Option<String>,
+}
+
+impl BuildCache {
+    pub fn new(
+        inputs: u64,
+        output: u64,
+        refs: Vec<CachedRef>,
+        diagnostics: Vec<SccDiagnostic>,
+    ) -> Self {
+        Self {
+            inputs,
+            output,
+            refs,
+            diagnostics,
+            conflict_report: None,
+        }
+    }
+
+    /// Sets the annotation conflict report written by the compilation, so that it can be
+    /// written again when the compilation is skipped.
+    pub fn with_conflict_report(mut self, report: Option<String>) -> Self {
+        self.conflict_report = report;
+        self
+    }
+
+    /// Loads the cache from a file, returns `None` if the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        match std::fs::File::open(path) {
+            Ok(file) => Self::read(&mut io::BufReader::new(file)).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut output = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut output)?;
+        output.flush()
+    }
+
+    pub fn inputs(&self) -> u64 {
+        self.inputs
+    }
+
+    pub fn output(&self) -> u64 {
+        self.output
+    }
+
+    /// Returns the diagnostics and the annotation conflict report of the compilation.
+    pub fn into_reports(self) -> (Vec<SccDiagnostic>, Option<String>) {
+        (self.diagnostics, self.conflict_report)
+    }
+
+    /// Restores the source references against freshly loaded sources, returns `None` if
+    /// any of them refers to a file that is no longer present.
+    pub fn restore_refs(
+        &self,
+        sources: &SourceMap,
+        interner: &TypeInterner,
+    ) -> Option<Vec<SourceRef>> {
+        let files = sources
+            .files()
+            .map(|(id, file)| (file.path(), id))
+            .collect::<HashMap<_, _>>();
+
+        self.refs
+            .iter()
+            .map(|ref_| {
+                let file = *files.get(Path::new(&ref_.path))?;
+                let span = Span {
+                    start: ref_.start,
+                    end: ref_.end,
+                    file,
+                };
+                let parent = || {
+                    interner.intern(ref_.parent.as_str());
+                    interner.get_index_of(&ref_.parent)
+                };
+                let symbol = match ref_.kind {
+                    RefKind::Type => SourceSymbol::Type(parent()?),
+                    RefKind::Enum => SourceSymbol::Enum(parent()?),
+                    RefKind::Function => SourceSymbol::Function(ref_.name.clone()),
+                    RefKind::Method => SourceSymbol::Method(parent()?, ref_.name.clone()),
+                    RefKind::Field => SourceSymbol::Field(parent()?, ref_.name.clone()),
+                };
+                let res = SourceRef::new(symbol, span)
+                    .with_is_native(ref_.is_native)
+                    .with_annotation(ref_.annotation);
+                Some(res)
+            })
+            .collect()
+    }
+
+    fn read(input: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC || read_u8(input)? != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unrecognized build cache format",
+            ));
+        }
+
+        let inputs = read_u64(input)?;
+        let output = read_u64(input)?;
+        let count = read_u32(input)?;
+        let refs = (0..count)
+            .map(|_| CachedRef::read(input))
+            .collect::<io::Result<_>>()?;
+        let count = read_u32(input)?;
+        let diagnostics = (0..count)
+            .map(|_| read_diagnostic(input))
+            .collect::<io::Result<_>>()?;
+        let conflict_report = match read_u8(input)? {
+            0 => None,
+            _ => Some(read_str(input)?),
+        };
+        Ok(Self::new(inputs, output, refs, diagnostics).with_conflict_report(conflict_report))
+    }
+
+    fn write(&self, output: &mut impl Write) -> io::Result<()> {
+        output.write_all(&MAGIC)?;
+        output.write_all(&[FORMAT_VERSION])?;
+        output.write_all(&self.inputs.to_le_bytes())?;
+        output.write_all(&self.output.to_le_bytes())?;
+
+        write_len(output, self.refs.len())?;
+        for ref_ in &self.refs {
+            ref_.write(output)?;
+        }
+        write_len(output, self.diagnostics.len())?;
+        for diagnostic in &self.diagnostics {
+            write_diagnostic(output, diagnostic)?;
+        }
+        match &self.conflict_report {
+            Some(report) => {
+                output.write_all(&[1])?;
+                write_str(output, report)
+            }
+            None => output.write_all(&[0]),
+        }
+    }
+}
+
+/// A source reference that does not depend on the state of a particular compilation.
+#[derive(Debug, PartialEq)]
+pub struct CachedRef {
+    kind: RefKind,
+    parent: String,
+    name: String,
+    path: String,
+    start: u32,
+    end: u32,
+    is_native: bool,
+    annotation: SourceRefAnnotation,
+}
+
+impl CachedRef {
+    pub fn from_output(output: &SccOutput, ref_: &SourceRef) -> Option<Self> {
+        let (kind, parent, name) = match ref_.symbol() {
+            SourceSymbol::Type(_) => (RefKind::Type, output.name(ref_)?, ""),
+            SourceSymbol::Enum(_) => (RefKind::Enum, output.name(ref_)?, ""),
+            SourceSymbol::Function(name) => (RefKind::Function, "", name.as_str()),
+            SourceSymbol::Method(_, name) => {
+                (RefKind::Method, output.parent_name(ref_)?, name.as_str())
+            }
+            SourceSymbol::Field(_, name) => {
+                (RefKind::Field, output.parent_name(ref_)?, name.as_str())
+            }
+        };
+        let span = ref_.span();
+        Some(Self {
+            kind,
+            parent: parent.to_owned(),
+            name: name.to_owned(),
+            path: output.path(ref_)?.to_str()?.to_owned(),
+            start: span.start,
+            end: span.end,
+            is_native: ref_.is_native(),
+            annotation: ref_.annotation(),
+        })
+    }
+
+    fn read(input: &mut impl Read) -> io::Result<Self> {
+        let kind = match read_u8(input)? {
+            0 => RefKind::Type,
+            1 => RefKind::Function,
+            2 => RefKind::Method,
+            3 => RefKind::Field,
+            4 => RefKind::Enum,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid source ref kind",
+                ));
+            }
+        };
+        Ok(Self {
+            kind,
+            parent: read_str(input)?,
+            name: read_str(input)?,
+            path: read_str(input)?,
+            start: read_u32(input)?,
+            end: read_u32(input)?,
+            is_native: read_u8(input)? != 0,
+            annotation: match read_u8(input)? {
+                0 => SourceRefAnnotation::None,
+                1 => SourceRefAnnotation::AddMethod,
+                2 => SourceRefAnnotation::AddField,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid source ref annotation",
+                    ));
+                }
+            },
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> io::Result<()> {
+        output.write_all(&[self.kind as u8])?;
+        write_str(output, &self.parent)?;
+        write_str(output, &self.name)?;
+        write_str(output, &self.path)?;
+        output.write_all(&self.start.to_le_bytes())?;
+        output.write_all(&self.end.to_le_bytes())?;
+        output.write_all(&[self.is_native.into(), self.annotation as u8])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum RefKind {
+    Type = 0,
+    Function = 1,
+    Method = 2,
+    Field = 3,
+    Enum = 4,
+}
+
+/// Computes a hash of everything that determines the output of a compilation. The hash is
+/// saved to disk, so the inputs are written in a fixed encoding instead of going through
+/// `Hash`, whose output can differ between platforms and compiler versions.
+#[derive(Default)]
+pub struct InputHasher(Xxh3Default);
+
+impl InputHasher {
+    pub fn add_sources(&mut self, sources: &SourceMap) -> &mut Self {
+        self.add_len(sources.len());
+        for (_, file) in sources.files() {
+            self.add_str(&file.path().to_string_lossy());
+            self.add_str(file.source());
+        }
+        self
+    }
+
+    pub fn add_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.add_len(bytes.len());
+        self.0.update(bytes);
+        self
+    }
+
+    pub fn add_str(&mut self, str: &str) -> &mut Self {
+        self.add_bytes(str.as_bytes())
+    }
+
+    pub fn add_strs(&mut self, strs: &[impl AsRef<str>]) -> &mut Self {
+        self.add_len(strs.len());
+        for str in strs {
+            self.add_str(str.as_ref());
+        }
+        self
+    }
+
+    pub fn add_bool(&mut self, value: bool) -> &mut Self {
+        self.0.update(&[value.into()]);
+        self
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0.digest()
+    }
+
+    fn add_len(&mut self, len: usize) {
+        self.0.update(&(len as u64).to_le_bytes());
+    }
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    xxh3_64(bytes)
+}
+
+fn read_diagnostic(input: &mut impl Read) -> io::Result<SccDiagnostic> {
+    let severity = match read_u8(input)? {
+        0 => DiagnosticSeverity::Warning,
+        1 => DiagnosticSeverity::Error,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid diagnostic severity",
+            ));
+        }
+    };
+    Ok(SccDiagnostic {
+        severity,
+        code: read_str(input)?,
+        message: read_str(input)?,
+        path: read_str(input)?.into(),
+        start: read_position(input)?,
+        end: read_position(input)?,
+        hint: match read_u8(input)? {
+            0 => None,
+            _ => Some(read_str(input)?),
+        },
+    })
+}
+
+fn write_diagnostic(output: &mut impl Write, diagnostic: &SccDiagnostic) -> io::Result<()> {
+    let path = diagnostic
+        .path
+        .to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "non UTF-8 diagnostic path"))?;
+    output.write_all(&[diagnostic.severity as u8])?;
+    write_str(output, &diagnostic.code)?;
+    write_str(output, &diagnostic.message)?;
+    write_str(output, path)?;
+    write_position(output, diagnostic.start)?;
+    write_position(output, diagnostic.end)?;
+    match &diagnostic.hint {
+        Some(hint) => {
+            output.write_all(&[1])?;
+            write_str(output, hint)
+        }
+        None => output.write_all(&[0]),
+    }
+}
+
+fn read_position(input: &mut impl Read) -> io::Result<Position> {
+    let line = read_u32(input)?;
+    let column = read_u32(input)?;
+    Ok(Position::new(line as usize, column as usize))
+}
+
+fn write_position(output: &mut impl Write, position: Position) -> io::Result<()> {
+    write_len(output, position.line)?;
+    write_len(output, position.column)
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    input.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_str(input: &mut impl Read) -> io::Result<String> {
+    let len = read_u32(input)?;
+    let mut buf = vec![];
+    input.take(len.into()).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(buf).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_str(output: &mut impl Write, str: &str) -> io::Result<()> {
+    write_len(output, str.len())?;
+    output.write_all(str.as_bytes())
+}
+
+fn write_len(output: &mut impl Write, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "value out of range"))?;
+    output.write_all(&len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn sample() -> BuildCache {
+        let refs = vec![
+            CachedRef {
+                kind: RefKind::Method,
+                parent: "PlayerPuppet".to_owned(),
+                name: "OnGameAttached".to_owned(),
+                path: "r6/scripts/player.reds".to_owned(),
+                start: 10,
+                end: 42,
+                is_native: false,
+                annotation: SourceRefAnnotation::AddMethod,
+            },
+            CachedRef {
+                kind: RefKind::Function,
+                parent: String::new(),
+                name: "GetGameInstance".to_owned(),
+                path: "r6/scripts/game.reds".to_owned(),
+                start: 0,
+                end: 7,
+                is_native: true,
+                annotation: SourceRefAnnotation::None,
+            },
+        ];
+        let diagnostics = vec![SccDiagnostic {
+            severity: DiagnosticSeverity::Warning,
+            code: "UNUSED_LOCAL".to_owned(),
+            message: "this variable is never used".to_owned(),
+            path: "r6/scripts/player.reds".into(),
+            start: Position::new(3, 7),
+            end: Position::new(3, 12),
+            hint: Some("remove the variable".to_owned()),
+        }];
+        BuildCache::new(1, 2, refs, diagnostics)
+            .with_conflict_report(Some("conflicting @wrapMethod annotations".to_owned()))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let cache = sample();
+        let mut buf = vec![];
+        cache.write(&mut buf).unwrap();
+
+        assert_eq!(BuildCache::read(&mut &buf[..]).unwrap(), cache);
+    }
+
+    #[test]
+    fn hashes_are_stable() {
+        // the hashes are persisted, so they must not change between builds
+        assert_eq!(hash_bytes(b""), 0x2d06_8005_38d3_94c2);
+
+        let hash = |strs: &[&str]| InputHasher::default().add_strs(strs).finish();
+        assert_eq!(hash(&["a", "b"]), hash(&["a", "b"]));
+        assert_ne!(hash(&["ab"]), hash(&["a", "b"]));
+    }
+
+    #[test]
+    fn truncated_cache_is_rejected() {
+        let mut buf = vec![];
+        sample().write(&mut buf).unwrap();
+        buf.truncate(buf.len() - 3);
+
+        assert!(BuildCache::read(&mut &buf[..]).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Diagnostic, DiagnosticReport, Diagnostics, Position, Severity};
+
+use crate::hints::UserHints;
+
+/// A diagnostic detached from the compilation it was produced by, so that it can be
+/// handed over to the host after the compilation is done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SccDiagnostic {
+    pub(crate) severity: DiagnosticSeverity,
+    pub(crate) code: String,
+    pub(crate) message: String,
+    pub(crate) path: PathBuf,
+    pub(crate) start: Position,
+    pub(crate) end: Position,
+    pub(crate) hint: Option<String>,
+}
+
+impl SccDiagnostic {
+    pub(crate) fn collect(
+        diagnostics: &Diagnostics<'_>,
+        sources: &SourceMap,
+        hints: &UserHints,
+    ) -> Vec<Self> {
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| Self::new(diagnostic, sources, hints))
+            .collect()
+    }
+
+    fn new(diagnostic: &Diagnostic<'_>, sources: &SourceMap, hints: &UserHints) -> Option<Self> {
+        let report = DiagnosticReport::new(diagnostic, sources).ok()?;
+        let severity = match report.severity {
+            Severity::Error => DiagnosticSeverity::Error,
+            Severity::Warning => DiagnosticSeverity::Warning,
+        };
+        Some(Self {
+            severity,
+            code: report.code.to_owned(),
+            message: report.message,
+            path: report.file.to_owned(),
+            start: report.start,
+            end: report.end,
+            hint: hints
+                .find_match(diagnostic, sources)
+                .map(|hint| hint.message().to_owned()),
+        })
+    }
+
+    #[inline]
+    pub fn severity(&self) -> DiagnosticSeverity {
+        self.severity
+    }
+
+    #[inline]
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the 1-based start position of the diagnostic.
+    #[inline]
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    /// Returns the 1-based end position of the diagnostic.
+    #[inline]
+    pub fn end(&self) -> Position {
+        self.end
+    }
+
+    /// Returns the message of a user hint that matched the diagnostic, if any.
+    #[inline]
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DiagnosticSeverity {
+    Warning = 0,
+    Error = 1,
+}
@@ -4,6 +4,8 @@ use std::path::Path;
 
 use anyhow::Context;
 use hashbrown::HashMap;
+use redscript_compiler_api::Diagnostic;
+use redscript_compiler_api::ast::SourceMap;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -71,4 +73,21 @@ impl UserHints {
     pub fn get_by_code(&self, error_code: &str) -> Option<&[UserHint]> {
         self.hints.get(error_code).map(Vec::as_slice)
     }
+
+    /// Returns the first hint that matches the code and the source of a diagnostic.
+    pub fn find_match(
+        &self,
+        diagnostic: &Diagnostic<'_>,
+        sources: &SourceMap,
+    ) -> Option<&UserHint> {
+        let candidates = self.get_by_code(diagnostic.code())?;
+        let span = diagnostic.span();
+        let file = sources.get(span.file)?;
+        let source = file.span_contents(span);
+        let (line, _) = file.line_and_offset(span.start);
+        let line_source = file.line_contents(line)?;
+        candidates
+            .iter()
+            .find(|hint| hint.does_match(file.path().file_name(), source, line_source))
+    }
 }
@@ -1,22 +1,36 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, fs, io};
 
 use anyhow::Context;
+use build_cache::{BuildCache, CachedRef, InputHasher, hash_bytes};
+pub use diagnostic::{DiagnosticSeverity, SccDiagnostic};
 use fd_lock::RwLock;
+use hashbrown::HashMap;
+use hints::UserHints;
 use output::extract_refs;
-pub use output::{SccOutput, SourceRef, SourceRefType};
+pub use output::{SccOutput, SourceRef, SourceRefAnnotation, SourceRefType, SourceSymbol};
 use redscript_compiler_api::ast::SourceMap;
-use redscript_compiler_api::{Compilation, FlushError, SaveError, SourceMapExt, TypeInterner};
-use report::{CompilationFailure, ErrorReport};
+use redscript_compiler_api::cte::{self, Define};
+use redscript_compiler_api::{
+    Compilation, FlushError, LintLevel, LintLevels, ModConflicts, Profiling, SaveError,
+    SourceMapExt, TypeInterner, configure_threads, pass,
+};
+use redscript_dotfile::{DOTFILE_NAME, DefineValue, DiagnosticsConfig, Dotfile};
+pub use report::CompilationFailure;
+use report::{ErrorReport, SafeModeReport};
+use safe_mode::DisabledMod;
 pub use settings::SccSettings;
-use settings::{BACKUP_FILE_EXT, TIMESTAMP_FILE_EXT};
+use settings::{BACKUP_FILE_EXT, BUILD_CACHE_FILE_EXT, TIMESTAMP_FILE_EXT};
 use timestamp::CompileTimestamp;
 use vmap::Map;
 
+mod build_cache;
+mod diagnostic;
 mod hints;
 mod logger;
 mod output;
 mod report;
+mod safe_mode;
 mod settings;
 mod timestamp;
 
@@ -42,6 +56,10 @@ pub fn compile(settings: &SccSettings) -> anyhow::Result<SccOutput> {
 fn compile_inner(settings: &SccSettings) -> anyhow::Result<SccOutput> {
     log::info!("Running REDscript {}", env!("CARGO_PKG_VERSION"));
 
+    if let Some(Err(err)) = settings.thread_count().map(configure_threads) {
+        log::warn!("Could not configure the thread count: {err}");
+    }
+
     let cache_file = settings.cache_file_path();
 
     let ts_path = cache_file.with_extension(TIMESTAMP_FILE_EXT);
@@ -61,7 +79,7 @@ fn compile_inner(settings: &SccSettings) -> anyhow::Result<SccOutput> {
     let input_file = prepare_input_cache(settings, &mut ts_file)?;
     let output_file = settings.output_cache_file_path();
 
-    let sources = SourceMap::from_paths_recursively(settings.script_paths())?;
+    let mut sources = SourceMap::from_paths_recursively(settings.script_paths())?;
     log::info!(
         "Compiling {} files:\n{}",
         sources.len(),
@@ -69,10 +87,42 @@ fn compile_inner(settings: &SccSettings) -> anyhow::Result<SccOutput> {
     );
     sources.populate_boot_lib();
 
+    let dotfile = load_dotfile(settings);
+    let levels = lint_levels(dotfile.diagnostics);
+    let passes = diagnostic_passes(&levels);
+    let conditions =
+        cte::Conditions::new(defines(dotfile.defines, settings), game_version(settings));
+    let user_hints = load_user_hints(settings);
+
     let interner = TypeInterner::default();
     let (mmap, _f) = Map::with_options().open(&input_file)?;
-    let refs = {
-        match Compilation::new_with(&mmap, &sources, &interner, &[])?.flush(&output_file) {
+
+    let build_cache_file = output_file.with_extension(BUILD_CACHE_FILE_EXT);
+    let inputs = hash_inputs(&sources, &mmap, settings);
+    if let Some((refs, diagnostics, conflict_report)) =
+        reuse_build(&build_cache_file, inputs, &output_file, &sources, &interner)
+    {
+        log::info!("Nothing has changed since the last compilation, skipping it");
+        write_conflict_report(conflict_report.as_deref(), settings);
+        return Ok(SccOutput::new(sources, interner, refs).with_diagnostics(diagnostics));
+    }
+
+    let mut disabled_mods: Vec<DisabledMod> = vec![];
+    let mut reported: Vec<SccDiagnostic> = vec![];
+    let mut conflict_report = None;
+    let refs = loop {
+        let comp =
+            Compilation::new_with(&mmap, &sources, &interner, &passes, &levels, &conditions)?
+                .with_optimizations(settings.should_optimize())
+                .with_breakpoints(settings.should_emit_breakpoints())
+                .with_profiling(if settings.should_profile() {
+                    Profiling::Functions
+                } else {
+                    Profiling::Disabled
+                });
+        conflict_report = report_conflicts(&comp.conflicts(), &sources, settings);
+
+        let failing = match comp.flush(&output_file) {
             Err(FlushError::Write(SaveError::Mmap(err)))
                 if err.kind() == io::ErrorKind::PermissionDenied =>
             {
@@ -83,22 +133,272 @@ fn compile_inner(settings: &SccSettings) -> anyhow::Result<SccOutput> {
             }
             Err(FlushError::CompilationErrors(diagnostics)) => {
                 diagnostics.dump(&sources)?;
+                let current = SccDiagnostic::collect(&diagnostics, &sources, &user_hints);
 
-                return Err(CompilationFailure::new(diagnostics, &sources, settings)?.into());
+                let failing = settings.is_safe_mode_enabled().then(|| {
+                    let is_dependent = !disabled_mods.is_empty();
+                    safe_mode::failing_mods(&diagnostics, &sources, settings, is_dependent)
+                });
+                match failing.flatten() {
+                    Some(failing) => {
+                        reported.extend(
+                            current
+                                .into_iter()
+                                .filter(|d| d.severity() == DiagnosticSeverity::Error),
+                        );
+                        failing
+                    }
+                    None => {
+                        reported.extend(current);
+                        let failure =
+                            CompilationFailure::new(diagnostics, &sources, settings, &user_hints)?
+                                .with_disabled_mods(disabled_mods)
+                                .with_diagnostics(reported);
+                        return Err(failure.into());
+                    }
+                }
             }
             Err(err) => anyhow::bail!("{err}"),
             Ok((syms, diagnostics)) => {
                 diagnostics.dump(&sources)?;
+                reported.extend(SccDiagnostic::collect(&diagnostics, &sources, &user_hints));
 
                 log::info!("Succesfully written '{}'", output_file.display());
-                extract_refs(&syms, &interner)
+                break extract_refs(&syms, &interner);
             }
+        };
+
+        for mod_ in &failing {
+            log::warn!("Safe mode is disabling {mod_}");
         }
+        sources = safe_mode::without_mods(&sources, &failing, settings);
+        disabled_mods.extend(failing);
+        log::info!("Recompiling without the disabled mods");
     };
 
     CompileTimestamp::try_from(&output_file.metadata()?)?.write(&mut *ts_file)?;
 
-    Ok(SccOutput::new(sources, interner, refs))
+    let output = SccOutput::new(sources, interner, refs).with_diagnostics(reported);
+    if disabled_mods.is_empty() {
+        if let Err(err) = save_build_cache(
+            &build_cache_file,
+            inputs,
+            &output_file,
+            &output,
+            conflict_report,
+        ) {
+            log::warn!("Failed to save the build cache: {err}");
+        }
+    } else {
+        // the cache is not saved so that the disabled mods are reported again on the next run
+        fs::remove_file(&build_cache_file).ok();
+        report_disabled_mods(&disabled_mods, settings);
+    }
+    Ok(output)
+}
+
+/// Hashes everything that affects the output of a compilation.
+fn hash_inputs(sources: &SourceMap, bundle: &[u8], settings: &SccSettings) -> u64 {
+    let dotfile = fs::read(settings.default_scripts_dir_path().join(DOTFILE_NAME)).ok();
+    InputHasher::default()
+        .add_str(env!("CARGO_PKG_VERSION"))
+        .add_sources(sources)
+        .add_bytes(bundle)
+        .add_bytes(dotfile.as_deref().unwrap_or_default())
+        .add_strs(settings.defines())
+        .add_bool(settings.should_optimize())
+        .add_bool(settings.is_safe_mode_enabled())
+        .add_bool(settings.should_emit_breakpoints())
+        .add_bool(settings.should_profile())
+        .add_str(settings.game_version().unwrap_or_default())
+        .finish()
+}
+
+/// Returns the source references of the previous compilation if its inputs were the same
+/// and the output bundle has not been modified since. Otherwise, logs why a rebuild is
+/// needed and returns `None`.
+fn reuse_build(
+    cache_file: &Path,
+    inputs: u64,
+    output_file: &Path,
+    sources: &SourceMap,
+    interner: &TypeInterner,
+) -> Option<(Vec<SourceRef>, Vec<SccDiagnostic>, Option<String>)> {
+    let cache = match BuildCache::load(cache_file) {
+        Ok(Some(cache)) => cache,
+        Ok(None) => {
+            log::info!("No build cache found, running a full compilation");
+            return None;
+        }
+        Err(err) => {
+            log::info!("The build cache could not be read ({err}), running a full compilation");
+            return None;
+        }
+    };
+    if cache.inputs() != inputs {
+        log::info!("Scripts or the base bundle have changed, recompiling");
+        return None;
+    }
+    match fs::read(output_file) {
+        Ok(bytes) if hash_bytes(&bytes) == cache.output() => {}
+        Ok(_) => {
+            log::info!("The output bundle was modified since the last compilation, recompiling");
+            return None;
+        }
+        Err(err) => {
+            log::info!("The output bundle could not be read ({err}), recompiling");
+            return None;
+        }
+    }
+    let Some(refs) = cache.restore_refs(sources, interner) else {
+        log::info!("The build cache is corrupted, running a full compilation");
+        return None;
+    };
+    let (diagnostics, conflict_report) = cache.into_reports();
+    Some((refs, diagnostics, conflict_report))
+}
+
+fn save_build_cache(
+    cache_file: &Path,
+    inputs: u64,
+    output_file: &Path,
+    output: &SccOutput,
+    conflict_report: Option<String>,
+) -> io::Result<()> {
+    let bundle = fs::read(output_file)?;
+    let refs = output
+        .refs()
+        .iter()
+        .filter_map(|ref_| CachedRef::from_output(output, ref_))
+        .collect();
+    BuildCache::new(
+        inputs,
+        hash_bytes(&bundle),
+        refs,
+        output.diagnostics().to_vec(),
+    )
+    .with_conflict_report(conflict_report)
+    .save(cache_file)
+}
+
+fn load_dotfile(settings: &SccSettings) -> Dotfile {
+    Dotfile::load_or_default(settings.default_scripts_dir_path()).unwrap_or_else(|err| {
+        log::warn!("Failed to load the .redscript file, using the defaults: {err}");
+        Dotfile::default()
+    })
+}
+
+/// Returns the diagnostic levels from the dotfile, unknown codes are skipped with a
+/// warning.
+fn lint_levels(config: DiagnosticsConfig) -> LintLevels {
+    let mut levels = LintLevels::default();
+    for (codes, level) in [
+        (config.allow, LintLevel::Allow),
+        (config.warn, LintLevel::Warn),
+        (config.deny, LintLevel::Deny),
+    ] {
+        for code in codes {
+            if LintLevels::is_known_code(&code) {
+                levels.set(code, level);
+            } else {
+                log::warn!(
+                    "Ignoring an unknown diagnostic code '{code}', expected one of: {}",
+                    LintLevels::KNOWN_CODES.join(", ")
+                );
+            }
+        }
+    }
+    levels
+}
+
+/// Returns the names defined in the dotfile followed by the ones from the settings, so
+/// that the latter take precedence. Invalid definitions are skipped with a warning.
+fn defines(dotfile_defines: HashMap<String, DefineValue>, settings: &SccSettings) -> Vec<Define> {
+    let mut defines = dotfile_defines
+        .into_iter()
+        .map(|(name, value)| Define::new(name, value.into()))
+        .collect::<Vec<_>>();
+    for define in settings.defines() {
+        match define.parse() {
+            Ok(define) => defines.push(define),
+            Err(err) => log::warn!("Ignoring a define: {err}"),
+        }
+    }
+    defines
+}
+
+/// Returns the game version from the settings, an invalid version is ignored with a warning.
+fn game_version(settings: &SccSettings) -> Option<cte::GameVersion> {
+    let version = settings.game_version()?;
+    match version.parse() {
+        Ok(version) => Some(version),
+        Err(err) => {
+            log::warn!("Ignoring the game version '{version}': {err}");
+            None
+        }
+    }
+}
+
+/// Returns the control flow pass, which always runs, and the passes whose diagnostics are
+/// configured to be reported. The latter are opt-in to avoid breaking existing mods that
+/// would not pass them.
+fn diagnostic_passes<'ctx>(levels: &LintLevels) -> Vec<Box<dyn pass::DiagnosticPass<'ctx>>> {
+    let enabled = |code: &str| matches!(levels.get(code), Some(LintLevel::Warn | LintLevel::Deny));
+
+    let mut passes: Vec<Box<dyn pass::DiagnosticPass<'ctx>>> = vec![Box::new(pass::ControlFlow)];
+    if enabled("UNUSED_LOCAL") {
+        passes.push(Box::new(pass::UnusedLocals));
+    }
+    if enabled("NULL_ACCESS") {
+        passes.push(Box::new(pass::NullSafety));
+    }
+    if enabled("NON_EXHAUSTIVE_SWITCH") || enabled("DUP_CASE_LABEL") {
+        passes.push(Box::new(pass::SwitchExhaustiveness));
+    }
+    passes
+}
+
+fn load_user_hints(settings: &SccSettings) -> UserHints {
+    UserHints::load(settings.user_hints_dir()).unwrap_or_else(|err| {
+        log::warn!("Failed to parse one of the user hints TOML files: {err}");
+        UserHints::default()
+    })
+}
+
+fn report_disabled_mods(disabled_mods: &[DisabledMod], settings: &SccSettings) {
+    let report = SafeModeReport::new(disabled_mods).to_string();
+    log::warn!("{report}");
+    if settings.should_show_error_report() {
+        msgbox::create("REDscript warning", &report, msgbox::IconType::Info).ok();
+    }
+}
+
+/// Logs the annotation conflicts and writes their report, returns the report so that it
+/// can be written again when a later compilation is skipped.
+fn report_conflicts(
+    conflicts: &ModConflicts,
+    sources: &SourceMap,
+    settings: &SccSettings,
+) -> Option<String> {
+    for conflict in conflicts.iter() {
+        log::warn!("{}", conflict.summary(sources));
+    }
+    let report = (!conflicts.is_empty()).then(|| conflicts.report(sources).to_string());
+    write_conflict_report(report.as_deref(), settings);
+    report
+}
+
+fn write_conflict_report(report: Option<&str>, settings: &SccSettings) {
+    let path = settings.conflict_log_path();
+    let Some(report) = report else {
+        // make sure a report from a previous run does not linger around
+        fs::remove_file(&path).ok();
+        return;
+    };
+    match fs::write(&path, report) {
+        Ok(()) => log::info!("Annotation conflicts written to '{}'", path.display()),
+        Err(err) => log::warn!("Failed to write the annotation conflict report: {err}"),
+    }
 }
 
 fn prepare_input_cache(settings: &SccSettings, ts_file: &mut fs::File) -> anyhow::Result<PathBuf> {
@@ -175,3 +475,197 @@ fn prepare_input_cache(settings: &SccSettings, ts_file: &mut fs::File) -> anyhow
         Ok(backup_file)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use redscript_io::{Class, ClassFlags, ScriptBundle, Visibility};
+
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("scc-{name}-{}", std::process::id()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    /// Writes an output bundle and a cache matching it, returns their paths.
+    fn cached_build(dir: &TempDir, inputs: u64) -> (PathBuf, PathBuf) {
+        let cache_file = dir.0.join("final.redscripts.bc");
+        let output_file = dir.0.join("final.redscripts");
+        fs::write(&output_file, b"bundle").unwrap();
+        BuildCache::new(inputs, hash_bytes(b"bundle"), vec![], vec![])
+            .save(&cache_file)
+            .unwrap();
+        (cache_file, output_file)
+    }
+
+    fn reuse(cache_file: &Path, inputs: u64, output_file: &Path) -> bool {
+        let sources = SourceMap::new();
+        let interner = TypeInterner::default();
+        reuse_build(cache_file, inputs, output_file, &sources, &interner).is_some()
+    }
+
+    #[test]
+    fn unchanged_build_is_reused() {
+        let dir = TempDir::new("unchanged-build");
+        let (cache_file, output_file) = cached_build(&dir, 1);
+
+        assert!(reuse(&cache_file, 1, &output_file));
+    }
+
+    #[test]
+    fn changed_inputs_are_rebuilt() {
+        let dir = TempDir::new("changed-inputs");
+        let (cache_file, output_file) = cached_build(&dir, 1);
+
+        assert!(!reuse(&cache_file, 2, &output_file));
+    }
+
+    #[test]
+    fn modified_output_is_rebuilt() {
+        let dir = TempDir::new("modified-output");
+        let (cache_file, output_file) = cached_build(&dir, 1);
+        fs::write(&output_file, b"modified").unwrap();
+
+        assert!(!reuse(&cache_file, 1, &output_file));
+    }
+
+    #[test]
+    fn missing_or_invalid_cache_falls_back_to_full_build() {
+        let dir = TempDir::new("invalid-cache");
+        let (cache_file, output_file) = cached_build(&dir, 1);
+
+        fs::write(&cache_file, b"garbage").unwrap();
+        assert!(!reuse(&cache_file, 1, &output_file));
+
+        fs::remove_file(&cache_file).unwrap();
+        assert!(!reuse(&cache_file, 1, &output_file));
+    }
+
+    #[test]
+    fn reused_build_restores_the_conflict_report() {
+        let dir = TempDir::new("conflict-report");
+        let (cache_file, output_file) = cached_build(&dir, 1);
+        BuildCache::new(1, hash_bytes(b"bundle"), vec![], vec![])
+            .with_conflict_report(Some("conflicting annotations".to_owned()))
+            .save(&cache_file)
+            .unwrap();
+
+        let sources = SourceMap::new();
+        let interner = TypeInterner::default();
+        let (_, _, report) =
+            reuse_build(&cache_file, 1, &output_file, &sources, &interner).unwrap();
+        assert_eq!(report.as_deref(), Some("conflicting annotations"));
+    }
+
+    #[test]
+    fn conflict_log_is_rewritten_or_removed() {
+        let dir = TempDir::new("conflict-log");
+        let settings = SccSettings::new(dir.0.clone(), None, None, vec![], vec![], false);
+        let path = settings.conflict_log_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        write_conflict_report(Some("conflicting annotations"), &settings);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "conflicting annotations"
+        );
+
+        write_conflict_report(None, &settings);
+        assert!(!path.exists());
+    }
+
+    /// Sets up a game directory with a minimal bundle and the scripts given as pairs of
+    /// mod names and sources.
+    fn game_dir(name: &str, mods: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(name);
+        let settings = SccSettings::new(dir.0.clone(), None, None, vec![], vec![], false);
+
+        let mut bundle = ScriptBundle::default();
+        let name = bundle.cnames_mut().add("IScriptable");
+        bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+        let cache_file = settings.cache_file_path();
+        fs::create_dir_all(cache_file.parent().unwrap()).unwrap();
+        fs::write(&cache_file, bundle.into_writeable().to_bytes().unwrap()).unwrap();
+
+        for (mod_name, source) in mods {
+            let mod_dir = settings.default_scripts_dir_path().join(mod_name);
+            fs::create_dir_all(&mod_dir).unwrap();
+            fs::write(mod_dir.join("main.reds"), source).unwrap();
+        }
+        dir
+    }
+
+    const MODS: &[(&str, &str)] = &[
+        ("Good", "func Good() -> Int32 { return 1; }"),
+        (
+            "Broken",
+            "func Helper() -> Int32 { return 1; }\nfunc Broken() -> Int32 { return Missing(); }",
+        ),
+        (
+            "Dependent",
+            "func Dependent() -> Int32 { return Helper(); }",
+        ),
+    ];
+
+    #[test]
+    fn safe_mode_recompiles_without_failing_mods() {
+        let dir = game_dir("safe-mode", MODS);
+        let mut settings = SccSettings::new(dir.0.clone(), None, None, vec![], vec![], false);
+        settings.set_safe_mode(true);
+
+        let output = compile_inner(&settings).unwrap();
+
+        // the dependent mod only fails once the broken one is disabled
+        let mut failed = output
+            .diagnostics()
+            .iter()
+            .filter(|d| d.severity() == DiagnosticSeverity::Error)
+            .map(|d| d.path().parent().unwrap().file_name().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        failed.dedup();
+        assert_eq!(failed, ["Broken", "Dependent"]);
+
+        let names = output
+            .refs()
+            .iter()
+            .filter_map(|r| output.name(r))
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"Good"));
+        assert!(!names.contains(&"Helper"));
+        assert!(!names.contains(&"Dependent"));
+        assert!(!build_cache_exists(&settings));
+    }
+
+    #[test]
+    fn failing_mods_fail_the_compilation_without_safe_mode() {
+        let dir = game_dir("no-safe-mode", MODS);
+        let settings = SccSettings::new(dir.0.clone(), None, None, vec![], vec![], false);
+
+        let err = compile_inner(&settings).unwrap_err();
+        let failure = err.downcast_ref::<CompilationFailure>().unwrap();
+        assert!(
+            failure
+                .diagnostics()
+                .iter()
+                .all(|d| d.path().ends_with(Path::new("Broken").join("main.reds")))
+        );
+    }
+
+    fn build_cache_exists(settings: &SccSettings) -> bool {
+        settings
+            .output_cache_file_path()
+            .with_extension(BUILD_CACHE_FILE_EXT)
+            .exists()
+    }
+}
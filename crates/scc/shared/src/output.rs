@@ -3,10 +3,13 @@ use std::path::Path;
 use redscript_compiler_api::ast::{self, SourceMap, Span};
 use redscript_compiler_api::{Symbols, TypeIndex, TypeInterner};
 
+use crate::diagnostic::SccDiagnostic;
+
 pub struct SccOutput {
     sources: SourceMap,
     interner: TypeInterner,
     refs: Vec<SourceRef>,
+    diagnostics: Vec<SccDiagnostic>,
 }
 
 impl SccOutput {
@@ -15,26 +18,42 @@ impl SccOutput {
             sources,
             interner,
             refs,
+            diagnostics: vec![],
         }
     }
 
+    pub fn with_diagnostics(mut self, diagnostics: Vec<SccDiagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Returns references to all symbols defined in sources, both native and scripted.
     #[inline]
     pub fn refs(&self) -> &[SourceRef] {
         &self.refs
     }
 
+    /// Returns the warnings reported during the compilation, along with the errors that
+    /// caused mods to be disabled in safe mode.
+    #[inline]
+    pub fn diagnostics(&self) -> &[SccDiagnostic] {
+        &self.diagnostics
+    }
+
     pub fn name<'a>(&'a self, ref_: &'a SourceRef) -> Option<&'a str> {
-        match ref_ {
-            SourceRef::Type(id, _) => Some(self.interner.get_index(*id)?.as_str()),
-            SourceRef::Function(name, _)
-            | SourceRef::Method(_, name, _)
-            | SourceRef::Field(_, name, _) => Some(name),
+        match &ref_.symbol {
+            SourceSymbol::Type(id) | SourceSymbol::Enum(id) => {
+                Some(self.interner.get_index(*id)?.as_str())
+            }
+            SourceSymbol::Function(name)
+            | SourceSymbol::Method(_, name)
+            | SourceSymbol::Field(_, name) => Some(name),
         }
     }
 
     pub fn parent_name<'a>(&'a self, ref_: &SourceRef) -> Option<&'a str> {
-        match ref_ {
-            SourceRef::Method(id, _, _) | SourceRef::Field(id, _, _) => {
+        match &ref_.symbol {
+            SourceSymbol::Method(id, _) | SourceSymbol::Field(id, _) => {
                 Some(self.interner.get_index(*id)?.as_str())
             }
             _ => None,
@@ -42,45 +61,91 @@ impl SccOutput {
     }
 
     pub fn path<'a>(&'a self, ref_: &SourceRef) -> Option<&'a Path> {
-        let span = ref_.span();
-        self.sources.get(span.file).map(ast::File::path)
+        self.sources.get(ref_.span.file).map(ast::File::path)
     }
 
     pub fn line(&self, ref_: &SourceRef) -> Option<usize> {
-        let span = ref_.span();
+        let span = ref_.span;
         self.sources
             .get(span.file)
             .map(|f| f.line_and_offset(span.start).0)
     }
+
+    pub fn column(&self, ref_: &SourceRef) -> Option<usize> {
+        let span = ref_.span;
+        self.sources
+            .get(span.file)
+            .map(|f| f.lookup(span.start).col)
+    }
 }
 
 #[derive(Debug)]
-pub enum SourceRef {
-    Type(TypeIndex, Span),
-    Function(String, Span),
-    Method(TypeIndex, String, Span),
-    Field(TypeIndex, String, Span),
+pub struct SourceRef {
+    symbol: SourceSymbol,
+    span: Span,
+    is_native: bool,
+    annotation: SourceRefAnnotation,
 }
 
 impl SourceRef {
+    pub fn new(symbol: SourceSymbol, span: Span) -> Self {
+        Self {
+            symbol,
+            span,
+            is_native: false,
+            annotation: SourceRefAnnotation::None,
+        }
+    }
+
+    pub fn with_is_native(mut self, is_native: bool) -> Self {
+        self.is_native = is_native;
+        self
+    }
+
+    pub fn with_annotation(mut self, annotation: SourceRefAnnotation) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    #[inline]
+    pub fn symbol(&self) -> &SourceSymbol {
+        &self.symbol
+    }
+
     pub fn type_(&self) -> SourceRefType {
-        match self {
-            SourceRef::Type(_, _) => SourceRefType::Class,
-            SourceRef::Function(_, _) | SourceRef::Method(_, _, _) => SourceRefType::Function,
-            SourceRef::Field(_, _, _) => SourceRefType::Field,
+        match self.symbol {
+            SourceSymbol::Type(_) => SourceRefType::Class,
+            SourceSymbol::Enum(_) => SourceRefType::Enum,
+            SourceSymbol::Function(_) | SourceSymbol::Method(_, _) => SourceRefType::Function,
+            SourceSymbol::Field(_, _) => SourceRefType::Field,
         }
     }
 
+    #[inline]
     pub fn span(&self) -> Span {
-        match self {
-            SourceRef::Type(_, span)
-            | SourceRef::Function(_, span)
-            | SourceRef::Method(_, _, span)
-            | SourceRef::Field(_, _, span) => *span,
-        }
+        self.span
+    }
+
+    #[inline]
+    pub fn is_native(&self) -> bool {
+        self.is_native
+    }
+
+    #[inline]
+    pub fn annotation(&self) -> SourceRefAnnotation {
+        self.annotation
     }
 }
 
+#[derive(Debug)]
+pub enum SourceSymbol {
+    Type(TypeIndex),
+    Enum(TypeIndex),
+    Function(String),
+    Method(TypeIndex, String),
+    Field(TypeIndex, String),
+}
+
 #[repr(u8)]
 pub enum SourceRefType {
     Undefined = 0,
@@ -90,6 +155,17 @@ pub enum SourceRefType {
     Enum = 4,
 }
 
+/// The annotation that a symbol was introduced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SourceRefAnnotation {
+    None = 0,
+    AddMethod = 1,
+    AddField = 2,
+}
+
+/// Returns references to all symbols defined in sources. Members added to existing types
+/// with `@addMethod` and `@addField` are included even though their parent types are not.
 pub(super) fn extract_refs(syms: &Symbols<'_>, interner: &TypeInterner) -> Vec<SourceRef> {
     let mut refs = Vec::new();
 
@@ -97,26 +173,50 @@ pub(super) fn extract_refs(syms: &Symbols<'_>, interner: &TypeInterner) -> Vec<S
         let Some(id) = interner.get_index_of(id) else {
             continue;
         };
+        if let Some(enum_) = def.schema().as_enum() {
+            if let Some(span) = enum_.span() {
+                refs.push(SourceRef::new(SourceSymbol::Enum(id), span));
+            }
+            continue;
+        }
         let Some(agg) = def.schema().as_aggregate() else {
             continue;
         };
-        if !agg.flags().is_native() {
-            continue;
+        if let Some(span) = agg.span() {
+            let ref_ = SourceRef::new(SourceSymbol::Type(id), span)
+                .with_is_native(agg.flags().is_native());
+            refs.push(ref_);
         }
-        let Some(span) = agg.span() else { continue };
-        refs.push(SourceRef::Type(id, span));
+        // members with spans of types that come from the bundle were added with annotations
+        let is_added = !agg.is_user_defined();
 
         for entry in agg.methods().iter() {
             let Some(span) = entry.func().span() else {
                 continue;
             };
-            refs.push(SourceRef::Method(id, (**entry.name()).into(), span));
+            let annotation = if is_added {
+                SourceRefAnnotation::AddMethod
+            } else {
+                SourceRefAnnotation::None
+            };
+            let ref_ = SourceRef::new(SourceSymbol::Method(id, (**entry.name()).into()), span)
+                .with_is_native(entry.func().flags().is_native())
+                .with_annotation(annotation);
+            refs.push(ref_);
         }
         for field in agg.fields().iter() {
             let Some(span) = field.field().span() else {
                 continue;
             };
-            refs.push(SourceRef::Field(id, field.name().into(), span));
+            let annotation = if is_added {
+                SourceRefAnnotation::AddField
+            } else {
+                SourceRefAnnotation::None
+            };
+            let ref_ = SourceRef::new(SourceSymbol::Field(id, field.name().into()), span)
+                .with_is_native(field.field().flags().is_native())
+                .with_annotation(annotation);
+            refs.push(ref_);
         }
     }
 
@@ -124,11 +224,126 @@ pub(super) fn extract_refs(syms: &Symbols<'_>, interner: &TypeInterner) -> Vec<S
         let Some(span) = entry.func().span() else {
             continue;
         };
-        if !entry.func().flags().is_native() {
-            continue;
-        }
-        refs.push(SourceRef::Function(entry.name().to_string(), span));
+        let ref_ = SourceRef::new(SourceSymbol::Function(entry.name().to_string()), span)
+            .with_is_native(entry.func().flags().is_native());
+        refs.push(ref_);
     }
 
     refs
 }
+
+#[cfg(test)]
+mod tests {
+    use redscript_compiler_api::{Compilation, SourceMapExt};
+    use redscript_io::{Class, ClassFlags, ScriptBundle, Visibility};
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    const SOURCE: &str = r#"
+class Scripted {
+  let field: Int32;
+
+  func Method() {}
+}
+
+struct Point {
+  let x: Float;
+}
+
+enum Color {
+  Red = 0,
+}
+
+@addMethod(IScriptable)
+func AddedMethod() {}
+
+@addField(IScriptable)
+let addedField: Int32;
+
+func Function() {}
+
+native func NativeFunction()
+"#;
+
+    fn compile(source: &str) -> SccOutput {
+        let sources = SourceMap::new();
+        sources.push_back("test.reds", source);
+        sources.populate_boot_lib();
+
+        let mut bundle = ScriptBundle::default();
+        let name = bundle.cnames_mut().add("IScriptable");
+        bundle.define(Class::new(name, Visibility::Public, ClassFlags::default()));
+        let base = bundle.into_writeable().to_bytes().unwrap();
+
+        let interner = TypeInterner::default();
+        let refs = {
+            let (_, syms, _) = Compilation::new(&base, &sources, &interner)
+                .unwrap()
+                .finish()
+                .unwrap();
+            extract_refs(&syms, &interner)
+        };
+        SccOutput::new(sources, interner, refs)
+    }
+
+    #[test]
+    fn scripted_and_native_symbols_are_referenced() {
+        let output = compile(SOURCE);
+
+        let mut refs = output
+            .refs()
+            .iter()
+            .filter(|r| output.path(r) == Some(Path::new("test.reds")))
+            .map(|r| {
+                (
+                    output.name(r).unwrap(),
+                    output.parent_name(r),
+                    r.is_native(),
+                    r.annotation(),
+                )
+            })
+            .collect::<Vec<_>>();
+        refs.sort_by_key(|&(name, _, _, _)| name);
+
+        assert_eq!(
+            refs,
+            [
+                (
+                    "AddedMethod",
+                    Some("IScriptable"),
+                    false,
+                    SourceRefAnnotation::AddMethod
+                ),
+                ("Color", None, false, SourceRefAnnotation::None),
+                ("Function", None, false, SourceRefAnnotation::None),
+                ("Method", Some("Scripted"), false, SourceRefAnnotation::None),
+                ("NativeFunction", None, true, SourceRefAnnotation::None),
+                ("Point", None, false, SourceRefAnnotation::None),
+                ("Scripted", None, false, SourceRefAnnotation::None),
+                (
+                    "addedField",
+                    Some("IScriptable"),
+                    false,
+                    SourceRefAnnotation::AddField
+                ),
+                ("field", Some("Scripted"), false, SourceRefAnnotation::None),
+                ("x", Some("Point"), false, SourceRefAnnotation::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn refs_point_at_symbol_names() {
+        let output = compile(SOURCE);
+
+        let enum_ = output
+            .refs()
+            .iter()
+            .find(|r| output.name(r) == Some("Color"))
+            .unwrap();
+        assert_eq!(enum_.type_() as u8, SourceRefType::Enum as u8);
+        assert_eq!(output.line(enum_), Some(11));
+        assert_eq!(output.column(enum_), Some(5));
+    }
+}
@@ -7,7 +7,9 @@ use redscript_compiler_api::{Diagnostic, Diagnostics};
 use thiserror::Error;
 
 use crate::SccSettings;
+use crate::diagnostic::SccDiagnostic;
 use crate::hints::UserHints;
+use crate::safe_mode::DisabledMod;
 
 #[derive(Debug)]
 pub struct ErrorReport<'ctx> {
@@ -26,6 +28,16 @@ impl fmt::Display for ErrorReport<'_> {
         writeln!(f)?;
 
         if let Some(source) = self.cause.downcast_ref::<CompilationFailure>() {
+            if !source.disabled_mods.is_empty() {
+                writeln!(f, "Safe mode has disabled the mods listed below:")?;
+                write_disabled_mods(f, &source.disabled_mods)?;
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "The remaining errors could not be resolved by disabling mods."
+                )?;
+            }
+
             writeln!(f, "Fatal errors were found in the files listed below:")?;
             for file in &source.failing_files {
                 writeln!(f, "{}", file.display())?;
@@ -57,11 +69,53 @@ impl fmt::Display for ErrorReport<'_> {
     }
 }
 
+/// A report of the mods that were disabled in safe mode in an otherwise successful
+/// compilation.
+#[derive(Debug)]
+pub struct SafeModeReport<'ctx> {
+    disabled_mods: &'ctx [DisabledMod],
+}
+
+impl<'ctx> SafeModeReport<'ctx> {
+    pub fn new(disabled_mods: &'ctx [DisabledMod]) -> Self {
+        Self { disabled_mods }
+    }
+}
+
+impl fmt::Display for SafeModeReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "REDscript compilation has succeeded, but some mods had to be disabled."
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "The following mods had fatal errors and were not loaded:"
+        )?;
+        write_disabled_mods(f, self.disabled_mods)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "You should check if these mods are outdated and update them if possible."
+        )
+    }
+}
+
+fn write_disabled_mods(f: &mut fmt::Formatter<'_>, mods: &[DisabledMod]) -> fmt::Result {
+    for mod_ in mods {
+        writeln!(f, "- {mod_}")?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 #[error("fatal errors found")]
 pub struct CompilationFailure {
     failing_files: Vec<PathBuf>,
     hints: Vec<String>,
+    disabled_mods: Vec<DisabledMod>,
+    diagnostics: Vec<SccDiagnostic>,
 }
 
 impl CompilationFailure {
@@ -69,6 +123,7 @@ impl CompilationFailure {
         diagnostics: Diagnostics<'_>,
         sources: &SourceMap,
         settings: &SccSettings,
+        user_hints: &UserHints,
     ) -> anyhow::Result<Self> {
         let fatal = diagnostics
             .into_iter()
@@ -87,23 +142,11 @@ impl CompilationFailure {
             })
             .collect();
 
-        let hints_config = UserHints::load(settings.user_hints_dir()).unwrap_or_else(|err| {
-            log::warn!("Failed to parse one of the user hints TOML files: {err}");
-            UserHints::default()
-        });
-
         let mut hints = HashMap::new();
-        for hint in fatal.iter().filter_map(|d| {
-            let candidates = hints_config.get_by_code(d.code())?;
-            let span = d.span();
-            let file = sources.get(span.file)?;
-            let source = file.span_contents(span);
-            let (line, _) = file.line_and_offset(span.start);
-            let line_source = file.line_contents(line)?;
-            candidates
-                .iter()
-                .find(|hint| hint.does_match(file.path().file_name(), source, line_source))
-        }) {
+        for hint in fatal
+            .iter()
+            .filter_map(|d| user_hints.find_match(d, sources))
+        {
             hints
                 .entry(hint.id())
                 .or_insert_with(|| hint.message().to_owned());
@@ -112,6 +155,24 @@ impl CompilationFailure {
         Ok(Self {
             failing_files,
             hints: hints.into_values().collect(),
+            disabled_mods: vec![],
+            diagnostics: vec![],
         })
     }
+
+    /// Returns the diagnostics of all compilation attempts, including the ones that
+    /// caused mods to be disabled in safe mode.
+    pub fn diagnostics(&self) -> &[SccDiagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<SccDiagnostic>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    pub fn with_disabled_mods(mut self, disabled_mods: Vec<DisabledMod>) -> Self {
+        self.disabled_mods = disabled_mods;
+        self
+    }
 }
@@ -0,0 +1,273 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use hashbrown::HashMap;
+use redscript_compiler_api::ast::SourceMap;
+use redscript_compiler_api::{Diagnostic, Diagnostics};
+
+use crate::SccSettings;
+
+/// A mod that was excluded from the compilation because of fatal errors.
+#[derive(Debug)]
+pub struct DisabledMod {
+    path: PathBuf,
+    reason: String,
+    is_dependent: bool,
+}
+
+impl fmt::Display for DisabledMod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)?;
+        if self.is_dependent {
+            write!(
+                f,
+                " (it likely depends on one of the mods disabled before it)"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the mods responsible for the fatal errors in the diagnostics, or `None` if
+/// some of the errors cannot be attributed to any mod.
+pub fn failing_mods(
+    diagnostics: &Diagnostics<'_>,
+    sources: &SourceMap,
+    settings: &SccSettings,
+    is_dependent: bool,
+) -> Option<Vec<DisabledMod>> {
+    let mut mods: HashMap<PathBuf, String> = HashMap::new();
+    for diagnostic in diagnostics.into_iter().filter(|d| d.is_fatal()) {
+        let span = diagnostic.span();
+        let file = sources.get(span.file)?;
+        let root = mod_root(file.path(), settings)?;
+        mods.entry(root)
+            .or_insert_with(|| describe(diagnostic, sources, settings));
+    }
+
+    let mut mods = mods
+        .into_iter()
+        .map(|(path, reason)| {
+            let path = path
+                .strip_prefix(settings.root_dir())
+                .map(Path::to_owned)
+                .unwrap_or(path);
+            DisabledMod {
+                path,
+                reason,
+                is_dependent,
+            }
+        })
+        .collect::<Vec<_>>();
+    mods.sort_by(|a, b| a.path.cmp(&b.path));
+    (!mods.is_empty()).then_some(mods)
+}
+
+/// Returns a copy of the source map without the files that belong to the disabled mods.
+pub fn without_mods(
+    sources: &SourceMap,
+    mods: &[DisabledMod],
+    settings: &SccSettings,
+) -> SourceMap {
+    let result = SourceMap::new();
+    for (_, file) in sources.files() {
+        let is_disabled = mod_root(file.path(), settings).is_some_and(|root| {
+            let root = root.strip_prefix(settings.root_dir()).unwrap_or(&root);
+            mods.iter().any(|m| m.path == root)
+        });
+        if !is_disabled {
+            result.push_back(file.path(), file.source());
+        }
+    }
+    result
+}
+
+/// Returns the directory of the mod that a file belongs to. For scripts in the default
+/// scripts directory, it's the top-level directory under it, or the file itself if it's
+/// not in a subdirectory. Additional script paths are treated as individual mods.
+fn mod_root(path: &Path, settings: &SccSettings) -> Option<PathBuf> {
+    let scripts_dir = settings.default_scripts_dir_path();
+    if let Ok(relative) = path.strip_prefix(&scripts_dir) {
+        let first = relative.components().next()?;
+        return Some(scripts_dir.join(first));
+    }
+    settings
+        .script_paths()
+        .skip(1)
+        .find(|root| path.starts_with(root))
+        .map(|root| root.into_owned())
+}
+
+fn describe(diagnostic: &Diagnostic<'_>, sources: &SourceMap, settings: &SccSettings) -> String {
+    let span = diagnostic.span();
+    let Some(file) = sources.get(span.file) else {
+        return diagnostic.to_string();
+    };
+    let path = file.path();
+    let path = path.strip_prefix(settings.root_dir()).unwrap_or(path);
+    let (line, _) = file.line_and_offset(span.start);
+    format!("{diagnostic} at {}:{}", path.display(), line + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use redscript_compiler_api::ast::Span;
+    use similar_asserts::assert_eq;
+
+    use super::*;
+
+    fn settings() -> SccSettings {
+        SccSettings::new(PathBuf::from("game"), None, None, vec![], vec![], false)
+    }
+
+    /// Returns a source map with two mods and a file that doesn't belong to any mod.
+    fn sources(settings: &SccSettings) -> SourceMap {
+        let scripts = settings.default_scripts_dir_path();
+        let sources = SourceMap::new();
+        sources.push_back(scripts.join("Good").join("main.reds"), "func Good() {}\n");
+        sources.push_back(
+            scripts.join("Broken").join("main.reds"),
+            "func A() {}\nfunc B() {}\n",
+        );
+        sources.push_back(scripts.join("Broken").join("other.reds"), "func C() {}\n");
+        sources.push_back("boot.reds", "func Boot() {}\n");
+        sources
+    }
+
+    fn span(sources: &SourceMap, path: &Path, start: u32) -> Span {
+        let (file, _) = sources.files().find(|(_, f)| f.path() == path).unwrap();
+        Span {
+            start,
+            end: start + 1,
+            file,
+        }
+    }
+
+    #[test]
+    fn files_are_attributed_to_mods() {
+        let root = PathBuf::from("game");
+        let extra = PathBuf::from("red4ext").join("plugins").join("Codeware");
+        let settings =
+            SccSettings::new(root.clone(), None, None, vec![extra.clone()], vec![], false);
+        let scripts = settings.default_scripts_dir_path();
+
+        assert_eq!(
+            mod_root(
+                &scripts.join("Mod").join("src").join("main.reds"),
+                &settings
+            ),
+            Some(scripts.join("Mod"))
+        );
+        assert_eq!(
+            mod_root(&scripts.join("loose.reds"), &settings),
+            Some(scripts.join("loose.reds"))
+        );
+        assert_eq!(
+            mod_root(&extra.join("scripts").join("Codeware.reds"), &settings),
+            Some(extra)
+        );
+        assert_eq!(mod_root(Path::new("boot.reds"), &settings), None);
+    }
+
+    #[test]
+    fn fatal_errors_disable_their_mods() {
+        let settings = settings();
+        let sources = sources(&settings);
+        let broken = settings.default_scripts_dir_path().join("Broken");
+        let good = settings.default_scripts_dir_path().join("Good");
+
+        let diagnostics = Diagnostics::from(vec![
+            Diagnostic::UnusedLocal(span(&sources, &good.join("main.reds"), 0)),
+            Diagnostic::InvalidBaseType(span(&sources, &broken.join("main.reds"), 12)),
+            Diagnostic::InvalidBaseType(span(&sources, &broken.join("other.reds"), 0)),
+        ]);
+        let mods = failing_mods(&diagnostics, &sources, &settings, false).unwrap();
+
+        let path = Path::new("r6").join("scripts").join("Broken");
+        assert_eq!(
+            mods.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [format!(
+                "{}: base type must be a valid known type at {}:2",
+                path.display(),
+                path.join("main.reds").display()
+            )]
+        );
+    }
+
+    #[test]
+    fn dependent_mods_are_reported_as_such() {
+        let settings = settings();
+        let sources = sources(&settings);
+        let broken = settings.default_scripts_dir_path().join("Broken");
+
+        let diagnostics = Diagnostics::from(vec![Diagnostic::InvalidBaseType(span(
+            &sources,
+            &broken.join("other.reds"),
+            0,
+        ))]);
+        let mods = failing_mods(&diagnostics, &sources, &settings, true).unwrap();
+
+        let path = Path::new("r6").join("scripts").join("Broken");
+        assert_eq!(
+            mods.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [format!(
+                "{}: base type must be a valid known type at {}:1 (it likely depends on one of \
+                 the mods disabled before it)",
+                path.display(),
+                path.join("other.reds").display()
+            )]
+        );
+    }
+
+    #[test]
+    fn errors_outside_of_mods_are_not_attributed() {
+        let settings = settings();
+        let sources = sources(&settings);
+
+        let warnings = Diagnostics::from(vec![Diagnostic::UnusedLocal(span(
+            &sources,
+            &settings
+                .default_scripts_dir_path()
+                .join("Good")
+                .join("main.reds"),
+            0,
+        ))]);
+        assert!(failing_mods(&warnings, &sources, &settings, false).is_none());
+
+        let errors = Diagnostics::from(vec![Diagnostic::InvalidBaseType(span(
+            &sources,
+            Path::new("boot.reds"),
+            0,
+        ))]);
+        assert!(failing_mods(&errors, &sources, &settings, false).is_none());
+    }
+
+    #[test]
+    fn disabled_mods_are_removed_from_sources() {
+        let settings = settings();
+        let sources = sources(&settings);
+        let broken = settings.default_scripts_dir_path().join("Broken");
+
+        let diagnostics = Diagnostics::from(vec![Diagnostic::InvalidBaseType(span(
+            &sources,
+            &broken.join("main.reds"),
+            0,
+        ))]);
+        let mods = failing_mods(&diagnostics, &sources, &settings, false).unwrap();
+        let remaining = without_mods(&sources, &mods, &settings);
+
+        assert_eq!(
+            remaining
+                .files()
+                .map(|(_, file)| file.path().to_owned())
+                .collect::<Vec<_>>(),
+            [
+                settings
+                    .default_scripts_dir_path()
+                    .join("Good")
+                    .join("main.reds"),
+                PathBuf::from("boot.reds")
+            ]
+        );
+    }
+}
@@ -11,11 +11,14 @@ const R6_DIR: &str = "r6";
 const CACHE_DIR: &str = "cache";
 const SCRIPTS_DIR: &str = "scripts";
 const CONFIG_DIR: &str = "config";
+const LOGS_DIR: &str = "logs";
+const CONFLICT_LOG_FILE: &str = "redscript_conflicts.log";
 const USER_HINTS_DIR: &str = "redsUserHints";
 
 pub(crate) const CACHE_FILE_EXT: &str = "redscripts";
 pub(crate) const BACKUP_FILE_EXT: &str = "redscripts.bk";
 pub(crate) const TIMESTAMP_FILE_EXT: &str = "redscripts.ts";
+pub(crate) const BUILD_CACHE_FILE_EXT: &str = "redscripts.build";
 
 #[derive(Debug)]
 #[repr(C)]
@@ -24,7 +27,14 @@ pub struct SccSettings {
     custom_cache_file: Option<PathBuf>,
     output_cache_file: Option<PathBuf>,
     additional_script_paths: Vec<PathBuf>,
+    defines: Vec<String>,
     show_error_report: bool,
+    optimize: bool,
+    thread_count: Option<usize>,
+    safe_mode: bool,
+    breakpoints: bool,
+    profile: bool,
+    game_version: Option<String>,
 }
 
 impl SccSettings {
@@ -33,6 +43,7 @@ impl SccSettings {
         custom_cache_file: Option<PathBuf>,
         output_cache_file: Option<PathBuf>,
         additional_script_paths: Vec<PathBuf>,
+        defines: Vec<String>,
         show_error_report: bool,
     ) -> Self {
         Self {
@@ -40,7 +51,16 @@ impl SccSettings {
             custom_cache_file,
             output_cache_file,
             additional_script_paths,
+            defines,
             show_error_report,
+            optimize: false,
+            thread_count: None,
+            safe_mode: false,
+            // the scc executable enables breakpoints unless they're disabled with
+            // -no-breakpoint, other callers of the library have to opt in
+            breakpoints: false,
+            profile: false,
+            game_version: None,
         }
     }
 
@@ -55,11 +75,18 @@ impl SccSettings {
             .join(USER_HINTS_DIR)
     }
 
+    pub fn conflict_log_path(&self) -> PathBuf {
+        self.root_dir
+            .join(R6_DIR)
+            .join(LOGS_DIR)
+            .join(CONFLICT_LOG_FILE)
+    }
+
     fn default_cache_file_path(&self) -> PathBuf {
         self.root_dir.join(R6_DIR).join(CACHE_DIR).join(BUNDLE_FILE)
     }
 
-    fn default_scripts_dir_path(&self) -> PathBuf {
+    pub fn default_scripts_dir_path(&self) -> PathBuf {
         self.root_dir.join(R6_DIR).join(SCRIPTS_DIR)
     }
 
@@ -107,10 +134,38 @@ impl SccSettings {
         }
     }
 
+    pub fn defines(&self) -> &[String] {
+        &self.defines
+    }
+
     pub fn should_show_error_report(&self) -> bool {
         self.show_error_report
     }
 
+    pub fn should_optimize(&self) -> bool {
+        self.optimize
+    }
+
+    pub fn thread_count(&self) -> Option<usize> {
+        self.thread_count
+    }
+
+    pub fn is_safe_mode_enabled(&self) -> bool {
+        self.safe_mode
+    }
+
+    pub fn should_emit_breakpoints(&self) -> bool {
+        self.breakpoints
+    }
+
+    pub fn should_profile(&self) -> bool {
+        self.profile
+    }
+
+    pub fn game_version(&self) -> Option<&str> {
+        self.game_version.as_deref()
+    }
+
     pub fn set_custom_cache_file(&mut self, path: PathBuf) {
         self.custom_cache_file = Some(path);
     }
@@ -123,7 +178,35 @@ impl SccSettings {
         self.show_error_report = show;
     }
 
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    pub fn set_thread_count(&mut self, count: usize) {
+        self.thread_count = Some(count);
+    }
+
+    pub fn set_safe_mode(&mut self, safe_mode: bool) {
+        self.safe_mode = safe_mode;
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: bool) {
+        self.breakpoints = breakpoints;
+    }
+
+    pub fn set_profile(&mut self, profile: bool) {
+        self.profile = profile;
+    }
+
+    pub fn set_game_version(&mut self, version: String) {
+        self.game_version = Some(version);
+    }
+
     pub fn add_script_path(&mut self, path: PathBuf) {
         self.additional_script_paths.push(path);
     }
+
+    pub fn add_define(&mut self, define: String) {
+        self.defines.push(define);
+    }
 }
@@ -118,6 +118,12 @@ impl<'src> ItemDecl<'src, WithSpan> {
                 .iter()
                 .filter_map(|(p, _)| p.typ.as_ref())
                 .find_map(|(typ, span)| span.contains(pos).then_some(typ.find_at(pos)))
+                .or_else(|| {
+                    f.params
+                        .iter()
+                        .filter_map(|(p, _)| p.default.as_deref())
+                        .find_map(|(def, span)| span.contains(pos).then_some(def.find_at(pos)))
+                })
                 .or_else(|| {
                     f.return_type
                         .as_deref()
@@ -532,6 +538,7 @@ pub struct Param<'src, K: AstKind = Identity> {
     pub name: &'src str,
     pub typ: Option<TypeT<'src, K>>,
     pub qualifiers: ParamQualifiers,
+    pub default: Option<Box<ExprT<'src, K>>>,
 }
 
 impl<'src, K: AstKind> Param<'src, K> {
@@ -541,14 +548,21 @@ impl<'src, K: AstKind> Param<'src, K> {
             name,
             typ,
             qualifiers,
+            default: None,
         }
     }
 
+    #[inline]
+    pub fn with_default(self, default: Option<Box<ExprT<'src, K>>>) -> Self {
+        Self { default, ..self }
+    }
+
     pub fn unwrapped(self) -> Param<'src> {
         Param {
             name: self.name,
             typ: self.typ.map(|t| t.into_wrapped().unwrapped()),
             qualifiers: self.qualifiers,
+            default: self.default.map(|d| (*d).into_wrapped().unwrapped().into()),
         }
     }
 }
@@ -916,6 +930,10 @@ pub enum Expr<'src, K: AstKind = Identity> {
         expr: Box<ExprT<'src, K>>,
         member: &'src str,
     },
+    OptionalMember {
+        expr: Box<ExprT<'src, K>>,
+        member: &'src str,
+    },
     Index {
         expr: Box<ExprT<'src, K>>,
         index: Box<ExprT<'src, K>>,
@@ -996,6 +1014,10 @@ impl<'src, K: AstKind> Expr<'src, K> {
                 expr: (*expr).into_wrapped().unwrapped().into(),
                 member,
             },
+            Expr::OptionalMember { expr, member } => Expr::OptionalMember {
+                expr: (*expr).into_wrapped().unwrapped().into(),
+                member,
+            },
             Expr::Index { expr, index } => Expr::Index {
                 expr: (*expr).into_wrapped().unwrapped().into(),
                 index: (*index).into_wrapped().unwrapped().into(),
@@ -1069,7 +1091,9 @@ impl<'src> Expr<'src, WithSpan> {
                     QueryResult::Expr(self)
                 }
             }
-            Expr::UnOp { expr, .. } | Expr::Member { expr, .. } => {
+            Expr::UnOp { expr, .. }
+            | Expr::Member { expr, .. }
+            | Expr::OptionalMember { expr, .. } => {
                 let (expr, span) = &**expr;
                 if span.contains(pos) {
                     expr.find_at(pos)
@@ -1157,11 +1181,36 @@ pub enum Constant<'src> {
     TweakDbId(Cow<'src, str>),
     F32(f32),
     F64(f64),
-    I32(i32),
-    I64(i64),
-    U32(u32),
-    U64(u64),
+    /// Integers keep the literal as written in the source, without the type suffix, so that
+    /// the formatter can preserve its radix and separators. Constants that don't come from
+    /// the source have no literal.
+    I32(i32, Option<&'src str>),
+    I64(i64, Option<&'src str>),
+    U32(u32, Option<&'src str>),
+    U64(u64, Option<&'src str>),
     Bool(bool),
+    /// An integer literal that does not fit in its type, kept verbatim so that it can be
+    /// reported during lowering.
+    OutOfRange(IntType, &'src str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    I32,
+    I64,
+    U32,
+    U64,
+}
+
+impl IntType {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::I32 => "",
+            Self::I64 => "l",
+            Self::U32 => "u",
+            Self::U64 => "ul",
+        }
+    }
 }
 
 #[derive_where(Debug, Clone, PartialEq)]
@@ -1283,6 +1332,9 @@ pub enum BinOp {
     AssignDiv,
     AssignBitOr,
     AssignBitAnd,
+    AssignBitXor,
+    AssignMod,
+    Coalesce,
     Or,
     And,
     BitOr,
@@ -1294,6 +1346,8 @@ pub enum BinOp {
     Le,
     Gt,
     Ge,
+    Shl,
+    Shr,
     Add,
     Sub,
     Mul,
@@ -1309,16 +1363,20 @@ impl BinOp {
             | Self::AssignMul
             | Self::AssignDiv
             | Self::AssignBitOr
-            | Self::AssignBitAnd => 0,
-            Self::Or => 1,
-            Self::And => 2,
-            Self::BitOr => 3,
-            Self::BitXor => 4,
-            Self::BitAnd => 5,
-            Self::Eq | Self::Ne => 6,
-            Self::Lt | Self::Le | Self::Gt | Self::Ge => 7,
-            Self::Add | Self::Sub => 8,
-            Self::Mul | Self::Div | Self::Mod => 9,
+            | Self::AssignBitAnd
+            | Self::AssignBitXor
+            | Self::AssignMod => 0,
+            Self::Coalesce => 1,
+            Self::Or => 2,
+            Self::And => 3,
+            Self::BitOr => 4,
+            Self::BitXor => 5,
+            Self::BitAnd => 6,
+            Self::Eq | Self::Ne => 7,
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => 8,
+            Self::Shl | Self::Shr => 9,
+            Self::Add | Self::Sub => 10,
+            Self::Mul | Self::Div | Self::Mod => 11,
         }
     }
 
@@ -1329,7 +1387,10 @@ impl BinOp {
             | Self::AssignMul
             | Self::AssignDiv
             | Self::AssignBitOr
-            | Self::AssignBitAnd => Assoc::Right,
+            | Self::AssignBitAnd
+            | Self::AssignBitXor
+            | Self::AssignMod
+            | Self::Coalesce => Assoc::Right,
             Self::Or
             | Self::And
             | Self::BitOr
@@ -1341,6 +1402,8 @@ impl BinOp {
             | Self::Le
             | Self::Gt
             | Self::Ge
+            | Self::Shl
+            | Self::Shr
             | Self::Add
             | Self::Sub
             | Self::Mul
@@ -1349,14 +1412,19 @@ impl BinOp {
         }
     }
 
-    pub fn name(&self) -> &'static str {
-        match self {
+    /// Returns the name of the function that implements the operator, `??` has none
+    /// because it's compiled into a conditional.
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self {
             Self::AssignAdd => "OperatorAssignAdd",
             Self::AssignSub => "OperatorAssignSubtract",
             Self::AssignMul => "OperatorAssignMultiply",
             Self::AssignDiv => "OperatorAssignDivide",
             Self::AssignBitOr => "OperatorAssignOr",
             Self::AssignBitAnd => "OperatorAssignAnd",
+            Self::AssignBitXor => "OperatorAssignXor",
+            Self::AssignMod => "OperatorAssignModulo",
+            Self::Coalesce => return None,
             Self::Or => "OperatorLogicOr",
             Self::And => "OperatorLogicAnd",
             Self::BitOr => "OperatorOr",
@@ -1368,12 +1436,15 @@ impl BinOp {
             Self::Le => "OperatorLessEqual",
             Self::Gt => "OperatorGreater",
             Self::Ge => "OperatorGreaterEqual",
+            Self::Shl => "OperatorShiftLeft",
+            Self::Shr => "OperatorShiftRight",
             Self::Add => "OperatorAdd",
             Self::Sub => "OperatorSubtract",
             Self::Mul => "OperatorMultiply",
             Self::Div => "OperatorDivide",
             Self::Mod => "OperatorModulo",
-        }
+        };
+        Some(name)
     }
 
     pub fn from_name(name: &str) -> Option<Self> {
@@ -1384,6 +1455,8 @@ impl BinOp {
             "OperatorAssignDivide" => Some(Self::AssignDiv),
             "OperatorAssignOr" => Some(Self::AssignBitOr),
             "OperatorAssignAnd" => Some(Self::AssignBitAnd),
+            "OperatorAssignXor" => Some(Self::AssignBitXor),
+            "OperatorAssignModulo" => Some(Self::AssignMod),
             "OperatorLogicOr" => Some(Self::Or),
             "OperatorLogicAnd" => Some(Self::And),
             "OperatorOr" => Some(Self::BitOr),
@@ -1395,6 +1468,8 @@ impl BinOp {
             "OperatorLessEqual" => Some(Self::Le),
             "OperatorGreater" => Some(Self::Gt),
             "OperatorGreaterEqual" => Some(Self::Ge),
+            "OperatorShiftLeft" => Some(Self::Shl),
+            "OperatorShiftRight" => Some(Self::Shr),
             "OperatorAdd" => Some(Self::Add),
             "OperatorSubtract" => Some(Self::Sub),
             "OperatorMultiply" => Some(Self::Mul),
@@ -1405,12 +1480,6 @@ impl BinOp {
     }
 }
 
-impl From<BinOp> for &'static str {
-    fn from(op: BinOp) -> Self {
-        op.name()
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Visibility {
     Public,
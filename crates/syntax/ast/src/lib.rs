@@ -6,8 +6,9 @@ mod visitor;
 pub use ast::{
     Aggregate, Annotation, ArraySpread, Assoc, AstKind, BinOp, Block, Case, Condition,
     ConditionalBlock, Constant, Enum, EnumVariant, Expr, Field, Function, FunctionBody, Import,
-    Item, ItemDecl, ItemQualifiers, LetCondition, Module, Param, ParamQualifiers, Path, Pattern,
-    QueryResult, Stmt, StrPart, Type, TypeParam, UnOp, Variance, Visibility, WithSpan, Wrapper,
+    IntType, Item, ItemDecl, ItemQualifiers, LetCondition, Module, Param, ParamQualifiers, Path,
+    Pattern, QueryResult, Stmt, StrPart, Type, TypeParam, UnOp, Variance, Visibility, WithSpan,
+    Wrapper,
 };
 pub use files::{File, SourceLoc, SourceMap};
 pub use span::{FileId, Span};
@@ -195,6 +195,14 @@ pub trait AstVisitor<'src, K: AstKind> {
         self.visit_expr(expr)
     }
 
+    fn visit_optional_member(
+        &mut self,
+        expr: &ExprT<'src, K>,
+        _member: &'src str,
+    ) -> Result<(), Self::Error> {
+        self.visit_expr(expr)
+    }
+
     fn visit_index(
         &mut self,
         expr: &ExprT<'src, K>,
@@ -341,6 +349,7 @@ pub trait AstVisitor<'src, K: AstKind> {
                 args,
             } => self.visit_call(expr, type_args, args)?,
             Expr::Member { expr, member } => self.visit_member(expr, member)?,
+            Expr::OptionalMember { expr, member } => self.visit_optional_member(expr, member)?,
             Expr::Index { expr, index } => self.visit_index(expr, index)?,
             Expr::DynCast { expr, typ } => self.visit_dyn_cast(expr, typ)?,
             Expr::New { typ, args } => self.visit_new(typ, args)?,
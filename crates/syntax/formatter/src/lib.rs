@@ -493,10 +493,15 @@ impl<K: AstKind> Formattable for Expr<'_, K> {
                 (&Constant::F64(v), _) => {
                     write!(f, "{}", dtoa(v, FmtFloatConfig::default()))
                 }
-                (Constant::I32(v), _) => write!(f, "{v}"),
-                (Constant::I64(v), _) => write!(f, "{v}l"),
-                (Constant::U32(v), _) => write!(f, "{v}u"),
-                (Constant::U64(v), _) => write!(f, "{v}ul"),
+                (Constant::I32(_, Some(lit)), _) => write!(f, "{lit}"),
+                (Constant::I64(_, Some(lit)), _) => write!(f, "{lit}l"),
+                (Constant::U32(_, Some(lit)), _) => write!(f, "{lit}u"),
+                (Constant::U64(_, Some(lit)), _) => write!(f, "{lit}ul"),
+                (Constant::I32(v, None), _) => write!(f, "{v}"),
+                (Constant::I64(v, None), _) => write!(f, "{v}l"),
+                (Constant::U32(v, None), _) => write!(f, "{v}u"),
+                (Constant::U64(v, None), _) => write!(f, "{v}ul"),
+                (Constant::OutOfRange(typ, lit), _) => write!(f, "{lit}{}", typ.suffix()),
                 (Constant::Bool(v), _) => write!(f, "{v}"),
             },
             Expr::ArrayLit(elems) => {
@@ -528,7 +533,9 @@ impl<K: AstKind> Formattable for Expr<'_, K> {
                     (**rhs).as_wrapped().as_fmt(ctx)
                 )
             }
-            Expr::Member { .. } | Expr::BinOp { .. } => format_chain(self, f, ctx),
+            Expr::Member { .. } | Expr::OptionalMember { .. } | Expr::BinOp { .. } => {
+                format_chain(self, f, ctx)
+            }
             Expr::UnOp { op, expr } => {
                 let parenthesize = matches!(ctx.parent, Some(ParentOp::TopPrec));
                 let ctx = ctx.with_parent_op(ParentOp::Unary);
@@ -544,7 +551,7 @@ impl<K: AstKind> Formattable for Expr<'_, K> {
                 type_args,
                 args,
             } => {
-                if let Expr::Member { .. } = (**expr).as_wrapped() {
+                if let Expr::Member { .. } | Expr::OptionalMember { .. } = (**expr).as_wrapped() {
                     format_chain(self, f, ctx)
                 } else {
                     write!(
@@ -644,10 +651,14 @@ impl<K: AstKind> Formattable for Param<'_, K> {
             write!(f, "const ")?;
         };
         if let Some(typ) = &self.typ {
-            write!(f, "{}: {}", self.name, typ.as_wrapped().as_fmt(ctx))
+            write!(f, "{}: {}", self.name, typ.as_wrapped().as_fmt(ctx))?;
         } else {
-            write!(f, "{}", self.name)
+            write!(f, "{}", self.name)?;
+        }
+        if let Some(value) = &self.default {
+            write!(f, " = {}", (**value).as_wrapped().as_fmt(ctx))?;
         }
+        Ok(())
     }
 }
 
@@ -780,6 +791,9 @@ impl Formattable for BinOp {
             Self::AssignDiv => write!(f, "/="),
             Self::AssignBitOr => write!(f, "|="),
             Self::AssignBitAnd => write!(f, "&="),
+            Self::AssignBitXor => write!(f, "^="),
+            Self::AssignMod => write!(f, "%="),
+            Self::Coalesce => write!(f, "??"),
             Self::Or => write!(f, "||"),
             Self::And => write!(f, "&&"),
             Self::BitOr => write!(f, "|"),
@@ -791,6 +805,8 @@ impl Formattable for BinOp {
             Self::Le => write!(f, "<="),
             Self::Gt => write!(f, ">"),
             Self::Ge => write!(f, ">="),
+            Self::Shl => write!(f, "<<"),
+            Self::Shr => write!(f, ">>"),
             Self::Add => write!(f, "+"),
             Self::Sub => write!(f, "-"),
             Self::Mul => write!(f, "*"),
@@ -992,9 +1008,11 @@ impl<A: Formattable> Formattable for &A {
     }
 }
 
+// member accesses and calls carry the accessor they were written with, either '.' or '?.'
 enum Chain<'ast, 'src, K: AstKind> {
-    Member(&'src str),
+    Member(&'static str, &'src str),
     Call(
+        &'static str,
         &'src str,
         &'ast [K::Inner<Type<'src, K>>],
         &'ast [K::Inner<Expr<'src, K>>],
@@ -1046,7 +1064,13 @@ fn format_chain<K: AstKind>(
             Expr::Member { expr, member } => {
                 cur = (**expr).as_wrapped();
                 cur_parent = Some(ParentOp::TopPrec);
-                chain.push(Chain::Member(member));
+                chain.push(Chain::Member(".", member));
+                chain_fields += 1;
+            }
+            Expr::OptionalMember { expr, member } => {
+                cur = (**expr).as_wrapped();
+                cur_parent = Some(ParentOp::TopPrec);
+                chain.push(Chain::Member("?.", member));
                 chain_fields += 1;
             }
             Expr::Index { expr, index } => {
@@ -1059,14 +1083,15 @@ fn format_chain<K: AstKind>(
                 type_args,
                 args,
             } => {
-                if let Expr::Member { expr, member } = (**expr).as_wrapped() {
-                    cur = (**expr).as_wrapped();
-                    cur_parent = Some(ParentOp::TopPrec);
-                    chain.push(Chain::Call(member, &type_args[..], &args[..]));
-                    chain_calls += 1;
-                } else {
-                    break false;
-                }
+                let (accessor, expr, member) = match (**expr).as_wrapped() {
+                    Expr::Member { expr, member } => (".", expr, member),
+                    Expr::OptionalMember { expr, member } => ("?.", expr, member),
+                    _ => break false,
+                };
+                cur = (**expr).as_wrapped();
+                cur_parent = Some(ParentOp::TopPrec);
+                chain.push(Chain::Call(accessor, member, &type_args[..], &args[..]));
+                chain_calls += 1;
             }
             _ => break false,
         };
@@ -1089,19 +1114,19 @@ fn format_chain<K: AstKind>(
 
     for i in chain.into_iter().rev() {
         match i {
-            Chain::Member(member) => {
+            Chain::Member(accessor, member) => {
                 if break_line {
                     writeln!(f)?;
                     write!(f, "{}", ctx.ws())?;
                 }
-                write!(f, ".{member}")?;
+                write!(f, "{accessor}{member}")?;
             }
-            Chain::Call(member, type_args, args) => {
+            Chain::Call(accessor, member, type_args, args) => {
                 if break_line {
                     writeln!(f)?;
                     write!(f, "{}", ctx.ws())?;
                 }
-                write!(f, ".{member}")?;
+                write!(f, "{accessor}{member}")?;
                 format_call_args::<K>(type_args, args, f, ctx)?;
             }
             Chain::Index(index) => write!(f, "[{}]", index.as_fmt(ctx))?,
@@ -1250,11 +1275,19 @@ where
                 Constant::TweakDbId(t) => t.len() as u16 + 3,
                 Constant::F32(f) => f.abs().log10().floor() as u16 + 1,
                 Constant::F64(f) => f.abs().log10().floor() as u16 + 1,
-                &Constant::I32(0) | &Constant::I64(0) | &Constant::U32(0) | &Constant::U64(0) => 1,
-                Constant::I32(i) => i.abs().ilog10() as u16 + 1,
-                Constant::I64(i) => i.abs().ilog10() as u16 + 1,
-                Constant::U32(u) => u.ilog10() as u16 + 1,
-                Constant::U64(u) => u.ilog10() as u16 + 1,
+                Constant::I32(_, Some(lit))
+                | Constant::I64(_, Some(lit))
+                | Constant::U32(_, Some(lit))
+                | Constant::U64(_, Some(lit))
+                | Constant::OutOfRange(_, lit) => lit.len() as u16,
+                &Constant::I32(0, None)
+                | &Constant::I64(0, None)
+                | &Constant::U32(0, None)
+                | &Constant::U64(0, None) => 1,
+                Constant::I32(i, None) => i.abs().ilog10() as u16 + 1,
+                Constant::I64(i, None) => i.abs().ilog10() as u16 + 1,
+                Constant::U32(u, None) => u.ilog10() as u16 + 1,
+                Constant::U64(u, None) => u.ilog10() as u16 + 1,
                 Constant::Bool(_) => 4,
             },
             Expr::ArrayLit(elems) => elems
@@ -1299,6 +1332,9 @@ where
             Expr::Member { expr, member } => {
                 (**expr).as_wrapped().approx_width() + member.len() as u16 + 1
             }
+            Expr::OptionalMember { expr, member } => {
+                (**expr).as_wrapped().approx_width() + member.len() as u16 + 2
+            }
             Expr::Index { expr, index } => {
                 (**expr).as_wrapped().approx_width() + (**index).as_wrapped().approx_width() + 2
             }
@@ -1450,12 +1486,17 @@ impl ApproxWidth for BinOp {
             | BinOp::AssignDiv
             | BinOp::AssignBitOr
             | BinOp::AssignBitAnd
+            | BinOp::AssignBitXor
+            | BinOp::AssignMod
+            | BinOp::Coalesce
             | BinOp::Or
             | BinOp::And
             | BinOp::Eq
             | BinOp::Ne
             | BinOp::Le
-            | BinOp::Ge => 2,
+            | BinOp::Ge
+            | BinOp::Shl
+            | BinOp::Shr => 2,
             BinOp::BitOr
             | BinOp::BitXor
             | BinOp::BitAnd
@@ -1481,8 +1522,8 @@ impl ApproxWidth for UnOp {
 impl<K: AstKind> ApproxWidth for Chain<'_, '_, K> {
     fn approx_width(&self) -> u16 {
         match self {
-            Chain::Member(member) => member.len() as u16 + 1,
-            Chain::Call(member, type_args, args) => {
+            Chain::Member(accessor, member) => member.len() as u16 + accessor.len() as u16,
+            Chain::Call(_, member, type_args, args) => {
                 member.len() as u16
                     + type_args
                         .iter()
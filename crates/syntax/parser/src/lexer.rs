@@ -1,9 +1,11 @@
 use std::borrow::Cow;
+use std::num::IntErrorKind;
 use std::{fmt, mem, ops};
 
 use chumsky::container::Container;
 use chumsky::input::InputRef;
 use chumsky::prelude::*;
+use redscript_ast::IntType;
 
 use crate::Span;
 
@@ -13,25 +15,53 @@ type LexExtra<'src> = extra::Err<Rich<'src, char, LexSpan>>;
 pub fn lex<'src>(
     keep_lf_and_comments: bool,
 ) -> impl Parser<'src, &'src str, Vec<(Token<'src, LexSpan>, LexSpan)>, LexExtra<'src>> + Clone {
-    let num = text::int(10)
-        .then(just('.').then(text::digits(10).or_not()).or_not())
+    let separated = any()
+        .filter(|c: &char| c.is_ascii_digit() || *c == '_')
+        .repeated();
+
+    let decimal = text::int(10)
+        .then(separated.clone())
+        .then(
+            just('.')
+                .then(text::digits(10).then(separated).or_not())
+                .or_not(),
+        )
         .to_slice()
         .then(choice([just("ul"), just("u"), just("l"), just("d")]).or_not())
         .try_map(|(str, suffix): (&str, _), span| match suffix {
-            Some("ul") => Ok(Token::Ulong(
-                str.parse().map_err(|e| Rich::custom(span, e))?,
-            )),
-            Some("u") => Ok(Token::Uint(str.parse().map_err(|e| Rich::custom(span, e))?)),
-            Some("l") => Ok(Token::Long(str.parse().map_err(|e| Rich::custom(span, e))?)),
             Some("d") => Ok(Token::Double(
-                str.parse().map_err(|e| Rich::custom(span, e))?,
+                str.replace('_', "")
+                    .parse()
+                    .map_err(|e| Rich::custom(span, e))?,
             )),
             _ if str.contains('.') => Ok(Token::Float(
-                str.parse().map_err(|e| Rich::custom(span, e))?,
+                str.replace('_', "")
+                    .parse()
+                    .map_err(|e| Rich::custom(span, e))?,
             )),
-            _ => Ok(Token::Int(str.parse().map_err(|e| Rich::custom(span, e))?)),
+            _ => int_token(str, str, 10, suffix, span),
+        });
+
+    let prefixed = just('0')
+        .then(one_of("xob"))
+        .then(
+            any()
+                .filter(|c: &char| c.is_ascii_hexdigit() || *c == '_')
+                .repeated(),
+        )
+        .to_slice()
+        .then(choice([just("ul"), just("u"), just("l")]).or_not())
+        .try_map(|(str, suffix): (&str, _), span| {
+            let radix = match &str[..2] {
+                "0x" => 16,
+                "0o" => 8,
+                _ => 2,
+            };
+            int_token(str, &str[2..], radix, suffix, span)
         });
 
+    let num = prefixed.or(decimal);
+
     let str = one_of("nrt")
         .or_not()
         .then(
@@ -112,6 +142,44 @@ pub fn lex<'src>(
     })
 }
 
+fn int_token<'src>(
+    str: &'src str,
+    digits: &str,
+    radix: u32,
+    suffix: Option<&str>,
+    span: LexSpan,
+) -> Result<Token<'src, LexSpan>, Rich<'src, char, LexSpan>> {
+    let digits = digits.replace('_', "");
+    if digits.is_empty() {
+        return Err(Rich::custom(span, "expected at least one digit"));
+    }
+    // values that don't fit are kept as they are to be reported as out of range later on
+    let n = match u64::from_str_radix(&digits, radix) {
+        Ok(n) => Some(n),
+        Err(err) if *err.kind() == IntErrorKind::PosOverflow => None,
+        Err(err) => return Err(Rich::custom(span, err)),
+    };
+    let typ = match suffix {
+        Some("ul") => IntType::U64,
+        Some("u") => IntType::U32,
+        Some("l") => IntType::I64,
+        _ => IntType::I32,
+    };
+    let tok = match typ {
+        IntType::I32 => n
+            .and_then(|n| i32::try_from(n).ok())
+            .map(|n| Token::Int(n, str)),
+        IntType::I64 => n
+            .and_then(|n| i64::try_from(n).ok())
+            .map(|n| Token::Long(n, str)),
+        IntType::U32 => n
+            .and_then(|n| u32::try_from(n).ok())
+            .map(|n| Token::Uint(n, str)),
+        IntType::U64 => n.map(|n| Token::Ulong(n, str)),
+    };
+    Ok(tok.unwrap_or(Token::OutOfRange(typ, str)))
+}
+
 fn str_elem<'src>() -> impl Parser<'src, &'src str, Cow<'src, str>, LexExtra<'src>> + Clone {
     let unicode_escape = any()
         .filter(char::is_ascii_hexdigit)
@@ -196,8 +264,10 @@ fn symbol<'src>() -> impl Parser<'src, &'src str, Token<'src, LexSpan>, LexExtra
             Some('*') => Token::Star,
             Some('/') if inp.peek() == Some('=') => skipped(inp, Token::AssignDiv),
             Some('/') => Token::Slash,
+            Some('%') if inp.peek() == Some('=') => skipped(inp, Token::AssignMod),
             Some('%') => Token::Percent,
             Some('<') if inp.peek() == Some('=') => skipped(inp, Token::Le),
+            Some('<') if inp.peek() == Some('<') => skipped(inp, Token::Shl),
             Some('<') => Token::LAngle,
             Some('>') if inp.peek() == Some('=') => skipped(inp, Token::Ge),
             Some('>') => Token::RAngle,
@@ -218,11 +288,14 @@ fn symbol<'src>() -> impl Parser<'src, &'src str, Token<'src, LexSpan>, LexExtra
             Some('|') if inp.peek() == Some('|') => skipped(inp, Token::Or),
             Some('|') if inp.peek() == Some('=') => skipped(inp, Token::AssignBitOr),
             Some('|') => Token::BitOr,
+            Some('^') if inp.peek() == Some('=') => skipped(inp, Token::AssignBitXor),
             Some('^') => Token::BitXor,
             Some(':') => Token::Colon,
             Some(';') => Token::Semicolon,
             Some('=') if inp.peek() == Some('=') => skipped(inp, Token::Eq),
             Some('=') => Token::Assign,
+            Some('?') if inp.peek() == Some('.') => skipped(inp, Token::QuestionPeriod),
+            Some('?') if inp.peek() == Some('?') => skipped(inp, Token::DoubleQuestion),
             Some('?') => Token::Question,
             Some('@') => Token::At,
             _ => {
@@ -238,10 +311,11 @@ fn symbol<'src>() -> impl Parser<'src, &'src str, Token<'src, LexSpan>, LexExtra
 pub enum Token<'src, S = Span> {
     Group(Box<[(Self, S)]>),
     Ident(&'src str),
-    Int(i32),
-    Uint(u32),
-    Ulong(u64),
-    Long(i64),
+    Int(i32, &'src str),
+    Uint(u32, &'src str),
+    Ulong(u64, &'src str),
+    Long(i64, &'src str),
+    OutOfRange(IntType, &'src str),
     Float(f32),
     Double(f64),
     Str(Cow<'src, str>),
@@ -262,6 +336,8 @@ pub enum Token<'src, S = Span> {
     AssignDiv,
     AssignBitOr,
     AssignBitAnd,
+    AssignBitXor,
+    AssignMod,
 
     Plus,
     Minus,
@@ -277,6 +353,7 @@ pub enum Token<'src, S = Span> {
     BitAnd,
     BitOr,
     BitXor,
+    Shl,
     Not,
     BitNot,
 
@@ -295,6 +372,8 @@ pub enum Token<'src, S = Span> {
     Semicolon,
     Assign,
     Question,
+    QuestionPeriod,
+    DoubleQuestion,
     At,
 
     True,
@@ -330,10 +409,11 @@ impl<'src, S1> Token<'src, S1> {
                     .collect(),
             ),
             Self::Ident(s) => Token::Ident(s),
-            Self::Int(n) => Token::Int(n),
-            Self::Uint(n) => Token::Uint(n),
-            Self::Ulong(n) => Token::Ulong(n),
-            Self::Long(n) => Token::Long(n),
+            Self::Int(n, r) => Token::Int(n, r),
+            Self::Uint(n, r) => Token::Uint(n, r),
+            Self::Ulong(n, r) => Token::Ulong(n, r),
+            Self::Long(n, r) => Token::Long(n, r),
+            Self::OutOfRange(t, s) => Token::OutOfRange(t, s),
             Self::Float(n) => Token::Float(n),
             Self::Double(n) => Token::Double(n),
             Self::Str(s) => Token::Str(s),
@@ -360,6 +440,8 @@ impl<'src, S1> Token<'src, S1> {
             Self::AssignDiv => Token::AssignDiv,
             Self::AssignBitOr => Token::AssignBitOr,
             Self::AssignBitAnd => Token::AssignBitAnd,
+            Self::AssignBitXor => Token::AssignBitXor,
+            Self::AssignMod => Token::AssignMod,
             Self::Plus => Token::Plus,
             Self::Minus => Token::Minus,
             Self::Star => Token::Star,
@@ -374,6 +456,7 @@ impl<'src, S1> Token<'src, S1> {
             Self::BitAnd => Token::BitAnd,
             Self::BitOr => Token::BitOr,
             Self::BitXor => Token::BitXor,
+            Self::Shl => Token::Shl,
             Self::Not => Token::Not,
             Self::BitNot => Token::BitNot,
             Self::Period => Token::Period,
@@ -391,6 +474,8 @@ impl<'src, S1> Token<'src, S1> {
             Self::Semicolon => Token::Semicolon,
             Self::Assign => Token::Assign,
             Self::Question => Token::Question,
+            Self::QuestionPeriod => Token::QuestionPeriod,
+            Self::DoubleQuestion => Token::DoubleQuestion,
             Self::At => Token::At,
             Self::True => Token::True,
             Self::False => Token::False,
@@ -409,10 +494,11 @@ impl fmt::Display for Token<'_> {
             Self::Ident(s) | Self::LineComment(s) | Self::DocComment(s) | Self::BlockComment(s) => {
                 write!(f, "{s}")
             }
-            Self::Int(n) => write!(f, "{n}"),
-            Self::Uint(n) => write!(f, "{n}u"),
-            Self::Ulong(n) => write!(f, "{n}ul"),
-            Self::Long(n) => write!(f, "{n}l"),
+            Self::Int(_, s) => write!(f, "{s}"),
+            Self::Uint(_, s) => write!(f, "{s}u"),
+            Self::Ulong(_, s) => write!(f, "{s}ul"),
+            Self::Long(_, s) => write!(f, "{s}l"),
+            Self::OutOfRange(t, s) => write!(f, "{s}{}", t.suffix()),
             Self::Float(n) => write!(f, "{n}"),
             Self::Double(n) => write!(f, "{n}d"),
             Self::Str(s) => write!(f, "\"{s}\""),
@@ -440,6 +526,8 @@ impl fmt::Display for Token<'_> {
             Self::AssignDiv => write!(f, "/="),
             Self::AssignBitOr => write!(f, "|="),
             Self::AssignBitAnd => write!(f, "&="),
+            Self::AssignBitXor => write!(f, "^="),
+            Self::AssignMod => write!(f, "%="),
             Self::Plus => write!(f, "+"),
             Self::Minus => write!(f, "-"),
             Self::Star => write!(f, "*"),
@@ -454,6 +542,7 @@ impl fmt::Display for Token<'_> {
             Self::BitAnd => write!(f, "&"),
             Self::BitOr => write!(f, "|"),
             Self::BitXor => write!(f, "^"),
+            Self::Shl => write!(f, "<<"),
             Self::Not => write!(f, "!"),
             Self::BitNot => write!(f, "~"),
             Self::Period => write!(f, "."),
@@ -471,6 +560,8 @@ impl fmt::Display for Token<'_> {
             Self::Semicolon => write!(f, ";"),
             Self::Assign => write!(f, "="),
             Self::Question => write!(f, "?"),
+            Self::QuestionPeriod => write!(f, "?."),
+            Self::DoubleQuestion => write!(f, "??"),
             Self::At => write!(f, "@"),
             Self::True => write!(f, "true"),
             Self::False => write!(f, "false"),
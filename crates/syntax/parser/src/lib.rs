@@ -6,6 +6,7 @@ use std::fmt;
 use chumsky::prelude::*;
 pub use lexer::Token;
 use parser::{Parse, ParserInput};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use redscript_ast::{
     self as ast, FileId, SourceExpr, SourceItem, SourceItemDecl, SourceLoc, SourceMap,
     SourceModule, SourceStmt, Span, Spanned,
@@ -27,28 +28,35 @@ macro_rules! parse {
     }};
 }
 
+/// Parses the modules in parallel on the current rayon thread pool. The modules and
+/// errors are returned in the order of the input files regardless of the thread count.
 pub fn parse_modules<'a>(
     it: impl IntoIterator<Item = (FileId, &'a str)>,
 ) -> (Vec<SourceModule<'a>>, Vec<Error>) {
-    let mut errs = vec![];
-    let toks = it
+    let lexed = it
         .into_iter()
-        .filter_map(|(file, src)| {
-            let (t, e) = lex(src, file);
-            errs.extend(e);
-            Some((file, t?))
-        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(file, src)| (file, lex(src, file)))
         .collect::<Vec<_>>();
 
-    let parser = parser::module();
-    let modules = toks
-        .iter()
-        .filter_map(|(file, toks)| {
-            let (res, e) = parse(parser.clone(), toks, *file);
-            errs.extend(e);
-            res
+    // the parser is expensive to build, so each thread builds it once and reuses it for
+    // all the files it handles
+    let parsed = lexed
+        .par_iter()
+        .map_init(parser::module, |parser, (file, (toks, _))| match toks {
+            Some(toks) => parse(parser.clone(), toks, *file),
+            None => (None, vec![]),
         })
         .collect::<Vec<_>>();
+
+    let mut modules = Vec::with_capacity(parsed.len());
+    let mut errs = vec![];
+    for ((_, (_, lex_errs)), (module, parse_errs)) in lexed.into_iter().zip(parsed) {
+        modules.extend(module);
+        errs.extend(lex_errs);
+        errs.extend(parse_errs);
+    }
     (modules, errs)
 }
 
@@ -167,7 +167,7 @@ fn ident_with_span<'tok, 'src: 'tok>() -> impl Parse<'tok, 'src, Spanned<&'src s
 
 fn type_with_span<'tok, 'src: 'tok>() -> impl Parse<'tok, 'src, Spanned<SourceType<'src>>> {
     recursive(|this| {
-        let array_size = select! {  Token::Int(i) => i };
+        let array_size = select! {  Token::Int(i, _) => i };
         choice((
             this.clone()
                 .then(just(Token::Semicolon).ignore_then(array_size).or_not())
@@ -244,7 +244,7 @@ mod tests {
     use similar_asserts::assert_eq;
 
     use super::*;
-    use crate::parse_module;
+    use crate::{Error, parse_module, parse_modules};
 
     #[test]
     fn mod_with_imports() {
@@ -338,7 +338,7 @@ mod tests {
                             [],
                             Some(Type::plain("Int32").into()),
                             Some(FunctionBody::Inline(
-                                Expr::Constant(Constant::I32(1)).into()
+                                Expr::Constant(Constant::I32(1, Some("1"))).into()
                             )),
                         )),
                     ),
@@ -346,4 +346,27 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn parallel_parsing_keeps_input_order() {
+        let sources = (0..64)
+            .map(|i| (FileId::from_i32(i), format!("module M{i}\nfunc F{i}(")))
+            .collect::<Vec<_>>();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let (_, errs) =
+            pool.install(|| parse_modules(sources.iter().map(|(id, src)| (*id, src.as_str()))));
+
+        let mut files = errs
+            .iter()
+            .map(|err| match err {
+                Error::Parse(_, span) | Error::Lex(_, span) => span.file,
+            })
+            .collect::<Vec<_>>();
+        files.dedup();
+        assert_eq!(files, (0..64).map(FileId::from_i32).collect::<Vec<_>>());
+    }
 }
@@ -35,10 +35,11 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
         Token::Super => Expr::Super,
         Token::True => Expr::Constant(Constant::Bool(true)),
         Token::False => Expr::Constant(Constant::Bool(false)),
-        Token::Int(s) => Expr::Constant(Constant::I32(s)),
-        Token::Uint(s) => Expr::Constant(Constant::U32(s)),
-        Token::Ulong(s) => Expr::Constant(Constant::U64(s)),
-        Token::Long(s) => Expr::Constant(Constant::I64(s)),
+        Token::Int(n, s) => Expr::Constant(Constant::I32(n, Some(s))),
+        Token::Uint(n, s) => Expr::Constant(Constant::U32(n, Some(s))),
+        Token::Ulong(n, s) => Expr::Constant(Constant::U64(n, Some(s))),
+        Token::Long(n, s) => Expr::Constant(Constant::I64(n, Some(s))),
+        Token::OutOfRange(t, s) => Expr::Constant(Constant::OutOfRange(t, s)),
         Token::Float(s) => Expr::Constant(Constant::F32(s)),
         Token::Double(s) => Expr::Constant(Constant::F64(s)),
         Token::Str(s) => Expr::Constant(Constant::String(s)),
@@ -55,6 +56,19 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
     }
     .labelled("unary operator");
 
+    // '>>' is not a token because it would clash with nested type arguments,
+    // so it's parsed as two adjacent '>' instead
+    let shr = just(Token::RAngle)
+        .map_with(|_, e| e.span())
+        .then(just(Token::RAngle).map_with(|_, e| e.span()))
+        .try_map(|(lhs, rhs): (Span, Span), span| {
+            if lhs.end == rhs.start {
+                Ok(BinOp::Shr)
+            } else {
+                Err(Rich::custom(span, "unexpected whitespace in '>>'"))
+            }
+        });
+
     let binop = select! {
         Token::AssignAdd => BinOp::AssignAdd,
         Token::AssignSub => BinOp::AssignSub,
@@ -62,6 +76,8 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
         Token::AssignDiv => BinOp::AssignDiv,
         Token::AssignBitOr => BinOp::AssignBitOr,
         Token::AssignBitAnd => BinOp::AssignBitAnd,
+        Token::AssignBitXor => BinOp::AssignBitXor,
+        Token::AssignMod => BinOp::AssignMod,
         Token::Plus => BinOp::Add,
         Token::Minus => BinOp::Sub,
         Token::Star => BinOp::Mul,
@@ -73,13 +89,15 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
         Token::Le => BinOp::Le,
         Token::RAngle => BinOp::Gt,
         Token::Ge => BinOp::Ge,
+        Token::Shl => BinOp::Shl,
         Token::And => BinOp::And,
         Token::Or => BinOp::Or,
         Token::BitAnd => BinOp::BitAnd,
         Token::BitOr => BinOp::BitOr,
         Token::BitXor => BinOp::BitXor,
-    }
-    .labelled("binary operator");
+        Token::DoubleQuestion => BinOp::Coalesce,
+    };
+    let binop = shr.or(binop).labelled("binary operator");
 
     let interp_str = expr
         .clone()
@@ -179,6 +197,10 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
         .ignore_then(extended_ident())
         .map(TopPrecedence::MemberAccess)
         .erased();
+    let optional_member_access = just(Token::QuestionPeriod)
+        .ignore_then(extended_ident())
+        .map(TopPrecedence::OptionalMemberAccess)
+        .erased();
     let array_access = expr
         .clone()
         .delimited_by(just(Token::LBracket), just(Token::RBracket))
@@ -191,11 +213,14 @@ fn expr_with_span_impl<'tok, 'src: 'tok>(
         .erased();
     let member = atom
         .foldl_with(
-            choice((member_access, array_access, call)).repeated(),
+            choice((member_access, optional_member_access, array_access, call)).repeated(),
             |expr, member, e| {
                 let expr = Box::new(expr);
                 let res = match member {
                     TopPrecedence::MemberAccess(member) => Expr::Member { expr, member },
+                    TopPrecedence::OptionalMemberAccess(member) => {
+                        Expr::OptionalMember { expr, member }
+                    }
                     TopPrecedence::ArrayAccess(index) => Expr::Index { expr, index },
                     TopPrecedence::Call(type_args, args) => Expr::Call {
                         expr,
@@ -317,15 +342,16 @@ enum TopPrecedence<'src> {
     ),
     ArrayAccess(Box<(SourceExpr<'src>, Span)>),
     MemberAccess(&'src str),
+    OptionalMemberAccess(&'src str),
 }
 
 #[cfg(test)]
 mod tests {
-    use redscript_ast::{FileId, Type};
+    use redscript_ast::{FileId, IntType, Type};
     use similar_asserts::assert_eq;
 
     use super::*;
-    use crate::parse_expr;
+    use crate::{Error, parse_expr};
 
     #[test]
     fn operators() {
@@ -346,24 +372,24 @@ mod tests {
                         .into(),
                         op: BinOp::Add,
                         rhs: Expr::BinOp {
-                            lhs: Expr::Constant(Constant::I32(10)).into(),
+                            lhs: Expr::Constant(Constant::I32(10, Some("10"))).into(),
                             op: BinOp::Mul,
-                            rhs: Expr::Constant(Constant::I32(23)).into()
+                            rhs: Expr::Constant(Constant::I32(23, Some("23"))).into()
                         }
                         .into()
                     }
                     .into(),
                     op: BinOp::Sub,
                     rhs: Expr::BinOp {
-                        lhs: Expr::Constant(Constant::I32(4)).into(),
+                        lhs: Expr::Constant(Constant::I32(4, Some("4"))).into(),
                         op: BinOp::Div,
-                        rhs: Expr::Constant(Constant::I32(20)).into()
+                        rhs: Expr::Constant(Constant::I32(20, Some("20"))).into()
                     }
                     .into()
                 }
                 .into(),
                 op: BinOp::Add,
-                rhs: Expr::Constant(Constant::I32(2)).into()
+                rhs: Expr::Constant(Constant::I32(2, Some("2"))).into()
             }
         );
     }
@@ -376,9 +402,96 @@ mod tests {
         assert_eq!(
             res,
             Expr::BinOp {
-                lhs: Expr::Constant(Constant::I32(5)).into(),
+                lhs: Expr::Constant(Constant::I32(5, Some("5"))).into(),
                 op: BinOp::Gt,
-                rhs: Expr::Constant(Constant::I32(4)).into()
+                rhs: Expr::Constant(Constant::I32(4, Some("4"))).into()
+            }
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        let code = r#"a << 2 + 1 >> b"#;
+        let res = parse_expr(code, FileId::from_i32(0));
+        assert_eq!(res.1, []);
+        let res = res.0.unwrap().unwrapped();
+
+        assert_eq!(
+            res,
+            Expr::BinOp {
+                lhs: Expr::BinOp {
+                    lhs: Expr::Ident("a").into(),
+                    op: BinOp::Shl,
+                    rhs: Expr::BinOp {
+                        lhs: Expr::Constant(Constant::I32(2, Some("2"))).into(),
+                        op: BinOp::Add,
+                        rhs: Expr::Constant(Constant::I32(1, Some("1"))).into()
+                    }
+                    .into()
+                }
+                .into(),
+                op: BinOp::Shr,
+                rhs: Expr::Ident("b").into()
+            }
+        );
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let code = r#"a %= b ^= 3"#;
+        let res = parse_expr(code, FileId::from_i32(0)).0.unwrap().unwrapped();
+
+        assert_eq!(
+            res,
+            Expr::BinOp {
+                lhs: Expr::Ident("a").into(),
+                op: BinOp::AssignMod,
+                rhs: Expr::BinOp {
+                    lhs: Expr::Ident("b").into(),
+                    op: BinOp::AssignBitXor,
+                    rhs: Expr::Constant(Constant::I32(3, Some("3"))).into()
+                }
+                .into()
+            }
+        );
+    }
+
+    #[test]
+    fn optional_chaining() {
+        let code = r#"a?.b?.Method(1) ?? c ?? d || e"#;
+        let res = parse_expr(code, FileId::from_i32(0));
+        assert_eq!(res.1, []);
+        let res = res.0.unwrap().unwrapped();
+
+        assert_eq!(
+            res,
+            Expr::BinOp {
+                lhs: Expr::Call {
+                    expr: Expr::OptionalMember {
+                        expr: Expr::OptionalMember {
+                            expr: Expr::Ident("a").into(),
+                            member: "b"
+                        }
+                        .into(),
+                        member: "Method"
+                    }
+                    .into(),
+                    type_args: [].into(),
+                    args: [Expr::Constant(Constant::I32(1, Some("1")))].into()
+                }
+                .into(),
+                op: BinOp::Coalesce,
+                rhs: Expr::BinOp {
+                    lhs: Expr::Ident("c").into(),
+                    op: BinOp::Coalesce,
+                    rhs: Expr::BinOp {
+                        lhs: Expr::Ident("d").into(),
+                        op: BinOp::Or,
+                        rhs: Expr::Ident("e").into()
+                    }
+                    .into()
+                }
+                .into()
             }
         );
     }
@@ -393,11 +506,11 @@ mod tests {
                 cond: Expr::Constant(Constant::Bool(true)).into(),
                 then: Expr::Conditional {
                     cond: Expr::Constant(Constant::Bool(false)).into(),
-                    then: Expr::Constant(Constant::I32(1)).into(),
-                    else_: Expr::Constant(Constant::I32(2)).into(),
+                    then: Expr::Constant(Constant::I32(1, Some("1"))).into(),
+                    else_: Expr::Constant(Constant::I32(2, Some("2"))).into(),
                 }
                 .into(),
-                else_: Expr::Constant(Constant::I32(3)).into(),
+                else_: Expr::Constant(Constant::I32(3, Some("3"))).into(),
             }
         );
     }
@@ -429,7 +542,7 @@ mod tests {
                         args: [].into(),
                     }
                     .into(),
-                    index: Expr::Constant(Constant::I32(0)).into(),
+                    index: Expr::Constant(Constant::I32(0, Some("0"))).into(),
                 }
                 .into(),
                 member: "field",
@@ -446,10 +559,10 @@ mod tests {
             res,
             Expr::ArrayLit(
                 [
-                    Expr::Constant(Constant::I32(1)),
-                    Expr::Constant(Constant::I64(2)),
-                    Expr::Constant(Constant::U32(3)),
-                    Expr::Constant(Constant::U64(4)),
+                    Expr::Constant(Constant::I32(1, Some("1"))),
+                    Expr::Constant(Constant::I64(2, Some("2"))),
+                    Expr::Constant(Constant::U32(3, Some("3"))),
+                    Expr::Constant(Constant::U64(4, Some("4"))),
                     Expr::Constant(Constant::F32(5.)),
                     Expr::Constant(Constant::F64(6.)),
                 ]
@@ -458,6 +571,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn prefixed_number_literals() {
+        let code = r#"[0xFF, 0b1010l, 0o17u, 0xDEAD_BEEFul, 1_000, 1_000.5, 0x1_0000_0000]"#;
+        let res = parse_expr(code, FileId::from_i32(0)).0.unwrap().unwrapped();
+
+        assert_eq!(
+            res,
+            Expr::ArrayLit(
+                [
+                    Expr::Constant(Constant::I32(0xFF, Some("0xFF"))),
+                    Expr::Constant(Constant::I64(0b1010, Some("0b1010"))),
+                    Expr::Constant(Constant::U32(0o17, Some("0o17"))),
+                    Expr::Constant(Constant::U64(0xDEAD_BEEF, Some("0xDEAD_BEEF"))),
+                    Expr::Constant(Constant::I32(1000, Some("1_000"))),
+                    Expr::Constant(Constant::F32(1000.5)),
+                    Expr::Constant(Constant::OutOfRange(IntType::I32, "0x1_0000_0000")),
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_prefixed_number_literals() {
+        for code in ["0b102", "0x_", "0o8u"] {
+            let (_, errors) = parse_expr(code, FileId::from_i32(0));
+            assert!(
+                matches!(errors.as_slice(), [Error::Lex(_, _), ..]),
+                "{code}"
+            );
+        }
+    }
+
     #[test]
     fn str_literals() {
         let code = r#"["a", n"b", r"c", t"d"]"#;
@@ -487,7 +633,7 @@ mod tests {
             Expr::Call {
                 expr: Expr::Ident("Cast").into(),
                 type_args: [Type::plain("Uint8")].into(),
-                args: [Expr::Constant(Constant::I32(1))].into(),
+                args: [Expr::Constant(Constant::I32(1, Some("1")))].into(),
             }
         );
     }
@@ -503,9 +649,9 @@ mod tests {
                 [
                     StrPart::Str("2 + 2 is ".into()),
                     StrPart::Expr(Expr::BinOp {
-                        lhs: Expr::Constant(Constant::I32(2)).into(),
+                        lhs: Expr::Constant(Constant::I32(2, Some("2"))).into(),
                         op: BinOp::Add,
-                        rhs: Expr::Constant(Constant::I32(2)).into(),
+                        rhs: Expr::Constant(Constant::I32(2, Some("2"))).into(),
                     }),
                 ]
                 .into()
@@ -72,8 +72,10 @@ fn function<'tok, 'src: 'tok>(
         .collect::<BitCollection<_>>()
         .then(ident())
         .then(just(Token::Colon).ignore_then(ty.clone()))
-        .map_with(|((qualifiers, name), typ), e| {
-            (Param::new(name, Some(typ), qualifiers.value), e.span())
+        .then(just(Token::Assign).ignore_then(expr.clone()).or_not())
+        .map_with(|(((qualifiers, name), typ), default), e| {
+            let param = Param::new(name, Some(typ), qualifiers.value);
+            (param.with_default(default.map(Box::new)), e.span())
         })
         .separated_by(just(Token::Comma))
         .allow_trailing()
@@ -121,7 +123,7 @@ fn field<'tok, 'src: 'tok>(
 fn enum_<'tok, 'src: 'tok>() -> impl Parse<'tok, 'src, SourceEnum<'src>> {
     let int = just(Token::Minus)
         .or_not()
-        .then(select! { Token::Int(i) => i });
+        .then(select! { Token::Int(i, _) => i });
 
     let variants = extended_ident()
         .then(just(Token::Assign).ignore_then(int).or_not())
@@ -374,7 +376,7 @@ mod tests {
                         Item::Let(Field::new(
                             "b",
                             Type::plain("Int32").into(),
-                            Some(Expr::Constant(Constant::I32(3)).into())
+                            Some(Expr::Constant(Constant::I32(3, Some("3"))).into())
                         ))
                     ),
                 ]
@@ -407,6 +409,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn func_with_default_params() {
+        let code = r#"
+        func Test(arg1: Int32, arg2: Int32 = 5)
+        "#;
+
+        assert_eq!(
+            parse_item(code, FileId::from_i32(0)).0.unwrap().unwrapped(),
+            Item::Function(Function::new(
+                "Test",
+                [],
+                [
+                    Param::new("arg1", Some(Type::plain("Int32")), ParamQualifiers::empty()),
+                    Param::new("arg2", Some(Type::plain("Int32")), ParamQualifiers::empty())
+                        .with_default(Some(Expr::Constant(Constant::I32(5, Some("5"))).into())),
+                ],
+                None,
+                None
+            ))
+        );
+    }
+
     #[test]
     fn annotations() {
         let code = r#"
@@ -248,16 +248,20 @@ mod tests {
                 blocks: [
                     ConditionalBlock::new(
                         LetCondition::Expr(Expr::Constant(Constant::Bool(true))),
-                        Block::single(Stmt::Return(Some(Expr::Constant(Constant::I32(1)).into())))
+                        Block::single(Stmt::Return(Some(
+                            Expr::Constant(Constant::I32(1, Some("1"))).into()
+                        )))
                     ),
                     ConditionalBlock::new(
                         LetCondition::Expr(Expr::Constant(Constant::Bool(false))),
-                        Block::single(Stmt::Return(Some(Expr::Constant(Constant::I32(2)).into())))
+                        Block::single(Stmt::Return(Some(
+                            Expr::Constant(Constant::I32(2, Some("2"))).into()
+                        )))
                     ),
                 ]
                 .into(),
                 else_: Some(Block::single(Stmt::Return(Some(
-                    Expr::Constant(Constant::I32(3)).into()
+                    Expr::Constant(Constant::I32(3, Some("3"))).into()
                 )))),
             }
         );
@@ -282,16 +286,25 @@ mod tests {
                 expr: Expr::Ident("a").into(),
                 cases: [
                     Case::new(
-                        Condition::Expr(Expr::Constant(Constant::I32(0))),
+                        Condition::Expr(Expr::Constant(Constant::I32(0, Some("0")))),
                         [Stmt::Break]
                     ),
                     Case::new(
-                        Condition::Expr(Expr::Constant(Constant::I32(1))),
-                        [(Stmt::Return(Some(Expr::Constant(Constant::I32(0)).into())))]
+                        Condition::Expr(Expr::Constant(Constant::I32(1, Some("1")))),
+                        [
+                            (Stmt::Return(Some(
+                                Expr::Constant(Constant::I32(0, Some("0"))).into()
+                            )))
+                        ]
                     ),
                 ]
                 .into(),
-                default: Some([Stmt::Return(Some(Expr::Constant(Constant::I32(1)).into()))].into()),
+                default: Some(
+                    [Stmt::Return(Some(
+                        Expr::Constant(Constant::I32(1, Some("1"))).into()
+                    ))]
+                    .into()
+                ),
             }
         );
     }
@@ -311,7 +324,7 @@ mod tests {
                     LetCondition::Expr(Expr::BinOp {
                         op: BinOp::Gt,
                         lhs: Box::new(Expr::Ident("i")),
-                        rhs: Box::new(Expr::Constant(Constant::I32(0))),
+                        rhs: Box::new(Expr::Constant(Constant::I32(0, Some("0")))),
                     }),
                     Block::single(Stmt::Expr(
                         Expr::Assign {
@@ -319,7 +332,7 @@ mod tests {
                             rhs: Box::new(Expr::BinOp {
                                 op: BinOp::Sub,
                                 lhs: Box::new(Expr::Ident("i")),
-                                rhs: Box::new(Expr::Constant(Constant::I32(1))),
+                                rhs: Box::new(Expr::Constant(Constant::I32(1, Some("1")))),
                             }),
                         }
                         .into()
@@ -370,7 +383,7 @@ mod tests {
             Stmt::Let {
                 name: "a",
                 typ: Some(Type::plain("Int32").into()),
-                value: Some(Expr::Constant(Constant::I32(1)).into()),
+                value: Some(Expr::Constant(Constant::I32(1, Some("1"))).into()),
             }
         );
     }